@@ -0,0 +1,293 @@
+//! Partner address book
+//!
+//! Partners are the businesses where participants spend their vouchers. The book keeps
+//! their names, payment addresses and optional signing keys in the app state
+//! (`AppState::partners`); redemptions, reports and the dashboard look partners up by
+//! address. Every address is parsed with `elements::Address` and checked against the
+//! network in the settings before it is saved, and the book can be exported to and
+//! imported from CSV or JSON.
+
+use super::csv;
+use super::models::{AppState, Partner};
+use anyhow::{Context, Result};
+use elements::{Address, AddressParams};
+use std::str::FromStr;
+
+/// Address parameters of an Elements chain name (`Settings::chain`)
+pub fn network_params(chain: &str) -> Result<&'static AddressParams> {
+    match chain {
+        "liquidtestnet" => Ok(&AddressParams::LIQUID_TESTNET),
+        "liquid" | "liquidv1" => Ok(&AddressParams::LIQUID),
+        "elementsregtest" => Ok(&AddressParams::ELEMENTS),
+        other => Err(anyhow::anyhow!(
+            "Unknown chain '{}' in the settings.\n\nUse liquidtestnet, liquid or elementsregtest.",
+            other
+        )),
+    }
+}
+
+/// Display name of a network, for messages
+pub fn network_name(params: &AddressParams) -> &'static str {
+    if *params == AddressParams::LIQUID {
+        "Liquid"
+    } else if *params == AddressParams::LIQUID_TESTNET {
+        "Liquid Testnet"
+    } else {
+        "Elements regtest"
+    }
+}
+
+/// Parse an address and check it belongs to `chain`
+///
+/// `confidential` says which form is expected: a confidential address carries a
+/// blinding key and hides the amounts paid to it, an unconfidential one does not.
+pub fn validate_address(address: &str, chain: &str, confidential: bool) -> Result<Address> {
+    let expected = network_params(chain)?;
+    let address = address.trim();
+    let parsed = parse_address(address)?;
+    if parsed.params != expected {
+        return Err(anyhow::anyhow!(
+            "{} is a {} address, but the app is configured for {} ({}).\n\nAsk the partner for an address on the same network.",
+            address,
+            network_name(parsed.params),
+            network_name(expected),
+            chain
+        ));
+    }
+    match (parsed.is_blinded(), confidential) {
+        (true, false) => Err(anyhow::anyhow!(
+            "{} is a confidential address, but an unconfidential one is needed here.\n\nPartner payments must be explicit so the covenant can check the amount and reports can total it. Use the unconfidential form of the address:\n\nelements-cli getaddressinfo {} (the \"unconfidential\" field)",
+            address,
+            address
+        )),
+        (false, true) => Err(anyhow::anyhow!(
+            "{} is an unconfidential address, but a confidential one is needed here.\n\nUse the address as the wallet shows it (elements-cli getnewaddress returns the confidential form).",
+            address
+        )),
+        _ => Ok(parsed),
+    }
+}
+
+/// Parse an address on any network, explaining a mistyped one
+pub fn parse_address(address: &str) -> Result<Address> {
+    let address = address.trim();
+    if address.is_empty() {
+        return Err(anyhow::anyhow!("Please enter an address"));
+    }
+    Address::from_str(address).map_err(|e| {
+        if e.to_string().to_ascii_lowercase().contains("checksum") {
+            anyhow::anyhow!(
+                "The address {} has a bad checksum.\n\nIt was probably mistyped or cut off. Copy it again from the partner's wallet or scan their payment request.",
+                address
+            )
+        } else {
+            anyhow::anyhow!("{} is not a Liquid address: {}", address, e)
+        }
+    })
+}
+
+/// Check a partner entry and return it normalised
+///
+/// The name must not be empty, the address must be an unconfidential address on
+/// `chain` and the optional public key must be 32 (x-only) or 33 bytes of hex.
+pub fn validate_partner(partner: &Partner, chain: &str) -> Result<Partner> {
+    let name = partner.name.trim();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Please enter the partner's name"));
+    }
+    let address = validate_address(&partner.address, chain, false)?;
+    let pubkey = match partner.pubkey.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        None => None,
+        Some(key) => {
+            let key = key.to_ascii_lowercase();
+            if !(key.len() == 64 || key.len() == 66) || hex::decode(&key).is_err() {
+                return Err(anyhow::anyhow!(
+                    "'{}' is not a public key.\n\nEnter 64 (x-only) or 66 hex characters, or leave it empty.",
+                    key
+                ));
+            }
+            Some(key)
+        }
+    };
+    Ok(Partner { address: address.to_string(), pubkey, name: name.to_string() })
+}
+
+/// Add a partner, or replace the entry with address `previous` when editing
+pub fn save_partner(state: &mut AppState, previous: Option<&str>, partner: &Partner, chain: &str) -> Result<Partner> {
+    let partner = validate_partner(partner, chain)?;
+    if let Some(existing) = state
+        .partners
+        .iter()
+        .find(|p| p.address == partner.address && Some(p.address.as_str()) != previous)
+    {
+        return Err(anyhow::anyhow!(
+            "{} is already in the address book as {}",
+            partner.address,
+            existing.name
+        ));
+    }
+    match previous.and_then(|a| state.partners.iter().position(|p| p.address == a)) {
+        Some(index) => state.partners[index] = partner.clone(),
+        None => state.partners.push(partner.clone()),
+    }
+    Ok(partner)
+}
+
+/// Remove the partner with `address`; returns whether there was one
+pub fn delete_partner(state: &mut AppState, address: &str) -> bool {
+    let before = state.partners.len();
+    state.partners.retain(|p| p.address != address);
+    state.partners.len() != before
+}
+
+/// The book as CSV with a `name,address,pubkey` header
+pub fn export_csv(partners: &[Partner]) -> String {
+    let mut csv = String::from("name,address,pubkey\n");
+    for partner in partners {
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            csv::field(&partner.name),
+            csv::field(&partner.address),
+            partner.pubkey.as_deref().unwrap_or("")
+        ));
+    }
+    csv
+}
+
+pub fn export_json(partners: &[Partner]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(partners)?)
+}
+
+/// Parse an exported book: a JSON array of partners, or CSV with a header naming
+/// `name` and `address` columns (`pubkey` optional)
+pub fn parse_import(text: &str) -> Result<Vec<Partner>> {
+    let text = text.trim();
+    if text.starts_with('[') {
+        return serde_json::from_str(text).context("The JSON is not a list of partners with name, address and pubkey fields");
+    }
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let header = lines.next().context("The file is empty.\n\nThe first line must be a header such as: name,address,pubkey")?;
+    let columns: Vec<String> = csv::cells(header).iter().map(|c| c.to_ascii_lowercase()).collect();
+    let find = |name: &str| columns.iter().position(|c| c == name);
+    let (Some(name_col), Some(address_col)) = (find("name"), find("address")) else {
+        return Err(anyhow::anyhow!(
+            "The CSV header must name a name and an address column, got: {}\n\nExample:\nname,address,pubkey\nCafe,<unconfidential address>,",
+            header
+        ));
+    };
+    let pubkey_col = find("pubkey");
+    Ok(lines
+        .map(|line| {
+            let cells = csv::cells(line);
+            let cell = |i: usize| cells.get(i).cloned().unwrap_or_default();
+            Partner {
+                address: cell(address_col),
+                pubkey: pubkey_col.map(cell).filter(|k| !k.is_empty()),
+                name: cell(name_col),
+            }
+        })
+        .collect())
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+}
+
+/// Merge imported partners into the book
+///
+/// Entries with an address already in the book replace it. Every entry is checked
+/// first; if any is invalid nothing is imported and all problems are reported.
+pub fn import(state: &mut AppState, partners: &[Partner], chain: &str) -> Result<ImportSummary> {
+    let mut valid = Vec::new();
+    let mut errors = Vec::new();
+    for (i, partner) in partners.iter().enumerate() {
+        match validate_partner(partner, chain) {
+            Ok(partner) => valid.push(partner),
+            Err(e) => errors.push(format!("Entry {} ({}): {}", i + 1, partner.name, e.to_string().lines().next().unwrap_or(""))),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} invalid partner(s):\n{}\n\nFix them and import again. The address book was not changed.",
+            errors.len(),
+            errors.join("\n")
+        ));
+    }
+    let mut summary = ImportSummary::default();
+    for partner in valid {
+        match state.partners.iter_mut().find(|p| p.address == partner.address) {
+            Some(existing) => {
+                *existing = partner;
+                summary.updated += 1;
+            }
+            None => {
+                state.partners.push(partner);
+                summary.added += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::secp256k1_zkp::PublicKey;
+    use elements::Script;
+
+    const KEY: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn address(params: &'static AddressParams, confidential: bool) -> String {
+        let script = Script::from(hex::decode(format!("5120{}", "11".repeat(32))).unwrap());
+        let blinder = confidential.then(|| PublicKey::from_str(KEY).unwrap());
+        Address::from_script(&script, blinder, params).unwrap().to_string()
+    }
+
+    #[test]
+    fn addresses_are_checked_and_the_book_round_trips() {
+        let cafe = address(&AddressParams::LIQUID_TESTNET, false);
+        assert!(validate_address(&cafe, "liquidtestnet", false).is_ok());
+
+        let mainnet = validate_address(&address(&AddressParams::LIQUID, false), "liquidtestnet", false);
+        assert!(mainnet.unwrap_err().to_string().contains("is a Liquid address"));
+        let blinded = address(&AddressParams::LIQUID_TESTNET, true);
+        assert!(validate_address(&blinded, "liquidtestnet", false).unwrap_err().to_string().contains("confidential address"));
+        assert!(validate_address(&blinded, "liquidtestnet", true).is_ok());
+        assert!(validate_address(&cafe, "liquidtestnet", true).unwrap_err().to_string().contains("unconfidential address"));
+        let mut typo = cafe.clone();
+        typo.replace_range(10..11, if &cafe[10..11] == "q" { "p" } else { "q" });
+        assert!(validate_address(&typo, "liquidtestnet", false).unwrap_err().to_string().contains("bad checksum"));
+
+        let mut state = AppState::default();
+        let entry = Partner { address: cafe.clone(), pubkey: Some(KEY.to_uppercase()), name: " Café, Bar ".to_string() };
+        let saved = save_partner(&mut state, None, &entry, "liquidtestnet").unwrap();
+        assert_eq!(saved.name, "Café, Bar");
+        assert_eq!(saved.pubkey.as_deref(), Some(KEY));
+        assert!(save_partner(&mut state, None, &entry, "liquidtestnet").is_err());
+        let renamed = Partner { name: "Cafe".to_string(), ..entry.clone() };
+        save_partner(&mut state, Some(&cafe), &renamed, "liquidtestnet").unwrap();
+        assert_eq!(state.partners.len(), 1);
+
+        let csv = export_csv(&state.partners);
+        assert_eq!(parse_import(&csv).unwrap(), state.partners);
+        let json = export_json(std::slice::from_ref(&saved)).unwrap();
+        let mut other = AppState::default();
+        let summary = import(&mut other, &parse_import(&json).unwrap(), "liquidtestnet").unwrap();
+        assert_eq!(summary, ImportSummary { added: 1, updated: 0 });
+        assert_eq!(other.partners[0].name, "Café, Bar");
+
+        // One bad row rejects the whole import
+        let bad = format!("name,address\nShop,{}\nBank,{}\n", cafe, blinded);
+        assert!(import(&mut state, &parse_import(&bad).unwrap(), "liquidtestnet").is_err());
+        assert_eq!(state.partners[0].name, "Cafe");
+
+        assert!(delete_partner(&mut state, &cafe));
+        assert!(state.partners.is_empty());
+    }
+}
@@ -0,0 +1,495 @@
+//! REST API over app_core, for partner point-of-sale systems and other services
+//!
+//! The API runs the same workflow steps, redemption checks and state store as the desktop
+//! app, so contracts, redemptions and partner decisions made through it show up in the app
+//! and the other way around. Requests and responses are JSON; errors are
+//! `{"error": "..."}` with a 4xx/5xx status.
+//!
+//! The API never sees private keys. PSETs come back with their `sig_all_hash` (input 0,
+//! hex); the caller signs it with BIP340 on its own device and posts the signature.
+//!
+//! Endpoints:
+//! - `GET /health` -> node sync status (502 if the node is unreachable)
+//! - `GET /templates` -> installed covenant templates
+//! - `GET /contracts`, `GET /contracts/<address>` -> contract records (with locked value)
+//! - `POST /contracts` -> generate, compile and record a contract
+//! - `GET /vouchers/<txid>/<vout>` -> voucher balance and lineage
+//! - `POST /redemptions` -> build an unsigned redemption PSET for a voucher
+//! - `GET /redemptions/<workflow>` -> redemption status and PSET
+//! - `POST /redemptions/<workflow>/signature` -> add the participant's signature
+//! - `POST /redemptions/<workflow>/submit` -> finalize a co-signed PSET and broadcast it
+//! - `POST /validate` -> check a redemption PSET against the contract rules
+//! - `POST /cosign` (with the partner's signature), `POST /reject` -> record the partner's decision
+//! - `GET /reports?partner=&contract=&from=YYYY-MM-DD&to=YYYY-MM-DD&confirmed=true` ->
+//!   redemption report with per-partner and per-contract totals
+
+use super::audit;
+use super::error;
+use super::http::Request;
+use super::internal_key::InternalKey;
+use super::lineage;
+use super::models::{RedemptionRequest, ReviewDecision, Settings};
+use super::participant::{self, RedemptionOrder, WalletVoucher};
+use super::redemption::{self, Review};
+use super::reports::{self, ReportFilter};
+use super::sighash::{self, SigningMode};
+use super::templates::TemplateValues;
+use super::workflow::{self, Workflow, WorkflowState};
+use super::dashboard;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+
+/// Error response: status code and message
+#[derive(Debug)]
+pub struct ApiError(pub u16, pub String);
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        ApiError(422, error::describe(&e))
+    }
+}
+
+type ApiResult = Result<(u16, Value), ApiError>;
+
+fn not_found(what: String) -> ApiError {
+    ApiError(404, what)
+}
+
+#[derive(Deserialize)]
+struct CreateContractBody {
+    /// Name of the workflow the contract is built under; defaults to a timestamped one
+    #[serde(default)]
+    name: Option<String>,
+    template_id: String,
+    #[serde(default)]
+    params: TemplateValues,
+    /// `nums` (default), `random` or `aggregate`
+    #[serde(default)]
+    internal_key: Option<String>,
+    /// Oracle signer recorded with the contract; required when the template has an oracle key
+    #[serde(default)]
+    oracle_url: String,
+}
+
+#[derive(Deserialize)]
+struct RedemptionBody {
+    txid: String,
+    vout: u32,
+    partner_address: String,
+    amount_sats: u64,
+    #[serde(default)]
+    invoice_id: String,
+}
+
+/// A BIP340 signature over a PSET's `sig_all_hash`, made by the caller
+#[derive(Deserialize)]
+struct SignatureBody {
+    pubkey: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct SubmitBody {
+    pset: String,
+}
+
+#[derive(Deserialize)]
+struct ValidateBody {
+    pset: String,
+    partner_address: String,
+    #[serde(default)]
+    amount_sats: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CoSignBody {
+    pset: String,
+    partner_address: String,
+    #[serde(default)]
+    amount_sats: Option<u64>,
+    #[serde(flatten)]
+    signature: SignatureBody,
+}
+
+#[derive(Deserialize)]
+struct RejectBody {
+    pset: String,
+    partner_address: String,
+    reason: String,
+}
+
+/// The API's services; `base.name` is the prefix of the workflows it creates
+pub struct Api {
+    pub base: Workflow,
+    /// Bearer token every request must carry
+    pub token: String,
+}
+
+impl Api {
+    pub fn new(base: Workflow, token: String) -> Self {
+        Self { base, token }
+    }
+
+    /// Answer one request
+    pub async fn handle(&self, request: &Request) -> (u16, Value) {
+        let expected = format!("Bearer {}", self.token);
+        let given = request.header("Authorization").unwrap_or_default();
+        if !bool::from(given.as_bytes().ct_eq(expected.as_bytes())) {
+            return (401, json!({"error": "Missing or wrong bearer token"}));
+        }
+        match self.route(request).await {
+            Ok(response) => response,
+            Err(ApiError(status, message)) => (status, json!({ "error": message })),
+        }
+    }
+
+    async fn route(&self, request: &Request) -> ApiResult {
+        match (request.method.as_str(), request.segments().as_slice()) {
+            ("GET", ["health"]) => Ok(match dashboard::node_status(&self.base.rpc).await {
+                Ok(node) => (200, json!({ "synced": node.is_synced(), "node": node })),
+                Err(e) => (502, json!({ "error": format!("Node unreachable: {}", e) })),
+            }),
+            ("GET", ["templates"]) => Ok((200, json!(self.base.registry.all()))),
+            ("GET", ["contracts"]) => Ok((200, json!(self.base.store.load()?.contracts))),
+            ("GET", ["contracts", address]) => self.contract(address).await,
+            ("POST", ["contracts"]) => self.create_contract(body(request)?).await,
+            ("GET", ["vouchers", txid, vout]) => self.voucher(txid, parse_vout(vout)?).await,
+            ("POST", ["redemptions"]) => self.start_redemption(body(request)?).await,
+            ("GET", ["redemptions", name]) => self.redemption(name),
+            ("POST", ["redemptions", name, "signature"]) => self.add_signature(name, body(request)?),
+            ("POST", ["redemptions", name, "submit"]) => self.submit(name, body(request)?).await,
+            ("POST", ["validate"]) => self.validate(body(request)?),
+            ("POST", ["cosign"]) => self.co_sign(body(request)?),
+            ("POST", ["reject"]) => self.reject(body(request)?),
+            ("GET", ["reports"]) => self.report(request).await,
+            (method, segments) => Err(match allowed_methods(segments) {
+                Some(allowed) => ApiError(405, format!("{} is not allowed on {}; use {}", method, request.path, allowed)),
+                None => not_found(format!("No route for {} {}", method, request.path)),
+            }),
+        }
+    }
+
+    async fn contract(&self, address: &str) -> ApiResult {
+        let record = self
+            .base
+            .store
+            .find_contract(address)?
+            .ok_or_else(|| not_found(format!("No contract recorded for {}", address)))?;
+        let locked = self.base.rpc.get_address_balance(address).await;
+        Ok((200, json!({
+            "contract": record,
+            "locked_sats": locked.as_ref().ok(),
+            "locked_error": locked.err().map(|e| e.to_string()),
+        })))
+    }
+
+    async fn create_contract(&self, body: CreateContractBody) -> ApiResult {
+        let name = body.name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| {
+            format!("{}", std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0))
+        });
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(ApiError(400, "name may only contain letters, digits, '-' and '_'".to_string()));
+        }
+        let flow = Workflow { name: format!("{}-contract-{}", self.base.name, name), ..self.base.clone() };
+        if flow.resume()?.is_some_and(|s| s.contract.is_some()) {
+            return Err(ApiError(409, format!("A contract named '{}' was already created", name)));
+        }
+
+        let template = flow.template(&body.template_id)?;
+        let mut params = template.default_values();
+        params.extend(body.params);
+        let params = template.validate(&params)?;
+        let internal_key = match body.internal_key.as_deref().unwrap_or("nums") {
+            "nums" => InternalKey::Nums,
+            "random" => InternalKey::random_unspendable()?,
+            "aggregate" => InternalKey::aggregate(&template.signer_pubkeys(&params))?,
+            other => return Err(ApiError(400, format!("Unknown internal_key '{}': use nums, random or aggregate", other))),
+        };
+
+        let dir = Settings::data_dir().join("api");
+        std::fs::create_dir_all(&dir).map_err(|e| ApiError(500, format!("Failed to create {}: {}", dir.display(), e)))?;
+        let simf_path = dir.join(format!("{}.simf", flow.name)).to_string_lossy().to_string();
+
+        let progress = |message: String| tracing::info!("{}: {}", flow.name, message);
+        let mut state = WorkflowState::new(&body.template_id, params.clone());
+        let input = workflow::GenerateInput { template_id: body.template_id.clone(), params: params.clone(), simf_path: simf_path.clone() };
+        flow.run(workflow::Generate, &mut state, input, &progress).await?;
+        let compiled = flow.run(workflow::Compile, &mut state, workflow::CompileInput { simf_path: simf_path.clone() }, &progress).await?;
+        let input = workflow::CreateContractInput {
+            template_id: body.template_id,
+            params,
+            simf_path,
+            program: compiled.program,
+            internal_key,
+            oracle_url: body.oracle_url,
+        };
+        let created = flow.run(workflow::CreateContract, &mut state, input, &progress).await?;
+        if !created.hal_mismatches.is_empty() {
+            return Err(ApiError(
+                500,
+                format!("hal-simplicity disagrees with the Rust derivation; do not fund {}:\n- {}", created.contract.address, created.hal_mismatches.join("\n- ")),
+            ));
+        }
+        Ok((201, json!({ "workflow": flow.name, "contract": created.contract })))
+    }
+
+    async fn voucher(&self, txid: &str, vout: u32) -> ApiResult {
+        let state = self.base.store.load()?;
+        let voucher = dashboard::vouchers(&state).into_iter().find(|v| v.txid == txid && v.vout == vout);
+        let lineage = lineage::follow_lineage(&self.base.rpc, txid, vout)
+            .await
+            .map_err(|e| ApiError(502, format!("{:#}", e)))?;
+        let contract = voucher
+            .as_ref()
+            .and_then(|v| state.contracts.iter().find(|c| c.address == v.covenant_address))
+            .cloned();
+        let wallet_voucher = voucher.clone().map(|voucher| WalletVoucher { voucher, contract, lineage: Ok(lineage.clone()) });
+        Ok((200, json!({
+            "registered": voucher.is_some(),
+            "owner_pubkey": voucher.as_ref().map(|v| v.owner_pubkey.clone()),
+            "balance_sats": lineage.remaining_sats(),
+            "redeemed_sats": lineage.paid_sats(),
+            "current_utxo": wallet_voucher.as_ref().and_then(|v| v.current_utxo()).map(|(txid, vout, _)| json!({ "txid": txid, "vout": vout })),
+            "redeemable": wallet_voucher.as_ref().is_some_and(|v| v.can_redeem()),
+            "lineage": lineage,
+        })))
+    }
+
+    async fn start_redemption(&self, body: RedemptionBody) -> ApiResult {
+        let state = self.base.store.load()?;
+        let voucher = dashboard::vouchers(&state)
+            .into_iter()
+            .find(|v| v.txid == body.txid && v.vout == body.vout)
+            .ok_or_else(|| not_found(format!("No voucher registered for {}:{}", body.txid, body.vout)))?;
+        let lineage = lineage::follow_lineage(&self.base.rpc, &voucher.txid, voucher.vout)
+            .await
+            .map_err(|e| ApiError(502, format!("{:#}", e)))?;
+        let contract = state.contracts.iter().find(|c| c.address == voucher.covenant_address).cloned();
+        let order = RedemptionOrder {
+            participant_pubkey: voucher.owner_pubkey.clone(),
+            voucher: WalletVoucher { voucher, contract, lineage: Ok(lineage) },
+            partner_address: body.partner_address,
+            amount: body.amount_sats as f64 / 100_000_000.0,
+            invoice_id: body.invoice_id,
+            privkey: String::new(),
+            mode: SigningMode::default(),
+        };
+        let progress = |message: String| tracing::info!("redemption: {}", message);
+        let request = participant::start_redemption(&self.base, order, &progress).await?;
+        let pset = participant::request_pset(&self.base, &request)?;
+        let sig_all_hash = self.request_sig_all_hash(&request, &pset)?;
+        Ok((201, json!({ "redemption": request, "pset": pset, "sig_all_hash": sig_all_hash })))
+    }
+
+    fn add_signature(&self, name: &str, body: SignatureBody) -> ApiResult {
+        let request = self.find_redemption(name)?;
+        let pset = participant::add_signature(&self.base, &request, &body.pubkey, &body.signature)?;
+        Ok((200, json!({ "pset": pset })))
+    }
+
+    fn find_redemption(&self, name: &str) -> Result<RedemptionRequest, ApiError> {
+        self.base
+            .store
+            .load()?
+            .redemptions
+            .into_iter()
+            .find(|r| r.workflow == name)
+            .ok_or_else(|| not_found(format!("No redemption named {}", name)))
+    }
+
+    /// `sig_all_hash` of a redemption's PSET, for the participant to sign
+    fn request_sig_all_hash(&self, request: &RedemptionRequest, pset: &str) -> Result<String, ApiError> {
+        let contract = self
+            .base
+            .store
+            .find_contract(&request.contract_address)?
+            .ok_or_else(|| not_found(format!("No contract recorded for {}", request.contract_address)))?;
+        Ok(hex::encode(sighash::sig_all_hash(pset, 0, &contract.cmr, &contract.genesis_hash)?))
+    }
+
+    async fn report(&self, request: &Request) -> ApiResult {
+        let param = |name: &str| request.query_param(name).filter(|v| !v.is_empty());
+        let date = |name: &str| match param(name) {
+            Some(value) => audit::parse_date(value)
+                .map(Some)
+                .ok_or_else(|| ApiError(400, format!("{} must be a YYYY-MM-DD date, got {}", name, value))),
+            None => Ok(None),
+        };
+        let filter = ReportFilter {
+            partner: param("partner").map(str::to_string),
+            contract: param("contract").map(str::to_string),
+            from: date("from")?,
+            // Through the end of the "to" day
+            to: date("to")?.map(|t| t + 86_399),
+            confirmed_only: param("confirmed") != Some("false"),
+        };
+        let state = self.base.store.load()?;
+        Ok((200, json!(reports::load(&self.base.rpc, &state, filter).await)))
+    }
+
+    fn redemption(&self, name: &str) -> ApiResult {
+        let state = self.base.store.load()?;
+        let request = self.find_redemption(name)?;
+        let pset = participant::request_pset(&self.base, &request).ok();
+        let sig_all_hash = pset.as_deref().and_then(|pset| self.request_sig_all_hash(&request, pset).ok());
+        Ok((200, json!({
+            "status": participant::status(&state, &request).to_string(),
+            "redemption": request,
+            "pset": pset,
+            "sig_all_hash": sig_all_hash,
+        })))
+    }
+
+    async fn submit(&self, name: &str, body: SubmitBody) -> ApiResult {
+        let request = self.find_redemption(name)?;
+        let progress = |message: String| tracing::info!("{}: {}", name, message);
+        let txid = participant::finish_redemption(&self.base, &request, &body.pset, &progress).await?;
+        Ok((200, json!({ "txid": txid })))
+    }
+
+    fn review(&self, pset: &str, partner_address: &str, amount_sats: Option<u64>) -> Result<Review, ApiError> {
+        let state = self.base.store.load()?;
+        Ok(redemption::review(&state, &self.base.registry, pset, partner_address, amount_sats)?)
+    }
+
+    fn validate(&self, body: ValidateBody) -> ApiResult {
+        let review = self.review(&body.pset, &body.partner_address, body.amount_sats)?;
+        Ok((200, review_json(&review)))
+    }
+
+    fn co_sign(&self, body: CoSignBody) -> ApiResult {
+        let review = self.review(&body.pset, &body.partner_address, body.amount_sats)?;
+        if !review.report.is_valid() {
+            return Ok((422, review_json(&review)));
+        }
+        let signers = review.signers(&self.base.registry);
+        let SignatureBody { pubkey, signature } = body.signature;
+        let (pubkey, signature) = (pubkey.trim().to_string(), signature.trim().to_string());
+        let pset = redemption::add_verified_signature(
+            &review.redemption.pset,
+            &review.contract.cmr,
+            &review.contract.genesis_hash,
+            &signers,
+            &pubkey,
+            &signature,
+        )?;
+        audit::record(&self.base.store, "api", audit::signed(&pset, vec![pubkey.clone()]));
+        self.base.store.save_review(review.record(ReviewDecision::CoSigned { pubkey: pubkey.clone(), signature: signature.clone() }))?;
+        Ok((200, json!({ "pset": pset, "pubkey": pubkey, "signature": signature })))
+    }
+
+    fn reject(&self, body: RejectBody) -> ApiResult {
+        if body.reason.trim().is_empty() {
+            return Err(ApiError(400, "reason is required".to_string()));
+        }
+        let review = self.review(&body.pset, &body.partner_address, None)?;
+        self.base.store.save_review(review.record(ReviewDecision::Rejected { reason: body.reason.trim().to_string() }))?;
+        Ok((200, json!({ "unsigned_txid": review.redemption.unsigned_txid })))
+    }
+}
+
+fn review_json(review: &Review) -> Value {
+    json!({
+        "valid": review.report.is_valid(),
+        "unsigned_txid": review.redemption.unsigned_txid,
+        "contract_address": review.contract.address,
+        "amount_sats": review.redemption.payment_sats(),
+        "fee_sats": review.redemption.fee_sats(),
+        "sig_all_hash": sighash::sig_all_hash(&review.redemption.pset, 0, &review.contract.cmr, &review.contract.genesis_hash)
+            .ok()
+            .map(hex::encode),
+        "report": review.report,
+    })
+}
+
+fn body<T: DeserializeOwned>(request: &Request) -> Result<T, ApiError> {
+    serde_json::from_slice(&request.body).map_err(|e| ApiError(400, format!("Invalid request body: {}", e)))
+}
+
+/// Methods served on a known path, for telling a 405 from a 404
+fn allowed_methods(segments: &[&str]) -> Option<&'static str> {
+    match segments {
+        ["contracts"] => Some("GET, POST"),
+        ["health"] | ["templates"] | ["contracts", _] | ["vouchers", _, _] | ["redemptions", _] | ["reports"] => Some("GET"),
+        ["redemptions"] | ["redemptions", _, "signature" | "submit"] | ["validate"] | ["cosign"] | ["reject"] => Some("POST"),
+        _ => None,
+    }
+}
+
+fn parse_vout(vout: &str) -> Result<u32, ApiError> {
+    vout.parse().map_err(|_| ApiError(400, format!("Invalid vout: {}", vout)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::{ElementsRPC, HalWrapper, Store, TemplateRegistry};
+    use std::sync::Arc;
+
+    fn api() -> Api {
+        let dir = std::env::temp_dir().join(format!("partnerfy-api-test-{}", std::process::id()));
+        let base = Workflow::new(
+            "api",
+            Arc::new(ElementsRPC::new(Settings::default()).unwrap()),
+            Arc::new(HalWrapper::new(None)),
+            Arc::new(TemplateRegistry::builtin()),
+            Arc::new(Store::new(dir.join("state.json"))),
+        );
+        Api::new(base, "secret".to_string())
+    }
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: vec![("Authorization".to_string(), "Bearer secret".to_string())],
+            body: body.as_bytes().to_vec(),
+            ..Request::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_and_rejects_bad_requests() {
+        let api = api();
+        let (status, templates) = api.handle(&request("GET", "/templates", "")).await;
+        assert_eq!(status, 200);
+        assert!(templates.as_array().is_some_and(|t| t.iter().any(|t| t["id"] == "cov_p2ms")));
+
+        let (status, error) = api.handle(&request("POST", "/validate", "{}")).await;
+        assert_eq!(status, 400);
+        assert!(error["error"].as_str().unwrap().contains("Invalid request body"));
+
+        let (status, _) = api.handle(&request("GET", "/vouchers/aa/x", "")).await;
+        assert_eq!(status, 400);
+
+        let (status, _) = api.handle(&request("GET", "/redemptions/nope", "")).await;
+        assert_eq!(status, 404);
+        let (status, _) = api.handle(&request("GET", "/nope", "")).await;
+        assert_eq!(status, 404);
+        let (status, error) = api.handle(&request("DELETE", "/contracts", "")).await;
+        assert_eq!(status, 405);
+        assert!(error["error"].as_str().unwrap().contains("GET, POST"));
+        let (status, _) = api.handle(&request("GET", "/validate", "")).await;
+        assert_eq!(status, 405);
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_bearer_token() {
+        let api = api();
+        let mut unauthenticated = request("GET", "/templates", "");
+        unauthenticated.headers.clear();
+        assert_eq!(api.handle(&unauthenticated).await.0, 401);
+
+        for wrong in ["Bearer secre", "Bearer secret2", "bearer secret", "secret"] {
+            let mut request = request("GET", "/templates", "");
+            request.headers = vec![("Authorization".to_string(), wrong.to_string())];
+            assert_eq!(api.handle(&request).await.0, 401, "{}", wrong);
+        }
+
+        let mut request = request("GET", "/templates", "");
+        request.headers = vec![("authorization".to_string(), "Bearer secret".to_string())];
+        assert_eq!(api.handle(&request).await.0, 200);
+    }
+}
@@ -0,0 +1,361 @@
+//! Append-only audit log of contract and signing operations
+//!
+//! Every compile, contract creation, funding, PSET, signature, finalization and broadcast
+//! is appended as one JSON line to `audit.jsonl` next to the state file. Entries carry
+//! SHA-256 hashes of what was produced and, for signatures, the public key that signed
+//! (never the private key). Each entry commits to the previous one through `prev_hash`,
+//! so edits and deletions show up in [`verify`].
+
+use super::platform;
+use super::store::Store;
+use anyhow::{Context, Result};
+use base64::Engine;
+use elements::hashes::{sha256, Hash};
+use elements::pset::PartiallySignedTransaction;
+use elements::Transaction;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A `.simf` source was compiled
+    Compiled {
+        template_id: String,
+        simf_path: String,
+        source_sha256: String,
+        program_sha256: String,
+    },
+    /// A contract address was derived and recorded
+    ContractCreated {
+        template_id: String,
+        address: String,
+        cmr: String,
+        program_sha256: String,
+        internal_key: String,
+    },
+    /// The contract address was funded
+    Funded {
+        address: String,
+        txid: String,
+        vout: u32,
+        amount_sats: u64,
+    },
+    /// A PSET spending a contract was created
+    PsetCreated {
+        unsigned_txid: String,
+        pset_sha256: String,
+        destination: String,
+        amount_sats: u64,
+        fee_sats: u64,
+    },
+    /// Input 0 of a PSET was signed with the keys of `pubkeys`
+    Signed {
+        unsigned_txid: String,
+        pset_sha256: String,
+        pubkeys: Vec<String>,
+    },
+    /// A PSET was finalized into a transaction
+    Finalized {
+        txid: String,
+        tx_sha256: String,
+        refund: bool,
+    },
+    /// A transaction was broadcast
+    Broadcast { txid: String },
+    /// A step failed after its input was accepted
+    Failed { step: String, error: String },
+}
+
+impl AuditEvent {
+    /// Event name, as written in the log
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditEvent::Compiled { .. } => "compiled",
+            AuditEvent::ContractCreated { .. } => "contract_created",
+            AuditEvent::Funded { .. } => "funded",
+            AuditEvent::PsetCreated { .. } => "pset_created",
+            AuditEvent::Signed { .. } => "signed",
+            AuditEvent::Finalized { .. } => "finalized",
+            AuditEvent::Broadcast { .. } => "broadcast",
+            AuditEvent::Failed { .. } => "failed",
+        }
+    }
+
+    /// Every event name, for filters
+    pub const KINDS: [&'static str; 8] = [
+        "compiled",
+        "contract_created",
+        "funded",
+        "pset_created",
+        "signed",
+        "finalized",
+        "broadcast",
+        "failed",
+    ];
+}
+
+/// One line of the log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix time in seconds
+    pub timestamp: u64,
+    /// Workflow name, or the page or tool for operations outside a workflow
+    pub source: String,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+    /// `hash` of the previous entry (empty for the first)
+    pub prev_hash: String,
+    /// SHA-256 of this entry serialized with an empty `hash`
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let unhashed = AuditEntry { hash: String::new(), ..self.clone() };
+        sha256_hex(serde_json::to_string(&unhashed).unwrap_or_default().as_bytes())
+    }
+}
+
+/// Which entries to show
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditFilter {
+    /// Event name (see [`AuditEvent::KINDS`])
+    pub kind: Option<String>,
+    /// Substring of the source
+    pub source: String,
+    /// Earliest timestamp, inclusive
+    pub from: Option<u64>,
+    /// Latest timestamp, inclusive
+    pub to: Option<u64>,
+    /// Text anywhere in the entry (txid, address, public key, hash...)
+    pub search: String,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.kind.as_deref().is_none_or(|kind| entry.event.kind() == kind)
+            && (self.source.trim().is_empty() || entry.source.contains(self.source.trim()))
+            && self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+            && (self.search.trim().is_empty()
+                || serde_json::to_string(entry).is_ok_and(|json| json.contains(self.search.trim())))
+    }
+}
+
+/// The log file
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// `audit.jsonl` next to the store's state file
+    pub fn for_store(store: &Store) -> Self {
+        Self::new(store.path().with_file_name("audit.jsonl"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every entry, oldest first
+    pub fn entries(&self) -> Result<Vec<AuditEntry>> {
+        let Some(content) = platform::read_file(&self.path)? else {
+            return Ok(Vec::new());
+        };
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid audit entry on line {} of {}", i + 1, self.path.display()))
+            })
+            .collect()
+    }
+
+    /// Append an entry for `event`, chained to the last one
+    ///
+    /// The log's file lock is held from reading the last hash until the entry is written,
+    /// so processes appending at the same time cannot fork the chain.
+    pub fn append(&self, source: &str, event: AuditEvent) -> Result<AuditEntry> {
+        platform::with_lock(&self.path, || {
+            let prev_hash = self.entries()?.last().map(|e| e.hash.clone()).unwrap_or_default();
+            let mut entry = AuditEntry {
+                timestamp: platform::now_secs(),
+                source: source.to_string(),
+                event,
+                prev_hash,
+                hash: String::new(),
+            };
+            entry.hash = entry.compute_hash();
+            platform::append_line(&self.path, &serde_json::to_string(&entry)?)?;
+            Ok(entry)
+        })
+    }
+}
+
+/// Append to the store's audit log, logging instead of failing the operation
+pub fn record(store: &Store, source: &str, event: AuditEvent) {
+    if let Err(e) = AuditLog::for_store(store).append(source, event) {
+        tracing::warn!("Failed to write the audit log for {}: {}", source, e);
+    }
+}
+
+/// Check the hash chain; returns one line per broken entry
+pub fn verify(entries: &[AuditEntry]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut prev = String::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.prev_hash != prev {
+            problems.push(format!("Entry {}: does not follow entry {} (removed or reordered entries)", i + 1, i));
+        }
+        if entry.compute_hash() != entry.hash {
+            problems.push(format!("Entry {}: contents do not match its hash (edited)", i + 1));
+        }
+        prev = entry.hash.clone();
+    }
+    problems
+}
+
+/// `YYYY-MM-DD HH:MM:SS` (UTC) for a Unix timestamp
+pub fn format_time(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, m, d, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Unix timestamp of midnight (UTC) at the start of a `YYYY-MM-DD` date
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: u32 = parts.next()?.parse().ok()?;
+    let d: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    // Days from 1970-01-01 (Howard Hinnant's days_from_civil)
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days).ok().map(|days| days * 86_400)
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(sha256::Hash::hash(data).to_byte_array())
+}
+
+/// Txid of the transaction a PSET describes (without witnesses), if it decodes
+pub fn pset_txid(pset_base64: &str) -> String {
+    base64::engine::general_purpose::STANDARD
+        .decode(pset_base64.trim())
+        .ok()
+        .and_then(|bytes| elements::encode::deserialize::<PartiallySignedTransaction>(&bytes).ok())
+        .and_then(|pset| pset.extract_tx().ok())
+        .map(|tx| tx.txid().to_string())
+        .unwrap_or_default()
+}
+
+/// Txid of a raw transaction, if it decodes
+pub fn tx_txid(tx_hex: &str) -> String {
+    hex::decode(tx_hex.trim())
+        .ok()
+        .and_then(|bytes| elements::encode::deserialize::<Transaction>(&bytes).ok())
+        .map(|tx| tx.txid().to_string())
+        .unwrap_or_default()
+}
+
+/// [`AuditEvent::Signed`] for a PSET signed by `pubkeys`
+pub fn signed(pset_base64: &str, pubkeys: Vec<String>) -> AuditEvent {
+    AuditEvent::Signed {
+        unsigned_txid: pset_txid(pset_base64),
+        pset_sha256: sha256_hex(pset_base64.trim().as_bytes()),
+        pubkeys,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_detects_edits_and_removals() {
+        let dir = std::env::temp_dir().join(format!("partnerfy-audit-test-{}", std::process::id()));
+        let log = AuditLog::new(dir.join("audit.jsonl"));
+        let _ = std::fs::remove_file(log.path());
+
+        log.append("wf", AuditEvent::Broadcast { txid: "aa".repeat(32) }).unwrap();
+        log.append("wf", signed("not a pset", vec!["02".repeat(32)])).unwrap();
+        log.append("other", AuditEvent::Failed { step: "compile".into(), error: "simc not found".into() }).unwrap();
+        let entries = log.entries().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(verify(&entries).is_empty());
+
+        let filter = AuditFilter { kind: Some("signed".into()), ..Default::default() };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 1);
+        let filter = AuditFilter { source: "wf".into(), search: "aaaa".into(), ..Default::default() };
+        assert_eq!(entries.iter().filter(|e| filter.matches(e)).count(), 1);
+
+        let mut edited = entries.clone();
+        edited[1].source = "someone else".into();
+        assert_eq!(verify(&edited).len(), 1);
+        let removed = vec![entries[0].clone(), entries[2].clone()];
+        assert_eq!(verify(&removed).len(), 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+
+        let day = parse_date("2026-10-18").unwrap();
+        assert_eq!(format_time(day + 3_723), "2026-10-18 01:02:03");
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2026-13-01"), None);
+    }
+
+    #[test]
+    fn concurrent_appends_keep_one_chain() {
+        let dir = std::env::temp_dir().join(format!("partnerfy-audit-concurrent-{}", std::process::id()));
+        let path = dir.join("audit.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let log = AuditLog::new(path.clone());
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        log.append(&format!("writer-{}", writer), AuditEvent::Broadcast { txid: "bb".repeat(32) }).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let entries = AuditLog::new(path).entries().unwrap();
+        assert_eq!(entries.len(), 80);
+        assert!(verify(&entries).is_empty(), "{:?}", verify(&entries));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
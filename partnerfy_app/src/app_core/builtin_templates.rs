@@ -0,0 +1,604 @@
+//! Built-in covenant templates
+//!
+//! `p2ms` is the plain 2-of-3 multisig from `p2ms.simf`; `cov_p2ms` is the voucher
+//! covenant from `cov_p2ms.simf` with its optional spending cap, oracle attestation
+//! and promoter refund branches. Both generate the same source the repo files contain
+//! when only the pubkeys are set.
+
+use crate::app_core::models::RefundLock;
+use crate::app_core::templates::{
+    Builtin, CovenantTemplate, OutputRole, ParamKind, RefundParams, SpendFlow, TemplateParam,
+    TemplateValues, WitnessField,
+};
+use anyhow::Result;
+
+/// ID of the plain multisig template
+pub const P2MS_ID: &str = "p2ms";
+/// ID of the voucher covenant template
+pub const COV_P2MS_ID: &str = "cov_p2ms";
+
+/// SimplicityHL witness type of the multisig signatures
+pub const MAYBE_SIGS_TYPE: &str = "[Option<Signature>; 3]";
+/// SimplicityHL witness type of `SPEND_PATH` when the refund branch is enabled
+pub const SPEND_PATH_TYPE: &str = "Either<[Option<Signature>; 3], Signature>";
+
+/// All built-in templates
+pub fn all() -> Vec<CovenantTemplate> {
+    vec![p2ms(), cov_p2ms()]
+}
+
+fn param(name: &str, label: &str, kind: ParamKind, description: &str) -> TemplateParam {
+    TemplateParam {
+        name: name.to_string(),
+        label: label.to_string(),
+        kind,
+        description: description.to_string(),
+        required: true,
+        default: None,
+        placeholder: None,
+        group: None,
+    }
+}
+
+fn participant_params() -> Vec<TemplateParam> {
+    let placeholders = [
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+    ];
+    let mut params: Vec<TemplateParam> = placeholders
+        .iter()
+        .enumerate()
+        .map(|(i, placeholder)| TemplateParam {
+            placeholder: Some(placeholder.to_string()),
+            ..param(
+                &format!("participant_{}", i + 1),
+                &format!("Public Key {} (Participant {})", i + 1, i + 1),
+                ParamKind::Pubkey,
+                "32-byte public key in hex format (64 characters)",
+            )
+        })
+        .collect();
+    params.push(TemplateParam {
+        default: Some("2".to_string()),
+        ..param(
+            "threshold",
+            "Required Signatures",
+            ParamKind::Threshold { max: 3 },
+            "Number of participant signatures needed to spend",
+        )
+    });
+    params
+}
+
+fn witness(name: &str, ty: &str, description: &str) -> WitnessField {
+    WitnessField {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        description: description.to_string(),
+        when: None,
+        unless: None,
+    }
+}
+
+fn p2ms() -> CovenantTemplate {
+    CovenantTemplate {
+        id: P2MS_ID.to_string(),
+        name: "Pay to Multisig (P2MS)".to_string(),
+        description: "The coins move if enough of the three participants sign.".to_string(),
+        file_name: "p2ms.simf".to_string(),
+        params: participant_params(),
+        witness: vec![witness(
+            "MAYBE_SIGS",
+            MAYBE_SIGS_TYPE,
+            "Participant signatures over sig_all_hash",
+        )],
+        spend_flow: SpendFlow {
+            signers: 3,
+            threshold_param: Some("threshold".to_string()),
+            outputs: vec![OutputRole::Payment, OutputRole::Fee],
+            payment_cap_param: None,
+            oracle_param: None,
+            refund: None,
+        },
+        source: String::new(),
+        source_file: None,
+        builtin: Some(Builtin::P2ms),
+    }
+}
+
+fn cov_p2ms() -> CovenantTemplate {
+    let mut params = participant_params();
+    params.push(TemplateParam {
+        required: false,
+        placeholder: Some("Leave empty for no cap".to_string()),
+        group: Some("Spending Cap".to_string()),
+        ..param(
+            "max_payment",
+            "Maximum Payment per Redemption (L-BTC)",
+            ParamKind::Amount,
+            "Caps the amount paid to Output 0 in a single spend, enforced with jet::output_amount",
+        )
+    });
+    params.push(TemplateParam {
+        required: false,
+        placeholder: Some("X-only public key of the oracle signer".to_string()),
+        group: Some("Oracle Attestation".to_string()),
+        ..param(
+            "oracle_pubkey",
+            "Oracle Public Key",
+            ParamKind::Pubkey,
+            "When set, the partner's oracle must sign the invoice paid by Output 0 before funds can move",
+        )
+    });
+    params.push(TemplateParam {
+        required: false,
+        group: Some("Promoter Refund".to_string()),
+        placeholder: Some("e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13".to_string()),
+        ..param(
+            "promoter_pubkey",
+            "Promoter Public Key",
+            ParamKind::Pubkey,
+            "After the refund lock, the promoter alone can sweep vouchers that were never redeemed",
+        )
+    });
+    params.push(TemplateParam {
+        required: false,
+        group: Some("Promoter Refund".to_string()),
+        ..param(
+            "refund_lock_height",
+            "Refund Lock (Block Height)",
+            ParamKind::BlockHeight,
+            "Enforced with jet::check_lock_height. Set either a height or a time.",
+        )
+    });
+    params.push(TemplateParam {
+        required: false,
+        group: Some("Promoter Refund".to_string()),
+        ..param(
+            "refund_lock_time",
+            "Refund Lock (UNIX Time)",
+            ParamKind::Timestamp,
+            "Enforced with jet::check_lock_time. The refund transaction's nLockTime must reach this value.",
+        )
+    });
+
+    CovenantTemplate {
+        id: COV_P2MS_ID.to_string(),
+        name: "Voucher Covenant (P2MS with Covenant)".to_string(),
+        description: "Multisig covenant that enforces payment, recursive change and fee outputs.".to_string(),
+        file_name: "cov_p2ms.simf".to_string(),
+        params,
+        witness: vec![
+            WitnessField {
+                unless: Some("promoter_pubkey".to_string()),
+                ..witness("MAYBE_SIGS", MAYBE_SIGS_TYPE, "Participant signatures over sig_all_hash")
+            },
+            WitnessField {
+                when: Some("promoter_pubkey".to_string()),
+                ..witness(
+                    "SPEND_PATH",
+                    SPEND_PATH_TYPE,
+                    "Left(participant signatures) to redeem, Right(promoter signature) to reclaim",
+                )
+            },
+            WitnessField {
+                when: Some("oracle_pubkey".to_string()),
+                ..witness("INVOICE_HASH", "u256", "SHA256 of the partner invoice ID")
+            },
+            WitnessField {
+                when: Some("oracle_pubkey".to_string()),
+                ..witness("ORACLE_SIG", "Signature", "Oracle signature over the invoice, payment output and spent voucher UTXO")
+            },
+        ],
+        spend_flow: SpendFlow {
+            signers: 3,
+            threshold_param: Some("threshold".to_string()),
+            outputs: vec![OutputRole::Payment, OutputRole::CovenantChange, OutputRole::Fee],
+            payment_cap_param: Some("max_payment".to_string()),
+            oracle_param: Some("oracle_pubkey".to_string()),
+            refund: Some(RefundParams {
+                pubkey_param: "promoter_pubkey".to_string(),
+                height_param: "refund_lock_height".to_string(),
+                time_param: "refund_lock_time".to_string(),
+            }),
+        },
+        source: String::new(),
+        source_file: None,
+        builtin: Some(Builtin::CovP2ms),
+    }
+}
+
+/// Read the three participant keys and threshold from validated values
+fn multisig_params(values: &TemplateValues) -> Result<([String; 3], u8)> {
+    let key = |name: &str| {
+        values
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Missing {}", name))
+    };
+    let threshold = values
+        .get("threshold")
+        .map(|t| t.parse::<u8>())
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Invalid threshold: {}", e))?
+        .unwrap_or(2);
+    Ok((
+        [key("participant_1")?, key("participant_2")?, key("participant_3")?],
+        threshold,
+    ))
+}
+
+/// Generate p2ms.simf from validated template values
+pub fn p2ms_source(values: &TemplateValues) -> Result<String> {
+    let (pks, threshold) = multisig_params(values)?;
+    Ok(format!(
+        r#"/*
+ * PAY TO MULTISIG
+ *
+ * The coins move if {threshold} of 3 people agree to move them. These people provide
+ * their signatures, of which exactly {threshold} are required.
+ *
+ * https://docs.ivylang.org/bitcoin/language/ExampleContracts.html#lockwithmultisig
+ */
+fn not(bit: bool) -> bool {{
+    <u1>::into(jet::complement_1(<bool>::into(bit)))
+}}
+
+fn checksig(pk: Pubkey, sig: Signature) {{
+    let msg: u256 = jet::sig_all_hash();
+    jet::bip_0340_verify((pk, msg), sig);
+}}
+
+fn checksig_add(counter: u8, pk: Pubkey, maybe_sig: Option<Signature>) -> u8 {{
+    match maybe_sig {{
+        Some(sig: Signature) => {{
+            checksig(pk, sig);
+            let (carry, new_counter): (bool, u8) = jet::increment_8(counter);
+            assert!(not(carry));
+            new_counter
+        }}
+        None => counter,
+    }}
+}}
+
+fn check{threshold}of3multisig(pks: [Pubkey; 3], maybe_sigs: [Option<Signature>; 3]) {{
+    let [pk1, pk2, pk3]: [Pubkey; 3] = pks;
+    let [sig1, sig2, sig3]: [Option<Signature>; 3] = maybe_sigs;
+
+    let counter1: u8 = checksig_add(0, pk1, sig1);
+    let counter2: u8 = checksig_add(counter1, pk2, sig2);
+    let counter3: u8 = checksig_add(counter2, pk3, sig3);
+
+    let threshold: u8 = {threshold};
+    assert!(jet::eq_8(counter3, threshold));
+}}
+
+fn main() {{
+    let pks: [Pubkey; 3] = [
+        0x{pk1}, // Participant 1
+        0x{pk2}, // Participant 2
+        0x{pk3}, // Participant 3
+    ];
+    check{threshold}of3multisig(pks, witness::MAYBE_SIGS);
+}}
+"#,
+        threshold = threshold,
+        pk1 = pks[0],
+        pk2 = pks[1],
+        pk3 = pks[2],
+    ))
+}
+
+/// Promoter refund branch parameters for the generated covenant
+struct RefundPath {
+    promoter_pk: String,
+    lock: RefundLock,
+}
+
+/// Parameters of the generated voucher covenant
+struct CovenantParams {
+    pks: [String; 3],
+    threshold: u8,
+    /// Time-locked promoter refund branch
+    refund: Option<RefundPath>,
+    /// Oracle key whose attestation is required for redemption
+    oracle_pk: Option<String>,
+    /// Maximum amount paid to output 0 per redemption
+    max_payment_sats: Option<u64>,
+}
+
+/// Generate cov_p2ms.simf from validated template values
+pub fn cov_p2ms_source(values: &TemplateValues) -> Result<String> {
+    let (pks, threshold) = multisig_params(values)?;
+
+    let lock = match (values.get("refund_lock_height"), values.get("refund_lock_time")) {
+        (Some(h), _) => Some(RefundLock::Height(h.parse()?)),
+        (None, Some(t)) => Some(RefundLock::Time(t.parse()?)),
+        (None, None) => None,
+    };
+    let refund = match (values.get("promoter_pubkey"), lock) {
+        (Some(pk), Some(lock)) => Some(RefundPath {
+            promoter_pk: pk.clone(),
+            lock,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(anyhow::anyhow!(
+                "The refund path needs both a promoter public key and a refund lock (block height or time)"
+            ))
+        }
+    };
+
+    let max_payment_sats = values
+        .get("max_payment")
+        .map(|sats| sats.parse::<u64>())
+        .transpose()?;
+
+    Ok(voucher_source(&CovenantParams {
+        pks,
+        threshold,
+        refund,
+        oracle_pk: values.get("oracle_pubkey").cloned(),
+        max_payment_sats,
+    }))
+}
+
+/// Generate the cov_p2ms.simf source
+///
+/// Without a refund path the multisig signatures come from `witness::MAYBE_SIGS`.
+/// With one, `witness::SPEND_PATH` selects between the multisig branch (Left) and
+/// the promoter branch (Right), which skips the covenant structure checks.
+/// With an oracle key, the multisig branch also verifies `witness::ORACLE_SIG` over
+/// `SHA256(witness::INVOICE_HASH || output 0 script hash || output 0 amount || spent outpoint)`.
+fn voucher_source(params: &CovenantParams) -> String {
+    let mut source = format!(
+        r#"/*
+ * P2MS COVENANT
+ *
+ * A {threshold}-of-3 multisig covenant that enforces three outputs:
+ * - Output 0: P2PK to any of the multisig public keys (payment)
+ * - Output 1: Same P2MS covenant script (change/recursive)
+ * - Output 2: Fee output
+"#,
+        threshold = params.threshold
+    );
+    if let Some(cap) = params.max_payment_sats {
+        source.push_str(&format!(" *\n * Output 0 may receive at most {} sats per redemption.\n", cap));
+    }
+    if params.oracle_pk.is_some() {
+        source.push_str(" *\n * Redemption requires an oracle attestation of the invoice paid by output 0.\n");
+    }
+    if let Some(r) = &params.refund {
+        source.push_str(&format!(
+            " *\n * After {} the promoter alone can sweep the UTXO.\n",
+            r.lock
+        ));
+    }
+    source.push_str(&format!(
+        r#" */
+fn not(bit: bool) -> bool {{
+    <u1>::into(jet::complement_1(<bool>::into(bit)))
+}}
+
+fn checksig(pk: Pubkey, sig: Signature) {{
+    let msg: u256 = jet::sig_all_hash();
+    jet::bip_0340_verify((pk, msg), sig);
+}}
+
+fn checksig_add(counter: u8, pk: Pubkey, maybe_sig: Option<Signature>) -> u8 {{
+    match maybe_sig {{
+        Some(sig: Signature) => {{
+            checksig(pk, sig);
+            let (carry, new_counter): (bool, u8) = jet::increment_8(counter);
+            assert!(not(carry));
+            new_counter
+        }}
+        None => counter,
+    }}
+}}
+
+fn check{threshold}of3multisig(pks: [Pubkey; 3], maybe_sigs: [Option<Signature>; 3]) {{
+    let [pk1, pk2, pk3]: [Pubkey; 3] = pks;
+    let [sig1, sig2, sig3]: [Option<Signature>; 3] = maybe_sigs;
+    let counter1: u8 = checksig_add(0, pk1, sig1);
+    let counter2: u8 = checksig_add(counter1, pk2, sig2);
+    let counter3: u8 = checksig_add(counter2, pk3, sig3);
+    let threshold: u8 = {threshold};
+    assert!(jet::eq_8(counter3, threshold));
+}}
+
+// Enforce the covenant structure with three outputs
+fn covenant_structure() {{
+    assert!(jet::eq_32(jet::num_outputs(), 3));
+    
+    // Output 1: Must be the same script (recursive covenant)
+    let this_script_hash: u256 = jet::current_script_hash();
+    let output_script_hash: u256 = unwrap(jet::output_script_hash(1));
+    assert!(jet::eq_256(this_script_hash, output_script_hash));
+    
+    // Output 2: Must be fee output
+    assert!(unwrap(jet::output_is_fee(2)));
+}}
+"#,
+        threshold = params.threshold
+    ));
+
+    if params.max_payment_sats.is_some() {
+        source.push_str(
+            r#"
+// Per-redemption spending cap: output 0 must carry an explicit amount of at most max_payment
+fn check_payment_cap(max_payment: u64) {
+    let (_, amount): (Asset1, Amount1) = unwrap(jet::output_amount(0));
+    let payment: u64 = unwrap_right::<(u1, u256)>(amount);
+    assert!(jet::le_64(payment, max_payment));
+}
+"#,
+        );
+    }
+
+    if params.oracle_pk.is_some() {
+        source.push_str(
+            r#"
+// Oracle attestation: the oracle signs SHA256(invoice hash || payment output script hash
+// || payment amount || spent txid || spent vout), binding it to this one redemption
+fn check_oracle_attestation(oracle_pk: Pubkey, invoice_hash: u256, oracle_sig: Signature) {
+    let payment_script_hash: u256 = unwrap(jet::output_script_hash(0));
+    let (_, amount): (Asset1, Amount1) = unwrap(jet::output_amount(0));
+    let payment: u64 = unwrap_right::<(u1, u256)>(amount);
+    let (prev_txid, prev_vout): (u256, u32) = jet::current_prev_outpoint();
+    let ctx: Ctx8 = jet::sha_256_ctx_8_init();
+    let ctx: Ctx8 = jet::sha_256_ctx_8_add_32(ctx, invoice_hash);
+    let ctx: Ctx8 = jet::sha_256_ctx_8_add_32(ctx, payment_script_hash);
+    let ctx: Ctx8 = jet::sha_256_ctx_8_add_8(ctx, payment);
+    let ctx: Ctx8 = jet::sha_256_ctx_8_add_32(ctx, prev_txid);
+    let ctx: Ctx8 = jet::sha_256_ctx_8_add_4(ctx, prev_vout);
+    let msg: u256 = jet::sha_256_ctx_8_finalize(ctx);
+    jet::bip_0340_verify((oracle_pk, msg), oracle_sig);
+}
+"#,
+        );
+    }
+
+    if let Some(r) = &params.refund {
+        let lock_check = match r.lock {
+            RefundLock::Height(h) => format!("jet::check_lock_height({})", h),
+            RefundLock::Time(t) => format!("jet::check_lock_time({})", t),
+        };
+        source.push_str(&format!(
+            r#"
+// Promoter refund: only valid once the transaction's lock time reaches the expiry
+fn promoter_refund(promoter_pk: Pubkey, promoter_sig: Signature) {{
+    {};
+    checksig(promoter_pk, promoter_sig);
+}}
+"#,
+            lock_check
+        ));
+    }
+
+    // Body of the redemption path, indented for its position inside main
+    let redemption_body = |indent: &str, sigs: &str| {
+        let mut body = format!(
+            "{i}// Verify {t}-of-3 multisig authorization\n{i}check{t}of3multisig(pks, {sigs});\n{i}\n{i}// Enforce covenant structure\n{i}covenant_structure();\n",
+            i = indent,
+            t = params.threshold,
+            sigs = sigs
+        );
+        if let Some(cap) = params.max_payment_sats {
+            body.push_str(&format!(
+                "{i}\n{i}// Cap the payment to output 0\n{i}check_payment_cap({cap});\n",
+                i = indent,
+                cap = cap
+            ));
+        }
+        if params.oracle_pk.is_some() {
+            body.push_str(&format!(
+                "{i}\n{i}// Require the oracle's attestation of the purchase\n{i}check_oracle_attestation(oracle_pk, witness::INVOICE_HASH, witness::ORACLE_SIG);\n",
+                i = indent
+            ));
+        }
+        body
+    };
+
+    source.push_str(&format!(
+        r#"
+fn main() {{
+    let pks: [Pubkey; 3] = [
+        0x{}, // Participant 1
+        0x{}, // Participant 2
+        0x{}, // Participant 3
+    ];
+"#,
+        params.pks[0], params.pks[1], params.pks[2]
+    ));
+    if let Some(oracle_pk) = &params.oracle_pk {
+        source.push_str(&format!("    let oracle_pk: Pubkey = 0x{};\n", oracle_pk));
+    }
+
+    match &params.refund {
+        None => {
+            source.push_str("    \n");
+            source.push_str(&redemption_body("    ", "witness::MAYBE_SIGS"));
+        }
+        Some(r) => {
+            source.push_str(&format!(
+                "    let promoter_pk: Pubkey = 0x{};\n    \n    match witness::SPEND_PATH {{\n        Left(maybe_sigs: [Option<Signature>; 3]) => {{\n{}        }}\n        Right(promoter_sig: Signature) => promoter_refund(promoter_pk, promoter_sig),\n    }}\n",
+                r.promoter_pk,
+                redemption_body("            ", "maybe_sigs")
+            ));
+        }
+    }
+    source.push_str("}\n");
+
+    source
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROMOTER: &str = "e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13";
+
+    fn voucher_values(extra: &[(&str, &str)]) -> TemplateValues {
+        let mut values = TemplateValues::from([
+            ("participant_1".to_string(), "11".repeat(32)),
+            ("participant_2".to_string(), "22".repeat(32)),
+            ("participant_3".to_string(), "33".repeat(32)),
+            ("threshold".to_string(), "2".to_string()),
+        ]);
+        values.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        values
+    }
+
+    #[test]
+    fn refund_lock_renders_as_height_or_time() {
+        let template = cov_p2ms();
+        let by_height = template
+            .render(&voucher_values(&[("promoter_pubkey", PROMOTER), ("refund_lock_height", "2000000")]))
+            .unwrap();
+        assert!(by_height.contains("jet::check_lock_height(2000000)"));
+        assert!(by_height.contains(&format!("let promoter_pk: Pubkey = 0x{};", PROMOTER)));
+        assert!(by_height.contains("match witness::SPEND_PATH"));
+
+        let by_time = template
+            .render(&voucher_values(&[("promoter_pubkey", PROMOTER), ("refund_lock_time", "1767225600")]))
+            .unwrap();
+        assert!(by_time.contains("jet::check_lock_time(1767225600)"));
+        assert!(!by_time.contains("check_lock_height"));
+
+        let without = template.render(&voucher_values(&[])).unwrap();
+        assert!(!without.contains("promoter_refund"));
+        assert!(without.contains("witness::MAYBE_SIGS"));
+    }
+
+    #[test]
+    fn payment_cap_is_checked_with_le_64_in_sats() {
+        let template = cov_p2ms();
+        let capped = template.render(&voucher_values(&[("max_payment", "0.0005")])).unwrap();
+        assert!(capped.contains("assert!(jet::le_64(payment, max_payment));"));
+        assert!(capped.contains("check_payment_cap(50000);"));
+
+        let uncapped = template.render(&voucher_values(&[])).unwrap();
+        assert!(!uncapped.contains("check_payment_cap"));
+    }
+
+    #[test]
+    fn refund_key_needs_exactly_one_lock() {
+        let template = cov_p2ms();
+        assert!(template.render(&voucher_values(&[])).is_ok());
+        for values in [
+            voucher_values(&[("promoter_pubkey", PROMOTER)]),
+            voucher_values(&[("refund_lock_height", "2000000")]),
+            voucher_values(&[("promoter_pubkey", PROMOTER), ("refund_lock_height", "2000000"), ("refund_lock_time", "1767225600")]),
+        ] {
+            assert!(template.render(&values).is_err(), "{:?}", values);
+        }
+        // Heights and times are told apart by the 500000000 threshold
+        assert!(template.render(&voucher_values(&[("promoter_pubkey", PROMOTER), ("refund_lock_height", "1767225600")])).is_err());
+        assert!(template.render(&voucher_values(&[("promoter_pubkey", PROMOTER), ("refund_lock_time", "2000000")])).is_err());
+
+        // The generator refuses a key without a lock even when validation is bypassed
+        assert!(cov_p2ms_source(&voucher_values(&[("promoter_pubkey", PROMOTER)])).is_err());
+    }
+}
@@ -0,0 +1,380 @@
+//! Reproducible contract records
+//!
+//! Before funding an address we want to be sure it comes from the source that was
+//! reviewed. A [`Contract`] record keeps the template and parameters, a hash of the
+//! `.simf` source, the compiler version, the CMR and the internal key (with its proof), and
+//! [`verify_contract`] recompiles the source and checks the CMR and address still match.
+
+use super::hal_wrapper::HalBackend;
+use super::internal_key::InternalKey;
+use super::models::Contract;
+use super::platform;
+use super::store::Store;
+use super::taproot::ContractOutput;
+use super::templates::{CovenantTemplate, TemplateRegistry, TemplateValues};
+use anyhow::{Context, Result};
+use elements::hashes::{sha256, Hash};
+use elements::AddressParams;
+use std::path::Path;
+
+/// SHA256 of a `.simf` source, hex encoded (same as `sha256sum file.simf`)
+pub fn source_hash(source: &str) -> String {
+    hex::encode(sha256::Hash::hash(source.as_bytes()).to_byte_array())
+}
+
+/// Extract the CMR and Liquid testnet address from `hal-simplicity simplicity info` output
+pub fn parse_covenant_info(info: &str) -> Result<(String, String)> {
+    let json: serde_json::Value = serde_json::from_str(info)
+        .with_context(|| format!("Invalid hal-simplicity info output:\n{}", info))?;
+    let cmr = json
+        .get("cmr")
+        .and_then(|v| v.as_str())
+        .context("No 'cmr' field in hal-simplicity info output")?;
+    let address = json
+        .get("liquid_testnet_address_unconf")
+        .and_then(|v| v.as_str())
+        .context("No 'liquid_testnet_address_unconf' field in hal-simplicity info output")?;
+    Ok((cmr.to_string(), address.to_string()))
+}
+
+/// Derive the contract output in Rust and cross-check it against `hal-simplicity simplicity info`
+///
+/// Returns the derived output (authoritative) and one line per disagreement with hal.
+pub fn derive_contract(program: &str, info: &str, internal_key: &InternalKey) -> Result<(ContractOutput, Vec<String>)> {
+    let output = ContractOutput::derive(program, internal_key, &AddressParams::LIQUID_TESTNET)?;
+    let (hal_cmr, hal_address) = parse_covenant_info(info)?;
+    let mismatches = output.cross_check_hal(&hal_cmr, &hal_address);
+    Ok((output, mismatches))
+}
+
+/// Confirm that a PSET input updated by hal-simplicity commits to the contract we derived
+pub fn check_pset_input(program: &str, internal_key: &InternalKey, pset: &str, index: usize) -> Result<()> {
+    ContractOutput::derive(program, internal_key, &AddressParams::LIQUID_TESTNET)?
+        .check_pset_input(pset, index, internal_key)
+}
+
+/// Build the record for a freshly created contract address
+///
+/// `source` is the `.simf` the program was compiled from, if known; without it the
+/// record can still be verified by re-rendering its template. `genesis_hash` is the
+/// chain the contract lives on, which its signatures commit to; `oracle_url` is where
+/// redemptions get their attestation (empty without an oracle).
+#[allow(clippy::too_many_arguments)]
+pub fn new_record(
+    template_id: &str,
+    params: &TemplateValues,
+    source: Option<&str>,
+    compiler_version: &str,
+    program: &str,
+    info: &str,
+    internal_key: &InternalKey,
+    genesis_hash: &str,
+    oracle_url: &str,
+) -> Result<Contract> {
+    let (output, _) = derive_contract(program, info, internal_key)?;
+    let created_at = platform::now_secs();
+
+    Ok(Contract {
+        voucher_base64: program.to_string(),
+        script_pubkey: output.script_pubkey,
+        address: output.address,
+        info: Some(info.to_string()),
+        template_id: template_id.to_string(),
+        params: params.clone(),
+        source_hash: source.map(source_hash).unwrap_or_default(),
+        compiler_version: compiler_version.to_string(),
+        cmr: output.cmr,
+        control_block: output.control_block,
+        internal_key: internal_key.clone(),
+        genesis_hash: genesis_hash.to_string(),
+        oracle_url: oracle_url.trim().to_string(),
+        created_at,
+    })
+}
+
+/// Build and persist the record for a contract created from the program compiled from `simf_path`
+///
+/// The template and parameters are only recorded when rendering them reproduces the
+/// `.simf` file byte for byte; a hand-edited file is recorded by its hash alone. If the
+/// file is missing the program was pasted in and only the program, CMR and address are kept.
+#[allow(clippy::too_many_arguments)]
+pub fn record_contract(
+    hal: &dyn HalBackend,
+    store: &Store,
+    template: Option<&CovenantTemplate>,
+    params: &TemplateValues,
+    simf_path: &str,
+    program: &str,
+    info: &str,
+    internal_key: &InternalKey,
+    genesis_hash: &str,
+    oracle_url: &str,
+) -> Result<Contract> {
+    let source = if simf_path.is_empty() {
+        None
+    } else {
+        platform::read_file(Path::new(simf_path))?
+    };
+    let from_template = match (template, &source) {
+        (Some(template), Some(source)) => template
+            .render(params)
+            .is_ok_and(|rendered| rendered == *source)
+            .then_some(template),
+        _ => None,
+    };
+    let (template_id, params) = match from_template {
+        Some(template) => (template.id.as_str(), params.clone()),
+        None => ("", TemplateValues::new()),
+    };
+    let compiler_version = hal.simc_version().unwrap_or_else(|_| "unknown".to_string());
+
+    let contract = new_record(
+        template_id,
+        &params,
+        source.as_deref(),
+        &compiler_version,
+        program,
+        info,
+        internal_key,
+        genesis_hash,
+        oracle_url,
+    )?;
+    store.save_contract(contract.clone())?;
+    Ok(contract)
+}
+
+/// One comparison made while verifying a contract
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyCheck {
+    pub name: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl VerifyCheck {
+    pub fn passed(&self) -> bool {
+        self.expected.eq_ignore_ascii_case(&self.actual)
+    }
+}
+
+/// Outcome of [`verify_contract`]
+#[derive(Debug, Clone, Default)]
+pub struct VerificationReport {
+    pub checks: Vec<VerifyCheck>,
+    /// Disagreements between the Rust derivation and hal-simplicity
+    pub hal_mismatches: Vec<String>,
+    /// Observations that do not fail verification (e.g. a different compiler version)
+    pub notes: Vec<String>,
+}
+
+impl VerificationReport {
+    /// True when every check passed
+    pub fn is_match(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|c| c.passed()) && self.hal_mismatches.is_empty()
+    }
+
+    fn check(&mut self, name: &'static str, expected: &str, actual: &str) {
+        self.checks.push(VerifyCheck {
+            name,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        });
+    }
+}
+
+impl std::fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.checks {
+            if check.passed() {
+                writeln!(f, "[OK] {}: {}", check.name, check.actual)?;
+            } else {
+                writeln!(
+                    f,
+                    "[MISMATCH] {}\n  recorded: {}\n  now:      {}",
+                    check.name, check.expected, check.actual
+                )?;
+            }
+        }
+        for mismatch in &self.hal_mismatches {
+            writeln!(f, "[MISMATCH] hal-simplicity disagrees - {}", mismatch)?;
+        }
+        for note in &self.notes {
+            writeln!(f, "Note: {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Recompile a contract and check that it still produces the recorded CMR and address
+///
+/// The source is re-rendered from the recorded template and parameters when the
+/// template is known; `simf_path`, if given, is hashed and compared as well, and is
+/// compiled instead when the template is not available.
+pub fn verify_contract(
+    hal: &dyn HalBackend,
+    registry: &TemplateRegistry,
+    contract: &Contract,
+    simf_path: Option<&str>,
+) -> Result<VerificationReport> {
+    let mut report = VerificationReport::default();
+
+    let rendered = match registry.get(&contract.template_id) {
+        Some(template) => Some(
+            template
+                .render(&contract.params)
+                .context("Failed to re-render the recorded template parameters")?,
+        ),
+        None => {
+            if !contract.template_id.is_empty() {
+                report.notes.push(format!(
+                    "Template '{}' is not installed; verifying from the .simf file only",
+                    contract.template_id
+                ));
+            }
+            None
+        }
+    };
+
+    let file_source = match simf_path.filter(|p| !p.is_empty()) {
+        Some(path) => platform::read_file(Path::new(path))?.map(|source| (path, source)),
+        None => None,
+    };
+
+    if contract.source_hash.is_empty() {
+        report
+            .notes
+            .push("The record has no source hash (program was entered without a .simf file)".to_string());
+    } else {
+        if let Some(source) = &rendered {
+            report.check("Template source hash", &contract.source_hash, &source_hash(source));
+        }
+        if let Some((_, source)) = &file_source {
+            report.check(".simf file hash", &contract.source_hash, &source_hash(source));
+        }
+    }
+
+    // Compile the template rendering if we have it, otherwise the file on disk
+    let (compile_path, is_temp) = match (&rendered, &file_source) {
+        (Some(source), _) => {
+            let path = std::env::temp_dir().join(format!(
+                "partnerfy-verify-{}.simf",
+                &source_hash(source)[..16]
+            ));
+            platform::write_file(&path, source)?;
+            (path.to_string_lossy().to_string(), true)
+        }
+        (None, Some((path, _))) => (path.to_string(), false),
+        (None, None) => {
+            return Err(anyhow::anyhow!(
+                "Nothing to recompile: template '{}' is unknown and no .simf file was given",
+                contract.template_id
+            ))
+        }
+    };
+
+    let compiled = hal.compile_simf(&compile_path);
+    if is_temp {
+        let _ = platform::remove_file(Path::new(&compile_path));
+    }
+    let program = compiled?;
+    let info = hal.get_covenant_info(&program)?;
+    let (output, hal_mismatches) = derive_contract(&program, &info, &contract.internal_key)?;
+    report.check("CMR", &contract.cmr, &output.cmr);
+    report.check("Address", &contract.address, &output.address);
+    if !contract.control_block.is_empty() {
+        report.check("Control block", &contract.control_block, &output.control_block);
+    }
+    report.hal_mismatches = hal_mismatches;
+    if program != contract.voucher_base64 {
+        report
+            .notes
+            .push("The compiled program encoding differs from the recorded one".to_string());
+    }
+
+    let key = contract.internal_key.key_hex();
+    match contract.internal_key.verify() {
+        Ok(_) => report.check("Internal key proof", &key, &key),
+        Err(e) => report.check("Internal key proof", &key, &format!("invalid ({})", e)),
+    }
+    if contract.internal_key.has_key_path() {
+        report
+            .notes
+            .push("The internal key is a real aggregate key: its signers can spend via the key path".to_string());
+    }
+
+    match hal.simc_version() {
+        Ok(version) if version != contract.compiler_version => report.notes.push(format!(
+            "Compiler version differs: recorded '{}', now '{}'",
+            contract.compiler_version, version
+        )),
+        Ok(_) => {}
+        Err(e) => report.notes.push(format!("Could not read the simc version: {}", e)),
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::fakes::FakeHal;
+    use crate::app_core::sighash::LIQUID_TESTNET_GENESIS_HASH;
+    use crate::app_core::taproot;
+
+    /// A P2MS contract recorded from a generated `.simf`, plus the path of that file
+    fn recorded(name: &str) -> (Contract, TemplateRegistry, String) {
+        let dir = std::env::temp_dir().join(format!("partnerfy-contracts-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let registry = TemplateRegistry::builtin();
+        let template = registry.get("p2ms").unwrap();
+        let params = TemplateValues::from([
+            ("participant_1".to_string(), "11".repeat(32)),
+            ("participant_2".to_string(), "22".repeat(32)),
+            ("participant_3".to_string(), "33".repeat(32)),
+            ("threshold".to_string(), "2".to_string()),
+        ]);
+        let simf_path = dir.join("p2ms.simf").display().to_string();
+        std::fs::write(&simf_path, template.render(&params).unwrap()).unwrap();
+
+        let hal = FakeHal::new();
+        let info = hal.get_covenant_info(&hal.program).unwrap();
+        let store = Store::new(dir.join("store.json"));
+        let contract = record_contract(&hal, &store, Some(template), &params, &simf_path, &hal.program, &info, &InternalKey::Nums, LIQUID_TESTNET_GENESIS_HASH, "").unwrap();
+        assert_eq!(contract.template_id, "p2ms");
+        (contract, registry, simf_path)
+    }
+
+    #[test]
+    fn matching_record_verifies() {
+        let (contract, registry, simf_path) = recorded("match");
+        let report = verify_contract(&FakeHal::new(), &registry, &contract, Some(&simf_path)).unwrap();
+        assert!(report.is_match(), "{}", report);
+        let names: Vec<_> = report.checks.iter().map(|c| c.name).collect();
+        for name in ["Template source hash", ".simf file hash", "CMR", "Address", "Internal key proof"] {
+            assert!(names.contains(&name), "{:?}", names);
+        }
+        let _ = std::fs::remove_dir_all(Path::new(&simf_path).parent().unwrap());
+    }
+
+    #[test]
+    fn tampered_records_fail() {
+        let (contract, registry, simf_path) = recorded("tampered");
+        let hal = FakeHal::new();
+
+        let tampered_cmr = Contract { cmr: "00".repeat(32), ..contract.clone() };
+        let report = verify_contract(&hal, &registry, &tampered_cmr, None).unwrap();
+        assert!(!report.is_match());
+        assert_eq!(report.checks.iter().filter(|c| !c.passed()).map(|c| c.name).collect::<Vec<_>>(), ["CMR"]);
+
+        let other = taproot::contract_address(&"11".repeat(32), &InternalKey::Nums.key_hex(), &AddressParams::LIQUID_TESTNET).unwrap();
+        let tampered_address = Contract { address: other.to_string(), ..contract.clone() };
+        let report = verify_contract(&hal, &registry, &tampered_address, None).unwrap();
+        assert_eq!(report.checks.iter().filter(|c| !c.passed()).map(|c| c.name).collect::<Vec<_>>(), ["Address"]);
+
+        // An edited .simf no longer matches the recorded source hash
+        std::fs::write(&simf_path, "fn main() {}").unwrap();
+        let report = verify_contract(&hal, &registry, &contract, Some(&simf_path)).unwrap();
+        assert_eq!(report.checks.iter().filter(|c| !c.passed()).map(|c| c.name).collect::<Vec<_>>(), [".simf file hash"]);
+        let _ = std::fs::remove_dir_all(Path::new(&simf_path).parent().unwrap());
+    }
+}
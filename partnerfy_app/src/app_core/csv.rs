@@ -0,0 +1,72 @@
+//! CSV fields for exports and imports
+//!
+//! Reports and the address book are opened in spreadsheets, and their text comes
+//! from users (partner names, template ids). A cell starting with `=`, `+`, `-` or `@`
+//! would run as a formula there, so such values are written with a leading `'`,
+//! which [`cells`] strips again on import.
+
+/// Characters that make a spreadsheet read a cell as a formula
+const FORMULA_START: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// One CSV field: formula-like text neutralised, then quoted if it holds a comma, quote or line break
+///
+/// Plain numbers (such as `-5`) are written unchanged.
+pub fn field(value: &str) -> String {
+    let value = if value.starts_with(FORMULA_START) && value.parse::<f64>().is_err() {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Split one CSV line, honouring double-quoted fields and undoing [`field`]'s formula guard
+pub fn cells(line: &str) -> Vec<String> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cells.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells
+        .into_iter()
+        .map(|c| {
+            let c = c.trim();
+            match c.strip_prefix('\'') {
+                Some(rest) if rest.starts_with(FORMULA_START) => rest.to_string(),
+                _ => c.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formulas_are_neutralised_and_round_trip() {
+        assert_eq!(field("Cafe"), "Cafe");
+        assert_eq!(field("a,b"), "\"a,b\"");
+        assert_eq!(field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(field("+1+1"), "'+1+1");
+        assert_eq!(field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(field("-5"), "-5");
+
+        let values = ["=cmd|' /C calc'!A0", "Cafe, \"Main\"", "-2+3", "plain"];
+        let line = values.iter().map(|v| field(v)).collect::<Vec<_>>().join(",");
+        assert_eq!(cells(&line), values);
+    }
+}
@@ -0,0 +1,250 @@
+//! Promoter dashboard data
+//!
+//! Summarises the persisted [`AppState`] against the chain: the value locked in each
+//! recorded contract, the vouchers still outstanding per participant, the redemptions
+//! paid to each partner, and whether the node is synced. Voucher balances follow each
+//! voucher's covenant lineage, so partial redemptions are accounted for.
+
+use super::elements_rpc::ElementsRPC;
+use super::lineage::{self, Lineage};
+use super::models::{AppState, Contract, VoucherUTXO};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Sync status reported by `getblockchaininfo`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeStatus {
+    pub chain: String,
+    pub blocks: u64,
+    pub headers: u64,
+    pub verification_progress: f64,
+    pub initial_block_download: bool,
+}
+
+impl NodeStatus {
+    pub fn is_synced(&self) -> bool {
+        !self.initial_block_download && self.blocks >= self.headers
+    }
+}
+
+/// A recorded contract and the value it currently holds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContractRow {
+    pub address: String,
+    pub template_id: String,
+    pub created_at: u64,
+    /// Value locked at the address; `Err` when the balance could not be fetched
+    pub locked_sats: Result<u64, String>,
+}
+
+/// Vouchers held by one participant
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParticipantRow {
+    pub pubkey: String,
+    /// Funding outpoints of the participant's vouchers
+    pub vouchers: Vec<(String, u32)>,
+    /// Balance left in the participant's vouchers
+    pub outstanding_sats: u64,
+    pub redeemed_sats: u64,
+    /// Vouchers whose lineage could not be followed
+    pub errors: Vec<String>,
+}
+
+/// Redemptions paid to one partner
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PartnerRow {
+    pub name: String,
+    pub address: String,
+    pub redemptions: usize,
+    pub redeemed_sats: u64,
+}
+
+/// Everything the promoter dashboard shows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dashboard {
+    pub node: Result<NodeStatus, String>,
+    pub contracts: Vec<ContractRow>,
+    pub participants: Vec<ParticipantRow>,
+    pub partners: Vec<PartnerRow>,
+}
+
+impl Dashboard {
+    /// Value locked across all contracts that could be looked up
+    pub fn total_locked_sats(&self) -> u64 {
+        self.contracts.iter().filter_map(|c| c.locked_sats.as_ref().ok()).sum()
+    }
+}
+
+/// Build the dashboard for the given state
+pub async fn load(rpc: &ElementsRPC, state: &AppState) -> Dashboard {
+    let node = node_status(rpc).await;
+
+    let mut contracts = Vec::with_capacity(state.contracts.len());
+    for contract in &state.contracts {
+        contracts.push(contract_row(rpc, contract).await);
+    }
+
+    let mut lineages = Vec::new();
+    for voucher in vouchers(state) {
+        let lineage = lineage::follow_lineage(rpc, &voucher.txid, voucher.vout)
+            .await
+            .map_err(|e| e.to_string());
+        lineages.push((voucher, lineage));
+    }
+    let (participants, partners) = aggregate(state, &lineages);
+
+    Dashboard {
+        node,
+        contracts,
+        participants,
+        partners,
+    }
+}
+
+/// Sync status of the node, or why it could not be asked
+pub async fn node_status(rpc: &ElementsRPC) -> Result<NodeStatus, String> {
+    let info = rpc.get_blockchain_info().await.map_err(|e| e.to_string())?;
+    Ok(NodeStatus {
+        chain: info["chain"].as_str().unwrap_or("unknown").to_string(),
+        blocks: info["blocks"].as_u64().unwrap_or(0),
+        headers: info["headers"].as_u64().unwrap_or(0),
+        verification_progress: info["verificationprogress"].as_f64().unwrap_or(0.0),
+        initial_block_download: info["initialblockdownload"].as_bool().unwrap_or(false),
+    })
+}
+
+async fn contract_row(rpc: &ElementsRPC, contract: &Contract) -> ContractRow {
+    ContractRow {
+        address: contract.address.clone(),
+        template_id: contract.template_id.clone(),
+        created_at: contract.created_at,
+        locked_sats: rpc.get_address_balance(&contract.address).await.map_err(|e| e.to_string()),
+    }
+}
+
+/// Every voucher in the state, from the voucher list and the participants, without duplicates
+pub fn vouchers(state: &AppState) -> Vec<VoucherUTXO> {
+    let mut seen = HashSet::new();
+    state
+        .vouchers
+        .iter()
+        .chain(state.participants.iter().flat_map(|p| p.voucher_utxos.iter()))
+        .filter(|v| seen.insert((v.txid.clone(), v.vout)))
+        .cloned()
+        .collect()
+}
+
+/// Group voucher lineages by participant (voucher owner) and by paid partner
+///
+/// Payments to addresses that are not a known partner are not listed.
+pub fn aggregate(
+    state: &AppState,
+    lineages: &[(VoucherUTXO, Result<Lineage, String>)],
+) -> (Vec<ParticipantRow>, Vec<PartnerRow>) {
+    let mut participants: BTreeMap<String, ParticipantRow> = state
+        .participants
+        .iter()
+        .map(|p| (p.pubkey.clone(), ParticipantRow { pubkey: p.pubkey.clone(), ..Default::default() }))
+        .collect();
+    let mut partners: Vec<PartnerRow> = state
+        .partners
+        .iter()
+        .map(|p| PartnerRow { name: p.name.clone(), address: p.address.clone(), ..Default::default() })
+        .collect();
+
+    for (voucher, lineage) in lineages {
+        let row = participants
+            .entry(voucher.owner_pubkey.clone())
+            .or_insert_with(|| ParticipantRow { pubkey: voucher.owner_pubkey.clone(), ..Default::default() });
+        row.vouchers.push((voucher.txid.clone(), voucher.vout));
+        let lineage = match lineage {
+            Ok(lineage) => lineage,
+            Err(e) => {
+                row.errors.push(format!("{}:{}: {}", voucher.txid, voucher.vout, e));
+                continue;
+            }
+        };
+        row.outstanding_sats += lineage.remaining_sats();
+        row.redeemed_sats += lineage.paid_sats();
+
+        for payment in lineage.hops.iter().filter_map(|h| h.payment.as_ref()) {
+            let Some(address) = payment.address.as_deref() else {
+                continue;
+            };
+            if let Some(partner) = partners.iter_mut().find(|p| p.address == address) {
+                partner.redemptions += 1;
+                partner.redeemed_sats += payment.value_sats.unwrap_or(0);
+            }
+        }
+    }
+
+    (participants.into_values().collect(), partners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::history::TxStatus;
+    use crate::app_core::lineage::{LineageEnd, LineageHop, Payment};
+    use crate::app_core::models::{Participant, Partner};
+
+    fn voucher(txid: &str, owner: &str) -> VoucherUTXO {
+        VoucherUTXO {
+            txid: txid.to_string(),
+            vout: 0,
+            amount: 0.001,
+            owner_pubkey: owner.to_string(),
+            covenant_script: String::new(),
+            covenant_address: "tex1pcontract".to_string(),
+        }
+    }
+
+    fn hop(payment: Option<(&str, u64)>, remaining: u64) -> LineageHop {
+        LineageHop {
+            txid: "tx".to_string(),
+            vout: 1,
+            value_sats: Some(remaining),
+            status: TxStatus::Unconfirmed,
+            payment: payment.map(|(address, value)| Payment {
+                address: Some(address.to_string()),
+                value_sats: Some(value),
+            }),
+            fee_sats: None,
+        }
+    }
+
+    #[test]
+    fn groups_vouchers_by_participant_and_partner() {
+        let mut state = AppState::default();
+        state.partners.push(Partner { address: "tex1qcafe".to_string(), pubkey: None, name: "Cafe".to_string() });
+        state.participants.push(Participant {
+            pubkey: "alice".to_string(),
+            wallet_path: None,
+            voucher_utxos: vec![voucher("aa", "alice")],
+        });
+        state.vouchers = vec![voucher("aa", "alice"), voucher("bb", "bob")];
+
+        let vouchers = vouchers(&state);
+        assert_eq!(vouchers.len(), 2);
+
+        let open = Lineage {
+            address: None,
+            script_pubkey: String::new(),
+            hops: vec![hop(None, 100_000), hop(Some(("tex1qcafe", 30_000)), 69_900), hop(Some(("tex1qshop", 9_900)), 59_900)],
+            end: LineageEnd::Unspent,
+        };
+        let lineages = vec![(vouchers[0].clone(), Ok(open)), (vouchers[1].clone(), Err("not found".to_string()))];
+        let (participants, partners) = aggregate(&state, &lineages);
+
+        assert_eq!(participants.len(), 2);
+        assert_eq!(participants[0].pubkey, "alice");
+        assert_eq!((participants[0].outstanding_sats, participants[0].redeemed_sats), (59_900, 39_900));
+        assert_eq!(participants[1].errors.len(), 1);
+        assert_eq!(partners, vec![PartnerRow {
+            name: "Cafe".to_string(),
+            address: "tex1qcafe".to_string(),
+            redemptions: 1,
+            redeemed_sats: 30_000,
+        }]);
+    }
+}
@@ -2,13 +2,74 @@
 //! 
 //! Provides a high-level interface to elementsd JSON-RPC API using direct JSON-RPC calls
 //! and Elements-specific types from the elements crate
-
-use crate::app_core::models::Settings;
+//!
+//! PSET commands go through `elements-cli` in the `server` build. The browser build has
+//! no processes to run, so it sends the same commands over JSON-RPC (a `fetch` request).
+//!
+//! The workflow steps only see the node through [`NodeBackend`], so they can run against
+//! an in-memory node in tests.
+
+use crate::app_core::error::AppError;
+#[cfg(feature = "server")]
+use crate::app_core::error::Tool;
+use crate::app_core::models::{Settings, UtxoInfo};
 use anyhow::{Result, Context};
 use serde_json::{json, Value};
+use std::future::Future;
+#[cfg(feature = "server")]
 use tokio::process::Command;
 use reqwest;
 
+/// Blockstream Esplora API for Liquid testnet, used where elementsd has no index
+const ESPLORA_API: &str = "https://blockstream.info/liquidtestnet/api";
+/// Confirmed transactions per page of Esplora's address history
+const ESPLORA_PAGE: usize = 25;
+
+/// The node operations the contract workflow needs
+///
+/// Same arguments and results as the [`ElementsRPC`] methods of the same names.
+pub trait NodeBackend {
+    /// `getblockchaininfo`
+    fn get_blockchain_info(&self) -> impl Future<Output = Result<Value>>;
+
+    /// `getblockhash 0`: the genesis hash `sig_all_hash` commits to
+    fn get_genesis_hash(&self) -> impl Future<Output = Result<String>>;
+
+    /// `gettxout`: `null` when the output is unknown or spent
+    fn get_txout(&self, txid: &str, vout: u32) -> impl Future<Output = Result<Value>>;
+
+    /// scriptPubKey, asset and value of an unspent output
+    fn get_utxo_info(&self, txid: &str, vout: u32) -> impl Future<Output = Result<UtxoInfo>>;
+
+    /// `createpsbt`, with every input's sequence set to 0xfffffffe when `locktime` is given
+    fn create_pset_with_locktime(
+        &self,
+        inputs: &[(String, u32)],
+        outputs: &[(String, f64)],
+        fee: Option<f64>,
+        locktime: Option<u32>,
+    ) -> impl Future<Output = Result<String>>;
+
+    /// `createpsbt` without a lock time
+    fn create_pset(
+        &self,
+        inputs: &[(String, u32)],
+        outputs: &[(String, f64)],
+        fee: Option<f64>,
+    ) -> impl Future<Output = Result<String>> {
+        self.create_pset_with_locktime(inputs, outputs, fee, None)
+    }
+
+    /// `finalizepsbt`: the extracted transaction hex
+    fn finalize_pset(&self, pset: &str) -> impl Future<Output = Result<String>>;
+
+    /// `decodepsbt`
+    fn decode_pset(&self, pset: &str) -> impl Future<Output = Result<Value>>;
+
+    /// `sendrawtransaction`: the txid, or [`AppError::NodeRejected`]
+    fn send_raw_transaction(&self, hex: &str) -> impl Future<Output = Result<String>>;
+}
+
 /// Elements RPC client wrapper using direct JSON-RPC
 pub struct ElementsRPC {
     client: reqwest::Client,
@@ -20,6 +81,7 @@ impl ElementsRPC {
     /// Get the elements-cli command path
     /// Tries to find elements-cli in common locations if not in PATH
     /// Also tries "elements" as an alternative name
+    #[cfg(feature = "server")]
     fn elements_cli_cmd(&self) -> String {
         // Try both "elements-cli" and "elements" as command names
         let command_names = ["elements-cli", "elements"];
@@ -69,13 +131,8 @@ impl ElementsRPC {
 
     /// Create a new RPC client with the given settings
     pub fn new(settings: Settings) -> Result<Self> {
-        let url = format!(
-            "http://{}:{}@{}:{}",
-            settings.rpc_user,
-            settings.rpc_password,
-            settings.rpc_host,
-            settings.rpc_port
-        );
+        // Credentials go in the Authorization header: browsers refuse URLs that carry them
+        let url = format!("http://{}:{}", settings.rpc_host, settings.rpc_port);
         
         Ok(Self {
             client: reqwest::Client::new(),
@@ -95,18 +152,21 @@ impl ElementsRPC {
 
         let response = self.client
             .post(&self.url)
+            .basic_auth(&self.settings.rpc_user, Some(&self.settings.rpc_password))
             .json(&payload)
             .send()
             .await
-            .context("Failed to send RPC request")?;
+            .map_err(|e| AppError::Rpc { method: method.to_string(), code: None, message: e.to_string() })?;
 
-        let result: Value = response
-            .json()
-            .await
-            .context("Failed to parse RPC response")?;
+        let status = response.status();
+        let result: Value = response.json().await.map_err(|e| AppError::Rpc {
+            method: method.to_string(),
+            code: None,
+            message: format!("unreadable response (HTTP {}): {}", status, e),
+        })?;
 
-        if let Some(error) = result.get("error") {
-            return Err(anyhow::anyhow!("RPC error: {}", error));
+        if let Some(error) = result.get("error").filter(|e| !e.is_null()) {
+            return Err(AppError::rpc(method, error).into());
         }
 
         result.get("result")
@@ -125,13 +185,36 @@ impl ElementsRPC {
         self.call("getblockchaininfo", json!([])).await
     }
 
-    /// Get wallet balance
+    /// Hash of the node's genesis block, which identifies the chain to signatures
+    pub async fn get_genesis_hash(&self) -> Result<String> {
+        let result = self.call("getblockhash", json!([0])).await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .context("getblockhash returned no block hash")
+    }
+
+    /// Get wallet balance (L-BTC)
+    ///
+    /// Elements reports balances per asset label; the L-BTC one is `bitcoin`.
     pub async fn get_balance(&self) -> Result<f64> {
         let result = self.call("getbalance", json!([])).await?;
-        result.as_f64()
+        result
+            .as_f64()
+            .or_else(|| result.get("bitcoin").and_then(Value::as_f64))
             .ok_or_else(|| anyhow::anyhow!("Invalid balance format"))
     }
 
+    /// Asset id of L-BTC on the node's chain
+    pub async fn policy_asset(&self) -> Result<String> {
+        let labels = self.call("dumpassetlabels", json!([])).await?;
+        labels
+            .get("bitcoin")
+            .and_then(Value::as_str)
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("The node did not report the L-BTC asset id (dumpassetlabels has no \"bitcoin\" entry)"))
+    }
+
     /// Get new address
     pub async fn get_new_address(&self, label: Option<&str>) -> Result<String> {
         let params = if let Some(l) = label {
@@ -181,6 +264,28 @@ impl ElementsRPC {
             .ok_or_else(|| anyhow::anyhow!("Invalid transaction hex format"))
     }
 
+    /// Add wallet inputs, change and the fee output to a raw transaction
+    ///
+    /// Returns the funded hex and the fee in L-BTC. `options` are passed to
+    /// `fundrawtransaction` as is (e.g. `feeRate`, `changePosition`).
+    pub async fn fund_raw_transaction(&self, hex: &str, options: Value) -> Result<(String, f64)> {
+        let result = self.call("fundrawtransaction", json!([hex, options])).await?;
+        let funded = result
+            .get("hex")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Invalid fundrawtransaction response: {}", result))?;
+        let fee = result.get("fee").and_then(Value::as_f64).unwrap_or(0.0);
+        Ok((funded.to_string(), fee))
+    }
+
+    /// Blind the confidential outputs (the wallet's change) of a funded transaction
+    pub async fn blind_raw_transaction(&self, hex: &str) -> Result<String> {
+        let result = self.call("blindrawtransaction", json!([hex])).await?;
+        result.as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Invalid transaction hex format"))
+    }
+
     /// Sign raw transaction with wallet
     pub async fn sign_raw_transaction_with_wallet(&self, hex: &str) -> Result<Value> {
         let result = self.call("signrawtransactionwithwallet", json!([hex])).await?;
@@ -189,58 +294,51 @@ impl ElementsRPC {
 
     /// Send raw transaction
     /// Tries RPC first, then falls back to Blockstream API (like the script does)
+    ///
+    /// A transaction the node rejects is not retried through the API: it comes back as
+    /// [`AppError::NodeRejected`] with the node's reject code.
     pub async fn send_raw_transaction(&self, hex: &str) -> Result<String> {
         // First try RPC
-        match self.call("sendrawtransaction", json!([hex])).await {
+        let rpc_error = match self.call("sendrawtransaction", json!([hex])).await {
             Ok(result) => {
-                if let Some(txid) = result.as_str() {
-                    return Ok(txid.to_string());
-                }
+                return result
+                    .as_str()
+                    .map(str::to_string)
+                    .context("sendrawtransaction did not return a txid");
             }
-            Err(e) => {
-                // If RPC fails, try Blockstream API as fallback (like the script does)
-                // Script uses: curl -X POST "https://blockstream.info/liquidtestnet/api/tx" -d "$RAW_TX"
-                let client = reqwest::Client::new();
-                match client
-                    .post("https://blockstream.info/liquidtestnet/api/tx")
-                    .body(hex.to_string())
-                    .send()
-                    .await
-                {
-                    Ok(response) => {
-                        if response.status().is_success() {
-                            match response.text().await {
-                                Ok(txid) => {
-                                    // Blockstream API returns just the txid as text
-                                    return Ok(txid.trim().to_string());
-                                }
-                                Err(e) => {
-                                    return Err(anyhow::anyhow!(
-                                        "RPC failed and Blockstream API response parse failed: {}\n\nOriginal RPC error: {}",
-                                        e, e
-                                    ));
-                                }
-                            }
-                        } else {
-                            let status = response.status();
-                            let error_text = response.text().await.unwrap_or_default();
-                            return Err(anyhow::anyhow!(
-                                "RPC failed and Blockstream API returned error status {}: {}\n\nOriginal RPC error: {}",
-                                status, error_text, e
-                            ));
-                        }
-                    }
-                    Err(api_err) => {
-                        return Err(anyhow::anyhow!(
-                            "RPC failed and Blockstream API request failed: {}\n\nOriginal RPC error: {}",
-                            api_err, e
-                        ));
-                    }
-                }
+            Err(e) => match e.downcast::<AppError>() {
+                Ok(rejected @ AppError::NodeRejected { .. }) => return Err(rejected.into()),
+                Ok(other) => anyhow::Error::new(other),
+                Err(e) => e,
+            },
+        };
+
+        // If RPC fails, try Blockstream API as fallback (like the script does)
+        // Script uses: curl -X POST "https://blockstream.info/liquidtestnet/api/tx" -d "$RAW_TX"
+        let response = reqwest::Client::new()
+            .post(format!("{}/tx", ESPLORA_API))
+            .body(hex.to_string())
+            .send()
+            .await
+            .map_err(|api_err| {
+                anyhow::anyhow!("RPC failed and Blockstream API request failed: {}\n\nOriginal RPC error: {:#}", api_err, rpc_error)
+            })?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if status.is_success() {
+            // Blockstream API returns just the txid as text
+            return Ok(text.trim().to_string());
+        }
+        // Esplora passes the node's answer on: "sendrawtransaction RPC error: {"code":-26,...}"
+        if let Some(error) = text.find('{').and_then(|i| serde_json::from_str::<Value>(&text[i..]).ok()) {
+            if let rejected @ AppError::NodeRejected { .. } = AppError::rpc("sendrawtransaction", &error) {
+                return Err(rejected.into());
             }
         }
-        
-        Err(anyhow::anyhow!("Failed to send transaction via both RPC and Blockstream API"))
+        Err(anyhow::anyhow!(
+            "RPC failed and Blockstream API returned error status {}: {}\n\nOriginal RPC error: {:#}",
+            status, text, rpc_error
+        ))
     }
 
     /// Get transaction details
@@ -274,116 +372,58 @@ impl ElementsRPC {
         outputs: &[(String, f64)],
         fee: Option<f64>,
     ) -> Result<String> {
-        // Format inputs as JSON array string
-        let inputs_json: Vec<Value> = inputs
-            .iter()
-            .map(|(txid, vout)| {
-                json!({
-                    "txid": txid,
-                    "vout": vout
-                })
-            })
-            .collect();
-        let inputs_str = serde_json::to_string(&inputs_json)
-            .context("Failed to serialize inputs")?;
-
-        // Format outputs as JSON array string
-        // Format: [{"address_string": amount}, ...]
-        // If fee is provided, add {"fee": amount} as the last output (like the script does)
-        let mut outputs_json: Vec<Value> = outputs
-            .iter()
-            .map(|(addr, amount)| {
-                let mut output_obj = serde_json::Map::new();
-                output_obj.insert(addr.clone(), json!(amount));
-                json!(output_obj)
-            })
-            .collect();
-        
-        // Add fee output if specified (matches script: { "fee": 0.00000100 })
-        if let Some(fee_amount) = fee {
-            let mut fee_obj = serde_json::Map::new();
-            fee_obj.insert("fee".to_string(), json!(fee_amount));
-            outputs_json.push(json!(fee_obj));
-        }
-        
-        let outputs_str = serde_json::to_string(&outputs_json)
-            .context("Failed to serialize outputs")?;
+        self.create_pset_with_locktime(inputs, outputs, fee, None).await
+    }
 
-        // Call elements-cli createpsbt directly (like simc)
-        let cmd = self.elements_cli_cmd();
-        let output = match Command::new(&cmd)
-            .arg("createpsbt")
-            .arg(&inputs_str)
-            .arg(&outputs_str)
+    /// Run an elements-cli command and return its stdout
+    ///
+    /// `shown` is the command line for error messages, with long arguments shortened.
+    #[cfg(feature = "server")]
+    async fn cli(&self, args: &[&str], shown: &str) -> Result<String> {
+        let output = Command::new(self.elements_cli_cmd())
+            .args(args)
             .output()
             .await
-        {
-            Ok(o) => o,
-            Err(e) => {
-                let error_kind = e.kind();
-                let error_msg = if error_kind == std::io::ErrorKind::NotFound {
-                    format!(
-                        "elements-cli command not found at: {}\n\nTroubleshooting:\n1. Check if elements-cli is installed: which elements-cli\n2. Try finding it: find /usr /opt ~/.cargo -name 'elements-cli' 2>/dev/null\n3. If not found, install Elements from: https://github.com/ElementsProject/elements\n4. Make sure elements-cli is in your PATH or specify full path\n5. Common locations:\n   - /usr/local/bin/elements-cli\n   - /usr/bin/elements-cli\n   - ~/.cargo/bin/elements-cli\n   - /opt/elements/bin/elements-cli\n   - ~/bin/elements-cli\n\nNote: The app tried to find elements-cli but couldn't locate it.\n\nOriginal error: {}",
-                        cmd, e
-                    )
-                } else if error_kind == std::io::ErrorKind::PermissionDenied {
-                    format!(
-                        "Permission denied when executing elements-cli\n\nTroubleshooting:\n1. Check if elements-cli has execute permissions: ls -l $(which elements-cli)\n2. Try running: chmod +x /path/to/elements-cli\n\nOriginal error: {}",
-                        e
-                    )
-                } else {
-                    format!(
-                        "Failed to execute elements-cli createpsbt: {}\n\nTroubleshooting:\n1. Make sure elements-cli is installed and accessible\n2. Check if elementsd is running: elements-cli getblockchaininfo\n3. Verify your elements.conf configuration\n\nOriginal error: {}",
-                        e, e
-                    )
-                };
-                return Err(anyhow::anyhow!(error_msg));
-            }
-        };
+            .map_err(|e| AppError::tool_missing(Tool::ElementsCli, shown, &e))?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let exit_code = output.status.code().unwrap_or(-1);
-            
-            // Parse common error patterns
-            let mut error_details = format!(
-                "elements-cli createpsbt failed with exit code {}\n\nCommand: elements-cli createpsbt\nInputs: {}\nOutputs: {}\n\nStderr:\n{}\n\nStdout:\n{}",
-                exit_code, inputs_str, outputs_str, stderr, stdout
-            );
-            
-            // Add specific troubleshooting based on error content
-            if stderr.contains("error code: -1") || stderr.contains("error message:") {
-                error_details.push_str("\n\nThis looks like an RPC error. Possible causes:\n");
-                error_details.push_str("1. elementsd is not running - start it with: elementsd\n");
-                error_details.push_str("2. Wrong RPC credentials in ~/.elements/elements.conf\n");
-                error_details.push_str("3. Wrong network (testnet vs mainnet) - check your elements.conf\n");
-            } else if stderr.contains("Could not connect") || stderr.contains("Connection refused") {
-                error_details.push_str("\n\nConnection error detected:\n");
-                error_details.push_str("1. Make sure elementsd is running: elements-cli getblockchaininfo\n");
-                error_details.push_str("2. Check RPC port in ~/.elements/elements.conf (default: 18884 for testnet)\n");
-            } else if stderr.contains("Invalid") || stderr.contains("invalid") {
-                error_details.push_str("\n\nInvalid input detected:\n");
-                error_details.push_str("1. Check that the transaction ID (txid) is valid and exists\n");
-                error_details.push_str("2. Verify the vout index is correct (usually 0)\n");
-                error_details.push_str("3. Ensure the destination address is valid for the network\n");
-            }
-            
-            return Err(anyhow::anyhow!(error_details));
+            return Err(AppError::tool_failed(Tool::ElementsCli, shown, &output).into());
         }
 
-        let stdout = String::from_utf8(output.stdout)
-            .context("Invalid UTF-8 in elements-cli output")?;
-        
+        String::from_utf8(output.stdout)
+            .with_context(|| format!("Invalid UTF-8 in the output of {}", shown))
+    }
+
+    /// Create a PSET with an explicit nLockTime
+    ///
+    /// When `locktime` is set, every input gets sequence 0xfffffffe so the lock time
+    /// is actually enforced (a transaction whose inputs are all final ignores nLockTime).
+    /// This is what the `jet::check_lock_height` / `jet::check_lock_time` jets inspect.
+    #[cfg(feature = "server")]
+    pub async fn create_pset_with_locktime(
+        &self,
+        inputs: &[(String, u32)],
+        outputs: &[(String, f64)],
+        fee: Option<f64>,
+        locktime: Option<u32>,
+    ) -> Result<String> {
+        let (inputs_json, outputs_json) = createpsbt_params(inputs, outputs, fee, locktime);
+        let inputs_str = serde_json::to_string(&inputs_json)
+            .context("Failed to serialize inputs")?;
+        let outputs_str = serde_json::to_string(&outputs_json)
+            .context("Failed to serialize outputs")?;
+
+        // Call elements-cli createpsbt directly (like simc)
+        // Syntax: createpsbt [inputs] [outputs] ( locktime replaceable )
+        let locktime_str = locktime.map(|lt| lt.to_string());
+        let mut args = vec!["createpsbt", inputs_str.as_str(), outputs_str.as_str()];
+        args.extend(locktime_str.as_deref());
+        let shown = format!("elements-cli createpsbt '{}' '{}'", inputs_str, outputs_str);
+        let stdout = self.cli(&args, &shown).await?;
+
         let result = stdout.trim();
         if result.is_empty() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!(
-                "elements-cli createpsbt returned empty output\n\nCommand: elements-cli createpsbt\nInputs: {}\nOutputs: {}\n\nStderr:\n{}",
-                inputs_str,
-                outputs_str,
-                stderr
-            ));
+            return Err(anyhow::anyhow!("{} returned empty output", shown));
         }
         
         // Validate PSET looks like base64 (basic check)
@@ -402,282 +442,266 @@ impl ElementsRPC {
     /// Update PSBT with UTXO data from the blockchain
     /// Uses elements-cli utxoupdatepsbt
     /// Syntax: utxoupdatepsbt "psbt" ( ["",{"desc":"str","range":n or [n,n]},...] )
+    #[cfg(feature = "server")]
     pub async fn update_psbt_utxo(&self, psbt: &str) -> Result<String> {
-        let cmd = self.elements_cli_cmd();
-        let output = match Command::new(&cmd)
-            .arg("utxoupdatepsbt")
-            .arg(psbt)
-            .output()
-            .await
-        {
-            Ok(o) => o,
-            Err(e) => {
-                let error_kind = e.kind();
-                let error_msg = if error_kind == std::io::ErrorKind::NotFound {
-                    format!(
-                        "elements-cli command not found at: {}\n\nTroubleshooting:\n1. Check if elements-cli is installed: which elements-cli\n2. Try finding it: find /usr /opt ~/.cargo -name 'elements-cli' 2>/dev/null\n\nOriginal error: {}",
-                        cmd, e
-                    )
-                } else {
-                    format!(
-                        "Failed to execute {} utxoupdatepsbt: {}\n\nOriginal error: {}",
-                        cmd, e, e
-                    )
-                };
-                return Err(anyhow::anyhow!(error_msg));
-            }
-        };
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let exit_code = output.status.code().unwrap_or(-1);
-            
-            let mut error_details = format!(
-                "{} utxoupdatepsbt failed with exit code {}\n\nCommand: {} utxoupdatepsbt\nPSBT (first 200 chars): {}...\n\nStderr:\n{}\n\nStdout:\n{}",
-                cmd,
-                exit_code,
-                cmd,
-                psbt.chars().take(200).collect::<String>(),
-                stderr,
-                stdout
-            );
-            
-            if stderr.contains("error code: -1") || stderr.contains("error message:") {
-                error_details.push_str("\n\nRPC error detected:\n");
-                error_details.push_str("1. Make sure elementsd is running: elements-cli getblockchaininfo\n");
-                error_details.push_str("2. Check RPC credentials in ~/.elements/elements.conf\n");
-            } else if stderr.contains("Invalid") || stderr.contains("invalid") {
-                error_details.push_str("\n\nInvalid PSBT detected:\n");
-                error_details.push_str("1. Verify the PSBT format is correct (base64)\n");
-                error_details.push_str("2. Make sure the PSBT hasn't been corrupted\n");
-            }
-            
-            return Err(anyhow::anyhow!(error_details));
-        }
-
-        let stdout = String::from_utf8(output.stdout)
-            .context("Invalid UTF-8 in elements-cli output")?;
-        
+        let shown = format!("elements-cli utxoupdatepsbt {}...", psbt.chars().take(100).collect::<String>());
+        let stdout = self.cli(&["utxoupdatepsbt", psbt], &shown).await?;
         Ok(stdout.trim().to_string())
     }
 
     /// Finalize a PSET to get the raw transaction hex
     /// Uses elements-cli finalizepsbt directly (like simc)
     /// Syntax: finalizepsbt "psbt" ( extract )
+    #[cfg(feature = "server")]
     pub async fn finalize_pset(&self, pset: &str) -> Result<String> {
         // Call elements-cli finalizepsbt directly
-        let cmd = self.elements_cli_cmd();
-        let output = match Command::new(&cmd)
-            .arg("finalizepsbt")
-            .arg(pset)
-            .output()
-            .await
-        {
-            Ok(o) => o,
-            Err(e) => {
-                let error_kind = e.kind();
-                let error_msg = if error_kind == std::io::ErrorKind::NotFound {
-                    format!(
-                        "elements-cli command not found at: {}\n\nTroubleshooting:\n1. Check if elements-cli is installed: which elements-cli\n2. Try finding it: find /usr /opt ~/.cargo -name 'elements-cli' 2>/dev/null\n3. If not found, install Elements from: https://github.com/ElementsProject/elements\n4. Make sure elements-cli is in your PATH\n\nOriginal error: {}",
-                        cmd, e
-                    )
-                } else {
-                    format!(
-                        "Failed to execute elements-cli finalizepsbt: {}\n\nMake sure elements-cli is installed and accessible.\n\nOriginal error: {}",
-                        e, e
-                    )
-                };
-                return Err(anyhow::anyhow!(error_msg));
-            }
-        };
+        let shown = format!("elements-cli finalizepsbt {}...", pset.chars().take(100).collect::<String>());
+        let stdout = self.cli(&["finalizepsbt", pset], &shown).await?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let exit_code = output.status.code().unwrap_or(-1);
-            let pset_preview = pset.chars().take(200).collect::<String>();
-            
-            let mut error_details = format!(
-                "{} finalizepsbt failed with exit code {}\n\nCommand: {} finalizepsbt\nPSET (first 200 chars): {}...\n\nStderr:\n{}\n\nStdout:\n{}",
-                cmd, exit_code, cmd, pset_preview, stderr, stdout
-            );
-            
-            // Add specific troubleshooting
-            if stderr.contains("not final") || stderr.contains("incomplete") {
-                error_details.push_str("\n\nPSET is not fully signed:\n");
-                error_details.push_str("1. Make sure all required signatures are present\n");
-                error_details.push_str("2. Verify that all participants have signed the PSET\n");
-                error_details.push_str("3. Check that the witness data is complete\n");
-            } else if stderr.contains("Invalid") || stderr.contains("invalid") {
-                error_details.push_str("\n\nInvalid PSET detected:\n");
-                error_details.push_str("1. Verify the PSET format is correct (base64)\n");
-                error_details.push_str("2. Make sure the PSET hasn't been corrupted\n");
-                error_details.push_str("3. Try recreating the PSET from scratch\n");
-            }
-            
-            return Err(anyhow::anyhow!(error_details));
-        }
-
-        let stdout = String::from_utf8(output.stdout)
-            .context(format!("Invalid UTF-8 in elements-cli output\n\nCommand: {} finalizepsbt", cmd))?;
-        
         // Parse JSON response to extract hex
         let json: Value = match serde_json::from_str(&stdout) {
             Ok(j) => j,
             Err(e) => {
                 return Err(anyhow::anyhow!(
-                    "Failed to parse finalizepsbt JSON response: {}\n\nCommand: {} finalizepsbt\n\nRaw stdout:\n{}\n\nExpected JSON with 'hex' field",
-                    e, cmd, stdout.chars().take(500).collect::<String>()
+                    "Failed to parse finalizepsbt JSON response: {}\n\nRaw stdout:\n{}\n\nExpected JSON with 'hex' field",
+                    e, stdout.chars().take(500).collect::<String>()
                 ));
             }
         };
         
         match json.get("hex").and_then(|v| v.as_str()) {
             Some(hex) => Ok(hex.to_string()),
-            None => {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Err(anyhow::anyhow!(
-                    "PSET finalization failed or incomplete - no 'hex' field in response\n\nCommand: {} finalizepsbt\n\nResponse JSON:\n{}\n\nStderr:\n{}\n\nAvailable fields: {:?}",
-                    cmd,
-                    serde_json::to_string_pretty(&json).unwrap_or_else(|_| stdout),
-                    stderr,
-                    json.as_object().map(|o| o.keys().collect::<Vec<_>>()).unwrap_or_default()
-                ))
-            }
+            None => Err(anyhow::anyhow!(
+                "PSET finalization failed or incomplete - no 'hex' field in response\n\nResponse JSON:\n{}\n\nMake sure all required signatures are present.",
+                serde_json::to_string_pretty(&json).unwrap_or_else(|_| stdout.clone())
+            )),
         }
     }
 
     /// Get transaction output details
     /// Uses elements-cli gettxout directly (like simc)
+    #[cfg(feature = "server")]
     pub async fn get_txout(&self, txid: &str, vout: u32) -> Result<Value> {
         // Call elements-cli gettxout directly
-        let cmd = self.elements_cli_cmd();
-        let output = match Command::new(&cmd)
-            .arg("gettxout")
-            .arg(txid)
-            .arg(vout.to_string())
-            .output()
-            .await
-        {
-            Ok(o) => o,
-            Err(e) => {
-                let error_kind = e.kind();
-                let error_msg = if error_kind == std::io::ErrorKind::NotFound {
-                    format!(
-                        "elements-cli command not found at: {}\n\nTroubleshooting:\n1. Check if elements-cli is installed: which elements-cli\n2. Try finding it: find /usr /opt ~/.cargo -name 'elements-cli' 2>/dev/null\n3. If not found, install Elements from: https://github.com/ElementsProject/elements\n4. Make sure elements-cli is in your PATH\n\nOriginal error: {}",
-                        cmd, e
-                    )
-                } else {
-                    format!(
-                        "Failed to execute elements-cli gettxout: {}\n\nMake sure elements-cli is installed and accessible.\n\nOriginal error: {}",
-                        e, e
-                    )
-                };
-                return Err(anyhow::anyhow!(error_msg));
-            }
-        };
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let exit_code = output.status.code().unwrap_or(-1);
-            
-            let mut error_details = format!(
-                "{} gettxout failed with exit code {}\n\nCommand: {} gettxout {} {}\n\nStderr:\n{}\n\nStdout:\n{}",
-                cmd, exit_code, cmd, txid, vout, stderr, stdout
-            );
-            
-            // Add specific troubleshooting
-            if stderr.contains("not found") || stderr.contains("null") || stdout.contains("null") {
-                error_details.push_str("\n\nUTXO not found. Possible causes:\n");
-                error_details.push_str("1. The transaction hasn't been confirmed yet - wait for confirmation\n");
-                error_details.push_str("2. The UTXO has already been spent\n");
-                error_details.push_str("3. Wrong transaction ID or vout index\n");
-                error_details.push_str("4. The transaction is on a different network (testnet vs mainnet)\n");
-                error_details.push_str("5. Your elementsd node hasn't synced this transaction yet\n");
-            } else if stderr.contains("error code: -1") || stderr.contains("error message:") {
-                error_details.push_str("\n\nRPC error detected:\n");
-                error_details.push_str("1. Make sure elementsd is running: elements-cli getblockchaininfo\n");
-                error_details.push_str("2. Check RPC credentials in ~/.elements/elements.conf\n");
-            } else if stderr.contains("Could not connect") || stderr.contains("Connection refused") {
-                error_details.push_str("\n\nConnection error:\n");
-                error_details.push_str("1. Start elementsd: elementsd\n");
-                error_details.push_str("2. Wait for it to sync: elements-cli getblockchaininfo\n");
-            }
-            
-            return Err(anyhow::anyhow!(error_details));
+        let vout_str = vout.to_string();
+        let shown = format!("elements-cli gettxout {} {}", txid, vout);
+        let stdout = self.cli(&["gettxout", txid, &vout_str], &shown).await?;
+        // An unknown or spent output prints nothing
+        if stdout.trim().is_empty() {
+            return Ok(Value::Null);
         }
 
-        let stdout = String::from_utf8(output.stdout)
-            .context(format!("Invalid UTF-8 in elements-cli output\n\nCommand: {} gettxout {} {}", cmd, txid, vout))?;
-        
         match serde_json::from_str(&stdout) {
             Ok(json) => Ok(json),
             Err(e) => {
                 Err(anyhow::anyhow!(
-                    "Failed to parse gettxout JSON response: {}\n\nCommand: {} gettxout {} {}\n\nRaw stdout:\n{}\n\nExpected JSON response",
-                    e, cmd, txid, vout, stdout.chars().take(500).collect::<String>()
+                    "Failed to parse gettxout JSON response: {}\n\nCommand: {}\n\nRaw stdout:\n{}\n\nExpected JSON response",
+                    e, shown, stdout.chars().take(500).collect::<String>()
                 ))
             }
         }
     }
 
-    /// Decode a PSET/PSBT to see its structure
-    /// Uses elements-cli decodepsbt
-    pub async fn decode_pset(&self, pset: &str) -> Result<Value> {
-        let cmd = self.elements_cli_cmd();
-        let output = match Command::new(&cmd)
-            .arg("decodepsbt")
-            .arg(pset)
-            .output()
+    /// Look up the scriptPubKey, asset and value of an unspent output
+    ///
+    /// Uses gettxout first and falls back to the Blockstream API when the node
+    /// does not know the output (e.g. pruned or not yet synced).
+    pub async fn get_utxo_info(&self, txid: &str, vout: u32) -> Result<UtxoInfo> {
+        if let Ok(data) = self.get_txout(txid, vout).await {
+            if !data.is_null() {
+                let script_pubkey = data["scriptPubKey"]["hex"].as_str().unwrap_or("");
+                let asset = data["asset"].as_str().unwrap_or("");
+                // gettxout returns the value in BTC
+                let value_sats = data["value"]
+                    .as_f64()
+                    .map(|v| (v * 100_000_000.0).round() as u64)
+                    .unwrap_or(0);
+                if !script_pubkey.is_empty() && !asset.is_empty() && value_sats > 0 {
+                    return Ok(UtxoInfo {
+                        script_pubkey: script_pubkey.to_string(),
+                        asset: asset.to_string(),
+                        value_sats,
+                    });
+                }
+            }
+        }
+
+        let tx_data: Value = reqwest::Client::new()
+            .get(format!("https://blockstream.info/liquidtestnet/api/tx/{}", txid))
+            .send()
             .await
-        {
-            Ok(o) => o,
-            Err(e) => {
-                let error_kind = e.kind();
-                let error_msg = if error_kind == std::io::ErrorKind::NotFound {
-                    format!(
-                        "elements-cli command not found at: {}\n\nTroubleshooting:\n1. Check if elements-cli is installed: which elements-cli\n2. Try finding it: find /usr /opt ~/.cargo -name 'elements-cli' 2>/dev/null\n\nOriginal error: {}",
-                        cmd, e
-                    )
-                } else {
-                    format!(
-                        "Failed to execute {} decodepsbt\n\nOriginal error: {}",
-                        cmd, e
-                    )
-                };
-                return Err(anyhow::anyhow!(error_msg));
+            .context("Failed to fetch transaction from Blockstream API")?
+            .json()
+            .await
+            .context("Failed to parse Blockstream API response")?;
+
+        let output = &tx_data["vout"][vout as usize];
+        let script_pubkey = output["scriptpubkey"].as_str().unwrap_or("");
+        let asset = output["asset"].as_str().unwrap_or("");
+        // Esplora returns the value in sats
+        let value_sats = output["value"].as_u64().unwrap_or(0);
+
+        if script_pubkey.is_empty() || asset.is_empty() || value_sats == 0 {
+            return Err(anyhow::anyhow!(
+                "Could not find UTXO {}:{} via gettxout or the Blockstream API\n\nThe output may be spent, confidential, or on a different network.\n\nResponse:\n{}",
+                txid,
+                vout,
+                serde_json::to_string_pretty(output).unwrap_or_default()
+            ));
+        }
+
+        Ok(UtxoInfo {
+            script_pubkey: script_pubkey.to_string(),
+            asset: asset.to_string(),
+            value_sats,
+        })
+    }
+
+    /// Raw transaction hex, from the node or the Blockstream API
+    ///
+    /// `getrawtransaction` only finds wallet and mempool transactions unless the node
+    /// runs with `-txindex`, so the Blockstream API is tried when the node cannot.
+    pub async fn get_raw_transaction_hex(&self, txid: &str) -> Result<String> {
+        if let Ok(Value::String(hex)) = self.call("getrawtransaction", json!([txid])).await {
+            return Ok(hex);
+        }
+        let response = self.client
+            .get(format!("{}/tx/{}/hex", ESPLORA_API, txid))
+            .send()
+            .await
+            .context("Failed to fetch transaction from Blockstream API")?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Transaction {} not found by the node or the Blockstream API (HTTP {})",
+                txid,
+                response.status()
+            ));
+        }
+        response
+            .text()
+            .await
+            .map(|hex| hex.trim().to_string())
+            .context("Failed to read Blockstream API response")
+    }
+
+    /// Every transaction paying to or spending from an address, newest first
+    ///
+    /// elementsd has no address index, so this comes from the Blockstream (Esplora) API.
+    /// Mempool transactions come first, then confirmed ones, paged 25 at a time.
+    pub async fn get_address_txs(&self, address: &str) -> Result<Vec<Value>> {
+        let mut txs: Vec<Value> = Vec::new();
+        let mut url = format!("{}/address/{}/txs", ESPLORA_API, address);
+        loop {
+            let page: Vec<Value> = self.client
+                .get(&url)
+                .send()
+                .await
+                .context("Failed to fetch address history from Blockstream API")?
+                .json()
+                .await
+                .context("Failed to parse Blockstream API address history")?;
+            let confirmed: Vec<&Value> = page
+                .iter()
+                .filter(|tx| tx["status"]["confirmed"].as_bool() == Some(true))
+                .collect();
+            let last_confirmed = confirmed.last().and_then(|tx| tx["txid"].as_str()).map(str::to_string);
+            let full_page = confirmed.len() >= ESPLORA_PAGE;
+            txs.extend(page);
+            match last_confirmed {
+                Some(last) if full_page => url = format!("{}/address/{}/txs/chain/{}", ESPLORA_API, address, last),
+                _ => break,
             }
-        };
+        }
+        Ok(txs)
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let exit_code = output.status.code().unwrap_or(-1);
-            
-            let error_details = format!(
-                "{} decodepsbt failed with exit code {}\n\nCommand: {} decodepsbt\nPSET (first 200 chars): {}...\n\nStderr:\n{}\n\nStdout:\n{}",
-                cmd,
-                exit_code,
-                cmd,
-                pset.chars().take(200).collect::<String>(),
-                stderr,
-                stdout
-            );
-            
-            return Err(anyhow::anyhow!(error_details));
+    /// Unspent value (in sats) held by an address, including mempool transactions
+    ///
+    /// Taken from the Blockstream API's address stats; confidential outputs are not counted.
+    pub async fn get_address_balance(&self, address: &str) -> Result<u64> {
+        let info: Value = self.client
+            .get(format!("{}/address/{}", ESPLORA_API, address))
+            .send()
+            .await
+            .context("Failed to fetch address from Blockstream API")?
+            .json()
+            .await
+            .context("Failed to parse Blockstream API address stats")?;
+        let sum = |stats: &Value, field: &str| stats[field].as_u64().unwrap_or(0);
+        let funded = sum(&info["chain_stats"], "funded_txo_sum") + sum(&info["mempool_stats"], "funded_txo_sum");
+        let spent = sum(&info["chain_stats"], "spent_txo_sum") + sum(&info["mempool_stats"], "spent_txo_sum");
+        Ok(funded.saturating_sub(spent))
+    }
+
+    /// A transaction as decoded by the Blockstream API, with prevouts and status
+    pub async fn get_esplora_tx(&self, txid: &str) -> Result<Value> {
+        let response = self.client
+            .get(format!("{}/tx/{}", ESPLORA_API, txid))
+            .send()
+            .await
+            .context("Failed to fetch transaction from Blockstream API")?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Transaction {} not found by the Blockstream API (HTTP {})", txid, response.status()));
         }
+        response.json().await.context("Failed to parse Blockstream API transaction")
+    }
+
+    /// The transaction spending an output, if it has been spent
+    ///
+    /// Unspent outputs are recognised from `gettxout` when the node knows them; otherwise
+    /// the Blockstream API's spend index is asked (the node has none).
+    pub async fn get_outspend(&self, txid: &str, vout: u32) -> Result<Option<String>> {
+        if self.get_txout(txid, vout).await.is_ok_and(|data| !data.is_null()) {
+            return Ok(None);
+        }
+        let outspend: Value = self.client
+            .get(format!("{}/tx/{}/outspend/{}", ESPLORA_API, txid, vout))
+            .send()
+            .await
+            .context("Failed to fetch output spend from Blockstream API")?
+            .json()
+            .await
+            .context("Failed to parse Blockstream API output spend")?;
+        if outspend["spent"].as_bool() != Some(true) {
+            return Ok(None);
+        }
+        outspend["txid"]
+            .as_str()
+            .map(|txid| Some(txid.to_string()))
+            .context("Blockstream API reported the output spent without a spending txid")
+    }
+
+    /// Current block height, from the node or the Blockstream API
+    pub async fn get_tip_height(&self) -> Result<u64> {
+        if let Some(height) = self.get_blockchain_info().await.ok().and_then(|info| info["blocks"].as_u64()) {
+            return Ok(height);
+        }
+        self.client
+            .get(format!("{}/blocks/tip/height", ESPLORA_API))
+            .send()
+            .await
+            .context("Failed to fetch tip height from Blockstream API")?
+            .text()
+            .await
+            .context("Failed to read Blockstream API response")?
+            .trim()
+            .parse()
+            .context("Blockstream API returned an invalid tip height")
+    }
+
+    /// Decode a PSET/PSBT to see its structure
+    /// Uses elements-cli decodepsbt
+    #[cfg(feature = "server")]
+    pub async fn decode_pset(&self, pset: &str) -> Result<Value> {
+        let shown = format!("elements-cli decodepsbt {}...", pset.chars().take(100).collect::<String>());
+        let stdout = self.cli(&["decodepsbt", pset], &shown).await?;
 
-        let stdout = String::from_utf8(output.stdout)
-            .context("Invalid UTF-8 in elements-cli output")?;
-        
         match serde_json::from_str(&stdout) {
             Ok(json) => Ok(json),
             Err(e) => {
                 Err(anyhow::anyhow!(
-                    "Failed to parse decodepsbt JSON response: {}\n\nCommand: {} decodepsbt\n\nRaw stdout:\n{}\n\nExpected JSON response",
-                    e, cmd, stdout.chars().take(500).collect::<String>()
+                    "Failed to parse decodepsbt JSON response: {}\n\nRaw stdout:\n{}\n\nExpected JSON response",
+                    e, stdout.chars().take(500).collect::<String>()
                 ))
             }
         }
@@ -688,3 +712,148 @@ impl ElementsRPC {
         &self.settings
     }
 }
+
+/// Browser build: the PSET commands `elements-cli` runs natively, sent over JSON-RPC instead
+///
+/// The node (or a proxy in front of it) must allow cross-origin requests from the page.
+#[cfg(not(feature = "server"))]
+impl ElementsRPC {
+    /// Create a PSET with an explicit nLockTime
+    ///
+    /// When `locktime` is set, every input gets sequence 0xfffffffe so the lock time
+    /// is actually enforced (a transaction whose inputs are all final ignores nLockTime).
+    pub async fn create_pset_with_locktime(
+        &self,
+        inputs: &[(String, u32)],
+        outputs: &[(String, f64)],
+        fee: Option<f64>,
+        locktime: Option<u32>,
+    ) -> Result<String> {
+        let (inputs_json, outputs_json) = createpsbt_params(inputs, outputs, fee, locktime);
+        let mut params = vec![json!(inputs_json), json!(outputs_json)];
+        if let Some(lt) = locktime {
+            params.push(json!(lt));
+        }
+        let result = self.call("createpsbt", json!(params)).await.with_context(|| {
+            format!("createpsbt failed for inputs {} and outputs {}", json!(inputs_json), json!(outputs_json))
+        })?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .context("createpsbt did not return a PSET")
+    }
+
+    /// Update PSBT with UTXO data from the blockchain
+    pub async fn update_psbt_utxo(&self, psbt: &str) -> Result<String> {
+        let result = self.call("utxoupdatepsbt", json!([psbt])).await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .context("utxoupdatepsbt did not return a PSBT")
+    }
+
+    /// Finalize a PSET to get the raw transaction hex
+    pub async fn finalize_pset(&self, pset: &str) -> Result<String> {
+        let json = self.call("finalizepsbt", json!([pset])).await?;
+        match json.get("hex").and_then(|v| v.as_str()) {
+            Some(hex) => Ok(hex.to_string()),
+            None => Err(anyhow::anyhow!(
+                "PSET finalization failed or incomplete - no 'hex' field in response\n\nResponse JSON:\n{}\n\nMake sure all required signatures are present.",
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            )),
+        }
+    }
+
+    /// Get transaction output details (`null` when the output is unknown or spent)
+    pub async fn get_txout(&self, txid: &str, vout: u32) -> Result<Value> {
+        self.call("gettxout", json!([txid, vout])).await
+    }
+
+    /// Decode a PSET/PSBT to see its structure
+    pub async fn decode_pset(&self, pset: &str) -> Result<Value> {
+        self.call("decodepsbt", json!([pset])).await
+    }
+}
+
+impl NodeBackend for ElementsRPC {
+    async fn get_blockchain_info(&self) -> Result<Value> {
+        ElementsRPC::get_blockchain_info(self).await
+    }
+
+    async fn get_genesis_hash(&self) -> Result<String> {
+        ElementsRPC::get_genesis_hash(self).await
+    }
+
+    async fn get_txout(&self, txid: &str, vout: u32) -> Result<Value> {
+        ElementsRPC::get_txout(self, txid, vout).await
+    }
+
+    async fn get_utxo_info(&self, txid: &str, vout: u32) -> Result<UtxoInfo> {
+        ElementsRPC::get_utxo_info(self, txid, vout).await
+    }
+
+    async fn create_pset_with_locktime(
+        &self,
+        inputs: &[(String, u32)],
+        outputs: &[(String, f64)],
+        fee: Option<f64>,
+        locktime: Option<u32>,
+    ) -> Result<String> {
+        ElementsRPC::create_pset_with_locktime(self, inputs, outputs, fee, locktime).await
+    }
+
+    async fn finalize_pset(&self, pset: &str) -> Result<String> {
+        ElementsRPC::finalize_pset(self, pset).await
+    }
+
+    async fn decode_pset(&self, pset: &str) -> Result<Value> {
+        ElementsRPC::decode_pset(self, pset).await
+    }
+
+    async fn send_raw_transaction(&self, hex: &str) -> Result<String> {
+        ElementsRPC::send_raw_transaction(self, hex).await
+    }
+}
+
+/// `createpsbt` inputs and outputs
+///
+/// Outputs are `[{"address": amount}, ...]`; a fee adds `{"fee": amount}` as the last
+/// output. With a lock time, every input gets sequence 0xfffffffe.
+fn createpsbt_params(
+    inputs: &[(String, u32)],
+    outputs: &[(String, f64)],
+    fee: Option<f64>,
+    locktime: Option<u32>,
+) -> (Vec<Value>, Vec<Value>) {
+    let inputs_json = inputs
+        .iter()
+        .map(|(txid, vout)| {
+            if locktime.is_some() {
+                json!({
+                    "txid": txid,
+                    "vout": vout,
+                    "sequence": 0xfffffffe_u32
+                })
+            } else {
+                json!({
+                    "txid": txid,
+                    "vout": vout
+                })
+            }
+        })
+        .collect();
+    let mut outputs_json: Vec<Value> = outputs
+        .iter()
+        .map(|(addr, amount)| {
+            let mut output_obj = serde_json::Map::new();
+            output_obj.insert(addr.clone(), json!(amount));
+            json!(output_obj)
+        })
+        .collect();
+    if let Some(fee_amount) = fee {
+        let mut fee_obj = serde_json::Map::new();
+        fee_obj.insert("fee".to_string(), json!(fee_amount));
+        outputs_json.push(json!(fee_obj));
+    }
+    (inputs_json, outputs_json)
+}
@@ -0,0 +1,307 @@
+//! Structured errors for `app_core`
+//!
+//! Failures that callers need to tell apart are raised as [`AppError`] variants with
+//! structured fields instead of pre-formatted text: a tool that cannot be started, a
+//! tool that failed or does not understand its input, an RPC error, a transaction the
+//! node rejected (with its reject code), a covenant rule the transaction breaks and a
+//! signature that does not match. They travel inside `anyhow::Error` like any other
+//! error, so `error.downcast_ref::<AppError>()` finds them, and the troubleshooting
+//! steps are derived from the fields by [`AppError::guidance`] when the UI shows them
+//! (see [`describe`]).
+
+use std::fmt;
+use std::io::ErrorKind;
+use std::process::Output;
+
+/// External programs the app runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Simc,
+    HalSimplicity,
+    ElementsCli,
+    Zbarimg,
+}
+
+impl Tool {
+    pub fn program(&self) -> &'static str {
+        match self {
+            Tool::Simc => "simc",
+            Tool::HalSimplicity => "hal-simplicity",
+            Tool::ElementsCli => "elements-cli",
+            Tool::Zbarimg => "zbarimg",
+        }
+    }
+
+    /// Where to get the tool
+    pub fn install_hint(&self) -> &'static str {
+        match self {
+            Tool::Simc => "Install SimplicityHL from: https://github.com/BlockstreamResearch/SimplicityHL",
+            Tool::HalSimplicity => "Install hal-simplicity from: https://github.com/Blockstream/hal-simplicity",
+            Tool::ElementsCli => "Install Elements from: https://github.com/ElementsProject/elements",
+            Tool::Zbarimg => "Install zbar (e.g. apt install zbar-tools or brew install zbar)",
+        }
+    }
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.program())
+    }
+}
+
+/// Reject codes `sendrawtransaction` and `testmempoolaccept` use for transactions
+/// (RPC_VERIFY_ERROR, RPC_VERIFY_REJECTED, RPC_VERIFY_ALREADY_IN_CHAIN)
+pub const REJECT_CODES: [i64; 3] = [-25, -26, -27];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AppError {
+    /// The program could not be started (`kind` is `Unsupported` in the web build)
+    #[error("{tool} could not be started ({}): {command}", startup_reason(*.kind))]
+    ToolMissing { tool: Tool, command: String, kind: ErrorKind },
+
+    /// The program ran and exited with an error
+    #[error("{command} failed with exit code {exit_code}: {}", first_line(.stderr, .stdout))]
+    ToolFailed { tool: Tool, command: String, exit_code: i32, stderr: String, stdout: String },
+
+    /// The program could not read data another tool produced, usually a version mismatch
+    #[error("{tool}{} could not read its input: {detail}", .found.as_deref().map(|v| format!(" ({})", v)).unwrap_or_default())]
+    ToolVersion { tool: Tool, command: String, found: Option<String>, detail: String },
+
+    /// The node answered an RPC call with an error, or could not be reached (`code` is `None`)
+    #[error("RPC {method} failed{}: {message}", .code.map(|c| format!(" (code {})", c)).unwrap_or_default())]
+    Rpc { method: String, code: Option<i64>, message: String },
+
+    /// The node refused a transaction
+    #[error("The node rejected the transaction (code {code}): {reason}")]
+    NodeRejected { code: i64, reason: String },
+
+    /// The transaction does not satisfy the contract's rules
+    #[error("{detail}")]
+    CovenantViolation { detail: String, required_outputs: Vec<String> },
+
+    /// A signature does not verify against the key or transaction it should sign
+    #[error("{detail}")]
+    SignatureMismatch { detail: String, signers: Vec<String>, threshold: Option<usize> },
+}
+
+fn startup_reason(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::NotFound => "not found",
+        ErrorKind::PermissionDenied => "permission denied",
+        ErrorKind::Unsupported => "not available in the web app",
+        _ => "failed to start",
+    }
+}
+
+fn first_line<'a>(stderr: &'a str, stdout: &'a str) -> &'a str {
+    stderr
+        .lines()
+        .chain(stdout.lines())
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("no output")
+}
+
+/// Whether a reject reason means the Simplicity program or a script check failed
+pub fn is_script_failure(reason: &str) -> bool {
+    reason.contains("Assertion failed inside jet")
+        || reason.contains("non-mandatory-script-verify-flag")
+        || reason.contains("mandatory-script-verify-flag-failed")
+}
+
+impl AppError {
+    pub fn tool_missing(tool: Tool, command: impl Into<String>, error: &std::io::Error) -> Self {
+        AppError::ToolMissing { tool, command: command.into(), kind: error.kind() }
+    }
+
+    /// Classify a failed run of `tool`
+    ///
+    /// A PSET the tool cannot deserialize is a [`AppError::ToolVersion`]; elements-cli
+    /// reporting an RPC error (`error code: N`) is an [`AppError::Rpc`] for its method.
+    pub fn tool_failed(tool: Tool, command: impl Into<String>, output: &Output) -> Self {
+        let command = command.into();
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if stderr.contains("Deserialize error") || stderr.contains("decoding PSET") || stdout.contains("Deserialize error") {
+            return AppError::ToolVersion { tool, command, found: None, detail: first_line(&stderr, &stdout).to_string() };
+        }
+        if tool == Tool::ElementsCli {
+            if let Some(code) = cli_error_code(&stderr) {
+                let method = command.split_whitespace().nth(1).unwrap_or("").to_string();
+                let message = stderr
+                    .split_once("error message:")
+                    .map(|(_, m)| m.trim().to_string())
+                    .unwrap_or_else(|| stderr.clone());
+                return AppError::Rpc { method, code: Some(code), message };
+            }
+        }
+        AppError::ToolFailed { tool, command, exit_code: output.status.code().unwrap_or(-1), stderr, stdout }
+    }
+
+    /// An RPC `error` object (`{"code": n, "message": "..."}`) from `method`
+    pub fn rpc(method: &str, error: &serde_json::Value) -> Self {
+        let code = error.get("code").and_then(|c| c.as_i64());
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string());
+        match code {
+            Some(code) if REJECT_CODES.contains(&code) && matches!(method, "sendrawtransaction" | "testmempoolaccept") => {
+                AppError::NodeRejected { code, reason: message }
+            }
+            _ => AppError::Rpc { method: method.to_string(), code, message },
+        }
+    }
+
+    /// Troubleshooting steps for this error
+    pub fn guidance(&self) -> String {
+        match self {
+            AppError::ToolMissing { tool, kind: ErrorKind::NotFound, .. } => format!(
+                "Troubleshooting:\n1. Check if {tool} is installed: which {tool}\n2. Verify PATH: echo $PATH\n3. Common locations:\n   - /usr/local/bin/{tool}\n   - /usr/bin/{tool}\n   - ~/.cargo/bin/{tool}\n   - ~/bin/{tool}\n4. {}",
+                tool.install_hint()
+            ),
+            AppError::ToolMissing { tool, kind: ErrorKind::PermissionDenied, .. } => format!(
+                "Troubleshooting:\n1. Check that {tool} has execute permissions: ls -l $(which {tool})\n2. Make it executable: chmod +x /path/to/{tool}"
+            ),
+            AppError::ToolMissing { kind: ErrorKind::Unsupported, .. } => {
+                "Compiling contracts and finalizing transactions need the desktop app, the partnerfy command line or the REST API server (partnerfy_api), which run the tools on your machine.".to_string()
+            }
+            AppError::ToolMissing { tool, .. } => format!("Make sure {tool} is installed and can be run from a terminal."),
+            AppError::ToolFailed { tool, command, stderr, stdout, .. } => {
+                let mut text = format!("Command: {}\n\nStderr:\n{}\n\nStdout:\n{}", command, stderr, stdout);
+                let hints: &[&str] = if stderr.contains("No such file") || stderr.contains("not found") {
+                    &["Verify the file exists and the path is correct", "Ensure you have read permissions"]
+                } else if stderr.contains("syntax error") || stderr.contains("parse error") {
+                    &["Check the SimplicityHL source file syntax", "Verify the witness file is valid JSON with correctly formatted signatures"]
+                } else if *tool == Tool::HalSimplicity && (stderr.contains("witness") || stderr.contains("signature")) {
+                    &["Check that all required signatures are present", "Verify signatures are correctly formatted", "Ensure the witness matches the contract's requirements"]
+                } else if stderr.contains("invalid") || stderr.contains("Invalid") {
+                    &["Verify the program and PSET are valid base64", "Check the txid:vout inputs and address:amount outputs", "Ensure the CMR and internal key match the contract"]
+                } else if stderr.contains("Could not connect") || stderr.contains("Connection refused") {
+                    &["Make sure elementsd is running: elements-cli getblockchaininfo", "Check the RPC port in ~/.elements/elements.conf"]
+                } else {
+                    &[]
+                };
+                if !hints.is_empty() {
+                    text.push_str("\n\nTroubleshooting:");
+                    for (i, hint) in hints.iter().enumerate() {
+                        text.push_str(&format!("\n{}. {}", i + 1, hint));
+                    }
+                }
+                text
+            }
+            AppError::ToolVersion { tool, command, .. } => format!(
+                "Command: {command}\n\n{tool} cannot decode the data it was given. Possible causes:\n1. Version incompatibility between elements-cli and hal-simplicity\n2. PSET format mismatch (elements-cli createpsbt might return PSBT, not PSET)\n\nTroubleshooting:\n1. Check the versions: elements-cli --version and hal-simplicity --version\n2. Update both to releases built for the same Elements version\n3. {}",
+                tool.install_hint()
+            ),
+            AppError::Rpc { code: None, .. } => "Troubleshooting:\n1. Make sure elementsd is running: elements-cli getblockchaininfo\n2. Check the RPC host, port, user and password in the settings against ~/.elements/elements.conf\n3. Check that the node runs on the configured network (testnet vs mainnet)".to_string(),
+            AppError::Rpc { code: Some(-28), .. } => "The node is still starting (loading blocks or verifying). Wait until it is ready and try again.".to_string(),
+            AppError::Rpc { code: Some(-18), .. } | AppError::Rpc { code: Some(-19), .. } => {
+                "No wallet is loaded. Load or create one: elements-cli loadwallet <name> (or createwallet <name>).".to_string()
+            }
+            AppError::Rpc { code: Some(-6), .. } => "The wallet does not have enough funds. Fund the node's wallet first.".to_string(),
+            AppError::Rpc { code: Some(-5), .. } => "The node does not know the address, transaction or output asked for. Check the txid and vout, and that the node runs on the configured network.".to_string(),
+            AppError::Rpc { code: Some(-8), .. } | AppError::Rpc { code: Some(-22), .. } => {
+                "The request had an invalid parameter. Check the transaction ID, output index, addresses and amounts.".to_string()
+            }
+            AppError::Rpc { .. } => "See the node's debug.log for details.".to_string(),
+            AppError::NodeRejected { reason, .. } if reason.contains("non-final") => {
+                "The transaction's nLockTime has not been reached yet. Refund transactions can only be broadcast once the refund lock has expired.".to_string()
+            }
+            AppError::NodeRejected { reason, .. } if is_script_failure(reason) => {
+                "The Simplicity program failed when the node executed it.\n\nCommon causes:\n1. Signatures don't match the public keys in the program, or are in the wrong positions\n2. The PSET changed after signing (signatures are PSET-specific); re-create the PSET and sign again\n3. The transaction's outputs do not satisfy the contract".to_string()
+            }
+            AppError::NodeRejected { code: -27, .. } => "The transaction is already in the blockchain. Nothing more to do.".to_string(),
+            AppError::NodeRejected { reason, .. } if reason.contains("missingorspent") || reason.contains("missing-inputs") || reason.contains("already-in-mempool") || reason.contains("conflict") => {
+                "An input is already spent, or the transaction was already broadcast. Refresh the contract's UTXO and check its history before trying again.".to_string()
+            }
+            AppError::NodeRejected { reason, .. } if reason.contains("fee") => {
+                "The fee is too low for the node's relay policy. Create the transaction again with a higher fee.".to_string()
+            }
+            AppError::NodeRejected { .. } => "See the node's debug.log for details.".to_string(),
+            AppError::CovenantViolation { required_outputs, .. } => {
+                let mut text = "The covenant would reject this transaction.".to_string();
+                if !required_outputs.is_empty() {
+                    text.push_str(" It requires exactly these outputs:\n");
+                    text.push_str(&required_outputs.iter().map(|o| format!("   - {}", o)).collect::<Vec<_>>().join("\n"));
+                }
+                text
+            }
+            AppError::SignatureMismatch { signers, threshold, .. } => {
+                let mut causes = Vec::new();
+                if signers.is_empty() {
+                    causes.push("The signature was made with a different key".to_string());
+                } else {
+                    let expected = signers
+                        .iter()
+                        .enumerate()
+                        .map(|(i, key)| format!("\n   - Position {}: signature for {}", i, key))
+                        .collect::<String>();
+                    causes.push(format!("Signatures don't match the public keys in the program. It expects:{}", expected));
+                }
+                if let Some(threshold) = threshold {
+                    causes.push(format!("Fewer than {} valid signatures were provided", threshold));
+                }
+                causes.push("The PSET changed after signing (signatures are PSET-specific); sign the current PSET again".to_string());
+                let causes = causes.iter().enumerate().map(|(i, c)| format!("{}. {}", i + 1, c)).collect::<Vec<_>>();
+                format!("Common causes:\n{}", causes.join("\n"))
+            }
+        }
+    }
+}
+
+/// The outermost [`AppError`] in an error's chain
+pub fn find(error: &anyhow::Error) -> Option<&AppError> {
+    error.downcast_ref::<AppError>()
+}
+
+/// Full message of an error with its causes, followed by the guidance of its [`AppError`]
+pub fn describe(error: &anyhow::Error) -> String {
+    match find(error) {
+        Some(app_error) => format!("{:#}\n\n{}", error, app_error.guidance()),
+        None => format!("{:#}", error),
+    }
+}
+
+/// `N` from elements-cli's "error code: N" line
+fn cli_error_code(stderr: &str) -> Option<i64> {
+    stderr
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("error code:"))
+        .and_then(|c| c.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use serde_json::json;
+
+    #[test]
+    fn errors_keep_their_fields_through_context() {
+        let rejected = AppError::rpc("sendrawtransaction", &json!({"code": -26, "message": "non-final"}));
+        assert_eq!(rejected, AppError::NodeRejected { code: -26, reason: "non-final".to_string() });
+        assert!(rejected.guidance().contains("nLockTime"));
+        let rpc = AppError::rpc("getbalance", &json!({"code": -18, "message": "No wallet is loaded"}));
+        assert!(matches!(rpc, AppError::Rpc { code: Some(-18), .. }));
+
+        let error = Err::<(), _>(anyhow::Error::new(rejected)).context("Failed to broadcast transaction").unwrap_err();
+        assert!(matches!(find(&error), Some(AppError::NodeRejected { code: -26, .. })));
+        let text = describe(&error);
+        assert!(text.starts_with("Failed to broadcast transaction: The node rejected the transaction (code -26): non-final"));
+        assert!(text.contains("refund lock"));
+
+        let missing = AppError::tool_missing(Tool::Simc, "simc contract.simf", &std::io::Error::from(ErrorKind::NotFound));
+        assert_eq!(missing.to_string(), "simc could not be started (not found): simc contract.simf");
+        assert!(missing.guidance().contains("which simc"));
+
+        let signature = AppError::SignatureMismatch {
+            detail: "Jet failed during execution".to_string(),
+            signers: vec!["aa".repeat(32), "bb".repeat(32)],
+            threshold: Some(2),
+        };
+        assert!(signature.guidance().contains("Position 1: signature for bbbb"));
+        assert_eq!(cli_error_code("error code: -5\nerror message:\nNo such mempool transaction"), Some(-5));
+    }
+}
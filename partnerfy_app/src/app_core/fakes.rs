@@ -0,0 +1,416 @@
+//! In-memory node and tools for tests
+//!
+//! [`FakeNode`] keeps a UTXO set, builds and finalizes PSETs and accepts transactions
+//! that spend it, enough for the workflow steps to run without elementsd. [`FakeHal`]
+//! stands in for simc and hal-simplicity: every source compiles to the same program,
+//! PSET inputs are updated and signed in Rust, and any operation can be scripted to fail.
+
+use super::elements_rpc::NodeBackend;
+use super::error::AppError;
+use super::hal_wrapper::{HalBackend, PsetInputUpdate};
+use super::models::UtxoInfo;
+use super::qr::TESTNET_LBTC;
+use super::sighash;
+use super::taproot::{self, HAL_INFO_INTERNAL_KEY};
+use anyhow::{Context, Result};
+use base64::Engine;
+use elements::confidential::{Asset, Nonce, Value as ConfValue};
+use elements::hashes::Hash;
+use elements::pset::{Input, Output, PartiallySignedTransaction};
+use elements::{Address, AddressParams, AssetId, LockTime, OutPoint, Script, Sequence, Transaction, TxIn, TxOut, Txid};
+use serde_json::{json, Value};
+use simplicity::jet::Elements;
+use simplicity::node::{ConstructNode, CoreConstructible};
+use simplicity::types;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// A valid compiled program (`unit`), base64 as `simc` prints it
+pub fn unit_program() -> String {
+    let ctx = types::Context::new();
+    let program = Arc::<ConstructNode<Elements>>::unit(&ctx)
+        .finalize_types()
+        .expect("unit is a program");
+    base64::engine::general_purpose::STANDARD.encode(program.to_vec_without_witness())
+}
+
+fn decode_pset(pset_base64: &str) -> Result<PartiallySignedTransaction> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(pset_base64.trim())
+        .context("PSET is not valid base64")?;
+    elements::encode::deserialize(&bytes).context("Failed to decode PSET")
+}
+
+fn encode_pset(pset: &PartiallySignedTransaction) -> String {
+    base64::engine::general_purpose::STANDARD.encode(elements::encode::serialize(pset))
+}
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn explicit_value(txout: &TxOut) -> u64 {
+    txout.value.explicit().unwrap_or(0)
+}
+
+fn address_of(script: &Script) -> Option<String> {
+    Address::from_script(script, None, &AddressParams::LIQUID_TESTNET).map(|a| a.to_string())
+}
+
+#[derive(Default)]
+struct Chain {
+    height: u64,
+    median_time: u64,
+    utxos: BTreeMap<OutPoint, TxOut>,
+    accepted: Vec<Transaction>,
+    fundings: u32,
+    rejection: Option<(i64, String)>,
+}
+
+/// Node with an in-memory UTXO set
+///
+/// Only explicit (unblinded) L-BTC outputs are modelled. A transaction is accepted when
+/// its inputs are unspent, it is final at the current tip, contract inputs carry a
+/// witness and the value in equals the value out; the Simplicity program itself is not run.
+pub struct FakeNode {
+    chain: Mutex<Chain>,
+}
+
+impl Default for FakeNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeNode {
+    pub fn new() -> Self {
+        Self {
+            chain: Mutex::new(Chain {
+                height: 1_000,
+                median_time: 1_700_000_000,
+                ..Chain::default()
+            }),
+        }
+    }
+
+    /// Pay `sats` to `address` from outside the UTXO set, like the faucet; the funding outpoint
+    pub fn fund(&self, address: &str, sats: u64) -> Result<(String, u32)> {
+        let address = Address::from_str(address).with_context(|| format!("Invalid address: {}", address))?;
+        let mut chain = self.chain.lock().unwrap();
+        chain.fundings += 1;
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::all_zeros(), chain.fundings),
+                ..Default::default()
+            }],
+            output: vec![TxOut {
+                asset: Asset::Explicit(AssetId::from_str(TESTNET_LBTC)?),
+                value: ConfValue::Explicit(sats),
+                nonce: Nonce::Null,
+                script_pubkey: address.script_pubkey(),
+                witness: Default::default(),
+            }],
+        };
+        let txid = tx.txid();
+        chain.utxos.insert(OutPoint::new(txid, 0), tx.output[0].clone());
+        Ok((txid.to_string(), 0))
+    }
+
+    /// Move the tip, e.g. past a refund lock
+    pub fn set_tip(&self, height: u64, median_time: u64) {
+        let mut chain = self.chain.lock().unwrap();
+        chain.height = height;
+        chain.median_time = median_time;
+    }
+
+    /// Reject the next transaction with this reject code and reason
+    pub fn reject_next(&self, code: i64, reason: &str) {
+        self.chain.lock().unwrap().rejection = Some((code, reason.to_string()));
+    }
+
+    /// Transactions accepted so far, oldest first
+    pub fn accepted(&self) -> Vec<Transaction> {
+        self.chain.lock().unwrap().accepted.clone()
+    }
+
+    pub fn is_unspent(&self, txid: &str, vout: u32) -> bool {
+        Txid::from_str(txid).is_ok_and(|txid| self.chain.lock().unwrap().utxos.contains_key(&OutPoint::new(txid, vout)))
+    }
+
+    fn utxo(&self, txid: &str, vout: u32) -> Result<Option<TxOut>> {
+        let txid = Txid::from_str(txid).with_context(|| format!("Invalid txid: {}", txid))?;
+        Ok(self.chain.lock().unwrap().utxos.get(&OutPoint::new(txid, vout)).cloned())
+    }
+
+    fn accept(&self, tx: Transaction) -> std::result::Result<Txid, AppError> {
+        let mut chain = self.chain.lock().unwrap();
+        let rejected = |code: i64, reason: &str| AppError::NodeRejected { code, reason: reason.to_string() };
+        if let Some((code, reason)) = chain.rejection.take() {
+            return Err(rejected(code, &reason));
+        }
+
+        let mut value_in = 0;
+        for input in &tx.input {
+            let spent = chain
+                .utxos
+                .get(&input.previous_output)
+                .ok_or_else(|| rejected(-25, "bad-txns-inputs-missingorspent"))?;
+            if spent.script_pubkey.is_v1_p2tr() && input.witness.script_witness.is_empty() {
+                return Err(rejected(-26, "non-mandatory-script-verify-flag (Witness program was passed an empty witness)"));
+            }
+            value_in += explicit_value(spent);
+        }
+
+        let locktime = u64::from(tx.lock_time.to_consensus_u32());
+        let limit = if locktime < 500_000_000 { chain.height + 1 } else { chain.median_time };
+        if locktime != 0 && locktime >= limit && tx.input.iter().any(|i| i.sequence != Sequence::MAX) {
+            return Err(rejected(-26, "non-final"));
+        }
+
+        let value_out: u64 = tx.output.iter().map(explicit_value).sum();
+        if value_in != value_out {
+            return Err(rejected(-26, "bad-txns-in-ne-out, value in != value out"));
+        }
+
+        let txid = tx.txid();
+        for input in &tx.input {
+            chain.utxos.remove(&input.previous_output);
+        }
+        for (vout, output) in tx.output.iter().enumerate() {
+            // The fee output has an empty script and is not spendable
+            if !output.script_pubkey.is_empty() {
+                chain.utxos.insert(OutPoint::new(txid, vout as u32), output.clone());
+            }
+        }
+        chain.accepted.push(tx);
+        Ok(txid)
+    }
+}
+
+impl NodeBackend for FakeNode {
+    async fn get_blockchain_info(&self) -> Result<Value> {
+        let chain = self.chain.lock().unwrap();
+        Ok(json!({
+            "chain": "liquidtestnet",
+            "blocks": chain.height,
+            "headers": chain.height,
+            "mediantime": chain.median_time,
+            "verificationprogress": 1.0,
+            "initialblockdownload": false,
+        }))
+    }
+
+    async fn get_genesis_hash(&self) -> Result<String> {
+        Ok(sighash::LIQUID_TESTNET_GENESIS_HASH.to_string())
+    }
+
+    async fn get_txout(&self, txid: &str, vout: u32) -> Result<Value> {
+        let Some(output) = self.utxo(txid, vout)? else {
+            return Ok(Value::Null);
+        };
+        Ok(json!({
+            "confirmations": 1,
+            "value": btc(explicit_value(&output)),
+            "asset": output.asset.explicit().map(|a| a.to_string()),
+            "scriptPubKey": {
+                "hex": hex::encode(output.script_pubkey.as_bytes()),
+                "address": address_of(&output.script_pubkey),
+            },
+            "coinbase": false,
+        }))
+    }
+
+    async fn get_utxo_info(&self, txid: &str, vout: u32) -> Result<UtxoInfo> {
+        let output = self
+            .utxo(txid, vout)?
+            .with_context(|| format!("UTXO {}:{} is unknown or spent", txid, vout))?;
+        Ok(UtxoInfo {
+            script_pubkey: hex::encode(output.script_pubkey.as_bytes()),
+            asset: output.asset.explicit().map(|a| a.to_string()).unwrap_or_default(),
+            value_sats: explicit_value(&output),
+        })
+    }
+
+    async fn create_pset_with_locktime(
+        &self,
+        inputs: &[(String, u32)],
+        outputs: &[(String, f64)],
+        fee: Option<f64>,
+        locktime: Option<u32>,
+    ) -> Result<String> {
+        let asset = AssetId::from_str(TESTNET_LBTC)?;
+        let mut pset = PartiallySignedTransaction::new_v2();
+        for (txid, vout) in inputs {
+            let txid = Txid::from_str(txid).with_context(|| format!("Invalid txid: {}", txid))?;
+            let mut input = Input::from_prevout(OutPoint::new(txid, *vout));
+            if locktime.is_some() {
+                input.sequence = Some(Sequence::ENABLE_LOCKTIME_NO_RBF);
+            }
+            pset.add_input(input);
+        }
+        pset.global.tx_data.fallback_locktime = locktime.map(LockTime::from_consensus);
+
+        let sats = |amount: f64| (amount * 100_000_000.0).round() as u64;
+        let mut txouts = Vec::new();
+        for (address, amount) in outputs {
+            let address = Address::from_str(address).with_context(|| format!("Invalid address: {}", address))?;
+            txouts.push((address.script_pubkey(), sats(*amount)));
+        }
+        txouts.extend(fee.map(|fee| (Script::new(), sats(fee))));
+        for (script_pubkey, value) in txouts {
+            pset.add_output(Output::from_txout(TxOut {
+                asset: Asset::Explicit(asset),
+                value: ConfValue::Explicit(value),
+                nonce: Nonce::Null,
+                script_pubkey,
+                witness: Default::default(),
+            }));
+        }
+        Ok(encode_pset(&pset))
+    }
+
+    async fn finalize_pset(&self, pset: &str) -> Result<String> {
+        let pset = decode_pset(pset)?;
+        if let Some(i) = pset.inputs().iter().position(|input| input.final_script_witness.is_none()) {
+            return Err(anyhow::anyhow!("PSET finalization failed or incomplete: input {} has no final witness", i));
+        }
+        let tx = pset.extract_tx().context("Failed to extract the transaction")?;
+        Ok(elements::encode::serialize_hex(&tx))
+    }
+
+    async fn decode_pset(&self, pset: &str) -> Result<Value> {
+        let pset = decode_pset(pset)?;
+        let outputs: Vec<Value> = pset
+            .outputs()
+            .iter()
+            .map(|output| {
+                json!({
+                    "amount": btc(output.amount.unwrap_or(0)),
+                    "script": {
+                        "hex": hex::encode(output.script_pubkey.as_bytes()),
+                        "address": address_of(&output.script_pubkey),
+                    },
+                })
+            })
+            .collect();
+        Ok(json!({ "inputs": pset.inputs().len(), "outputs": outputs }))
+    }
+
+    async fn send_raw_transaction(&self, hex: &str) -> Result<String> {
+        let tx: Transaction = hex::decode(hex.trim())
+            .ok()
+            .and_then(|bytes| elements::encode::deserialize(&bytes).ok())
+            .ok_or_else(|| AppError::Rpc {
+                method: "sendrawtransaction".to_string(),
+                code: Some(-22),
+                message: "TX decode failed".to_string(),
+            })?;
+        Ok(self.accept(tx)?.to_string())
+    }
+}
+
+/// simc and hal-simplicity with scripted answers
+///
+/// Every `.simf` compiles to [`FakeHal::program`]; the witness comes back as the base64
+/// of the witness file, so tests can read what was signed. Calls are recorded by method
+/// name, and [`FakeHal::fail`] makes the next call of a method fail.
+pub struct FakeHal {
+    pub program: String,
+    failures: Mutex<BTreeMap<&'static str, AppError>>,
+    calls: Mutex<Vec<&'static str>>,
+}
+
+impl Default for FakeHal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeHal {
+    pub fn new() -> Self {
+        Self {
+            program: unit_program(),
+            failures: Mutex::new(BTreeMap::new()),
+            calls: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Make the next call of `method` (e.g. `"finalize_pset_with_witness"`) return `error`
+    pub fn fail(&self, method: &'static str, error: AppError) {
+        self.failures.lock().unwrap().insert(method, error);
+    }
+
+    /// Methods called so far, in order
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn call(&self, method: &'static str) -> Result<()> {
+        self.calls.lock().unwrap().push(method);
+        match self.failures.lock().unwrap().remove(method) {
+            Some(error) => Err(error.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl HalBackend for FakeHal {
+    fn simc_version(&self) -> Result<String> {
+        self.call("simc_version")?;
+        Ok("simc 0.0.0 (fake)".to_string())
+    }
+
+    fn compile_simf(&self, input_path: &str) -> Result<String> {
+        self.call("compile_simf")?;
+        std::fs::metadata(input_path).with_context(|| format!("No such file: {}", input_path))?;
+        Ok(self.program.clone())
+    }
+
+    fn compile_simf_with_witness(&self, input_path: &str, witness_path: &str) -> Result<(String, String)> {
+        self.call("compile_simf_with_witness")?;
+        std::fs::metadata(input_path).with_context(|| format!("No such file: {}", input_path))?;
+        let witness = std::fs::read(witness_path).with_context(|| format!("No such file: {}", witness_path))?;
+        Ok((self.program.clone(), base64::engine::general_purpose::STANDARD.encode(witness)))
+    }
+
+    fn get_covenant_info(&self, program_base64: &str) -> Result<String> {
+        self.call("get_covenant_info")?;
+        let cmr = taproot::program_cmr(program_base64)?;
+        let address = taproot::contract_address(&cmr, HAL_INFO_INTERNAL_KEY, &AddressParams::LIQUID_TESTNET)?;
+        Ok(json!({ "cmr": cmr, "liquid_testnet_address_unconf": address.to_string() }).to_string())
+    }
+
+    fn update_pset_input(&self, pset_base64: &str, update: &PsetInputUpdate) -> Result<String> {
+        self.call("update_pset_input")?;
+        update.apply_natively(pset_base64)
+    }
+
+    fn sighash_and_sign(&self, pset_base64: &str, input_index: u32, cmr: &str, genesis_hash: &str, privkey: &str) -> Result<String> {
+        self.call("sighash_and_sign")?;
+        let sighash = sighash::sig_all_hash(pset_base64, input_index, cmr, genesis_hash)?;
+        sighash::sign_hash(&sighash, privkey)
+    }
+
+    /// Final witness as for a Simplicity tap leaf: witness, program, leaf script, control block
+    fn finalize_pset_with_witness(&self, pset_base64: &str, input_index: u32, program: &str, witness: &str) -> Result<String> {
+        self.call("finalize_pset_with_witness")?;
+        let decode = |value: &str| base64::engine::general_purpose::STANDARD.decode(value.trim());
+        let (witness, program) = (decode(witness)?, decode(program)?);
+        let mut pset = decode_pset(pset_base64)?;
+        let input = pset
+            .inputs_mut()
+            .get_mut(input_index as usize)
+            .with_context(|| format!("PSET has no input {}", input_index))?;
+        let (control_block, (leaf, _)) = input
+            .tap_scripts
+            .iter()
+            .next()
+            .with_context(|| format!("Input {} has no Simplicity leaf", input_index))?;
+        let stack = vec![witness, program, leaf.to_bytes(), control_block.serialize()];
+        input.final_script_witness = Some(stack);
+        Ok(encode_pset(&pset))
+    }
+}
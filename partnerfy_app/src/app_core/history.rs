@@ -0,0 +1,384 @@
+//! Transaction history of a contract address
+//!
+//! Lists every transaction that funds or spends a contract, with its outputs and
+//! confirmation status. The address index comes from the Blockstream (Esplora) API,
+//! since elementsd has none; the tip height and raw transactions come from the node
+//! where it has them. For each spend of the contract the witness is checked against the
+//! keys in the contract record, so the history shows which signers actually signed.
+
+use super::elements_rpc::ElementsRPC;
+use super::models::Contract;
+use super::sighash;
+use super::templates::TemplateRegistry;
+use anyhow::{Context, Result};
+use elements::{Transaction, TxOut};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+/// Confirmation status of a transaction
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TxStatus {
+    Unconfirmed,
+    Confirmed {
+        height: u64,
+        /// `None` when the tip height could not be fetched
+        confirmations: Option<u64>,
+        /// Block time (UNIX seconds)
+        time: Option<u64>,
+    },
+}
+
+impl TxStatus {
+    /// Status from an Esplora `status` object
+    pub fn from_esplora(status: &Value, tip_height: Option<u64>) -> Self {
+        match status["block_height"].as_u64() {
+            Some(height) if status["confirmed"].as_bool() == Some(true) => TxStatus::Confirmed {
+                height,
+                confirmations: tip_height.map(|tip| tip.saturating_sub(height) + 1),
+                time: status["block_time"].as_u64(),
+            },
+            _ => TxStatus::Unconfirmed,
+        }
+    }
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxStatus::Unconfirmed => write!(f, "Unconfirmed (in mempool)"),
+            TxStatus::Confirmed { height, confirmations: Some(n), .. } => {
+                write!(f, "{} confirmation{} (block {})", n, if *n == 1 { "" } else { "s" }, height)
+            }
+            TxStatus::Confirmed { height, confirmations: None, .. } => write!(f, "Confirmed in block {}", height),
+        }
+    }
+}
+
+/// One output of a transaction in the history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryOutput {
+    pub index: u32,
+    pub address: Option<String>,
+    /// `None` for confidential outputs
+    pub value_sats: Option<u64>,
+    /// Pays back to the contract address (funding or covenant change)
+    pub to_contract: bool,
+    pub fee: bool,
+}
+
+/// An input of a transaction that spends a contract output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContractSpend {
+    pub input: u32,
+    pub prev_txid: String,
+    pub prev_vout: u32,
+    pub value_sats: Option<u64>,
+    /// Labels of the known keys with a valid signature in the witness
+    pub signers: Vec<String>,
+    /// Why the signers could not be determined, if they could not
+    pub note: Option<String>,
+}
+
+/// A transaction that funds or spends the contract
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryEntry {
+    pub txid: String,
+    pub status: TxStatus,
+    pub outputs: Vec<HistoryOutput>,
+    /// Inputs spending the contract; empty for a funding
+    pub spends: Vec<ContractSpend>,
+    pub fee_sats: Option<u64>,
+}
+
+impl HistoryEntry {
+    pub fn kind(&self) -> &'static str {
+        if self.spends.is_empty() {
+            "Funding"
+        } else if self.outputs.iter().any(|o| o.to_contract) {
+            "Redemption (change back to the contract)"
+        } else {
+            "Spend"
+        }
+    }
+
+    /// Amount paid to the contract
+    pub fn received_sats(&self) -> u64 {
+        self.outputs.iter().filter(|o| o.to_contract).filter_map(|o| o.value_sats).sum()
+    }
+
+    /// Amount taken from the contract
+    pub fn spent_sats(&self) -> u64 {
+        self.spends.iter().filter_map(|s| s.value_sats).sum()
+    }
+}
+
+/// History of a contract address, newest transaction first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContractHistory {
+    pub address: String,
+    pub template_id: Option<String>,
+    pub tip_height: Option<u64>,
+    /// Keys the witnesses were checked against, as `(label, x-only pubkey)`
+    pub known_keys: Vec<(String, String)>,
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl ContractHistory {
+    /// Value of the contract outputs not spent by any transaction in the history
+    pub fn balance_sats(&self) -> u64 {
+        let spent: HashSet<(&str, u32)> = self
+            .entries
+            .iter()
+            .flat_map(|e| e.spends.iter().map(|s| (s.prev_txid.as_str(), s.prev_vout)))
+            .collect();
+        self.entries
+            .iter()
+            .flat_map(|e| {
+                e.outputs
+                    .iter()
+                    .filter(|o| o.to_contract && !spent.contains(&(e.txid.as_str(), o.index)))
+                    .filter_map(|o| o.value_sats)
+            })
+            .sum()
+    }
+}
+
+/// Fetch the history of a contract address
+///
+/// `record` (from the store) supplies the keys witnesses are checked against; without it
+/// the history is still listed but no signers are identified.
+pub async fn contract_history(
+    rpc: &ElementsRPC,
+    registry: &TemplateRegistry,
+    record: Option<&Contract>,
+    address: &str,
+) -> Result<ContractHistory> {
+    let tip_height = rpc.get_tip_height().await.ok();
+    let txs = rpc
+        .get_address_txs(address)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch the history of {}: {}", address, e))?;
+    let known_keys = record.map(|r| known_keys(registry, r)).unwrap_or_default();
+    let genesis_hash = record.map(|r| r.genesis_hash.as_str()).unwrap_or_default();
+
+    let mut entries = Vec::new();
+    for tx in &txs {
+        let mut entry = parse_entry(tx, address, tip_height)?;
+        if !entry.spends.is_empty() {
+            identify_signers(rpc, &mut entry, &known_keys, genesis_hash).await;
+        }
+        entries.push(entry);
+    }
+
+    Ok(ContractHistory {
+        address: address.to_string(),
+        template_id: record.map(|r| r.template_id.clone()).filter(|id| !id.is_empty()),
+        tip_height,
+        known_keys,
+        entries,
+    })
+}
+
+/// Keys that can appear in the contract's witnesses: signers, promoter and oracle
+pub fn known_keys(registry: &TemplateRegistry, record: &Contract) -> Vec<(String, String)> {
+    let Some(template) = registry.get(&record.template_id) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<(String, String)> = template
+        .signer_pubkeys(&record.params)
+        .into_iter()
+        .enumerate()
+        .map(|(i, pk)| (format!("Signer {}", i + 1), pk))
+        .collect();
+    if let Some((pk, _)) = template.refund(&record.params) {
+        keys.push(("Promoter (refund)".to_string(), pk));
+    }
+    if let Some(pk) = template.oracle_pubkey(&record.params) {
+        keys.push(("Oracle".to_string(), pk));
+    }
+    keys
+}
+
+/// Read an Esplora transaction into a history entry (signers are filled in later)
+pub fn parse_entry(tx: &Value, address: &str, tip_height: Option<u64>) -> Result<HistoryEntry> {
+    let txid = tx["txid"].as_str().context("Transaction without txid in address history")?;
+    let status = TxStatus::from_esplora(&tx["status"], tip_height);
+
+    let outputs = tx["vout"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, out)| {
+            let out_address = out["scriptpubkey_address"].as_str().map(str::to_string);
+            HistoryOutput {
+                index: i as u32,
+                to_contract: out_address.as_deref() == Some(address),
+                address: out_address,
+                value_sats: out["value"].as_u64(),
+                fee: out["scriptpubkey_type"].as_str() == Some("fee"),
+            }
+        })
+        .collect();
+
+    let spends = tx["vin"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| input["prevout"]["scriptpubkey_address"].as_str() == Some(address))
+        .map(|(i, input)| ContractSpend {
+            input: i as u32,
+            prev_txid: input["txid"].as_str().unwrap_or_default().to_string(),
+            prev_vout: input["vout"].as_u64().unwrap_or_default() as u32,
+            value_sats: input["prevout"]["value"].as_u64(),
+            signers: Vec::new(),
+            note: None,
+        })
+        .collect();
+
+    Ok(HistoryEntry {
+        txid: txid.to_string(),
+        status,
+        outputs,
+        spends,
+        fee_sats: tx["fee"].as_u64(),
+    })
+}
+
+/// Check the witness of each contract spend in `entry` against the known keys
+async fn identify_signers(rpc: &ElementsRPC, entry: &mut HistoryEntry, keys: &[(String, String)], genesis_hash: &str) {
+    if keys.is_empty() {
+        for spend in &mut entry.spends {
+            spend.note = Some("No contract record with signer keys for this address".to_string());
+        }
+        return;
+    }
+    let (tx, prevouts) = match fetch_spend(rpc, &entry.txid).await {
+        Ok(spent) => spent,
+        Err(e) => {
+            for spend in &mut entry.spends {
+                spend.note = Some(format!("Could not check the witness: {}", e));
+            }
+            return;
+        }
+    };
+    let pubkeys: Vec<String> = keys.iter().map(|(_, pk)| pk.clone()).collect();
+    for spend in &mut entry.spends {
+        let witness = tx.input.get(spend.input as usize).and_then(|i| i.witness.script_witness.first());
+        let result = sighash::spent_sig_all_hash(&tx, &prevouts, spend.input, genesis_hash)
+            .and_then(|hash| witness.map(|w| (hash, w)).context("Input has no witness"));
+        match result {
+            Ok((hash, witness)) => {
+                let found = sighash::find_signatures(&hash, witness, &pubkeys);
+                spend.signers = keys
+                    .iter()
+                    .zip(found)
+                    .filter(|(_, found)| *found)
+                    .map(|((label, _), _)| label.clone())
+                    .collect();
+                if spend.signers.is_empty() {
+                    spend.note = Some("No signature from a known key found in the witness".to_string());
+                }
+            }
+            Err(e) => spend.note = Some(format!("Could not check the witness: {}", e)),
+        }
+    }
+}
+
+/// A transaction and the outputs its inputs spend
+async fn fetch_spend(rpc: &ElementsRPC, txid: &str) -> Result<(Transaction, Vec<TxOut>)> {
+    let tx = fetch_tx(rpc, txid).await?;
+    let mut prevouts = Vec::with_capacity(tx.input.len());
+    for input in &tx.input {
+        let prev_txid = input.previous_output.txid.to_string();
+        let prev = fetch_tx(rpc, &prev_txid).await?;
+        let output = prev
+            .output
+            .get(input.previous_output.vout as usize)
+            .cloned()
+            .with_context(|| format!("{} has no output {}", prev_txid, input.previous_output.vout))?;
+        prevouts.push(output);
+    }
+    Ok((tx, prevouts))
+}
+
+async fn fetch_tx(rpc: &ElementsRPC, txid: &str) -> Result<Transaction> {
+    let hex = rpc.get_raw_transaction_hex(txid).await?;
+    let bytes = hex::decode(&hex).with_context(|| format!("Transaction {} is not valid hex", txid))?;
+    elements::encode::deserialize(&bytes).with_context(|| format!("Failed to decode transaction {}", txid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONTRACT: &str = "tex1pcontract";
+
+    fn funding() -> Value {
+        json!({
+            "txid": "aa",
+            "vin": [{ "txid": "00", "vout": 1, "prevout": { "scriptpubkey_address": "tex1qfaucet", "value": 200_000 } }],
+            "vout": [
+                { "scriptpubkey_address": CONTRACT, "scriptpubkey_type": "v1_p2tr", "value": 100_000 },
+                { "scriptpubkey_address": "tex1qfaucet", "scriptpubkey_type": "v0_p2wpkh", "value": 99_700 },
+                { "scriptpubkey_type": "fee", "value": 300 }
+            ],
+            "fee": 300,
+            "status": { "confirmed": true, "block_height": 100, "block_time": 1_700_000_000 }
+        })
+    }
+
+    fn redemption() -> Value {
+        json!({
+            "txid": "bb",
+            "vin": [{ "txid": "aa", "vout": 0, "prevout": { "scriptpubkey_address": CONTRACT, "value": 100_000 } }],
+            "vout": [
+                { "scriptpubkey_address": "tex1qpartner", "scriptpubkey_type": "v1_p2tr", "value": 30_000 },
+                { "scriptpubkey_address": CONTRACT, "scriptpubkey_type": "v1_p2tr", "value": 69_900 },
+                { "scriptpubkey_type": "fee", "value": 100 }
+            ],
+            "fee": 100,
+            "status": { "confirmed": false }
+        })
+    }
+
+    #[test]
+    fn reads_fundings_and_spends() {
+        let funded = parse_entry(&funding(), CONTRACT, Some(105)).unwrap();
+        assert_eq!(funded.kind(), "Funding");
+        assert_eq!(funded.received_sats(), 100_000);
+        assert_eq!(
+            funded.status,
+            TxStatus::Confirmed { height: 100, confirmations: Some(6), time: Some(1_700_000_000) }
+        );
+        assert!(funded.outputs[2].fee);
+
+        let redeemed = parse_entry(&redemption(), CONTRACT, Some(105)).unwrap();
+        assert_eq!(redeemed.kind(), "Redemption (change back to the contract)");
+        assert_eq!(redeemed.status, TxStatus::Unconfirmed);
+        assert_eq!(redeemed.spent_sats(), 100_000);
+        assert_eq!((redeemed.spends[0].prev_txid.as_str(), redeemed.spends[0].prev_vout), ("aa", 0));
+    }
+
+    #[test]
+    fn balance_counts_unspent_contract_outputs() {
+        let history = ContractHistory {
+            address: CONTRACT.to_string(),
+            template_id: None,
+            tip_height: Some(105),
+            known_keys: Vec::new(),
+            entries: vec![
+                parse_entry(&redemption(), CONTRACT, Some(105)).unwrap(),
+                parse_entry(&funding(), CONTRACT, Some(105)).unwrap(),
+            ],
+        };
+        assert_eq!(history.balance_sats(), 69_900);
+    }
+}
@@ -0,0 +1,239 @@
+//! Minimal JSON-over-HTTP/1.1 plumbing for the helper services in `src/bin`
+//!
+//! One request per connection, bodies read by `Content-Length`, JSON responses with
+//! `Connection: close`. Enough for the oracle signer and the API server, which sit behind
+//! a reverse proxy when exposed beyond localhost. Slow clients are cut off after
+//! [`READ_TIMEOUT`] / [`WRITE_TIMEOUT`], and the servers answer 503 past [`MAX_CONNECTIONS`].
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Largest request we are willing to read
+pub const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+/// How long a client has to send its whole request
+pub const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a client has to take the response
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections served at once; further ones get a 503
+pub const MAX_CONNECTIONS: usize = 64;
+
+/// A parsed request
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Path segments, e.g. `["vouchers", "<txid>", "0"]` for `/vouchers/<txid>/0`
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|s| !s.is_empty()).collect()
+    }
+
+    /// Value of a query string parameter (not percent-decoded)
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Read one request from the stream
+///
+/// Returns `None` when the client closed the connection before sending a request, or
+/// when the request was too large or too slow (a 413 or 408 response has then already
+/// been sent).
+pub async fn read_request<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<Option<Request>> {
+    read_request_within(stream, READ_TIMEOUT).await
+}
+
+/// [`read_request`] with the client given `limit` instead of [`READ_TIMEOUT`]
+pub async fn read_request_within<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    limit: Duration,
+) -> Result<Option<Request>> {
+    match tokio::time::timeout(limit, read(stream)).await {
+        Ok(request) => request,
+        Err(_) => {
+            write_response(stream, 408, &json!({"error": "Request timed out"})).await?;
+            Ok(None)
+        }
+    }
+}
+
+async fn read<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<Option<Request>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    // Read until the end of the headers, then the body according to Content-Length
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_REQUEST_SIZE {
+            write_response(stream, 413, &json!({"error": "Request too large"})).await?;
+            return Ok(None);
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_SIZE {
+        write_response(stream, 413, &json!({"error": "Request too large"})).await?;
+        return Ok(None);
+    }
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body_end = (header_end + content_length).min(buf.len());
+
+    Ok(Some(Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: buf[header_end..body_end].to_vec(),
+    }))
+}
+
+/// Write a JSON response and close the connection
+pub async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, status: u16, body: &Value) -> Result<()> {
+    let response = response_bytes(status, body);
+    let write = async {
+        stream.write_all(&response).await?;
+        stream.shutdown().await
+    };
+    tokio::time::timeout(WRITE_TIMEOUT, write)
+        .await
+        .map_err(|_| anyhow!("Timed out writing the response"))??;
+    Ok(())
+}
+
+/// Answer a connection accepted past [`MAX_CONNECTIONS`] with a 503, without waiting on the client
+pub fn refuse_busy(stream: TcpStream) {
+    let _ = stream.try_write(&response_bytes(503, &json!({"error": "Server busy, try again later"})));
+}
+
+fn response_bytes(status: u16, body: &Value) -> Vec<u8> {
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    let body = body.to_string();
+    format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    /// Feed `input` to `read_request_within` and return what it parsed and what it answered
+    async fn exchange(input: &[u8], limit: Duration) -> (Option<Request>, String) {
+        let (mut client, mut server) = duplex(4 * MAX_REQUEST_SIZE);
+        client.write_all(input).await.unwrap();
+        let request = read_request_within(&mut server, limit).await.unwrap();
+        if request.is_some() {
+            write_response(&mut server, 200, &json!({"ok": true})).await.unwrap();
+        }
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).await.unwrap();
+        (request, answer)
+    }
+
+    #[tokio::test]
+    async fn parses_a_request_with_a_body() {
+        let input = b"POST /redemptions?x=1 HTTP/1.1\r\nAuthorization: Bearer t\r\nContent-Length: 7\r\n\r\n{\"a\":1}";
+        let (request, answer) = exchange(input, READ_TIMEOUT).await;
+        let request = request.unwrap();
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/redemptions"));
+        assert_eq!(request.query_param("x"), Some("1"));
+        assert_eq!(request.header("authorization"), Some("Bearer t"));
+        assert_eq!(request.body, b"{\"a\":1}");
+        assert!(answer.starts_with("HTTP/1.1 200 OK\r\n"));
+    }
+
+    #[tokio::test]
+    async fn oversized_requests_get_a_413() {
+        let declared = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_REQUEST_SIZE + 1);
+        let (request, answer) = exchange(declared.as_bytes(), READ_TIMEOUT).await;
+        assert!(request.is_none());
+        assert!(answer.starts_with("HTTP/1.1 413 "));
+
+        let headers = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n", "a".repeat(MAX_REQUEST_SIZE));
+        let (request, answer) = exchange(headers.as_bytes(), READ_TIMEOUT).await;
+        assert!(request.is_none());
+        assert!(answer.starts_with("HTTP/1.1 413 "));
+    }
+
+    #[tokio::test]
+    async fn slow_requests_get_a_408() {
+        let (request, answer) = exchange(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{", Duration::from_millis(50)).await;
+        assert!(request.is_none());
+        assert!(answer.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+    }
+}
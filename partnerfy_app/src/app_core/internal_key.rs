@@ -0,0 +1,296 @@
+//! Taproot internal key management
+//!
+//! A Simplicity contract lives in a Taproot script leaf; the output key also commits
+//! to an internal key that could spend the output directly (key path). Three choices:
+//!
+//! - [`InternalKey::Nums`]: the BIP341 NUMS point `H`, whose x coordinate is
+//!   `SHA256(uncompressed G)`. Nobody knows its discrete log, so the key path is disabled.
+//! - [`InternalKey::Unspendable`]: `H + r*G` for a random `r`. Revealing `r` proves the
+//!   key is unspendable without making every contract share the same recognisable key.
+//! - [`InternalKey::Aggregate`]: a real BIP327 (MuSig2) aggregate of the signers' keys,
+//!   so they can cooperatively spend via the key path.
+
+use anyhow::{Context, Result};
+use elements::hashes::{sha256, Hash, HashEngine};
+use elements::secp256k1_zkp::{PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// x coordinate of the BIP341 NUMS point `H`
+pub const NUMS_KEY: &str = "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0";
+
+/// Uncompressed encoding of the secp256k1 generator `G`; `SHA256` of it is [`NUMS_KEY`]
+const GENERATOR_UNCOMPRESSED: &str = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+/// Internal key a contract's Taproot output is built with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum InternalKey {
+    /// BIP341 NUMS point `H`
+    #[default]
+    Nums,
+    /// `H + tweak*G`; the tweak is the proof that nobody holds the key
+    Unspendable { key: String, tweak: String },
+    /// BIP327 aggregate of `signers`, spendable by all of them together
+    Aggregate { key: String, signers: Vec<String> },
+}
+
+impl InternalKey {
+    /// Provably unspendable key from a fresh random tweak
+    pub fn random_unspendable() -> Result<Self> {
+        loop {
+            let bytes: [u8; 32] = rand::random();
+            // Out-of-range scalars are astronomically unlikely; just draw again
+            if let Ok(tweak) = SecretKey::from_slice(&bytes) {
+                return Self::unspendable_from_tweak(&hex::encode(tweak.secret_bytes()));
+            }
+        }
+    }
+
+    /// Provably unspendable key `H + tweak*G` for a given tweak (hex)
+    pub fn unspendable_from_tweak(tweak_hex: &str) -> Result<Self> {
+        let tweak = parse_scalar(tweak_hex)?;
+        let key = tweak_nums(&tweak)?;
+        Ok(InternalKey::Unspendable {
+            key: key.to_string(),
+            tweak: hex::encode(tweak.to_be_bytes()),
+        })
+    }
+
+    /// Aggregate key of the given x-only signer keys (BIP327 KeyAgg, keys in the given order)
+    pub fn aggregate(signers: &[String]) -> Result<Self> {
+        let key = aggregate_xonly(signers)?;
+        Ok(InternalKey::Aggregate {
+            key: key.to_string(),
+            signers: signers.iter().map(|s| normalize_hex(s)).collect(),
+        })
+    }
+
+    /// x-only key (hex) as passed to `hal-simplicity ... -p`
+    pub fn key_hex(&self) -> String {
+        match self {
+            InternalKey::Nums => NUMS_KEY.to_string(),
+            InternalKey::Unspendable { key, .. } | InternalKey::Aggregate { key, .. } => key.clone(),
+        }
+    }
+
+    /// Whether someone can spend the output without running the Simplicity program
+    pub fn has_key_path(&self) -> bool {
+        matches!(self, InternalKey::Aggregate { .. })
+    }
+
+    /// Short label for the UI
+    pub fn label(&self) -> &'static str {
+        match self {
+            InternalKey::Nums => "NUMS point H (BIP341)",
+            InternalKey::Unspendable { .. } => "Provably unspendable (H + r*G)",
+            InternalKey::Aggregate { .. } => "Aggregate key (key-path spend enabled)",
+        }
+    }
+
+    /// Recompute the key from its proof and explain why it is (un)spendable
+    ///
+    /// This is what to show an auditor: every step can be reproduced with standard tools.
+    pub fn verify(&self) -> Result<String> {
+        let nums_hash = hex::encode(
+            sha256::Hash::hash(&hex::decode(GENERATOR_UNCOMPRESSED).expect("constant hex")).to_byte_array(),
+        );
+        if nums_hash != NUMS_KEY {
+            return Err(anyhow::anyhow!("SHA256(G) does not match the NUMS constant"));
+        }
+
+        match self {
+            InternalKey::Nums => Ok(format!(
+                "Internal key {} is the BIP341 NUMS point H.\nIts x coordinate is SHA256 of the uncompressed generator G ({}), so no one knows its discrete log and the key path cannot be used.",
+                NUMS_KEY, GENERATOR_UNCOMPRESSED
+            )),
+            InternalKey::Unspendable { key, tweak } => {
+                let expected = tweak_nums(&parse_scalar(tweak)?)?;
+                if expected.to_string() != normalize_hex(key) {
+                    return Err(anyhow::anyhow!(
+                        "Internal key {} does not equal H + r*G for the stored tweak r = {} (expected {})",
+                        key, tweak, expected
+                    ));
+                }
+                Ok(format!(
+                    "Internal key {} = H + r*G with r = {}.\nH is the BIP341 NUMS point (x = SHA256(G)). Anyone spending via the key path would need the discrete log of H, which no one knows, so the key path cannot be used.",
+                    key, tweak
+                ))
+            }
+            InternalKey::Aggregate { key, signers } => {
+                let expected = aggregate_xonly(signers)?;
+                if expected.to_string() != normalize_hex(key) {
+                    return Err(anyhow::anyhow!(
+                        "Internal key {} is not the BIP327 aggregate of the stored signers (expected {})",
+                        key, expected
+                    ));
+                }
+                Ok(format!(
+                    "Internal key {} is the BIP327 (MuSig2) aggregate of {} signer key(s):\n{}\nThe signers together CAN spend this output via the key path, bypassing the covenant.",
+                    key,
+                    signers.len(),
+                    signers.join("\n")
+                ))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for InternalKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.key_hex(), self.label())
+    }
+}
+
+fn normalize_hex(value: &str) -> String {
+    value.trim().trim_start_matches("0x").to_lowercase()
+}
+
+fn parse_scalar(value: &str) -> Result<Scalar> {
+    let bytes: [u8; 32] = hex::decode(normalize_hex(value))
+        .context("Tweak must be hex")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Tweak must be 32 bytes"))?;
+    Scalar::from_be_bytes(bytes).map_err(|_| anyhow::anyhow!("Tweak is not a valid scalar"))
+}
+
+fn tweak_nums(tweak: &Scalar) -> Result<XOnlyPublicKey> {
+    let secp = Secp256k1::new();
+    let nums = XOnlyPublicKey::from_str(NUMS_KEY).expect("NUMS key is a valid point");
+    let tweaked = nums
+        .public_key(elements::secp256k1_zkp::Parity::Even)
+        .add_exp_tweak(&secp, tweak)
+        .context("Tweak produced the point at infinity")?;
+    Ok(tweaked.x_only_public_key().0)
+}
+
+/// BIP340-style tagged hash
+fn tagged_hash(tag: &str, data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = sha256::Hash::hash(tag.as_bytes());
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag_hash.as_ref());
+    engine.input(tag_hash.as_ref());
+    for chunk in data {
+        engine.input(chunk);
+    }
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// BIP327 KeyAgg over x-only keys (each lifted to its even-y point)
+fn aggregate_xonly(signers: &[String]) -> Result<XOnlyPublicKey> {
+    let keys = signers
+        .iter()
+        .map(|s| {
+            XOnlyPublicKey::from_str(&normalize_hex(s))
+                .map(|k| k.public_key(elements::secp256k1_zkp::Parity::Even))
+                .with_context(|| format!("Invalid signer public key: {}", s))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(key_agg(&keys)?.x_only_public_key().0)
+}
+
+/// BIP327 KeyAgg: `Q = sum(a_i * P_i)` with `a_i = H("KeyAgg coefficient", L || P_i)`,
+/// except the second distinct key, whose coefficient is 1
+fn key_agg(keys: &[PublicKey]) -> Result<PublicKey> {
+    if keys.is_empty() {
+        return Err(anyhow::anyhow!("At least one signer key is required"));
+    }
+    let secp = Secp256k1::new();
+    let serialized: Vec<[u8; 33]> = keys.iter().map(|k| k.serialize()).collect();
+    let list: Vec<&[u8]> = serialized.iter().map(|k| k.as_slice()).collect();
+    let list_hash = tagged_hash("KeyAgg list", &list);
+    let second = serialized.iter().find(|k| **k != serialized[0]);
+
+    let mut terms = Vec::with_capacity(keys.len());
+    for (key, bytes) in keys.iter().zip(&serialized) {
+        if Some(bytes) == second {
+            terms.push(*key);
+            continue;
+        }
+        let coefficient = tagged_hash("KeyAgg coefficient", &[&list_hash, bytes]);
+        let coefficient = Scalar::from_be_bytes(coefficient)
+            .map_err(|_| anyhow::anyhow!("KeyAgg coefficient out of range"))?;
+        terms.push(key.mul_tweak(&secp, &coefficient).context("Invalid KeyAgg coefficient")?);
+    }
+    let refs: Vec<&PublicKey> = terms.iter().collect();
+    PublicKey::combine_keys(&refs).context("Aggregate key is the point at infinity")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Public keys of the BIP327 KeyAgg test vectors
+    const VECTOR_KEYS: [&str; 3] = [
+        "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        "03dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+        "023590a94e768f8e1815c2f24b4d80a8e3149316c3518ce7b7ad338368d038ca66",
+    ];
+
+    fn agg(indices: &[usize]) -> String {
+        let keys: Vec<PublicKey> = indices.iter().map(|&i| PublicKey::from_str(VECTOR_KEYS[i]).unwrap()).collect();
+        key_agg(&keys).unwrap().x_only_public_key().0.to_string()
+    }
+
+    #[test]
+    fn key_agg_matches_bip327_vectors() {
+        assert_eq!(agg(&[0, 1, 2]), "90539eede565f5d054f32cc0c220126889ed1e5d193baf15aef344fe59d4610c");
+        // Order matters
+        assert_eq!(agg(&[2, 1, 0]), "6204de8b083426dc6eaf9502d27024d53fc826bf7d2012148a0575435df54b2b");
+        // All keys equal: there is no second distinct key, every key gets a coefficient
+        assert_eq!(agg(&[0, 0, 0]), "b436e3bad62b8cd409969a224731c193d051162d8c5ae8b109306127da3aa935");
+        // Duplicates: the first key different from the first one gets coefficient 1
+        assert_eq!(agg(&[0, 0, 1, 1]), "69bc22bfa5d106306e48a20679de1d7389386124d07571d0d872686028c26a3e");
+        assert!(key_agg(&[]).is_err());
+    }
+
+    #[test]
+    fn aggregate_key_is_checked_against_its_signers() {
+        let signers: Vec<String> = VECTOR_KEYS.iter().map(|k| k[2..].to_string()).collect();
+        let key = InternalKey::aggregate(&signers).unwrap();
+        key.verify().unwrap();
+        assert!(key.has_key_path());
+
+        let InternalKey::Aggregate { key: agg_key, signers } = key else { unreachable!() };
+        let reordered = InternalKey::Aggregate { key: agg_key.clone(), signers: signers.iter().rev().cloned().collect() };
+        assert!(reordered.verify().is_err());
+        let dropped = InternalKey::Aggregate { key: agg_key, signers: signers[..2].to_vec() };
+        assert!(dropped.verify().is_err());
+        assert!(InternalKey::aggregate(&["zz".repeat(32)]).is_err());
+        assert!(InternalKey::aggregate(&[]).is_err());
+    }
+
+    #[test]
+    fn nums_is_the_bip341_h_point() {
+        // H from BIP341: lift_x(0x50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0)
+        assert_eq!(NUMS_KEY, "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0");
+        let generator = PublicKey::from_str(GENERATOR_UNCOMPRESSED).unwrap();
+        let one = SecretKey::from_slice(&[[0u8; 31].as_slice(), &[1]].concat()).unwrap();
+        assert_eq!(generator, PublicKey::from_secret_key(&Secp256k1::new(), &one));
+        InternalKey::Nums.verify().unwrap();
+        assert!(!InternalKey::Nums.has_key_path());
+    }
+
+    #[test]
+    fn unspendable_tweak_round_trips() {
+        // r = 1 gives H + G
+        let one = format!("{:064x}", 1);
+        let key = InternalKey::unspendable_from_tweak(&one).unwrap();
+        let nums = XOnlyPublicKey::from_str(NUMS_KEY).unwrap().public_key(elements::secp256k1_zkp::Parity::Even);
+        let generator = PublicKey::from_str(GENERATOR_UNCOMPRESSED).unwrap();
+        assert_eq!(key.key_hex(), nums.combine(&generator).unwrap().x_only_public_key().0.to_string());
+        key.verify().unwrap();
+
+        let random = InternalKey::random_unspendable().unwrap();
+        random.verify().unwrap();
+        let InternalKey::Unspendable { key, tweak } = random else { unreachable!() };
+        let round_trip = InternalKey::unspendable_from_tweak(&tweak).unwrap();
+        assert_eq!(round_trip.key_hex(), key);
+
+        let tampered_tweak = InternalKey::Unspendable { key: key.clone(), tweak: format!("{:064x}", 2) };
+        assert!(tampered_tweak.verify().is_err());
+        let tampered_key = InternalKey::Unspendable { key: NUMS_KEY.to_string(), tweak };
+        assert!(tampered_key.verify().is_err());
+        assert!(InternalKey::unspendable_from_tweak(&"ff".repeat(32)).is_err());
+    }
+}
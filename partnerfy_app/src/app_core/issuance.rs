@@ -0,0 +1,464 @@
+//! Bulk voucher issuance
+//!
+//! A campaign issues vouchers from a CSV of participants and amounts. Each row is
+//! matched to a recorded contract: the contract named in the row, the campaign's shared
+//! contract, or, for per-participant contracts, the recorded contract whose template
+//! parameters contain the participant's public key. The vouchers are then split into
+//! batches of at most `max_outputs` outputs, and each batch becomes one transaction
+//! built with [`TxBuilder::build_split_tx`], funded, blinded and signed by the node's
+//! wallet, and broadcast. Voucher `i` of a batch is output `i` of its transaction. Every
+//! resulting [`VoucherUTXO`] is registered against its participant in the app state.
+
+use super::audit::{self, AuditEvent};
+use super::elements_rpc::ElementsRPC;
+use super::models::{AppState, Participant, RawTransaction, TxOutput, VoucherUTXO};
+use super::qr;
+use super::store::Store;
+use super::tx_builder::TxBuilder;
+use super::workflow::MIN_FEE_SATS;
+use anyhow::{Context, Result};
+use elements::{confidential, Address, Transaction};
+use serde::Serialize;
+use serde_json::json;
+use std::str::FromStr;
+
+/// Voucher outputs per split transaction, well under the 100,000 vbyte standardness limit
+pub const DEFAULT_MAX_OUTPUTS: usize = 250;
+
+/// Fee rate in sat/vB (Liquid's minimum relay fee rate)
+pub const DEFAULT_FEE_RATE: f64 = 0.1;
+
+/// Estimated vsize of everything but the voucher outputs: wallet inputs, blinded change
+/// and the fee output
+const BASE_VSIZE: u64 = 1_500;
+
+/// Estimated vsize of one explicit L-BTC output to a Taproot covenant address
+const OUTPUT_VSIZE: u64 = 80;
+
+/// Audit log source of issuance transactions
+pub const AUDIT_SOURCE: &str = "issuance";
+
+/// One voucher to issue, as read from the CSV
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IssuanceRow {
+    /// Line in the CSV (1-based, header included)
+    pub line: usize,
+    /// Participant public key, lowercase hex
+    pub pubkey: String,
+    pub amount_sats: u64,
+    /// Contract address from the CSV's `contract` column, if given
+    pub contract: Option<String>,
+}
+
+/// A row matched to the contract the voucher is locked in
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Allocation {
+    pub line: usize,
+    pub pubkey: String,
+    pub amount_sats: u64,
+    pub address: String,
+}
+
+/// Vouchers funded by one split transaction
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Batch {
+    /// Position of the batch in the plan (0-based)
+    pub index: usize,
+    pub allocations: Vec<Allocation>,
+    pub estimated_fee_sats: u64,
+}
+
+impl Batch {
+    pub fn total_sats(&self) -> u64 {
+        self.allocations.iter().map(|a| a.amount_sats).sum()
+    }
+
+    /// The split transaction, without inputs (the wallet adds them)
+    pub fn split_tx(&self) -> Result<RawTransaction> {
+        let outputs: Vec<TxOutput> = self
+            .allocations
+            .iter()
+            .map(|a| TxOutput { address: a.address.clone(), amount: btc(a.amount_sats) })
+            .collect();
+        TxBuilder::build_split_tx(&[], &outputs)
+    }
+}
+
+/// Result of broadcasting one batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IssuedBatch {
+    pub index: usize,
+    pub txid: String,
+    pub fee_sats: u64,
+    pub vouchers: Vec<VoucherUTXO>,
+}
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+/// Lowercase hex key without the `02`/`03` prefix of compressed keys
+fn x_only(pubkey: &str) -> String {
+    let key = pubkey.trim().to_ascii_lowercase();
+    if key.len() == 66 && (key.starts_with("02") || key.starts_with("03")) {
+        key[2..].to_string()
+    } else {
+        key
+    }
+}
+
+/// Parse the issuance CSV
+///
+/// The first line is a header naming the columns: `pubkey` (or `participant`) and
+/// `amount` (L-BTC) are required, `contract` (a recorded contract address) is optional.
+/// Empty lines and lines starting with `#` are skipped. Every invalid row is reported.
+pub fn parse_csv(text: &str) -> Result<Vec<IssuanceRow>> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let (_, header) = lines.next().context("The CSV is empty.\n\nThe first line must be a header such as: pubkey,amount,contract")?;
+    let columns: Vec<String> = header.split(',').map(|c| c.trim().to_ascii_lowercase()).collect();
+    let find = |names: &[&str]| columns.iter().position(|c| names.contains(&c.as_str()));
+    let pubkey_col = find(&["pubkey", "participant"]);
+    let amount_col = find(&["amount"]);
+    let (Some(pubkey_col), Some(amount_col)) = (pubkey_col, amount_col) else {
+        return Err(anyhow::anyhow!(
+            "The CSV header must name a pubkey and an amount column, got: {}\n\nExample:\npubkey,amount,contract\n<participant key hex>,0.001,\n<participant key hex>,0.002,<contract address>",
+            header
+        ));
+    };
+    let contract_col = find(&["contract", "address"]);
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (line, text) in lines {
+        let cells: Vec<&str> = text.split(',').map(str::trim).collect();
+        let cell = |i: usize| cells.get(i).copied().unwrap_or("");
+        let pubkey = cell(pubkey_col).to_ascii_lowercase();
+        if !(pubkey.len() == 64 || pubkey.len() == 66) || hex::decode(&pubkey).is_err() {
+            errors.push(format!("Line {}: '{}' is not a public key (64 or 66 hex characters)", line, cell(pubkey_col)));
+            continue;
+        }
+        let amount_sats = match qr::parse_btc(cell(amount_col)) {
+            Ok(sats) if sats > MIN_FEE_SATS => sats,
+            Ok(_) => {
+                errors.push(format!(
+                    "Line {}: amount {} is too small; a voucher must be worth more than one redemption fee ({} sats)",
+                    line, cell(amount_col), MIN_FEE_SATS
+                ));
+                continue;
+            }
+            Err(e) => {
+                errors.push(format!("Line {}: invalid amount '{}': {}", line, cell(amount_col), e));
+                continue;
+            }
+        };
+        let contract = contract_col.map(cell).filter(|c| !c.is_empty()).map(str::to_string);
+        rows.push(IssuanceRow { line, pubkey, amount_sats, contract });
+    }
+
+    if !errors.is_empty() {
+        let shown = errors.len().min(20);
+        let more = if errors.len() > shown { format!("\n... and {} more", errors.len() - shown) } else { String::new() };
+        return Err(anyhow::anyhow!(
+            "{} invalid row(s) in the CSV:\n{}{}\n\nFix them and import again. Nothing was issued.",
+            errors.len(),
+            errors[..shown].join("\n"),
+            more
+        ));
+    }
+    if rows.is_empty() {
+        return Err(anyhow::anyhow!("The CSV has a header but no participants"));
+    }
+    Ok(rows)
+}
+
+/// Match every row to a recorded contract
+///
+/// A row's own `contract` wins, then `shared_contract`. Without either, the row needs
+/// exactly one recorded contract whose template parameters contain its public key.
+pub fn allocate(state: &AppState, rows: &[IssuanceRow], shared_contract: Option<&str>) -> Result<Vec<Allocation>> {
+    let mut allocations = Vec::new();
+    let mut errors = Vec::new();
+    for row in rows {
+        let address = match row.contract.as_deref().or(shared_contract) {
+            Some(address) => {
+                if state.contracts.iter().any(|c| c.address == address) {
+                    Ok(address.to_string())
+                } else {
+                    Err(format!("Line {}: {} is not a recorded contract", row.line, address))
+                }
+            }
+            None => {
+                let key = x_only(&row.pubkey);
+                let matches: Vec<&str> = state
+                    .contracts
+                    .iter()
+                    .filter(|c| c.params.values().any(|v| v.to_ascii_lowercase().contains(&key)))
+                    .map(|c| c.address.as_str())
+                    .collect();
+                match matches.as_slice() {
+                    [address] => Ok(address.to_string()),
+                    [] => Err(format!("Line {}: no recorded contract has {} as a parameter", row.line, row.pubkey)),
+                    _ => Err(format!(
+                        "Line {}: {} is a parameter of {} contracts ({}); name one in the contract column",
+                        row.line,
+                        row.pubkey,
+                        matches.len(),
+                        matches.join(", ")
+                    )),
+                }
+            }
+        };
+        match address {
+            Ok(address) => allocations.push(Allocation {
+                line: row.line,
+                pubkey: row.pubkey.clone(),
+                amount_sats: row.amount_sats,
+                address,
+            }),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if !errors.is_empty() {
+        let shown = errors.len().min(20);
+        return Err(anyhow::anyhow!(
+            "{} row(s) could not be matched to a contract:\n{}\n\nPick a shared contract, add a contract column, or create the per-participant contracts on the Voucher page (or with `partnerfy generate/compile/address`) first.",
+            errors.len(),
+            errors[..shown].join("\n")
+        ));
+    }
+    Ok(allocations)
+}
+
+/// Estimated fee of a split transaction with `outputs` voucher outputs
+pub fn estimate_fee_sats(outputs: usize, fee_rate: f64) -> u64 {
+    let vsize = BASE_VSIZE + OUTPUT_VSIZE * outputs as u64;
+    (vsize as f64 * fee_rate).ceil() as u64
+}
+
+/// Split the allocations into batches of at most `max_outputs` vouchers
+pub fn plan(allocations: Vec<Allocation>, max_outputs: usize, fee_rate: f64) -> Result<Vec<Batch>> {
+    if max_outputs == 0 {
+        return Err(anyhow::anyhow!("The output limit per transaction must be at least 1"));
+    }
+    if fee_rate.is_nan() || fee_rate <= 0.0 {
+        return Err(anyhow::anyhow!("The fee rate must be positive, got {} sat/vB", fee_rate));
+    }
+    Ok(allocations
+        .chunks(max_outputs)
+        .enumerate()
+        .map(|(index, chunk)| Batch {
+            index,
+            allocations: chunk.to_vec(),
+            estimated_fee_sats: estimate_fee_sats(chunk.len(), fee_rate),
+        })
+        .collect())
+}
+
+/// Amount the wallet needs for `batches`: vouchers plus estimated fees
+pub fn required_sats(batches: &[Batch]) -> u64 {
+    batches.iter().map(|b| b.total_sats() + b.estimated_fee_sats).sum()
+}
+
+/// Check that outputs `0..n` of a signed split transaction are the batch's vouchers
+pub fn check_split_outputs(tx_hex: &str, batch: &Batch) -> Result<()> {
+    let bytes = hex::decode(tx_hex.trim()).context("The signed transaction is not valid hex")?;
+    let tx: Transaction = elements::encode::deserialize(&bytes).context("The signed transaction could not be decoded")?;
+    for (vout, allocation) in batch.allocations.iter().enumerate() {
+        let output = tx
+            .output
+            .get(vout)
+            .with_context(|| format!("The signed transaction has no output {}", vout))?;
+        let script = Address::from_str(&allocation.address)
+            .with_context(|| format!("Invalid contract address: {}", allocation.address))?
+            .script_pubkey();
+        if output.script_pubkey != script || output.value != confidential::Value::Explicit(allocation.amount_sats) {
+            return Err(anyhow::anyhow!(
+                "Output {} of the signed transaction is not the voucher for line {} ({} sats to {}).\n\nThe wallet changed the outputs, so nothing was broadcast.",
+                vout, allocation.line, allocation.amount_sats, allocation.address
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Register the vouchers of a broadcast batch against their participants
+pub fn register(state: &mut AppState, txid: &str, batch: &Batch) -> Result<Vec<VoucherUTXO>> {
+    let mut vouchers = Vec::new();
+    for (vout, allocation) in batch.allocations.iter().enumerate() {
+        let script = Address::from_str(&allocation.address)
+            .with_context(|| format!("Invalid contract address: {}", allocation.address))?
+            .script_pubkey();
+        let voucher = VoucherUTXO {
+            txid: txid.to_string(),
+            vout: vout as u32,
+            amount: btc(allocation.amount_sats),
+            owner_pubkey: allocation.pubkey.clone(),
+            covenant_script: hex::encode(script.as_bytes()),
+            covenant_address: allocation.address.clone(),
+        };
+        match state.participants.iter_mut().find(|p| p.pubkey == allocation.pubkey) {
+            Some(participant) => participant.voucher_utxos.push(voucher.clone()),
+            None => state.participants.push(Participant {
+                pubkey: allocation.pubkey.clone(),
+                wallet_path: None,
+                voucher_utxos: vec![voucher.clone()],
+            }),
+        }
+        vouchers.push(voucher);
+    }
+    Ok(vouchers)
+}
+
+/// Fund, sign and broadcast one batch from the node's wallet, then register its vouchers
+pub async fn issue_batch(
+    rpc: &ElementsRPC,
+    store: &Store,
+    batch: &Batch,
+    fee_rate: f64,
+    progress: &dyn Fn(String),
+) -> Result<IssuedBatch> {
+    let asset = rpc.policy_asset().await?;
+    let unsigned = TxBuilder::to_unsigned_hex(&batch.split_tx()?, &asset)?;
+
+    progress(format!("Batch {}: funding {} vouchers from the wallet...", batch.index + 1, batch.allocations.len()));
+    // feeRate is in L-BTC per kvB; the change goes after the vouchers so their vouts stay put
+    let options = json!({
+        "feeRate": fee_rate * 1_000.0 / 100_000_000.0,
+        "changePosition": batch.allocations.len(),
+    });
+    let (funded, fee) = rpc.fund_raw_transaction(&unsigned, options).await.map_err(|e| anyhow::anyhow!(
+        "Failed to fund batch {} from the wallet: {}\n\nThe wallet needs {} L-BTC plus fees. Check its balance with `elements-cli getbalance`.",
+        batch.index + 1, e, btc(batch.total_sats())
+    ))?;
+    let blinded = rpc.blind_raw_transaction(&funded).await
+        .map_err(|e| anyhow::anyhow!("Failed to blind the change of batch {}: {}", batch.index + 1, e))?;
+    let signed = rpc.sign_raw_transaction_with_wallet(&blinded).await?;
+    if signed.get("complete").and_then(|c| c.as_bool()) != Some(true) {
+        return Err(anyhow::anyhow!(
+            "The wallet could not sign every input of batch {}: {}\n\nIs the wallet unlocked? Try `elements-cli walletpassphrase`.",
+            batch.index + 1, signed.get("errors").cloned().unwrap_or_default()
+        ));
+    }
+    let signed_hex = signed.get("hex").and_then(|h| h.as_str()).unwrap_or_default();
+    check_split_outputs(signed_hex, batch)?;
+
+    progress(format!("Batch {}: broadcasting...", batch.index + 1));
+    let txid = rpc.send_raw_transaction(signed_hex).await?;
+
+    // Save right away so a failure in a later batch does not lose these vouchers
+    let vouchers = store.update(|state| register(state, &txid, batch))?;
+    for voucher in &vouchers {
+        let event = AuditEvent::Funded {
+            address: voucher.covenant_address.clone(),
+            txid: voucher.txid.clone(),
+            vout: voucher.vout,
+            amount_sats: (voucher.amount * 100_000_000.0).round() as u64,
+        };
+        audit::record(store, AUDIT_SOURCE, event);
+    }
+
+    Ok(IssuedBatch {
+        index: batch.index,
+        txid,
+        fee_sats: (fee * 100_000_000.0).round() as u64,
+        vouchers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::models::Contract;
+    use crate::app_core::{InternalKey, TemplateValues};
+    use elements::{AddressParams, Script};
+
+    const TLBTC: &str = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+
+    /// Explicit Liquid testnet Taproot address with output key `byte` repeated
+    fn address(byte: &str) -> String {
+        let script = Script::from(hex::decode(format!("5120{}", byte.repeat(32))).unwrap());
+        Address::from_script(&script, None, &AddressParams::LIQUID_TESTNET).unwrap().to_string()
+    }
+
+    fn contract(address: &str, participant_key: &str) -> Contract {
+        let mut params = TemplateValues::new();
+        params.insert("participant_key".to_string(), participant_key.to_string());
+        Contract {
+            voucher_base64: String::new(),
+            script_pubkey: String::new(),
+            address: address.to_string(),
+            info: None,
+            template_id: String::new(),
+            params,
+            source_hash: String::new(),
+            compiler_version: String::new(),
+            cmr: String::new(),
+            control_block: String::new(),
+            internal_key: InternalKey::Nums,
+            genesis_hash: String::new(),
+            oracle_url: String::new(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn csv_rows_are_matched_batched_and_registered() {
+        let alice = "aa".repeat(32);
+        let bob = format!("02{}", "bb".repeat(32));
+        let (alice_contract, shared) = (address("11"), address("22"));
+        let csv = format!("# campaign\npubkey,amount,contract\n{},0.001,\n\n{},0.0005,{}\n{},0.002,\n", alice, bob, shared, alice);
+        let rows = parse_csv(&csv).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].line, 3);
+        assert_eq!(rows[1].contract.as_deref(), Some(shared.as_str()));
+
+        let bad = format!("pubkey,amount\nxyz,0.1\n{},0.00000050\n", alice);
+        let err = parse_csv(&bad).unwrap_err().to_string();
+        assert!(err.contains("Line 2") && err.contains("Line 3"));
+        assert!(parse_csv("name,value\n").is_err());
+
+        // Bob's row names the shared contract; Alice's are matched by her key
+        let mut state = AppState::default();
+        state.contracts.push(contract(&shared, &"33".repeat(32)));
+        assert!(allocate(&state, &rows, None).is_err());
+        state.contracts.push(contract(&alice_contract, &alice.to_uppercase()));
+        let allocations = allocate(&state, &rows, None).unwrap();
+        let addresses: Vec<&str> = allocations.iter().map(|a| a.address.as_str()).collect();
+        assert_eq!(addresses, vec![alice_contract.as_str(), shared.as_str(), alice_contract.as_str()]);
+        assert!(allocate(&state, &rows, Some(&shared)).unwrap().iter().skip(1).all(|a| a.address == shared));
+
+        let batches = plan(allocations, 2, DEFAULT_FEE_RATE).unwrap();
+        assert_eq!(batches.iter().map(|b| b.allocations.len()).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(batches[0].total_sats(), 150_000);
+        assert_eq!(required_sats(&batches), 350_000 + estimate_fee_sats(2, 0.1) + estimate_fee_sats(1, 0.1));
+        assert!(plan(Vec::new(), 0, DEFAULT_FEE_RATE).is_err());
+
+        let hex = TxBuilder::to_unsigned_hex(&batches[0].split_tx().unwrap(), TLBTC).unwrap();
+        check_split_outputs(&hex, &batches[0]).unwrap();
+        assert!(check_split_outputs(&hex, &batches[1]).is_err());
+
+        let vouchers = register(&mut state, &"cd".repeat(32), &batches[0]).unwrap();
+        assert_eq!(vouchers[1].vout, 1);
+        assert_eq!(vouchers[1].owner_pubkey, bob);
+        assert_eq!(state.participants.len(), 2);
+        assert_eq!(state.participants[0].voucher_utxos[0].owner_pubkey, alice);
+    }
+
+    #[test]
+    fn one_covenant_address_can_receive_many_vouchers() {
+        let shared = address("44");
+        let outputs: Vec<TxOutput> = (1..=3)
+            .map(|i| TxOutput { address: shared.clone(), amount: i as f64 * 0.001 })
+            .collect();
+        let hex = TxBuilder::to_unsigned_hex(&TxBuilder::build_split_tx(&[], &outputs).unwrap(), TLBTC).unwrap();
+        let tx: Transaction = elements::encode::deserialize(&hex::decode(hex).unwrap()).unwrap();
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[2].value, confidential::Value::Explicit(300_000));
+        assert!(TxBuilder::build_split_tx(&[], &[]).is_err());
+    }
+}
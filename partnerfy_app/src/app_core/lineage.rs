@@ -0,0 +1,251 @@
+//! Lineage of a covenant voucher
+//!
+//! A covenant like `cov_p2ms.simf` sends the change of every redemption back to its own
+//! script as output 1, so one funding turns into a chain of UTXOs. Starting from the
+//! funding outpoint, [`follow_lineage`] follows output 1 spend by spend until it reaches
+//! the unspent tip of the chain, recording the remaining balance at each hop and the
+//! payment (output 0) each redemption made. Spends come from the Blockstream (Esplora)
+//! spend index; the node is asked first whether an output is still unspent.
+
+use super::elements_rpc::ElementsRPC;
+use super::history::TxStatus;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Output of a spend that continues the covenant chain
+pub const CHANGE_VOUT: u32 = 1;
+
+/// Stop following after this many hops
+pub const MAX_HOPS: usize = 1000;
+
+/// An output paid by a redemption
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Payment {
+    pub address: Option<String>,
+    /// `None` for confidential outputs
+    pub value_sats: Option<u64>,
+}
+
+/// One covenant UTXO in the chain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineageHop {
+    pub txid: String,
+    pub vout: u32,
+    /// Balance left in the covenant at this hop
+    pub value_sats: Option<u64>,
+    /// Status of the transaction that created this UTXO
+    pub status: TxStatus,
+    /// Output 0 of the redemption that created this UTXO; `None` for the funding
+    pub payment: Option<Payment>,
+    pub fee_sats: Option<u64>,
+}
+
+/// How the chain ends
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "end", rename_all = "snake_case")]
+pub enum LineageEnd {
+    /// The last hop is unspent: it is the voucher's current balance
+    Unspent,
+    /// The last hop was spent without change back to the covenant (e.g. a refund sweep)
+    Closed {
+        txid: String,
+        status: TxStatus,
+        outputs: Vec<Payment>,
+    },
+    /// Stopped after [`MAX_HOPS`] hops
+    Truncated,
+}
+
+/// The chain of covenant UTXOs descending from a funding outpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Lineage {
+    pub address: Option<String>,
+    pub script_pubkey: String,
+    pub hops: Vec<LineageHop>,
+    pub end: LineageEnd,
+}
+
+impl Lineage {
+    /// Balance still held by the covenant
+    pub fn remaining_sats(&self) -> u64 {
+        match self.end {
+            LineageEnd::Unspent => self.hops.last().and_then(|h| h.value_sats).unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Total paid to output 0 across all redemptions
+    pub fn paid_sats(&self) -> u64 {
+        self.hops
+            .iter()
+            .filter_map(|h| h.payment.as_ref().and_then(|p| p.value_sats))
+            .sum()
+    }
+
+    /// Number of redemptions that continued the chain
+    pub fn redemptions(&self) -> usize {
+        self.hops.len().saturating_sub(1)
+    }
+}
+
+/// Where a spend of a covenant UTXO leads
+#[derive(Debug, Clone, PartialEq)]
+pub enum NextHop {
+    Continue(LineageHop),
+    Closed(LineageEnd),
+}
+
+/// Follow the chain of covenant UTXOs starting at `txid:vout`
+pub async fn follow_lineage(rpc: &ElementsRPC, txid: &str, vout: u32) -> Result<Lineage> {
+    let tip_height = rpc.get_tip_height().await.ok();
+    let funding = rpc
+        .get_esplora_tx(txid)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch funding transaction {}: {}", txid, e))?;
+    let output = funding["vout"]
+        .get(vout as usize)
+        .with_context(|| format!("Transaction {} has no output {}", txid, vout))?;
+    let script_pubkey = output["scriptpubkey"]
+        .as_str()
+        .context("Funding output has no scriptPubKey")?
+        .to_string();
+
+    let mut hops = vec![LineageHop {
+        txid: txid.to_string(),
+        vout,
+        value_sats: output["value"].as_u64(),
+        status: TxStatus::from_esplora(&funding["status"], tip_height),
+        payment: None,
+        fee_sats: None,
+    }];
+
+    let end = loop {
+        if hops.len() > MAX_HOPS {
+            break LineageEnd::Truncated;
+        }
+        let current = hops.last().expect("lineage starts with the funding");
+        let spender = rpc
+            .get_outspend(&current.txid, current.vout)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to look up the spend of {}:{}: {}", current.txid, current.vout, e))?;
+        let Some(spender) = spender else {
+            break LineageEnd::Unspent;
+        };
+        let spend = rpc
+            .get_esplora_tx(&spender)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to fetch spending transaction {}: {}", spender, e))?;
+        match next_hop(&spend, &script_pubkey, tip_height)? {
+            NextHop::Continue(hop) => hops.push(hop),
+            NextHop::Closed(end) => break end,
+        }
+    };
+
+    Ok(Lineage {
+        address: output["scriptpubkey_address"].as_str().map(str::to_string),
+        script_pubkey,
+        hops,
+        end,
+    })
+}
+
+/// Read a spend of a covenant UTXO: output 1 back to `script_pubkey` continues the chain
+pub fn next_hop(spend: &Value, script_pubkey: &str, tip_height: Option<u64>) -> Result<NextHop> {
+    let txid = spend["txid"].as_str().context("Spending transaction without txid")?;
+    let status = TxStatus::from_esplora(&spend["status"], tip_height);
+    let outputs = spend["vout"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let payment = |out: &Value| Payment {
+        address: out["scriptpubkey_address"].as_str().map(str::to_string),
+        value_sats: out["value"].as_u64(),
+    };
+
+    match outputs.get(CHANGE_VOUT as usize) {
+        Some(change) if change["scriptpubkey"].as_str() == Some(script_pubkey) => Ok(NextHop::Continue(LineageHop {
+            txid: txid.to_string(),
+            vout: CHANGE_VOUT,
+            value_sats: change["value"].as_u64(),
+            status,
+            payment: outputs.first().map(payment),
+            fee_sats: spend["fee"].as_u64(),
+        })),
+        _ => Ok(NextHop::Closed(LineageEnd::Closed {
+            txid: txid.to_string(),
+            status,
+            outputs: outputs
+                .iter()
+                .filter(|out| out["scriptpubkey_type"].as_str() != Some("fee"))
+                .map(payment)
+                .collect(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const COVENANT_SPK: &str = "5120aa";
+
+    fn redemption(change_spk: &str) -> Value {
+        json!({
+            "txid": "bb",
+            "vout": [
+                { "scriptpubkey": "5120cc", "scriptpubkey_address": "tex1qpartner", "scriptpubkey_type": "v1_p2tr", "value": 30_000 },
+                { "scriptpubkey": change_spk, "scriptpubkey_address": "tex1pcontract", "scriptpubkey_type": "v1_p2tr", "value": 69_900 },
+                { "scriptpubkey": "", "scriptpubkey_type": "fee", "value": 100 }
+            ],
+            "fee": 100,
+            "status": { "confirmed": true, "block_height": 10 }
+        })
+    }
+
+    #[test]
+    fn change_to_the_covenant_continues_the_chain() {
+        let NextHop::Continue(hop) = next_hop(&redemption(COVENANT_SPK), COVENANT_SPK, Some(12)).unwrap() else {
+            panic!("expected the chain to continue");
+        };
+        assert_eq!((hop.txid.as_str(), hop.vout, hop.value_sats), ("bb", 1, Some(69_900)));
+        assert_eq!(
+            hop.payment,
+            Some(Payment { address: Some("tex1qpartner".to_string()), value_sats: Some(30_000) })
+        );
+        assert_eq!(hop.status, TxStatus::Confirmed { height: 10, confirmations: Some(3), time: None });
+    }
+
+    #[test]
+    fn spend_without_covenant_change_closes_the_chain() {
+        let NextHop::Closed(LineageEnd::Closed { txid, outputs, .. }) =
+            next_hop(&redemption("5120dd"), COVENANT_SPK, None).unwrap()
+        else {
+            panic!("expected the chain to close");
+        };
+        assert_eq!(txid, "bb");
+        assert_eq!(outputs.len(), 2);
+    }
+
+    #[test]
+    fn totals_follow_the_hops() {
+        let funding = LineageHop {
+            txid: "aa".to_string(),
+            vout: 0,
+            value_sats: Some(100_000),
+            status: TxStatus::Unconfirmed,
+            payment: None,
+            fee_sats: None,
+        };
+        let NextHop::Continue(hop) = next_hop(&redemption(COVENANT_SPK), COVENANT_SPK, None).unwrap() else {
+            panic!("expected the chain to continue");
+        };
+        let mut lineage = Lineage {
+            address: None,
+            script_pubkey: COVENANT_SPK.to_string(),
+            hops: vec![funding, hop],
+            end: LineageEnd::Unspent,
+        };
+        assert_eq!((lineage.redemptions(), lineage.paid_sats(), lineage.remaining_sats()), (1, 30_000, 69_900));
+        lineage.end = LineageEnd::Truncated;
+        assert_eq!(lineage.remaining_sats(), 0);
+    }
+}
@@ -8,10 +8,43 @@ pub mod tx_builder;
 pub mod witness;
 pub mod hal_wrapper;
 pub mod models;
+pub mod oracle;
+pub mod templates;
+pub mod builtin_templates;
+pub mod internal_key;
+pub mod taproot;
+pub mod contracts;
+pub mod store;
+pub mod sighash;
+pub mod workflow;
+pub mod history;
+pub mod lineage;
+pub mod dashboard;
+pub mod redemption;
+pub mod participant;
+pub mod qr;
+pub mod platform;
+pub mod audit;
+pub mod issuance;
+pub mod reports;
+pub mod address_book;
+pub mod csv;
+pub mod error;
+#[cfg(test)]
+pub mod fakes;
+#[cfg(feature = "server")]
+pub mod http;
+#[cfg(feature = "server")]
+pub mod api;
 
 pub use elements_rpc::ElementsRPC;
 pub use tx_builder::TxBuilder;
 pub use witness::WitnessBuilder;
 pub use hal_wrapper::HalWrapper;
 pub use models::*;
+pub use oracle::{OracleAttestation, OracleClient, OracleSigner};
+pub use templates::{CovenantTemplate, TemplateRegistry, TemplateValues};
+pub use store::Store;
+pub use internal_key::InternalKey;
+pub use workflow::{Workflow, WorkflowState};
 
@@ -1,10 +1,17 @@
 //! Data models for Partnerfy
 
+use super::internal_key::InternalKey;
+use super::sighash;
+use super::templates::TemplateValues;
+use super::workflow::WorkflowState;
+use anyhow::{Context, Result};
+use elements::{Address, AddressParams};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
 /// Voucher UTXO information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VoucherUTXO {
     pub txid: String,
     pub vout: u32,
@@ -15,16 +22,57 @@ pub struct VoucherUTXO {
 }
 
 /// Compiled Simplicity covenant contract
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Besides the program and address, the record keeps everything needed to reproduce
+/// the address (see `app_core::contracts::verify_contract`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Contract {
     pub voucher_base64: String,
     pub script_pubkey: String,
     pub address: String,
     pub info: Option<String>, // Output from hal-simplicity info
+    /// Covenant template the source was rendered from
+    #[serde(default)]
+    pub template_id: String,
+    /// Template parameters the source was rendered with
+    #[serde(default)]
+    pub params: TemplateValues,
+    /// SHA256 (hex) of the `.simf` source, empty if the program was pasted without source
+    #[serde(default)]
+    pub source_hash: String,
+    /// `simc --version` at compile time
+    #[serde(default)]
+    pub compiler_version: String,
+    #[serde(default)]
+    pub cmr: String,
+    /// Control block (hex) for spending the Simplicity leaf
+    #[serde(default)]
+    pub control_block: String,
+    /// Taproot internal key the Simplicity leaf is committed under, with its proof
+    #[serde(default)]
+    pub internal_key: InternalKey,
+    /// Genesis hash of the chain the contract lives on; signatures commit to it
+    #[serde(default = "sighash::default_genesis_hash")]
+    pub genesis_hash: String,
+    /// Oracle signer that attests redemptions, for covenants that need an attestation
+    #[serde(default)]
+    pub oracle_url: String,
+    /// UNIX time the record was created
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl Contract {
+    /// Address parameters of the network the contract lives on, read from its address
+    pub fn network(&self) -> Result<&'static AddressParams> {
+        Address::from_str(&self.address)
+            .map(|a| a.params)
+            .with_context(|| format!("The contract record has an invalid address: {}", self.address))
+    }
 }
 
 /// Partner information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Partner {
     pub address: String,
     pub pubkey: Option<String>,
@@ -39,6 +87,50 @@ pub struct Participant {
     pub voucher_utxos: Vec<VoucherUTXO>,
 }
 
+/// A partner's decision on a redemption PSET
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// The partner added their signature for the multisig slot of `pubkey`
+    CoSigned { pubkey: String, signature: String },
+    Rejected { reason: String },
+}
+
+/// Outcome of a partner reviewing a redemption PSET (see `app_core::redemption`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedemptionReview {
+    /// Txid of the unsigned transaction, which identifies the PSET across signatures
+    pub unsigned_txid: String,
+    pub contract_address: String,
+    pub partner_address: String,
+    pub amount_sats: Option<u64>,
+    pub fee_sats: Option<u64>,
+    pub decision: ReviewDecision,
+    /// UNIX time of the decision
+    pub reviewed_at: u64,
+}
+
+/// A redemption a participant started (see `app_core::participant`)
+///
+/// The PSET and its signing progress live in the workflow saved under `workflow`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedemptionRequest {
+    /// Name of the saved workflow that builds, signs and broadcasts the redemption
+    pub workflow: String,
+    pub participant_pubkey: String,
+    /// Voucher outpoint being spent
+    pub voucher_txid: String,
+    pub voucher_vout: u32,
+    pub contract_address: String,
+    pub partner_address: String,
+    pub amount_sats: u64,
+    pub fee_sats: u64,
+    /// Txid of the redemption transaction, known before it is signed
+    pub unsigned_txid: String,
+    /// UNIX time the redemption was started
+    pub created_at: u64,
+}
+
 /// Witness data for transaction signing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Witness {
@@ -69,26 +161,149 @@ impl Default for Settings {
     }
 }
 
+impl Settings {
+    /// Directory for Partnerfy's own files (`$PARTNERFY_HOME`, or `~/.partnerfy`)
+    pub fn data_dir() -> std::path::PathBuf {
+        if let Ok(dir) = std::env::var("PARTNERFY_HOME") {
+            return std::path::PathBuf::from(dir);
+        }
+        let home = std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .unwrap_or_else(|_| ".".to_string());
+        std::path::PathBuf::from(home).join(".partnerfy")
+    }
+}
+
 /// Application state
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppState {
+    /// RPC credentials are not written to disk with the rest of the state
+    #[serde(skip)]
     pub settings: Settings,
-    pub contract: Option<Contract>,
+    #[serde(default)]
+    pub contracts: Vec<Contract>,
+    #[serde(default)]
     pub partners: Vec<Partner>,
+    #[serde(default)]
     pub participants: Vec<Participant>,
+    #[serde(default)]
     pub vouchers: Vec<VoucherUTXO>,
+    /// Saved progress of the contract pages, by workflow name
+    #[serde(default)]
+    pub workflows: BTreeMap<String, WorkflowState>,
+    /// Partner decisions on redemption PSETs, oldest first
+    #[serde(default)]
+    pub reviews: Vec<RedemptionReview>,
+    /// Redemptions started by participants, oldest first
+    #[serde(default)]
+    pub redemptions: Vec<RedemptionRequest>,
+    /// Role last picked on the landing page
+    #[serde(default)]
+    pub role: Option<Role>,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            settings: Settings::default(),
-            contract: None,
-            partners: Vec::new(),
-            participants: Vec::new(),
-            vouchers: Vec::new(),
+/// Who is using the app (see the actor table in the README)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Deploys and funds covenants and distributes vouchers
+    Promoter,
+    /// Holds vouchers and redeems them at partners
+    Participant,
+    /// Reviews and co-signs redemptions
+    Partner,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Promoter, Role::Participant, Role::Partner];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Promoter => "Promoter",
+            Role::Participant => "Participant",
+            Role::Partner => "Partner",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Role::Promoter => "Compile and fund voucher covenants, distribute vouchers and follow their history.",
+            Role::Participant => "See your voucher balances and redeem them at partners.",
+            Role::Partner => "Check redemptions participants send you and co-sign the valid ones.",
+        }
+    }
+}
+
+/// Lock condition for the promoter refund path of a voucher covenant
+///
+/// Values below 500,000,000 are block heights, anything above is a UNIX
+/// timestamp, matching the nLockTime interpretation used by consensus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefundLock {
+    /// Refund allowed once the chain reaches this block height (`jet::check_lock_height`)
+    Height(u32),
+    /// Refund allowed once median time past exceeds this timestamp (`jet::check_lock_time`)
+    Time(u32),
+}
+
+impl RefundLock {
+    /// Threshold separating height-based from time-based lock times
+    pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+    /// Build a lock from a raw value, validating it against the chosen kind
+    pub fn new(kind: &str, value: u32) -> Result<Self, String> {
+        match kind {
+            "height" if value < Self::LOCKTIME_THRESHOLD => Ok(RefundLock::Height(value)),
+            "height" => Err(format!(
+                "Block height {} is too large (must be below {})",
+                value,
+                Self::LOCKTIME_THRESHOLD
+            )),
+            "time" if value >= Self::LOCKTIME_THRESHOLD => Ok(RefundLock::Time(value)),
+            "time" => Err(format!(
+                "Timestamp {} is too small (must be at least {})",
+                value,
+                Self::LOCKTIME_THRESHOLD
+            )),
+            other => Err(format!("Unknown lock kind: {}", other)),
+        }
+    }
+
+    /// The nLockTime the refund transaction must carry
+    pub fn locktime(&self) -> u32 {
+        match self {
+            RefundLock::Height(h) => *h,
+            RefundLock::Time(t) => *t,
         }
     }
+
+    /// Whether a transaction with this lock time can be mined on top of the given tip
+    ///
+    /// Height locks need `locktime <= tip height`; time locks need
+    /// `locktime < median time past` (BIP113).
+    pub fn is_expired(&self, tip_height: u64, median_time: u64) -> bool {
+        match self {
+            RefundLock::Height(h) => u64::from(*h) <= tip_height,
+            RefundLock::Time(t) => u64::from(*t) < median_time,
+        }
+    }
+}
+
+impl std::fmt::Display for RefundLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefundLock::Height(h) => write!(f, "block height {}", h),
+            RefundLock::Time(t) => write!(f, "UNIX time {}", t),
+        }
+    }
+}
+
+/// Details of an unspent output needed to update a Simplicity PSET input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtxoInfo {
+    pub script_pubkey: String,
+    pub asset: String,
+    pub value_sats: u64,
 }
 
 /// Transaction output specification
@@ -0,0 +1,279 @@
+//! Oracle attestations for redemption
+//!
+//! An oracle (e.g. the partner's point-of-sale) attests that a real purchase took place
+//! by signing a BIP340 message that binds the invoice to one redemption:
+//!
+//! `message = SHA256(SHA256(invoice_id) || SHA256(output 0 scriptPubKey) || output 0 amount
+//! || spent txid || spent vout)`
+//!
+//! with the amount as 8 and the vout as 4 big-endian bytes, and the txid in its internal
+//! byte order. The covenant recomputes the same message from `witness::INVOICE_HASH`,
+//! `jet::output_script_hash(0)`, `jet::output_amount(0)` and `jet::current_prev_outpoint`,
+//! so an attestation cannot be reused for another partner, another amount or a later
+//! spend of the same voucher.
+
+use anyhow::{Context, Result};
+use elements::hashes::{sha256, Hash};
+use elements::secp256k1_zkp::{schnorr, Keypair, Message, Secp256k1, XOnlyPublicKey};
+use elements::Txid;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Default address of the local oracle signer service (`oracle_signer` binary)
+pub const DEFAULT_ORACLE_URL: &str = "http://127.0.0.1:8787";
+
+/// Attestation returned by the oracle signer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OracleAttestation {
+    pub invoice_id: String,
+    /// SHA256 of the invoice ID (hex), used as `witness::INVOICE_HASH`
+    pub invoice_hash: String,
+    /// Message that was signed (hex)
+    pub message: String,
+    /// BIP340 signature over `message` (hex, 64 bytes), used as `witness::ORACLE_SIG`
+    pub signature: String,
+    /// X-only oracle public key (hex)
+    pub pubkey: String,
+    /// Redemption the attestation is bound to
+    pub spend: AttestedSpend,
+}
+
+/// The redemption an attestation commits to: the payment output and the voucher UTXO it spends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestedSpend {
+    /// scriptPubKey of the payment output (output 0), hex encoded
+    pub output_script_pubkey: String,
+    /// Amount of the payment output in sats
+    pub amount_sats: u64,
+    /// Outpoint of the voucher UTXO being spent
+    pub txid: String,
+    pub vout: u32,
+}
+
+/// Request body for `POST /attest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationRequest {
+    pub invoice_id: String,
+    #[serde(flatten)]
+    pub spend: AttestedSpend,
+}
+
+/// Hash an invoice ID into the 32-byte value committed in the witness
+pub fn invoice_hash(invoice_id: &str) -> [u8; 32] {
+    sha256::Hash::hash(invoice_id.as_bytes()).to_byte_array()
+}
+
+/// Compute the message the oracle signs for a given invoice and redemption
+pub fn attestation_message(invoice_hash: &[u8; 32], spend: &AttestedSpend) -> Result<[u8; 32]> {
+    let script = hex::decode(spend.output_script_pubkey.trim())
+        .context("Invalid output scriptPubKey hex")?;
+    let txid = Txid::from_str(spend.txid.trim())
+        .map_err(|e| anyhow::anyhow!("Invalid txid {}: {}", spend.txid, e))?;
+    let mut preimage = Vec::with_capacity(108);
+    preimage.extend_from_slice(invoice_hash);
+    preimage.extend_from_slice(&sha256::Hash::hash(&script).to_byte_array());
+    preimage.extend_from_slice(&spend.amount_sats.to_be_bytes());
+    preimage.extend_from_slice(&txid.to_byte_array());
+    preimage.extend_from_slice(&spend.vout.to_be_bytes());
+    Ok(sha256::Hash::hash(&preimage).to_byte_array())
+}
+
+/// scriptPubKey (hex) of an Elements address
+pub fn script_pubkey_hex(address: &str) -> Result<String> {
+    let address = elements::Address::from_str(address.trim())
+        .map_err(|e| anyhow::anyhow!("Invalid address {}: {}", address, e))?;
+    Ok(hex::encode(address.script_pubkey().as_bytes()))
+}
+
+impl OracleAttestation {
+    /// Check that the attestation is a valid signature by `oracle_pubkey` for this redemption
+    pub fn verify(&self, oracle_pubkey: &str, spend: &AttestedSpend) -> Result<()> {
+        if !self.pubkey.eq_ignore_ascii_case(oracle_pubkey.trim()) {
+            return Err(anyhow::anyhow!(
+                "Attestation was signed by {} but the covenant expects oracle key {}",
+                self.pubkey,
+                oracle_pubkey
+            ));
+        }
+
+        let expected_hash = invoice_hash(&self.invoice_id);
+        if hex::encode(expected_hash) != self.invoice_hash.to_lowercase() {
+            return Err(anyhow::anyhow!(
+                "Invoice hash {} does not match invoice ID {}",
+                self.invoice_hash,
+                self.invoice_id
+            ));
+        }
+
+        let message = attestation_message(&expected_hash, spend)?;
+        if hex::encode(message) != self.message.to_lowercase() {
+            return Err(anyhow::anyhow!(
+                "Attestation message does not commit to this payment output, amount and voucher UTXO.\n\nExpected: {}\nGot: {}",
+                hex::encode(message),
+                self.message
+            ));
+        }
+
+        let pubkey = XOnlyPublicKey::from_str(&self.pubkey)
+            .map_err(|e| anyhow::anyhow!("Invalid oracle public key: {}", e))?;
+        let signature = schnorr::Signature::from_str(&self.signature)
+            .map_err(|e| anyhow::anyhow!("Invalid oracle signature: {}", e))?;
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature, &Message::from_digest(message), &pubkey)
+            .map_err(|e| anyhow::anyhow!("Oracle signature verification failed: {}", e))
+    }
+}
+
+/// Oracle signing key, used by the `oracle_signer` service
+pub struct OracleSigner {
+    keypair: Keypair,
+}
+
+impl OracleSigner {
+    /// Create a signer from a 32-byte secret key in hex
+    pub fn new(secret_key_hex: &str) -> Result<Self> {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_str(&secp, secret_key_hex.trim())
+            .map_err(|e| anyhow::anyhow!("Invalid oracle secret key: {}", e))?;
+        Ok(Self { keypair })
+    }
+
+    /// X-only public key (hex) to embed in the covenant
+    pub fn pubkey_hex(&self) -> String {
+        self.keypair.x_only_public_key().0.to_string()
+    }
+
+    /// Sign an attestation for an invoice paid by `spend`
+    pub fn attest(&self, invoice_id: &str, spend: &AttestedSpend) -> Result<OracleAttestation> {
+        let hash = invoice_hash(invoice_id);
+        let message = attestation_message(&hash, spend)?;
+        let signature = Secp256k1::new()
+            .sign_schnorr_no_aux_rand(&Message::from_digest(message), &self.keypair);
+
+        Ok(OracleAttestation {
+            invoice_id: invoice_id.to_string(),
+            invoice_hash: hex::encode(hash),
+            message: hex::encode(message),
+            signature: signature.to_string(),
+            pubkey: self.pubkey_hex(),
+            spend: spend.clone(),
+        })
+    }
+}
+
+/// HTTP client for the oracle signer service
+pub struct OracleClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl OracleClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetch the oracle public key (`GET /pubkey`)
+    pub async fn pubkey(&self) -> Result<String> {
+        let url = format!("{}/pubkey", self.base_url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach oracle signer at {}", url))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .context("Failed to parse oracle signer response")?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Oracle signer returned {}: {}", status, body));
+        }
+
+        body["pubkey"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Oracle signer response has no 'pubkey' field: {}", body))
+    }
+
+    /// Request an attestation for an invoice paid by `spend` (`POST /attest`)
+    pub async fn request_attestation(&self, invoice_id: &str, spend: &AttestedSpend) -> Result<OracleAttestation> {
+        let url = format!("{}/attest", self.base_url);
+        let request = AttestationRequest {
+            invoice_id: invoice_id.to_string(),
+            spend: spend.clone(),
+        };
+        let response = self
+            .client
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach oracle signer at {}", url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Oracle refused attestation ({}): {}", status, body));
+        }
+
+        response
+            .json::<OracleAttestation>()
+            .await
+            .context("Failed to parse oracle attestation")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spend() -> AttestedSpend {
+        AttestedSpend {
+            output_script_pubkey: format!("5120{}", "ab".repeat(32)),
+            amount_sats: 40_000,
+            txid: "cd".repeat(32),
+            vout: 1,
+        }
+    }
+
+    #[test]
+    fn attestation_is_bound_to_the_redemption() {
+        let signer = OracleSigner::new(&format!("{:064x}", 7)).unwrap();
+        let pubkey = signer.pubkey_hex();
+        let attestation = signer.attest("INV-1", &spend()).unwrap();
+        attestation.verify(&pubkey, &spend()).unwrap();
+        assert_eq!(attestation.invoice_hash, hex::encode(invoice_hash("INV-1")));
+
+        let other_script = AttestedSpend { output_script_pubkey: format!("5120{}", "ac".repeat(32)), ..spend() };
+        let other_amount = AttestedSpend { amount_sats: 40_001, ..spend() };
+        let later_hop = AttestedSpend { txid: "ce".repeat(32), ..spend() };
+        let other_vout = AttestedSpend { vout: 0, ..spend() };
+        for other in [other_script, other_amount, later_hop, other_vout] {
+            assert!(attestation.verify(&pubkey, &other).is_err(), "{:?}", other);
+        }
+
+        let other_oracle = OracleSigner::new(&format!("{:064x}", 8)).unwrap().pubkey_hex();
+        assert!(attestation.verify(&other_oracle, &spend()).is_err());
+        let forged = OracleAttestation { invoice_id: "INV-2".to_string(), ..attestation.clone() };
+        assert!(forged.verify(&pubkey, &spend()).is_err());
+    }
+
+    #[test]
+    fn message_layout_matches_the_covenant() {
+        // SHA256(invoice hash || script hash || amount (8 BE) || txid (internal order) || vout (4 BE)),
+        // the txid being displayed byte-reversed
+        let spend = AttestedSpend { txid: (0u8..32).map(|b| format!("{:02x}", b)).collect(), ..spend() };
+        let hash = invoice_hash("INV-1");
+        let mut preimage = hash.to_vec();
+        preimage.extend(sha256::Hash::hash(&hex::decode(&spend.output_script_pubkey).unwrap()).to_byte_array());
+        preimage.extend(40_000u64.to_be_bytes());
+        preimage.extend((0u8..32).rev());
+        preimage.extend([0, 0, 0, 1]);
+        assert_eq!(attestation_message(&hash, &spend).unwrap(), sha256::Hash::hash(&preimage).to_byte_array());
+    }
+}
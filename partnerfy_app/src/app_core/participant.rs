@@ -0,0 +1,450 @@
+//! Participant voucher wallet
+//!
+//! A participant holds vouchers (covenant UTXOs registered under their public key) and
+//! spends them at partners. [`load_wallet`] follows each voucher's lineage to its
+//! current balance and sorts the participant's redemptions into pending and completed.
+//!
+//! A redemption reuses the contract [`Workflow`]: [`start_redemption`] builds the PSET
+//! for the voucher's current UTXO, gets the oracle attestation when the covenant needs
+//! one, and adds the participant's signature, so the partner only has to review and
+//! co-sign it. [`finish_redemption`] takes the co-signed PSET back, finalizes and
+//! broadcasts it. Each redemption is saved as its own workflow.
+
+use super::audit;
+use super::contracts;
+use super::elements_rpc::ElementsRPC;
+use super::history::TxStatus;
+use super::lineage::{self, Lineage, LineageEnd};
+use super::models::{AppState, Contract, RedemptionRequest, ReviewDecision, Settings, VoucherUTXO};
+use super::platform;
+use super::redemption;
+use super::sighash::{self, SigningMode};
+use super::workflow::{self, ContractInfo, Funding, Workflow, WorkflowState};
+use super::{dashboard, TemplateRegistry};
+use anyhow::{Context, Result};
+use std::fmt;
+
+/// Prefix of the workflow names redemptions are saved under
+pub const WORKFLOW_PREFIX: &str = "redeem";
+
+/// A voucher with its current balance
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalletVoucher {
+    pub voucher: VoucherUTXO,
+    /// Contract record for the voucher's covenant, if this app knows it
+    pub contract: Option<Contract>,
+    pub lineage: Result<Lineage, String>,
+}
+
+impl WalletVoucher {
+    /// The unspent covenant output the voucher's balance sits in, with its value
+    pub fn current_utxo(&self) -> Option<(String, u32, u64)> {
+        let lineage = self.lineage.as_ref().ok()?;
+        if lineage.end != LineageEnd::Unspent {
+            return None;
+        }
+        let tip = lineage.hops.last()?;
+        Some((tip.txid.clone(), tip.vout, tip.value_sats?))
+    }
+
+    pub fn balance_sats(&self) -> u64 {
+        self.current_utxo().map(|(_, _, value)| value).unwrap_or(0)
+    }
+
+    /// Whether a redemption can be started from this app
+    pub fn can_redeem(&self) -> bool {
+        self.contract.is_some() && self.balance_sats() > 0
+    }
+}
+
+/// Where a started redemption stands
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedemptionStatus {
+    /// Waiting for the partner to co-sign
+    AwaitingPartner,
+    /// The partner co-signed; the participant can finish it
+    CoSigned,
+    Rejected { reason: String },
+    /// Sent to the network, not yet seen by the Blockstream index
+    Broadcast { txid: String },
+}
+
+impl fmt::Display for RedemptionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedemptionStatus::AwaitingPartner => write!(f, "Waiting for the partner to approve"),
+            RedemptionStatus::CoSigned => write!(f, "Approved by the partner, ready to finish"),
+            RedemptionStatus::Rejected { reason } => write!(f, "Rejected by the partner: {}", reason),
+            RedemptionStatus::Broadcast { .. } => write!(f, "Sent, waiting for the network"),
+        }
+    }
+}
+
+/// A redemption that is on chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletedRedemption {
+    pub txid: String,
+    pub status: TxStatus,
+    pub address: Option<String>,
+    /// Address book name of the partner paid, if known
+    pub partner: Option<String>,
+    pub value_sats: Option<u64>,
+}
+
+/// Everything the participant page shows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wallet {
+    pub vouchers: Vec<WalletVoucher>,
+    pub pending: Vec<(RedemptionRequest, RedemptionStatus)>,
+    pub completed: Vec<CompletedRedemption>,
+}
+
+impl Wallet {
+    pub fn balance_sats(&self) -> u64 {
+        self.vouchers.iter().map(WalletVoucher::balance_sats).sum()
+    }
+}
+
+/// Public keys that hold vouchers, registered participants first
+pub fn participant_keys(state: &AppState) -> Vec<String> {
+    let mut keys: Vec<String> = state.participants.iter().map(|p| p.pubkey.clone()).collect();
+    for voucher in dashboard::vouchers(state) {
+        if !keys.contains(&voucher.owner_pubkey) {
+            keys.push(voucher.owner_pubkey);
+        }
+    }
+    keys
+}
+
+/// Vouchers, pending and completed redemptions of the participant with `pubkey`
+pub async fn load_wallet(rpc: &ElementsRPC, state: &AppState, pubkey: &str) -> Wallet {
+    let mut vouchers = Vec::new();
+    for voucher in dashboard::vouchers(state).into_iter().filter(|v| v.owner_pubkey == pubkey) {
+        let lineage = lineage::follow_lineage(rpc, &voucher.txid, voucher.vout)
+            .await
+            .map_err(|e| e.to_string());
+        let contract = state.contracts.iter().find(|c| c.address == voucher.covenant_address).cloned();
+        vouchers.push(WalletVoucher { voucher, contract, lineage });
+    }
+    let (pending, completed) = sort_redemptions(state, pubkey, &vouchers);
+    Wallet { vouchers, pending, completed }
+}
+
+/// Split the participant's redemptions into pending (started here, not on chain yet) and
+/// completed (every redemption in the vouchers' lineages, wherever it was started)
+pub fn sort_redemptions(
+    state: &AppState,
+    pubkey: &str,
+    vouchers: &[WalletVoucher],
+) -> (Vec<(RedemptionRequest, RedemptionStatus)>, Vec<CompletedRedemption>) {
+    let completed: Vec<CompletedRedemption> = vouchers
+        .iter()
+        .filter_map(|v| v.lineage.as_ref().ok())
+        .flat_map(|l| l.hops.iter())
+        .filter_map(|hop| {
+            let payment = hop.payment.as_ref()?;
+            Some(CompletedRedemption {
+                txid: hop.txid.clone(),
+                status: hop.status.clone(),
+                address: payment.address.clone(),
+                partner: payment
+                    .address
+                    .as_ref()
+                    .and_then(|a| state.partners.iter().find(|p| p.address == *a))
+                    .map(|p| p.name.clone()),
+                value_sats: payment.value_sats,
+            })
+        })
+        .collect();
+
+    let pending = state
+        .redemptions
+        .iter()
+        .filter(|r| r.participant_pubkey == pubkey)
+        .filter(|r| !completed.iter().any(|c| c.txid == r.unsigned_txid))
+        .map(|r| (r.clone(), status(state, r)))
+        .collect();
+    (pending, completed)
+}
+
+/// Status of a redemption that is not on chain yet
+pub fn status(state: &AppState, request: &RedemptionRequest) -> RedemptionStatus {
+    if let Some(txid) = state.workflows.get(&request.workflow).and_then(|w| w.broadcast_txid.clone()) {
+        return RedemptionStatus::Broadcast { txid };
+    }
+    match state
+        .reviews
+        .iter()
+        .rev()
+        .find(|r| r.unsigned_txid == request.unsigned_txid)
+        .map(|r| &r.decision)
+    {
+        Some(ReviewDecision::Rejected { reason }) => RedemptionStatus::Rejected { reason: reason.clone() },
+        Some(ReviewDecision::CoSigned { .. }) => RedemptionStatus::CoSigned,
+        None => RedemptionStatus::AwaitingPartner,
+    }
+}
+
+/// What the participant asks for
+pub struct RedemptionOrder {
+    pub participant_pubkey: String,
+    pub voucher: WalletVoucher,
+    pub partner_address: String,
+    /// Amount paid to the partner, in L-BTC
+    pub amount: f64,
+    /// Invoice the oracle attests, for covenants that need an attestation
+    pub invoice_id: String,
+    pub privkey: String,
+    pub mode: SigningMode,
+}
+
+/// Build the redemption PSET for a voucher and add the participant's signature, if their
+/// key is given
+///
+/// Returns the saved request; the PSET to hand to the partner is in its workflow.
+pub async fn start_redemption(base: &Workflow, order: RedemptionOrder, progress: &dyn Fn(String)) -> Result<RedemptionRequest> {
+    let contract = order
+        .voucher
+        .contract
+        .clone()
+        .context("This voucher's contract is not recorded in this app, so it cannot be redeemed from here.")?;
+    let (txid, vout, value_sats) = order.voucher.current_utxo().context(
+        "This voucher has no balance left to redeem. Refresh to see its latest state.",
+    )?;
+    let template = base.template(&contract.template_id)?;
+    let signers = template.signers(&contract.params);
+
+    let name = format!("{}-{}-{}", WORKFLOW_PREFIX, txid, vout);
+    let flow = Workflow { name: name.clone(), ..base.clone() };
+    let mut state = redemption_state(&base.registry, &contract, &name, &txid, vout, value_sats)?;
+
+    let input = workflow::CreatePsetInput {
+        destination: order.partner_address.trim().to_string(),
+        amount: order.amount,
+    };
+    let spend = flow.run(workflow::CreatePset, &mut state, input, progress).await?;
+
+    if template.oracle_pubkey(&contract.params).is_some() {
+        if contract.oracle_url.is_empty() {
+            return Err(anyhow::anyhow!(
+                "This voucher's contract record has no oracle signer URL, so its attestation cannot be requested.\n\nImport the contract record again from the promoter."
+            ));
+        }
+        progress("Requesting the oracle attestation for the invoice...".to_string());
+        let input = workflow::AttestInput {
+            oracle_url: contract.oracle_url.clone(),
+            invoice_id: order.invoice_id.clone(),
+        };
+        flow.run(workflow::Attest, &mut state, input, progress).await?;
+    }
+
+    // Without a key the PSET goes out unsigned, for the participant to sign elsewhere
+    let signed = if order.privkey.trim().is_empty() {
+        spend.pset.clone()
+    } else {
+        progress("Signing your part of the redemption...".to_string());
+        let (signed, _) = redemption::co_sign(flow.hal.as_ref(), order.mode, &spend.pset, &contract.cmr, &contract.genesis_hash, &signers, &order.privkey)?;
+        let pubkey = sighash::pubkey_of(&order.privkey)?;
+        audit::record(&flow.store, &flow.name, audit::signed(&signed, vec![pubkey]));
+        if let Some(spend) = state.spend.as_mut() {
+            spend.pset = signed.clone();
+        }
+        flow.save(&state)?;
+        signed
+    };
+
+    let request = RedemptionRequest {
+        workflow: name,
+        participant_pubkey: order.participant_pubkey,
+        voucher_txid: txid,
+        voucher_vout: vout,
+        contract_address: contract.address.clone(),
+        partner_address: spend.destination.clone(),
+        amount_sats: spend.amount_sats,
+        fee_sats: spend.fee_sats,
+        unsigned_txid: redemption::RedemptionPset::decode(&signed, contract.network()?)?.unsigned_txid,
+        created_at: platform::now_secs(),
+    };
+    base.store.save_redemption(request.clone())?;
+    Ok(request)
+}
+
+/// Add the participant's signature, made elsewhere, to a started redemption; returns the PSET
+///
+/// For callers that never see the participant's key (the REST API): they sign the
+/// `sig_all_hash` of the PSET from [`request_pset`] themselves and hand back the signature.
+pub fn add_signature(base: &Workflow, request: &RedemptionRequest, pubkey: &str, signature: &str) -> Result<String> {
+    let flow = Workflow { name: request.workflow.clone(), ..base.clone() };
+    let mut state = flow
+        .resume()?
+        .context("The redemption's saved progress is missing. Start the redemption again.")?;
+    let contract = state.contract.clone().context("The redemption has no contract")?;
+    let signers = flow.template(&state.template_id)?.signers(&state.params);
+    let spend = state.spend.as_mut().context("The redemption has no PSET. Start the redemption again.")?;
+    let signed = redemption::add_verified_signature(&spend.pset, &contract.cmr, &contract.genesis_hash, &signers, pubkey, signature)?;
+    spend.pset = signed.clone();
+    flow.save(&state)?;
+    audit::record(&flow.store, &flow.name, audit::signed(&signed, vec![pubkey.trim().to_string()]));
+    Ok(signed)
+}
+
+/// The signed PSET to send to the partner
+pub fn request_pset(base: &Workflow, request: &RedemptionRequest) -> Result<String> {
+    let flow = Workflow { name: request.workflow.clone(), ..base.clone() };
+    flow.resume()?
+        .and_then(|s| s.spend)
+        .map(|s| s.pset)
+        .context("The redemption's saved progress is missing. Start the redemption again.")
+}
+
+/// Finalize the PSET the partner co-signed and broadcast it; returns the txid
+pub async fn finish_redemption(
+    base: &Workflow,
+    request: &RedemptionRequest,
+    cosigned_pset: &str,
+    progress: &dyn Fn(String),
+) -> Result<String> {
+    let flow = Workflow { name: request.workflow.clone(), ..base.clone() };
+    let mut state = flow
+        .resume()?
+        .context("The redemption's saved progress is missing. Start the redemption again.")?;
+    if state.finalized.is_none() {
+        let input = workflow::SignInput {
+            privkeys: Vec::new(),
+            mode: SigningMode::default(),
+            witness_path: state.witness_path.clone(),
+            cosigned_pset: cosigned_pset.trim().to_string(),
+        };
+        flow.run(workflow::Sign, &mut state, input, progress).await?;
+    }
+    let broadcast = flow.run(workflow::Broadcast, &mut state, (), progress).await?;
+    Ok(broadcast.txid)
+}
+
+/// Workflow state for spending `txid:vout` of a recorded contract
+///
+/// The contract's source is re-rendered from its template into the data directory, since
+/// finalizing compiles the program together with the witness.
+fn redemption_state(
+    registry: &TemplateRegistry,
+    contract: &Contract,
+    name: &str,
+    txid: &str,
+    vout: u32,
+    value_sats: u64,
+) -> Result<WorkflowState> {
+    let template = registry
+        .get(&contract.template_id)
+        .with_context(|| format!("Covenant template '{}' is not installed", contract.template_id))?;
+    let source = template
+        .render(&contract.params)
+        .context("Failed to re-render the contract source from its template")?;
+    if !contract.source_hash.is_empty() && contracts::source_hash(&source) != contract.source_hash {
+        return Err(anyhow::anyhow!(
+            "The contract source rendered from template '{}' does not match the recorded source hash.\n\nThe template changed since the contract was created. Redeem it from the Voucher page with the original .simf file.",
+            contract.template_id
+        ));
+    }
+    let dir = Settings::data_dir().join("redemptions");
+    let simf_path = dir.join(format!("{}.simf", name));
+    platform::write_file(&simf_path, &source)?;
+
+    let mut state = WorkflowState::new(&contract.template_id, contract.params.clone());
+    state.simf_path = simf_path.to_string_lossy().to_string();
+    state.witness_path = dir.join(format!("{}.wit", name)).to_string_lossy().to_string();
+    state.program = contract.voucher_base64.clone();
+    state.internal_key = contract.internal_key.clone();
+    state.contract = Some(ContractInfo {
+        cmr: contract.cmr.clone(),
+        address: contract.address.clone(),
+        program: contract.voucher_base64.clone(),
+        genesis_hash: contract.genesis_hash.clone(),
+    });
+    state.funding = Some(Funding {
+        txid: txid.to_string(),
+        vout,
+        amount: format!("{}", value_sats as f64 / 100_000_000.0),
+    });
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::lineage::{LineageHop, Payment};
+    use crate::app_core::models::{Partner, RedemptionReview};
+
+    fn hop(txid: &str, payment: Option<(&str, u64)>, remaining: u64) -> LineageHop {
+        LineageHop {
+            txid: txid.to_string(),
+            vout: 1,
+            value_sats: Some(remaining),
+            status: TxStatus::Unconfirmed,
+            payment: payment.map(|(address, value)| Payment {
+                address: Some(address.to_string()),
+                value_sats: Some(value),
+            }),
+            fee_sats: None,
+        }
+    }
+
+    fn request(workflow: &str, txid: &str) -> RedemptionRequest {
+        RedemptionRequest {
+            workflow: workflow.to_string(),
+            participant_pubkey: "alice".to_string(),
+            voucher_txid: "aa".to_string(),
+            voucher_vout: 0,
+            contract_address: "tex1pcontract".to_string(),
+            partner_address: "tex1qcafe".to_string(),
+            amount_sats: 30_000,
+            fee_sats: 100,
+            unsigned_txid: txid.to_string(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn sorts_redemptions_by_where_they_stand() {
+        let mut state = AppState::default();
+        state.partners.push(Partner { address: "tex1qcafe".to_string(), pubkey: None, name: "Cafe".to_string() });
+        state.redemptions = vec![request("done", "bb"), request("waiting", "cc"), request("refused", "dd")];
+        state.reviews.push(RedemptionReview {
+            unsigned_txid: "dd".to_string(),
+            contract_address: String::new(),
+            partner_address: String::new(),
+            amount_sats: None,
+            fee_sats: None,
+            decision: ReviewDecision::Rejected { reason: "wrong amount".to_string() },
+            reviewed_at: 0,
+        });
+
+        let voucher = WalletVoucher {
+            voucher: VoucherUTXO {
+                txid: "aa".to_string(),
+                vout: 0,
+                amount: 0.001,
+                owner_pubkey: "alice".to_string(),
+                covenant_script: String::new(),
+                covenant_address: "tex1pcontract".to_string(),
+            },
+            contract: None,
+            lineage: Ok(Lineage {
+                address: None,
+                script_pubkey: String::new(),
+                hops: vec![hop("aa", None, 100_000), hop("bb", Some(("tex1qcafe", 30_000)), 69_900)],
+                end: LineageEnd::Unspent,
+            }),
+        };
+        assert_eq!(voucher.current_utxo(), Some(("bb".to_string(), 1, 69_900)));
+        assert!(!voucher.can_redeem());
+
+        let (pending, completed) = sort_redemptions(&state, "alice", &[voucher]);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].partner.as_deref(), Some("Cafe"));
+        let statuses: Vec<_> = pending.iter().map(|(r, s)| (r.workflow.as_str(), s.clone())).collect();
+        assert_eq!(statuses, vec![
+            ("waiting", RedemptionStatus::AwaitingPartner),
+            ("refused", RedemptionStatus::Rejected { reason: "wrong amount".to_string() }),
+        ]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Services that differ between the native and the browser build
+//!
+//! Native builds use the filesystem, the system clock and tokio's timer. In the browser
+//! (`wasm32`) there is no filesystem, so files are kept in `localStorage` under their
+//! path, and the clock and timer come from JavaScript.
+//!
+//! External tools (simc, hal-simplicity, zbarimg) can only be run by the `server` build;
+//! without it [`command`] explains which build to use instead.
+
+#[cfg(not(feature = "server"))]
+use super::error::AppError;
+use super::error::Tool;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Command for an external tool (`program` is its path, usually `tool.program()`)
+#[cfg(feature = "server")]
+pub fn command(_tool: Tool, program: &str) -> Result<std::process::Command> {
+    Ok(std::process::Command::new(program))
+}
+
+/// Command for an external tool, which this build cannot run
+#[cfg(not(feature = "server"))]
+pub fn command(tool: Tool, program: &str) -> Result<std::process::Command> {
+    Err(AppError::ToolMissing { tool, command: program.to_string(), kind: std::io::ErrorKind::Unsupported }.into())
+}
+
+/// Seconds since the Unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Seconds since the Unix epoch
+#[cfg(target_arch = "wasm32")]
+pub fn now_secs() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Wait without blocking the UI
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Wait without blocking the UI
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Contents of a file, or `None` if it does not exist
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_file(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    std::fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Write a file, creating its directory and replacing the previous contents atomically
+///
+/// The contents are written to a temporary file unique to this process and write, which
+/// is then renamed over the target, so concurrent writers never rename each other's
+/// half-written file into place.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_file(path: &Path, contents: &str) -> Result<()> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let unique = format!("{}.{}.tmp", std::process::id(), WRITES.fetch_add(1, Ordering::Relaxed));
+    let tmp = path.with_extension(match path.extension() {
+        Some(ext) => format!("{}.{}", ext.to_string_lossy(), unique),
+        None => unique,
+    });
+    let written = std::fs::write(&tmp, contents)
+        .with_context(|| format!("Failed to write {}", tmp.display()))
+        .and_then(|_| std::fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display())));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Delete a file; a missing file is not an error
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove_file(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to delete {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Files directly inside `dir`, sorted; empty if the directory does not exist
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Run `f` while holding an exclusive advisory lock on `<path>.lock`
+///
+/// Every process sharing the data directory (desktop app, CLI, API server) takes the
+/// same lock around a read-modify-write of `path`, so none of them loses another's
+/// update. The lock is released when `f` returns.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn with_lock<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_path = Path::new(&lock_path);
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    file.lock().with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    let result = f();
+    let _ = file.unlock();
+    result
+}
+
+/// Append a line to a file, creating it and its directory if needed
+#[cfg(not(target_arch = "wasm32"))]
+pub fn append_line(path: &Path, line: &str) -> Result<()> {
+    use std::io::Write;
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .context("The browser does not allow Partnerfy to use localStorage.\n\nAllow site data for this page, or use the desktop app.")
+}
+
+/// Contents of a file, or `None` if it does not exist
+#[cfg(target_arch = "wasm32")]
+pub fn read_file(path: &Path) -> Result<Option<String>> {
+    local_storage()?
+        .get_item(&path.to_string_lossy())
+        .map_err(|_| anyhow::anyhow!("Failed to read {} from localStorage", path.display()))
+}
+
+/// Write a file, replacing the previous contents
+#[cfg(target_arch = "wasm32")]
+pub fn write_file(path: &Path, contents: &str) -> Result<()> {
+    local_storage()?
+        .set_item(&path.to_string_lossy(), contents)
+        .map_err(|_| anyhow::anyhow!(
+            "Failed to write {} to localStorage.\n\nThe browser's storage quota may be full.",
+            path.display()
+        ))
+}
+
+/// Delete a file
+#[cfg(target_arch = "wasm32")]
+pub fn remove_file(path: &Path) -> Result<()> {
+    local_storage()?
+        .remove_item(&path.to_string_lossy())
+        .map_err(|_| anyhow::anyhow!("Failed to delete {} from localStorage", path.display()))
+}
+
+/// Files stored directly under `dir`, sorted
+#[cfg(target_arch = "wasm32")]
+pub fn list_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let storage = local_storage()?;
+    let count = storage.length().unwrap_or(0);
+    let mut paths: Vec<PathBuf> = (0..count)
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .map(PathBuf::from)
+        .filter(|p| p.parent() == Some(dir))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Run `f`; a page's `localStorage` has a single writer, so there is nothing to lock
+#[cfg(target_arch = "wasm32")]
+pub fn with_lock<T>(_path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    f()
+}
+
+/// Append a line to a file
+#[cfg(target_arch = "wasm32")]
+pub fn append_line(path: &Path, line: &str) -> Result<()> {
+    let mut contents = read_file(path)?.unwrap_or_default();
+    contents.push_str(line);
+    contents.push('\n');
+    write_file(path, &contents)
+}
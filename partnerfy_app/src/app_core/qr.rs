@@ -0,0 +1,427 @@
+//! QR codes for addresses, payment requests and PSETs
+//!
+//! Addresses and payment URIs fit in a single QR code. PSETs usually do not, so larger
+//! payloads are split into BBQr parts (https://github.com/coinkite/BBQr) that are shown
+//! one after the other as an animated code. Wallets that read BBQr (Sparrow, Coldcard,
+//! Nunchuk) can scan them as well.
+//!
+//! Reading QR codes from an image file runs `zbarimg` from zbar-tools (`server` build only).
+
+use super::error::{AppError, Tool};
+use super::platform;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose, Engine as _};
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+use std::collections::BTreeMap;
+
+/// URI scheme of Liquid Testnet payment requests
+pub const SCHEME: &str = "liquidtestnet";
+
+/// URI scheme of Liquid mainnet payment requests, accepted when parsing
+pub const MAINNET_SCHEME: &str = "liquidnetwork";
+
+/// L-BTC asset id on Liquid Testnet
+pub const TESTNET_LBTC: &str = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+
+/// Payloads up to this many characters are shown as a single QR code
+pub const MAX_SINGLE_CHARS: usize = 600;
+
+/// Base32 characters per BBQr part (a multiple of 8, so each part decodes on its own)
+pub const PART_CHARS: usize = 400;
+
+const BBQR_PREFIX: &str = "B$";
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A payment request: an address, optionally with the amount and asset to pay
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentUri {
+    pub address: String,
+    pub amount_sats: Option<u64>,
+    pub asset_id: Option<String>,
+    pub label: Option<String>,
+}
+
+impl PaymentUri {
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.trim().to_string(),
+            amount_sats: None,
+            asset_id: None,
+            label: None,
+        }
+    }
+
+    /// Request `amount_sats` of testnet L-BTC
+    pub fn with_amount(mut self, amount_sats: u64) -> Self {
+        self.amount_sats = Some(amount_sats);
+        self.asset_id = Some(TESTNET_LBTC.to_string());
+        self
+    }
+
+    pub fn with_label(mut self, label: &str) -> Self {
+        if !label.trim().is_empty() {
+            self.label = Some(label.trim().to_string());
+        }
+        self
+    }
+
+    /// `liquidtestnet:<address>?amount=<L-BTC>&assetid=<hex>&label=<text>`
+    pub fn to_uri(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(sats) = self.amount_sats {
+            params.push(format!("amount={}", format_btc(sats)));
+        }
+        if let Some(asset) = &self.asset_id {
+            params.push(format!("assetid={}", asset));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if params.is_empty() {
+            format!("{}:{}", SCHEME, self.address)
+        } else {
+            format!("{}:{}?{}", SCHEME, self.address, params.join("&"))
+        }
+    }
+
+    /// Parse a payment URI, or a bare address
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let rest = match text.split_once(':') {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case(SCHEME) || scheme.eq_ignore_ascii_case(MAINNET_SCHEME) => rest,
+            Some((scheme, _)) => {
+                return Err(anyhow::anyhow!(
+                    "Unsupported payment URI scheme '{}'\n\nExpected a '{}:' or '{}:' URI, or a plain address",
+                    scheme, SCHEME, MAINNET_SCHEME
+                ))
+            }
+            None => text,
+        };
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if address.is_empty() {
+            return Err(anyhow::anyhow!("The payment request has no address"));
+        }
+
+        let mut uri = PaymentUri::new(address);
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "amount" => {
+                    uri.amount_sats = Some(
+                        parse_btc(value).with_context(|| format!("Invalid amount '{}' in the payment request", value))?,
+                    )
+                }
+                "assetid" => uri.asset_id = Some(value.to_string()),
+                "label" => uri.label = Some(percent_decode(value)),
+                _ => {}
+            }
+        }
+        Ok(uri)
+    }
+}
+
+/// The QR frames that carry `data`: the text itself if it is short, BBQr parts otherwise
+///
+/// Base64 payloads (PSETs) are decoded and sent as binary, which the receiving side
+/// encodes back to base64.
+pub fn frames(data: &str) -> Vec<String> {
+    let data = data.trim();
+    if data.len() <= MAX_SINGLE_CHARS {
+        return vec![data.to_string()];
+    }
+    let (file_type, bytes) = match general_purpose::STANDARD.decode(data) {
+        Ok(bytes) => ('P', bytes),
+        Err(_) => ('U', data.as_bytes().to_vec()),
+    };
+    let encoded = base32_encode(&bytes);
+    let chunks: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(PART_CHARS)
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            format!("{}2{}{}{}{}", BBQR_PREFIX, file_type, base36(chunks.len()), base36(i), chunk)
+        })
+        .collect()
+}
+
+/// Render one frame as an SVG image
+pub fn svg(frame: &str) -> Result<String> {
+    let code = QrCode::with_error_correction_level(frame.as_bytes(), EcLevel::L)
+        .with_context(|| format!("The data is too long for a QR code ({} characters)", frame.len()))?;
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .quiet_zone(true)
+        .build())
+}
+
+/// `data:` URI of [`svg`], for use as an image source
+pub fn svg_data_uri(frame: &str) -> Result<String> {
+    Ok(format!(
+        "data:image/svg+xml;base64,{}",
+        general_purpose::STANDARD.encode(svg(frame)?)
+    ))
+}
+
+/// Collects scanned frames until the payload is complete
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Assembler {
+    file_type: char,
+    total: usize,
+    parts: BTreeMap<usize, String>,
+    single: Option<String>,
+}
+
+impl Assembler {
+    /// Add a scanned frame. Frames that are not BBQr are taken as the whole payload.
+    pub fn add(&mut self, frame: &str) -> Result<()> {
+        let frame = frame.trim();
+        let Some(header) = frame.strip_prefix(BBQR_PREFIX) else {
+            self.single = Some(frame.to_string());
+            return Ok(());
+        };
+        let Some(head) = header.get(..6).filter(|h| h.is_ascii()) else {
+            return Err(anyhow::anyhow!("Truncated BBQr header in scanned QR code"));
+        };
+        let encoding = head.as_bytes()[0] as char;
+        if encoding != '2' {
+            return Err(anyhow::anyhow!(
+                "Unsupported BBQr encoding '{}'\n\nOnly base32 ('2') parts can be imported. Export the data uncompressed from the other wallet.",
+                encoding
+            ));
+        }
+        let file_type = head.as_bytes()[1] as char;
+        let total = parse_base36(&head[2..4]).context("Invalid BBQr part count")?;
+        let index = parse_base36(&head[4..6]).context("Invalid BBQr part index")?;
+        if total == 0 || index >= total {
+            return Err(anyhow::anyhow!("Invalid BBQr part {} of {}", index + 1, total));
+        }
+        if !self.parts.is_empty() && (self.total != total || self.file_type != file_type) {
+            return Err(anyhow::anyhow!(
+                "This QR code belongs to a different multi-part code ({} parts) than the ones scanned before ({} parts)\n\nStart the import again.",
+                total, self.total
+            ));
+        }
+        self.file_type = file_type;
+        self.total = total;
+        self.parts.insert(index, header[6..].to_string());
+        Ok(())
+    }
+
+    /// Parts scanned so far and parts expected (1 of 1 for a single frame)
+    pub fn progress(&self) -> (usize, usize) {
+        if self.parts.is_empty() {
+            (usize::from(self.single.is_some()), 1)
+        } else {
+            (self.parts.len(), self.total)
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        let (scanned, total) = self.progress();
+        scanned == total
+    }
+
+    /// The reassembled payload; PSET parts are returned as base64
+    pub fn finish(&self) -> Result<String> {
+        if self.parts.is_empty() {
+            return self.single.clone().context("No QR code has been scanned yet");
+        }
+        if !self.is_complete() {
+            let missing: Vec<String> = (0..self.total)
+                .filter(|i| !self.parts.contains_key(i))
+                .map(|i| (i + 1).to_string())
+                .collect();
+            return Err(anyhow::anyhow!(
+                "Parts {} of {} are still missing\n\nScan the remaining parts of the animated code.",
+                missing.join(", "),
+                self.total
+            ));
+        }
+        let encoded: String = self.parts.values().map(String::as_str).collect();
+        let bytes = base32_decode(&encoded).context("The scanned parts do not decode as base32")?;
+        match self.file_type {
+            'U' => String::from_utf8(bytes).context("The scanned text is not valid UTF-8"),
+            _ => Ok(general_purpose::STANDARD.encode(bytes)),
+        }
+    }
+}
+
+/// Read every QR code in an image file
+///
+/// Runs: zbarimg --raw -q <image>
+pub fn scan_image(path: &str) -> Result<Vec<String>> {
+    let shown = format!("zbarimg --raw -q {}", path);
+    let output = platform::command(Tool::Zbarimg, "zbarimg")?
+        .args(["--raw", "-q", path])
+        .output()
+        .map_err(|e| AppError::tool_missing(Tool::Zbarimg, &shown, &e))?;
+    // zbarimg exits with 4 when the image has no barcode in it
+    if output.status.code() == Some(4) {
+        return Err(anyhow::anyhow!(
+            "No QR code found in {}\n\nUse a sharp, well-lit picture with the whole code in frame.",
+            path
+        ));
+    }
+    if !output.status.success() {
+        return Err(AppError::tool_failed(Tool::Zbarimg, shown, &output).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// Amount in L-BTC with up to 8 decimals and no trailing zeros
+fn format_btc(sats: u64) -> String {
+    let fraction = format!("{:08}", sats % 100_000_000);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (sats / 100_000_000).to_string()
+    } else {
+        format!("{}.{}", sats / 100_000_000, fraction)
+    }
+}
+
+/// Parse an amount in L-BTC (up to 8 decimals) into sats, without going through floats
+pub fn parse_btc(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 8 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow::anyhow!("at most 8 decimals are allowed"));
+    }
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u64 = format!("{:0<8}", fraction).parse()?;
+    whole
+        .checked_mul(100_000_000)
+        .and_then(|w| w.checked_add(fraction))
+        .context("amount too large")
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn base36(n: usize) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    format!("{}{}", DIGITS[(n / 36) % 36] as char, DIGITS[n % 36] as char)
+}
+
+fn parse_base36(text: &str) -> Result<usize> {
+    Ok(usize::from_str_radix(text, 36)?)
+}
+
+/// RFC 4648 base32 without padding
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &b in bytes {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        let value = BASE32
+            .iter()
+            .position(|&d| d == c.to_ascii_uppercase())
+            .with_context(|| format!("invalid base32 character '{}'", c as char))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_uri_round_trip() {
+        let uri = PaymentUri::new("tlq1qqexample")
+            .with_amount(30_000)
+            .with_label("Cafe #12");
+        let text = uri.to_uri();
+        assert_eq!(
+            text,
+            format!("liquidtestnet:tlq1qqexample?amount=0.0003&assetid={}&label=Cafe%20%2312", TESTNET_LBTC)
+        );
+        assert_eq!(PaymentUri::parse(&text).unwrap(), uri);
+        assert_eq!(PaymentUri::parse(" tlq1qqexample ").unwrap(), PaymentUri::new("tlq1qqexample"));
+        assert_eq!(PaymentUri::parse("liquidnetwork:lq1x?amount=1.5").unwrap().amount_sats, Some(150_000_000));
+        assert!(PaymentUri::parse("bitcoin:bc1q").is_err());
+    }
+
+    #[test]
+    fn large_psets_round_trip_through_parts() {
+        let pset = general_purpose::STANDARD.encode((0..2000u32).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>());
+        let parts = frames(&pset);
+        assert!(parts.len() > 1);
+        assert!(parts.iter().all(|p| p.starts_with("B$2P")));
+
+        let mut assembler = Assembler::default();
+        for part in parts.iter().skip(1).rev() {
+            assembler.add(part).unwrap();
+        }
+        assert!(!assembler.is_complete());
+        assert!(assembler.finish().unwrap_err().to_string().contains("Parts 1 of"));
+        assembler.add(&parts[0]).unwrap();
+        assert_eq!(assembler.finish().unwrap(), pset);
+
+        assert_eq!(frames("tlq1qqexample"), vec!["tlq1qqexample".to_string()]);
+        assert!(svg(&parts[0]).unwrap().starts_with("<?xml"));
+    }
+}
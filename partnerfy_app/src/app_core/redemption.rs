@@ -0,0 +1,825 @@
+//! Partner review of redemption PSETs
+//!
+//! A participant builds the redemption PSET, signs their multisig slot and hands the
+//! PSET to the partner. The partner decodes it, checks it against the contract record
+//! (payment to the partner, amount, covenant change, fee) and either adds their own
+//! signature or rejects it. Signatures travel inside the PSET as Taproot script
+//! signatures (`PSBT_IN_TAP_SCRIPT_SIG`) for the contract's Simplicity leaf, so the PSET
+//! can go back and forth without losing any of them; [`slot_signatures`] puts them in
+//! the signer order the witness expects when the transaction is finalized.
+
+use super::address_book;
+use super::error::AppError;
+use super::hal_wrapper::HalBackend;
+use super::models::{AppState, Contract, RedemptionReview, ReviewDecision};
+use super::platform;
+use super::sighash::{self, SigningMode};
+use super::taproot::{leaf_script, SIMPLICITY_LEAF_VERSION};
+use super::templates::{CovenantTemplate, Signers, TemplateRegistry};
+use super::workflow::MIN_FEE_SATS;
+use anyhow::{Context, Result};
+use base64::Engine;
+use elements::confidential::{Asset, Value};
+use elements::pset::PartiallySignedTransaction;
+use elements::schnorr::SchnorrSig;
+use elements::secp256k1_zkp::{schnorr, XOnlyPublicKey};
+use elements::taproot::{LeafVersion, TapLeafHash};
+use elements::{Address, AddressParams, SchnorrSighashType, TxOut};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Largest fee a partner accepts without question
+pub const MAX_FEE_SATS: u64 = 10_000;
+
+/// An output of the redemption transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedemptionOutput {
+    pub script_pubkey: String,
+    pub address: Option<String>,
+    /// `None` for confidential outputs
+    pub value_sats: Option<u64>,
+    /// Asset id (hex), `None` when confidential
+    pub asset: Option<String>,
+    pub fee: bool,
+}
+
+/// A redemption PSET decoded for review
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedemptionPset {
+    pub pset: String,
+    /// Txid of the unsigned transaction; the same for every signed copy of the PSET
+    pub unsigned_txid: String,
+    pub input_txid: String,
+    pub input_vout: u32,
+    pub inputs: usize,
+    pub spent_script_pubkey: String,
+    pub spent_value_sats: Option<u64>,
+    pub spent_asset: Option<String>,
+    /// CMR of the Simplicity leaf input 0 spends, if the PSET carries one
+    pub cmr: Option<String>,
+    pub outputs: Vec<RedemptionOutput>,
+    /// Signatures already in the PSET for the leaf, as (x-only public key, signature) hex
+    pub signatures: Vec<(String, String)>,
+}
+
+impl RedemptionPset {
+    /// Decode a base64 PSET spending a Simplicity contract in input 0
+    ///
+    /// Output addresses are shown for `network`, the network of the contract spent.
+    pub fn decode(pset_base64: &str, network: &'static AddressParams) -> Result<Self> {
+        let pset = parse(pset_base64)?;
+        let input = pset.inputs().first().context("PSET has no inputs")?;
+        let spent = spent_utxo(&pset)?;
+        let cmr = input
+            .tap_scripts
+            .values()
+            .find(|(_, version)| version.as_u8() == SIMPLICITY_LEAF_VERSION)
+            .map(|(script, _)| hex::encode(script.as_bytes()));
+        let signatures = match &cmr {
+            Some(cmr) => leaf_signatures(&pset, cmr)?,
+            None => Vec::new(),
+        };
+        let tx = pset.extract_tx().context("Failed to extract the transaction from the PSET")?;
+
+        Ok(RedemptionPset {
+            pset: pset_base64.trim().to_string(),
+            unsigned_txid: tx.txid().to_string(),
+            input_txid: input.previous_txid.to_string(),
+            input_vout: input.previous_output_index,
+            inputs: pset.inputs().len(),
+            spent_script_pubkey: hex::encode(spent.script_pubkey.as_bytes()),
+            spent_value_sats: explicit_value(&spent.value),
+            spent_asset: explicit_asset(&spent.asset),
+            cmr,
+            outputs: tx
+                .output
+                .iter()
+                .map(|out| RedemptionOutput {
+                    script_pubkey: hex::encode(out.script_pubkey.as_bytes()),
+                    address: Address::from_script(&out.script_pubkey, None, network)
+                        .map(|a| a.to_string()),
+                    value_sats: explicit_value(&out.value),
+                    asset: explicit_asset(&out.asset),
+                    fee: out.is_fee(),
+                })
+                .collect(),
+            signatures,
+        })
+    }
+
+    /// Value paid to output 0
+    pub fn payment_sats(&self) -> Option<u64> {
+        self.outputs.first().and_then(|o| o.value_sats)
+    }
+
+    /// Sum of the explicit fee outputs
+    pub fn fee_sats(&self) -> Option<u64> {
+        let fees: Vec<_> = self.outputs.iter().filter(|o| o.fee).collect();
+        if fees.is_empty() {
+            return None;
+        }
+        fees.iter().map(|o| o.value_sats).sum()
+    }
+}
+
+/// One rule a redemption is checked against
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedemptionCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Outcome of [`validate`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RedemptionReport {
+    pub checks: Vec<RedemptionCheck>,
+    /// Valid signatures already in the PSET, by signer slot
+    pub signed_slots: Vec<usize>,
+    pub threshold: usize,
+}
+
+impl RedemptionReport {
+    /// True when every check passed
+    pub fn is_valid(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|c| c.passed)
+    }
+
+    /// Reasons the redemption should be rejected, one line per failed check
+    pub fn failures(&self) -> String {
+        self.checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| format!("{}: {}", c.name, c.detail))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn check(&mut self, name: &'static str, passed: bool, detail: String) {
+        self.checks.push(RedemptionCheck { name, passed, detail });
+    }
+}
+
+impl fmt::Display for RedemptionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let marker = if check.passed { "[OK]" } else { "[FAIL]" };
+            writeln!(f, "{} {}: {}", marker, check.name, check.detail)?;
+        }
+        write!(f, "Signatures: {} of {} required", self.signed_slots.len(), self.threshold)
+    }
+}
+
+/// What the partner expects the redemption to look like
+pub struct Expectation<'a> {
+    pub contract: &'a Contract,
+    /// Template of the contract; without it the output layout and cap are not checked
+    pub template: Option<&'a CovenantTemplate>,
+    pub partner_address: &'a str,
+    /// Amount the partner is owed (e.g. the invoice total), if known
+    pub amount_sats: Option<u64>,
+}
+
+/// Check a redemption against the contract rules and the partner's expectations
+pub fn validate(redemption: &RedemptionPset, expected: &Expectation) -> RedemptionReport {
+    let contract = expected.contract;
+    let template = expected.template;
+    let mut report = RedemptionReport {
+        threshold: template.map(|t| t.threshold(&contract.params)).unwrap_or(0),
+        ..Default::default()
+    };
+
+    // Input 0 must spend the recorded contract through its Simplicity leaf
+    let spends_contract = redemption.spent_script_pubkey.eq_ignore_ascii_case(&contract.script_pubkey)
+        && redemption.cmr.as_deref().is_some_and(|cmr| cmr.eq_ignore_ascii_case(&contract.cmr));
+    report.check(
+        "Spends the contract",
+        spends_contract && redemption.inputs == 1,
+        if redemption.inputs != 1 {
+            format!("expected 1 input, found {}", redemption.inputs)
+        } else if spends_contract {
+            format!("{}:{} at {}", redemption.input_txid, redemption.input_vout, contract.address)
+        } else {
+            format!(
+                "input 0 spends script {} with leaf {} but the contract is {} with CMR {}",
+                redemption.spent_script_pubkey,
+                redemption.cmr.as_deref().unwrap_or("(none)"),
+                contract.script_pubkey,
+                contract.cmr
+            )
+        },
+    );
+
+    if let Some(template) = template {
+        let roles = &template.spend_flow.outputs;
+        report.check(
+            "Output layout",
+            redemption.outputs.len() == roles.len(),
+            format!("expected {} outputs, found {}", roles.len(), redemption.outputs.len()),
+        );
+    }
+
+    // Output 0 pays the partner, at an address on the contract's network
+    let payment = redemption.outputs.first();
+    let partner_script = address_book::parse_address(expected.partner_address).and_then(|address| {
+        let network = contract.network()?;
+        if address.params != network {
+            return Err(anyhow::anyhow!(
+                "{} is a {} address, but the contract is on {}",
+                address,
+                address_book::network_name(address.params),
+                address_book::network_name(network)
+            ));
+        }
+        Ok(hex::encode(address.script_pubkey().as_bytes()))
+    });
+    report.check(
+        "Partner output",
+        matches!((&partner_script, payment), (Ok(spk), Some(out)) if *spk == out.script_pubkey),
+        match (&partner_script, payment) {
+            (Err(e), _) => e.to_string().lines().next().unwrap_or_default().to_string(),
+            (_, None) => "the transaction has no outputs".to_string(),
+            (_, Some(out)) => format!(
+                "output 0 pays {}",
+                out.address.clone().unwrap_or_else(|| out.script_pubkey.clone())
+            ),
+        },
+    );
+
+    // The amount matches what the partner is owed and the covenant's cap
+    let amount = redemption.payment_sats();
+    let cap = template
+        .and_then(|t| t.payment_cap(&contract.params))
+        .map(|cap| (cap * 100_000_000.0).round() as u64);
+    let (amount_ok, amount_detail) = match (amount, expected.amount_sats, cap) {
+        (None, _, _) => (false, "output 0 is confidential; the amount cannot be checked".to_string()),
+        (Some(a), Some(want), _) if a != want => (false, format!("pays {} sats, expected {} sats", a, want)),
+        (Some(a), _, Some(cap)) if a > cap => (false, format!("pays {} sats, above the covenant cap of {} sats", a, cap)),
+        (Some(a), _, _) => (true, format!("pays {} sats", a)),
+    };
+    report.check("Amount", amount_ok, amount_detail);
+
+    // Every output is in the asset being spent
+    let foreign: Vec<usize> = redemption
+        .outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| o.asset.is_none() || o.asset != redemption.spent_asset)
+        .map(|(i, _)| i)
+        .collect();
+    report.check(
+        "Asset",
+        redemption.spent_asset.is_some() && foreign.is_empty(),
+        if foreign.is_empty() {
+            redemption.spent_asset.clone().unwrap_or_else(|| "input 0 is confidential".to_string())
+        } else {
+            format!("outputs {:?} are not in the spent asset", foreign)
+        },
+    );
+
+    let fee = redemption.fee_sats();
+    if template.is_some_and(|t| t.spend_flow.is_covenant()) {
+        // Output 1 sends everything that is not paid or spent on fees back to the covenant
+        let change = redemption.outputs.get(1);
+        let expected_change = match (redemption.spent_value_sats, amount, fee) {
+            (Some(value), Some(paid), Some(fee)) => value.checked_sub(paid + fee),
+            _ => None,
+        };
+        let intact = change.is_some_and(|c| {
+            c.script_pubkey.eq_ignore_ascii_case(&redemption.spent_script_pubkey)
+                && expected_change.is_some_and(|want| c.value_sats == Some(want))
+        });
+        report.check(
+            "Covenant change",
+            intact,
+            match change {
+                None => "output 1 (covenant change) is missing".to_string(),
+                Some(c) if !c.script_pubkey.eq_ignore_ascii_case(&redemption.spent_script_pubkey) => format!(
+                    "output 1 pays {} instead of the covenant",
+                    c.address.clone().unwrap_or_else(|| c.script_pubkey.clone())
+                ),
+                Some(c) => match (c.value_sats, expected_change) {
+                    (Some(v), Some(want)) if v == want => format!("{} sats back to the covenant", v),
+                    (Some(v), Some(want)) => format!("returns {} sats but {} sats are left after payment and fee", v, want),
+                    _ => "the change amount cannot be checked (confidential values or no fee output)".to_string(),
+                },
+            },
+        );
+    }
+
+    // Inputs balance the outputs and the fee is in a sensible range
+    let total_out: Option<u64> = redemption.outputs.iter().map(|o| o.value_sats).sum();
+    report.check(
+        "Fee",
+        fee.is_some_and(|f| (MIN_FEE_SATS..=MAX_FEE_SATS).contains(&f)) && total_out == redemption.spent_value_sats,
+        match fee {
+            None => "no explicit fee output".to_string(),
+            Some(f) if total_out != redemption.spent_value_sats => format!(
+                "{} sats, but the outputs add up to {} sats for an input of {} sats",
+                f,
+                total_out.map(|t| t.to_string()).unwrap_or_else(|| "?".to_string()),
+                redemption.spent_value_sats.map(|t| t.to_string()).unwrap_or_else(|| "?".to_string())
+            ),
+            Some(f) if f < MIN_FEE_SATS => format!("{} sats is below the minimum of {} sats", f, MIN_FEE_SATS),
+            Some(f) if f > MAX_FEE_SATS => format!("{} sats is above the {} sats a redemption should need", f, MAX_FEE_SATS),
+            Some(f) => format!("{} sats", f),
+        },
+    );
+
+    // Signatures already present belong to the contract's signers and sign this transaction
+    if let Some(template) = template {
+        let signers = template.signer_pubkeys(&contract.params);
+        let sighash = sighash::sig_all_hash(&redemption.pset, 0, &contract.cmr, &contract.genesis_hash);
+        let mut problems = Vec::new();
+        for (pubkey, signature) in &redemption.signatures {
+            let Some(slot) = signers.iter().position(|s| s.eq_ignore_ascii_case(pubkey)) else {
+                problems.push(format!("{} is not one of the contract's signers", pubkey));
+                continue;
+            };
+            match sighash.as_ref().map_err(|e| e.to_string()).and_then(|h| {
+                sighash::verify_signature(h, signature, pubkey).map_err(|e| e.to_string())
+            }) {
+                Ok(()) => report.signed_slots.push(slot),
+                Err(e) => problems.push(format!("signer {}: {}", slot + 1, e)),
+            }
+        }
+        // The program counts the signatures and wants exactly the threshold
+        if report.signed_slots.len() > report.threshold {
+            problems.push(format!(
+                "{} signatures, but the contract takes exactly {}",
+                report.signed_slots.len(),
+                report.threshold
+            ));
+        }
+        report.check(
+            "Signatures",
+            problems.is_empty(),
+            if !problems.is_empty() {
+                problems.join("; ")
+            } else if report.signed_slots.is_empty() {
+                "none yet".to_string()
+            } else {
+                format!(
+                    "valid for signer(s) {}",
+                    report.signed_slots.iter().map(|s| (s + 1).to_string()).collect::<Vec<_>>().join(", ")
+                )
+            },
+        );
+    }
+
+    report
+}
+
+/// A decoded redemption, the contract it spends and how it fared against the rules
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub redemption: RedemptionPset,
+    pub contract: Contract,
+    pub partner_address: String,
+    pub report: RedemptionReport,
+}
+
+impl Review {
+    /// The contract's signers, in slot order
+    pub fn signers(&self, registry: &TemplateRegistry) -> Signers {
+        registry
+            .get(&self.contract.template_id)
+            .map(|t| t.signers(&self.contract.params))
+            .unwrap_or_default()
+    }
+
+    /// Record of the partner's decision on this redemption
+    pub fn record(&self, decision: ReviewDecision) -> RedemptionReview {
+        RedemptionReview {
+            unsigned_txid: self.redemption.unsigned_txid.clone(),
+            contract_address: self.contract.address.clone(),
+            partner_address: self.partner_address.clone(),
+            amount_sats: self.redemption.payment_sats(),
+            fee_sats: self.redemption.fee_sats(),
+            decision,
+            reviewed_at: platform::now_secs(),
+        }
+    }
+}
+
+/// Decode a redemption PSET, find the recorded contract it spends and validate it
+pub fn review(
+    state: &AppState,
+    registry: &TemplateRegistry,
+    pset_base64: &str,
+    partner_address: &str,
+    amount_sats: Option<u64>,
+) -> Result<Review> {
+    let spent_script_pubkey = hex::encode(spent_utxo(&parse(pset_base64)?)?.script_pubkey.as_bytes());
+    let contract = state
+        .contracts
+        .iter()
+        .find(|c| c.script_pubkey.eq_ignore_ascii_case(&spent_script_pubkey))
+        .cloned()
+        .with_context(|| {
+            format!(
+                "Input 0 spends script {} which is not a recorded contract.\n\nOnly redemptions of contracts known to this app can be checked. Import the contract record from the promoter first.",
+                spent_script_pubkey
+            )
+        })?;
+    let redemption = RedemptionPset::decode(pset_base64, contract.network()?)?;
+    let expected = Expectation {
+        contract: &contract,
+        template: registry.get(&contract.template_id),
+        partner_address,
+        amount_sats,
+    };
+    let report = validate(&redemption, &expected);
+    Ok(Review {
+        redemption,
+        contract,
+        partner_address: partner_address.trim().to_string(),
+        report,
+    })
+}
+
+/// Sign input 0 for the signer slot of `privkey` and add the signature to the PSET
+///
+/// Returns the updated PSET and the signature. The key must be one of `signers`, or the
+/// program would ignore the signature, and the PSET must still be short of the threshold.
+pub fn co_sign(
+    hal: &dyn HalBackend,
+    mode: SigningMode,
+    pset_base64: &str,
+    cmr: &str,
+    genesis_hash: &str,
+    signers: &Signers,
+    privkey: &str,
+) -> Result<(String, String)> {
+    let pubkey = sighash::pubkey_of(privkey)?;
+    check_signer(pset_base64, cmr, signers, &pubkey)
+        .with_context(|| format!("Cannot sign with the private key for {}", pubkey))?;
+    let signature = sighash::sign_input(hal, mode, pset_base64, 0, cmr, genesis_hash, privkey)?;
+    let pset = add_signature(pset_base64, cmr, &pubkey, &signature)?;
+    Ok((pset, signature))
+}
+
+/// Add a signature made elsewhere (e.g. on a point-of-sale terminal) to the PSET
+///
+/// The key must be one of `signers`, the PSET must still be short of the threshold, and
+/// the signature must verify against input 0's `sig_all_hash`, so a wrong key, a surplus
+/// signature or a signature over another PSET is rejected here instead of failing the
+/// program at broadcast.
+pub fn add_verified_signature(
+    pset_base64: &str,
+    cmr: &str,
+    genesis_hash: &str,
+    signers: &Signers,
+    pubkey: &str,
+    signature: &str,
+) -> Result<String> {
+    check_signer(pset_base64, cmr, signers, pubkey.trim())?;
+    let sighash = sighash::sig_all_hash(pset_base64, 0, cmr, genesis_hash)?;
+    sighash::verify_signature(&sighash, signature, pubkey)
+        .context("The signature does not sign this PSET's sig_all_hash")?;
+    add_signature(pset_base64, cmr, pubkey, signature)
+}
+
+/// Refuse a key that is not a signer, or a signature past the threshold
+///
+/// The program checks for exactly `threshold` signatures, so one more would make the
+/// witness fail. Replacing the key's own signature is allowed.
+fn check_signer(pset_base64: &str, cmr: &str, signers: &Signers, pubkey: &str) -> Result<()> {
+    let is_signer = |key: &str| signers.pubkeys.iter().any(|s| s.eq_ignore_ascii_case(key));
+    if !is_signer(pubkey) {
+        return Err(AppError::SignatureMismatch {
+            detail: format!("{} is not a signer of this contract", pubkey),
+            signers: signers.pubkeys.clone(),
+            threshold: None,
+        }
+        .into());
+    }
+    let others = signatures(pset_base64, cmr)?
+        .iter()
+        .filter(|(key, _)| is_signer(key) && !key.eq_ignore_ascii_case(pubkey))
+        .count();
+    if others >= signers.threshold {
+        return Err(anyhow::anyhow!(
+            "The PSET already has {} signature(s) and the contract takes exactly {}; it is ready to finalize",
+            others,
+            signers.threshold
+        ));
+    }
+    Ok(())
+}
+
+/// Add a signature over input 0's Simplicity leaf to the PSET
+pub fn add_signature(pset_base64: &str, cmr: &str, pubkey: &str, signature: &str) -> Result<String> {
+    let mut pset = parse(pset_base64)?;
+    let key = XOnlyPublicKey::from_str(pubkey.trim()).with_context(|| format!("Invalid public key: {}", pubkey))?;
+    let sig = schnorr::Signature::from_str(signature.trim()).with_context(|| format!("Invalid signature: {}", signature))?;
+    let input = pset.inputs_mut().first_mut().context("PSET has no inputs")?;
+    input.tap_script_sigs.insert(
+        (key, leaf_hash(cmr)?),
+        SchnorrSig { sig, hash_ty: SchnorrSighashType::Default },
+    );
+    Ok(base64::engine::general_purpose::STANDARD.encode(elements::encode::serialize(&pset)))
+}
+
+/// Signatures in the PSET for input 0's Simplicity leaf, as (x-only public key, signature) hex
+pub fn signatures(pset_base64: &str, cmr: &str) -> Result<Vec<(String, String)>> {
+    leaf_signatures(&parse(pset_base64)?, cmr)
+}
+
+/// Signatures in signer order, one slot per signer, as the witness expects them
+pub fn slot_signatures(signers: &[String], signatures: &[(String, String)]) -> Vec<Option<String>> {
+    signers
+        .iter()
+        .map(|signer| {
+            signatures
+                .iter()
+                .find(|(pubkey, _)| pubkey.eq_ignore_ascii_case(signer))
+                .map(|(_, sig)| sig.clone())
+        })
+        .collect()
+}
+
+/// Whether two PSETs are copies of the same unsigned transaction
+pub fn same_transaction(a: &str, b: &str) -> Result<bool> {
+    let txid = |pset: &str| -> Result<_> {
+        Ok(parse(pset)?.extract_tx().context("Failed to extract the transaction from the PSET")?.txid())
+    };
+    Ok(txid(a)? == txid(b)?)
+}
+
+/// The UTXO input 0 spends
+fn spent_utxo(pset: &PartiallySignedTransaction) -> Result<&TxOut> {
+    pset.inputs()
+        .first()
+        .context("PSET has no inputs")?
+        .witness_utxo
+        .as_ref()
+        .context("Input 0 has no witness UTXO. Ask the participant for the PSET after it was updated with the contract's Simplicity data.")
+}
+
+fn parse(pset_base64: &str) -> Result<PartiallySignedTransaction> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(pset_base64.trim())
+        .context("PSET is not valid base64")?;
+    elements::encode::deserialize(&bytes).context("Failed to decode PSET")
+}
+
+fn leaf_hash(cmr: &str) -> Result<TapLeafHash> {
+    let version = LeafVersion::from_u8(SIMPLICITY_LEAF_VERSION).expect("valid leaf version");
+    Ok(TapLeafHash::from_script(&leaf_script(cmr)?, version))
+}
+
+fn leaf_signatures(pset: &PartiallySignedTransaction, cmr: &str) -> Result<Vec<(String, String)>> {
+    let leaf = leaf_hash(cmr)?;
+    Ok(pset
+        .inputs()
+        .first()
+        .map(|input| {
+            input
+                .tap_script_sigs
+                .iter()
+                .filter(|((_, hash), _)| *hash == leaf)
+                .map(|((key, _), sig)| (key.to_string(), hex::encode(sig.sig.serialize())))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn explicit_value(value: &Value) -> Option<u64> {
+    match value {
+        Value::Explicit(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn explicit_asset(asset: &Asset) -> Option<String> {
+    match asset {
+        Asset::Explicit(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::builtin_templates::COV_P2MS_ID;
+    use crate::app_core::internal_key::InternalKey;
+    use crate::app_core::sighash::LIQUID_TESTNET_GENESIS_HASH;
+    use crate::app_core::taproot::spend_info;
+    use crate::app_core::templates::{TemplateRegistry, TemplateValues};
+    use elements::confidential::Nonce;
+    use elements::pset::{Input, Output};
+    use elements::secp256k1_zkp::Secp256k1;
+    use elements::{AssetId, OutPoint, Script, TxOut, Txid};
+    use simplicity::hashes::Hash;
+
+    /// Any 32 bytes will do: the leaf is only hashed, never executed
+    const CMR: &str = "af5b897effb80a06fa19362347b7807dc0e774eaf4271d6526545965b44ddc3e";
+    const TLBTC: &str = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+    /// Signers 1*G, 2*G and 3*G
+    const KEYS: [(&str, &str); 3] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        ),
+    ];
+
+    fn contract_script() -> Script {
+        Script::new_v1_p2tr_tweaked(spend_info(CMR, &InternalKey::Nums.key_hex()).unwrap().output_key())
+    }
+
+    fn partner_address() -> Address {
+        partner_address_on(&AddressParams::LIQUID_TESTNET)
+    }
+
+    fn partner_address_on(network: &'static AddressParams) -> Address {
+        let key = XOnlyPublicKey::from_str(KEYS[2].1).unwrap();
+        Address::p2tr(&Secp256k1::new(), key, None, None, network)
+    }
+
+    fn contract() -> Contract {
+        let mut params = TemplateValues::new();
+        for (i, (_, pubkey)) in KEYS.iter().enumerate() {
+            params.insert(format!("participant_{}", i + 1), pubkey.to_string());
+        }
+        params.insert("threshold".to_string(), "2".to_string());
+        params.insert("max_payment".to_string(), "0.0005".to_string());
+        Contract {
+            voucher_base64: String::new(),
+            script_pubkey: hex::encode(contract_script().as_bytes()),
+            address: Address::from_script(&contract_script(), None, &AddressParams::LIQUID_TESTNET).unwrap().to_string(),
+            info: None,
+            template_id: COV_P2MS_ID.to_string(),
+            params,
+            source_hash: String::new(),
+            compiler_version: String::new(),
+            cmr: CMR.to_string(),
+            control_block: String::new(),
+            internal_key: InternalKey::Nums,
+            genesis_hash: LIQUID_TESTNET_GENESIS_HASH.to_string(),
+            oracle_url: String::new(),
+            created_at: 0,
+        }
+    }
+
+    /// Voucher of 100,000 sats paying `payment` to the partner, `change` back and `fee`
+    fn redemption(payment: u64, change_script: Script, change: u64, fee: u64) -> String {
+        let asset = Asset::Explicit(AssetId::from_str(TLBTC).unwrap());
+        let info = spend_info(CMR, &InternalKey::Nums.key_hex()).unwrap();
+        let leaf = (leaf_script(CMR).unwrap(), LeafVersion::from_u8(SIMPLICITY_LEAF_VERSION).unwrap());
+
+        let mut pset = PartiallySignedTransaction::new_v2();
+        let mut input = Input::from_prevout(OutPoint::new(Txid::all_zeros(), 0));
+        input.witness_utxo = Some(TxOut {
+            asset,
+            value: Value::Explicit(100_000),
+            nonce: Nonce::Null,
+            script_pubkey: contract_script(),
+            witness: Default::default(),
+        });
+        input.tap_scripts.insert(info.control_block(&leaf).unwrap(), leaf);
+        pset.add_input(input);
+        for (script_pubkey, amount) in [(partner_address().script_pubkey(), payment), (change_script, change), (Script::new(), fee)] {
+            pset.add_output(Output::from_txout(TxOut {
+                asset,
+                value: Value::Explicit(amount),
+                nonce: Nonce::Null,
+                script_pubkey,
+                witness: Default::default(),
+            }));
+        }
+        base64::engine::general_purpose::STANDARD.encode(elements::encode::serialize(&pset))
+    }
+
+    fn check(pset: &str, amount_sats: Option<u64>) -> RedemptionReport {
+        let registry = TemplateRegistry::builtin();
+        let contract = contract();
+        let partner = partner_address().to_string();
+        let expected = Expectation {
+            contract: &contract,
+            template: registry.get(COV_P2MS_ID),
+            partner_address: &partner,
+            amount_sats,
+        };
+        validate(&RedemptionPset::decode(pset, &AddressParams::LIQUID_TESTNET).unwrap(), &expected)
+    }
+
+    #[test]
+    fn accepts_a_well_formed_redemption() {
+        let report = check(&redemption(30_000, contract_script(), 69_900, 100), Some(30_000));
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.threshold, 2);
+    }
+
+    #[test]
+    fn rejects_broken_rules() {
+        let failed = |report: RedemptionReport| -> Vec<&'static str> {
+            report.checks.iter().filter(|c| !c.passed).map(|c| c.name).collect()
+        };
+        // Change diverted away from the covenant
+        let diverted = check(&redemption(30_000, partner_address().script_pubkey(), 69_900, 100), None);
+        assert_eq!(failed(diverted), vec!["Covenant change"]);
+        // Change short-changed, with the difference hidden in the fee
+        let greedy_fee = check(&redemption(30_000, contract_script(), 49_900, 20_100), None);
+        assert_eq!(failed(greedy_fee), vec!["Fee"]);
+        // Wrong amount and above the cap
+        assert_eq!(failed(check(&redemption(30_000, contract_script(), 69_900, 100), Some(25_000))), vec!["Amount"]);
+        assert_eq!(failed(check(&redemption(60_000, contract_script(), 39_900, 100), None)), vec!["Amount"]);
+    }
+
+    #[test]
+    fn co_signatures_travel_in_the_pset() {
+        let pset = redemption(30_000, contract_script(), 69_900, 100);
+        let sighash = sighash::sig_all_hash(&pset, 0, CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        let participant = sighash::sign_hash(&sighash, KEYS[0].0).unwrap();
+        let signed = add_signature(&pset, CMR, KEYS[0].1, &participant).unwrap();
+        assert!(same_transaction(&pset, &signed).unwrap());
+
+        let report = check(&signed, Some(30_000));
+        assert!(report.is_valid(), "{}", report);
+        assert_eq!(report.signed_slots, vec![0]);
+
+        let partner = sighash::sign_hash(&sighash, KEYS[2].0).unwrap();
+        let signed = add_signature(&signed, CMR, KEYS[2].1, &partner).unwrap();
+        let signers: Vec<String> = KEYS.iter().map(|(_, pk)| pk.to_string()).collect();
+        let slots = slot_signatures(&signers, &signatures(&signed, CMR).unwrap());
+        assert_eq!(slots, vec![Some(participant), None, Some(partner)]);
+
+        // A signature over a different transaction is caught
+        let forged = add_signature(&pset, CMR, KEYS[1].1, &slots[0].clone().unwrap()).unwrap();
+        assert_eq!(check(&forged, None).checks.last().map(|c| c.passed), Some(false));
+    }
+
+    #[test]
+    fn outside_signatures_are_checked_before_they_are_added() {
+        let pset = redemption(30_000, contract_script(), 69_900, 100);
+        let signers = Signers { pubkeys: KEYS.iter().map(|(_, pk)| pk.to_string()).collect(), threshold: 2 };
+        let sighash = sighash::sig_all_hash(&pset, 0, CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        let signature = sighash::sign_hash(&sighash, KEYS[0].0).unwrap();
+
+        let signed = add_verified_signature(&pset, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[0].1, &signature).unwrap();
+        assert_eq!(signatures(&signed, CMR).unwrap(), vec![(KEYS[0].1.to_string(), signature.clone())]);
+
+        // Someone else's key, a signature under another key, and a signature over another PSET
+        assert!(add_verified_signature(&pset, CMR, LIQUID_TESTNET_GENESIS_HASH, &Signers { pubkeys: signers.pubkeys[1..].to_vec(), threshold: 2 }, KEYS[0].1, &signature).is_err());
+        assert!(add_verified_signature(&pset, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[1].1, &signature).is_err());
+        let other = redemption(20_000, contract_script(), 79_900, 100);
+        assert!(add_verified_signature(&other, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[0].1, &signature).is_err());
+    }
+
+    #[test]
+    fn signatures_stop_at_the_threshold() {
+        let pset = redemption(30_000, contract_script(), 69_900, 100);
+        let signers = Signers { pubkeys: KEYS.iter().map(|(_, pk)| pk.to_string()).collect(), threshold: 2 };
+        let sighash = sighash::sig_all_hash(&pset, 0, CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        let sign = |i: usize| sighash::sign_hash(&sighash, KEYS[i].0).unwrap();
+
+        let signed = add_verified_signature(&pset, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[0].1, &sign(0)).unwrap();
+        let signed = add_verified_signature(&signed, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[2].1, &sign(2)).unwrap();
+        let error = add_verified_signature(&signed, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[1].1, &sign(1)).unwrap_err();
+        assert!(error.to_string().contains("takes exactly 2"), "{}", error);
+        // A signer may still replace their own signature
+        assert!(add_verified_signature(&signed, CMR, LIQUID_TESTNET_GENESIS_HASH, &signers, KEYS[0].1, &sign(0)).is_ok());
+
+        // A third signature slipped in some other way fails the review
+        let report = check(&signed, Some(30_000));
+        assert!(report.is_valid(), "{}", report);
+        let overfull = add_signature(&signed, CMR, KEYS[1].1, &sign(1)).unwrap();
+        let report = check(&overfull, Some(30_000));
+        assert!(!report.is_valid());
+        assert!(report.failures().contains("3 signatures, but the contract takes exactly 2"), "{}", report);
+    }
+
+    #[test]
+    fn partner_addresses_must_be_on_the_contracts_network() {
+        let pset = redemption(30_000, contract_script(), 69_900, 100);
+        let registry = TemplateRegistry::builtin();
+        let mainnet_partner = partner_address_on(&AddressParams::LIQUID).to_string();
+
+        // Same key, other network: the script matches but the address is refused
+        let mut state = AppState::default();
+        state.contracts.push(contract());
+        let checked = review(&state, &registry, &pset, &mainnet_partner, Some(30_000)).unwrap();
+        assert!(!checked.report.is_valid());
+        assert!(checked.report.failures().contains("is a Liquid address, but the contract is on Liquid Testnet"), "{}", checked.report);
+
+        // A mainnet contract shows and checks mainnet addresses
+        let mainnet = Contract {
+            address: Address::from_script(&contract_script(), None, &AddressParams::LIQUID).unwrap().to_string(),
+            ..contract()
+        };
+        state.contracts = vec![mainnet];
+        let checked = review(&state, &registry, &pset, &mainnet_partner, Some(30_000)).unwrap();
+        assert!(checked.report.is_valid(), "{}", checked.report);
+        assert_eq!(checked.redemption.outputs[0].address.as_deref(), Some(mainnet_partner.as_str()));
+    }
+}
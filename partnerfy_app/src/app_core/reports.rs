@@ -0,0 +1,307 @@
+//! Redemption and settlement reports
+//!
+//! Builds partner statements from the tracked transaction history of the recorded
+//! contracts (see `app_core::history`): every spend of a contract that pays a partner in
+//! the address book becomes one [`RedemptionLine`]. Lines can be narrowed to a partner,
+//! a contract and a date range (by block time), and are totalled per partner and per
+//! contract. [`Report::to_csv`] and [`Report::to_json`] export the same data the
+//! Reports page shows.
+
+use super::audit;
+use super::csv;
+use super::elements_rpc::ElementsRPC;
+use super::history::{self, HistoryEntry, TxStatus};
+use super::models::AppState;
+use super::platform;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// One payment to a partner by a redemption
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RedemptionLine {
+    pub txid: String,
+    /// Output paying the partner
+    pub vout: u32,
+    pub contract: String,
+    pub template_id: String,
+    pub partner_name: String,
+    pub partner_address: String,
+    /// `None` for confidential outputs
+    pub amount_sats: Option<u64>,
+    /// Transaction fee, on the first partner output of the transaction only so totals
+    /// count it once
+    pub fee_sats: Option<u64>,
+    /// `None` while unconfirmed
+    pub height: Option<u64>,
+    /// Block time (UNIX seconds)
+    pub time: Option<u64>,
+}
+
+/// Which redemptions to report
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReportFilter {
+    /// Partner address
+    pub partner: Option<String>,
+    /// Contract address
+    pub contract: Option<String>,
+    /// Earliest block time, inclusive
+    pub from: Option<u64>,
+    /// Latest block time, inclusive
+    pub to: Option<u64>,
+    /// Leave out transactions that are not in a block yet
+    pub confirmed_only: bool,
+}
+
+impl ReportFilter {
+    pub fn matches(&self, line: &RedemptionLine) -> bool {
+        let dated = self.from.is_some() || self.to.is_some();
+        self.partner.as_deref().is_none_or(|p| line.partner_address == p)
+            && self.contract.as_deref().is_none_or(|c| line.contract == c)
+            && !((self.confirmed_only || dated) && line.height.is_none())
+            && self.from.is_none_or(|from| line.time.is_some_and(|t| t >= from))
+            && self.to.is_none_or(|to| line.time.is_some_and(|t| t <= to))
+    }
+}
+
+/// Totals for one partner or one contract
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Total {
+    /// Partner name, or the contract's template id
+    pub label: String,
+    pub address: String,
+    pub redemptions: usize,
+    pub amount_sats: u64,
+    pub fee_sats: u64,
+    /// Lines with a confidential amount, not included in `amount_sats`
+    pub confidential: usize,
+}
+
+impl Total {
+    fn add(&mut self, line: &RedemptionLine) {
+        self.redemptions += 1;
+        match line.amount_sats {
+            Some(amount) => self.amount_sats += amount,
+            None => self.confidential += 1,
+        }
+        self.fee_sats += line.fee_sats.unwrap_or(0);
+    }
+}
+
+/// Redemptions matching a filter, oldest first, with their totals
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Report {
+    pub generated_at: u64,
+    pub filter: ReportFilter,
+    pub lines: Vec<RedemptionLine>,
+    pub by_partner: Vec<Total>,
+    pub by_contract: Vec<Total>,
+    /// Contracts whose history could not be fetched
+    pub errors: Vec<String>,
+}
+
+impl Report {
+    pub fn total_sats(&self) -> u64 {
+        self.by_partner.iter().map(|t| t.amount_sats).sum()
+    }
+
+    pub fn total_fee_sats(&self) -> u64 {
+        self.by_partner.iter().map(|t| t.fee_sats).sum()
+    }
+
+    /// One row per redemption line, with a header
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,txid,vout,partner,partner_address,contract,template,amount_sats,fee_sats,height\n");
+        for line in &self.lines {
+            let fields = [
+                line.time.map(audit::format_time).unwrap_or_default(),
+                line.txid.clone(),
+                line.vout.to_string(),
+                line.partner_name.clone(),
+                line.partner_address.clone(),
+                line.contract.clone(),
+                line.template_id.clone(),
+                line.amount_sats.map(|a| a.to_string()).unwrap_or_default(),
+                line.fee_sats.map(|f| f.to_string()).unwrap_or_default(),
+                line.height.map(|h| h.to_string()).unwrap_or_default(),
+            ];
+            csv.push_str(&fields.iter().map(|f| csv::field(f)).collect::<Vec<_>>().join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Partner payments in the history of one contract
+pub fn redemption_lines(state: &AppState, contract: &str, entries: &[HistoryEntry]) -> Vec<RedemptionLine> {
+    let template_id = state
+        .contracts
+        .iter()
+        .find(|c| c.address == contract)
+        .map(|c| c.template_id.clone())
+        .unwrap_or_default();
+    let mut lines = Vec::new();
+    for entry in entries.iter().filter(|e| !e.spends.is_empty()) {
+        let (height, time) = match entry.status {
+            TxStatus::Confirmed { height, time, .. } => (Some(height), time),
+            TxStatus::Unconfirmed => (None, None),
+        };
+        let mut fee_sats = entry.fee_sats;
+        for output in entry.outputs.iter().filter(|o| !o.to_contract && !o.fee) {
+            let Some(partner) = state.partners.iter().find(|p| output.address.as_deref() == Some(p.address.as_str())) else {
+                continue;
+            };
+            lines.push(RedemptionLine {
+                txid: entry.txid.clone(),
+                vout: output.index,
+                contract: contract.to_string(),
+                template_id: template_id.clone(),
+                partner_name: partner.name.clone(),
+                partner_address: partner.address.clone(),
+                amount_sats: output.value_sats,
+                fee_sats: fee_sats.take(),
+                height,
+                time,
+            });
+        }
+    }
+    lines
+}
+
+/// Filter and total redemption lines
+pub fn build(state: &AppState, lines: Vec<RedemptionLine>, filter: ReportFilter, errors: Vec<String>) -> Report {
+    // A transaction spending several contracts appears in each history; keep it once
+    let mut seen = HashSet::new();
+    let mut lines: Vec<RedemptionLine> = lines
+        .into_iter()
+        .filter(|l| filter.matches(l) && seen.insert((l.txid.clone(), l.vout)))
+        .collect();
+    // Oldest first, unconfirmed last
+    lines.sort_by_key(|l| (l.height.unwrap_or(u64::MAX), l.txid.clone(), l.vout));
+
+    let mut by_partner: Vec<Total> = state
+        .partners
+        .iter()
+        .filter(|p| filter.partner.as_deref().is_none_or(|f| p.address == f))
+        .map(|p| Total { label: p.name.clone(), address: p.address.clone(), ..Default::default() })
+        .collect();
+    let mut by_contract: BTreeMap<String, Total> = BTreeMap::new();
+    for line in &lines {
+        if let Some(total) = by_partner.iter_mut().find(|t| t.address == line.partner_address) {
+            total.add(line);
+        }
+        by_contract
+            .entry(line.contract.clone())
+            .or_insert_with(|| Total { label: line.template_id.clone(), address: line.contract.clone(), ..Default::default() })
+            .add(line);
+    }
+
+    Report {
+        generated_at: platform::now_secs(),
+        filter,
+        lines,
+        by_partner,
+        by_contract: by_contract.into_values().collect(),
+        errors,
+    }
+}
+
+/// Fetch the history of the recorded contracts (or only the filtered one) and build the report
+pub async fn load(rpc: &ElementsRPC, state: &AppState, filter: ReportFilter) -> Report {
+    let tip_height = rpc.get_tip_height().await.ok();
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    let contracts = state
+        .contracts
+        .iter()
+        .map(|c| c.address.as_str())
+        .filter(|a| filter.contract.as_deref().is_none_or(|c| *a == c));
+    for address in contracts {
+        let entries = match rpc.get_address_txs(address).await {
+            Ok(txs) => txs
+                .iter()
+                .map(|tx| history::parse_entry(tx, address, tip_height))
+                .collect::<Result<Vec<_>>>(),
+            Err(e) => Err(e),
+        };
+        match entries {
+            Ok(entries) => lines.extend(redemption_lines(state, address, &entries)),
+            Err(e) => errors.push(format!("{}: {}", address, e)),
+        }
+    }
+    build(state, lines, filter, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::history::{ContractSpend, HistoryOutput};
+    use crate::app_core::models::Partner;
+
+    fn output(index: u32, address: &str, value_sats: Option<u64>, to_contract: bool) -> HistoryOutput {
+        HistoryOutput { index, address: Some(address.to_string()), value_sats, to_contract, fee: false }
+    }
+
+    fn spend(txid: &str, height: Option<u64>, outputs: Vec<HistoryOutput>) -> HistoryEntry {
+        HistoryEntry {
+            txid: txid.to_string(),
+            status: match height {
+                Some(height) => TxStatus::Confirmed { height, confirmations: None, time: Some(height * 60) },
+                None => TxStatus::Unconfirmed,
+            },
+            outputs,
+            spends: vec![ContractSpend {
+                input: 0,
+                prev_txid: "00".repeat(32),
+                prev_vout: 0,
+                value_sats: None,
+                signers: Vec::new(),
+                note: None,
+            }],
+            fee_sats: Some(100),
+        }
+    }
+
+    #[test]
+    fn redemptions_are_grouped_by_partner_contract_and_date() {
+        let mut state = AppState::default();
+        for (name, address) in [("Café, Bar", "tex1cafe"), ("Shop", "tex1shop")] {
+            state.partners.push(Partner { address: address.to_string(), pubkey: None, name: name.to_string() });
+        }
+        let entries = vec![
+            // Funding: not a redemption
+            HistoryEntry { spends: Vec::new(), ..spend("f0", Some(10), vec![output(0, "tex1c", Some(100_000), true)]) },
+            spend("a1", Some(20), vec![output(0, "tex1cafe", Some(3_000), false), output(1, "tex1c", Some(96_900), true)]),
+            spend("a2", Some(30), vec![output(0, "tex1shop", Some(5_000), false), output(1, "tex1stranger", Some(1_000), false)]),
+            spend("a3", None, vec![output(0, "tex1cafe", None, false)]),
+        ];
+        let lines = redemption_lines(&state, "tex1c", &entries);
+        assert_eq!(lines.len(), 3);
+
+        let all = build(&state, lines.clone(), ReportFilter::default(), Vec::new());
+        assert_eq!(all.by_partner[0].redemptions, 2);
+        assert_eq!(all.by_partner[0].confidential, 1);
+        assert_eq!(all.total_sats(), 8_000);
+        assert_eq!(all.total_fee_sats(), 300);
+        assert_eq!(all.by_contract.len(), 1);
+        assert_eq!(all.lines.last().unwrap().txid, "a3");
+
+        // Dates and confirmed_only leave out the mempool transaction
+        let dated = ReportFilter { from: Some(25 * 60), ..Default::default() };
+        let report = build(&state, lines.clone(), dated, Vec::new());
+        assert_eq!(report.lines.iter().map(|l| l.txid.as_str()).collect::<Vec<_>>(), vec!["a2"]);
+        let cafe = ReportFilter { partner: Some("tex1cafe".into()), confirmed_only: true, ..Default::default() };
+        let report = build(&state, lines, cafe, Vec::new());
+        assert_eq!(report.by_partner.len(), 1);
+        assert_eq!(report.total_sats(), 3_000);
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.contains("\"Café, Bar\",tex1cafe,tex1c,,3000,100,20"));
+        assert!(report.to_json().unwrap().contains("\"by_partner\""));
+    }
+}
@@ -0,0 +1,422 @@
+//! Native Simplicity `sig_all_hash` and signing
+//!
+//! The contracts check signatures with `jet::sig_all_hash()`, which commits to the whole
+//! transaction, every spent output, the input index, the tap leaf (CMR and control block)
+//! and the chain's genesis hash. Computing it here lets us check the signatures
+//! `hal-simplicity simplicity sighash` produces, or skip the CLI and sign in Rust.
+
+use super::error::AppError;
+use super::hal_wrapper::HalBackend;
+use super::taproot::leaf_script;
+use anyhow::{Context, Result};
+use base64::Engine;
+use elements::pset::PartiallySignedTransaction;
+use elements::secp256k1_zkp::{schnorr, Keypair, Message, Secp256k1, XOnlyPublicKey};
+use elements::taproot::ControlBlock;
+use elements::{BlockHash, Transaction, TxOut};
+use serde::{Deserialize, Serialize};
+use simplicity::hashes::Hash;
+use simplicity::jet::elements::{ElementsEnv, ElementsUtxo};
+use simplicity::Cmr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Liquid testnet genesis block hash, the one `hal-simplicity` commits to by default
+pub const LIQUID_TESTNET_GENESIS_HASH: &str = "a771da8e52ee6ad581ed1e9a99825e5b3b7992225534eaa2ae23244fe26ab1c1";
+
+/// Genesis hash assumed for records saved before the chain was recorded with them
+///
+/// Those contracts could only be created on Liquid testnet.
+pub fn default_genesis_hash() -> String {
+    LIQUID_TESTNET_GENESIS_HASH.to_string()
+}
+
+/// How contract inputs get signed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningMode {
+    /// `hal-simplicity simplicity sighash -x`, with the signature checked against the native hash
+    #[default]
+    Hal,
+    /// Hash and sign in Rust; `hal-simplicity` is not involved
+    Native,
+}
+
+/// `sig_all_hash` of input `input_index` of a PSET spending the Simplicity leaf `cmr_hex`
+///
+/// Every input needs its `witness_utxo`, and the spent input needs the tap leaf and
+/// control block (as set by `hal-simplicity simplicity pset update-input`).
+pub fn sig_all_hash(pset_base64: &str, input_index: u32, cmr_hex: &str, genesis_hash: &str) -> Result<[u8; 32]> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(pset_base64.trim())
+        .context("PSET is not valid base64")?;
+    let pset: PartiallySignedTransaction =
+        elements::encode::deserialize(&bytes).context("Failed to decode PSET")?;
+
+    let input = pset
+        .inputs()
+        .get(input_index as usize)
+        .with_context(|| format!("PSET has no input {}", input_index))?;
+    let leaf = leaf_script(cmr_hex)?;
+    let control_block = input
+        .tap_scripts
+        .iter()
+        .find(|(_, (script, _))| *script == leaf)
+        .map(|(cb, _)| cb.clone())
+        .with_context(|| format!("Input {} has no tap leaf for CMR {}", input_index, cmr_hex))?;
+
+    let utxos = pset
+        .inputs()
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            input
+                .witness_utxo
+                .clone()
+                .map(ElementsUtxo::from)
+                .with_context(|| format!("Input {} has no witness UTXO; the sighash commits to every spent output", i))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let tx = pset.extract_tx().context("Failed to extract the transaction from the PSET")?;
+    let cmr = Cmr::from_str(cmr_hex.trim()).map_err(|e| anyhow::anyhow!("Invalid CMR {}: {}", cmr_hex, e))?;
+    env_sighash(tx, utxos, input_index, cmr, control_block, genesis_hash)
+}
+
+/// `sig_all_hash` of input `input_index` of a broadcast transaction
+///
+/// The CMR and control block come from the input's Taproot script-path witness
+/// (`[simplicity witness, program, cmr, control block]`); `spent` are the outputs the
+/// transaction's inputs spend, in input order.
+pub fn spent_sig_all_hash(tx: &Transaction, spent: &[TxOut], input_index: u32, genesis_hash: &str) -> Result<[u8; 32]> {
+    let stack = &tx
+        .input
+        .get(input_index as usize)
+        .with_context(|| format!("Transaction has no input {}", input_index))?
+        .witness
+        .script_witness;
+    if stack.len() < 2 {
+        return Err(anyhow::anyhow!("Input {} is not a Taproot script-path spend", input_index));
+    }
+    let control_block = ControlBlock::from_slice(&stack[stack.len() - 1])
+        .map_err(|e| anyhow::anyhow!("Input {} has an invalid control block: {}", input_index, e))?;
+    let cmr_bytes: [u8; 32] = stack[stack.len() - 2]
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Input {} does not spend a Simplicity leaf (tap script is not a 32-byte CMR)", input_index))?;
+    if spent.len() != tx.input.len() {
+        return Err(anyhow::anyhow!(
+            "Got {} spent outputs for {} inputs; the sighash commits to every spent output",
+            spent.len(),
+            tx.input.len()
+        ));
+    }
+    let utxos = spent.iter().cloned().map(ElementsUtxo::from).collect();
+    env_sighash(tx.clone(), utxos, input_index, Cmr::from_byte_array(cmr_bytes), control_block, genesis_hash)
+}
+
+fn env_sighash(
+    tx: Transaction,
+    utxos: Vec<ElementsUtxo>,
+    input_index: u32,
+    cmr: Cmr,
+    control_block: ControlBlock,
+    genesis_hash: &str,
+) -> Result<[u8; 32]> {
+    let genesis_hash = BlockHash::from_str(genesis_hash.trim())
+        .with_context(|| format!("Invalid genesis hash: {}", genesis_hash))?;
+    let env = ElementsEnv::new(Arc::new(tx), utxos, input_index, cmr, control_block, None, genesis_hash);
+    Ok(env.c_tx_env().sighash_all().to_byte_array())
+}
+
+/// Which of `pubkeys` (x-only hex) have a valid signature over `sighash` inside `witness`
+///
+/// Simplicity witnesses are bit strings, so a signature inside an `Option` or `Either`
+/// is not byte aligned; every bit offset is tried. Returns one flag per public key.
+pub fn find_signatures(sighash: &[u8; 32], witness: &[u8], pubkeys: &[String]) -> Vec<bool> {
+    let secp = Secp256k1::verification_only();
+    let message = Message::from_digest(*sighash);
+    let keys: Vec<Option<XOnlyPublicKey>> = pubkeys
+        .iter()
+        .map(|pk| XOnlyPublicKey::from_str(strip_hex_prefix(pk)).ok())
+        .collect();
+    let mut found = vec![false; pubkeys.len()];
+    let bits = witness.len() * 8;
+    if bits < 512 {
+        return found;
+    }
+    for offset in 0..=bits - 512 {
+        let candidate = bits_at(witness, offset);
+        let Ok(signature) = schnorr::Signature::from_slice(&candidate) else {
+            continue;
+        };
+        for (i, key) in keys.iter().enumerate() {
+            if let Some(key) = key.filter(|_| !found[i]) {
+                if secp.verify_schnorr(&signature, &message, &key).is_ok() {
+                    found[i] = true;
+                }
+            }
+        }
+    }
+    found
+}
+
+/// 64 bytes of `data` starting at bit `offset` (most significant bit first)
+fn bits_at(data: &[u8], offset: usize) -> [u8; 64] {
+    let (byte, shift) = (offset / 8, offset % 8);
+    let mut out = [0u8; 64];
+    for (i, b) in out.iter_mut().enumerate() {
+        let hi = data[byte + i] << shift;
+        let lo = if shift == 0 { 0 } else { data.get(byte + i + 1).map_or(0, |n| n >> (8 - shift)) };
+        *b = hi | lo;
+    }
+    out
+}
+
+/// BIP340 signature (hex) over a `sig_all_hash`
+pub fn sign_hash(sighash: &[u8; 32], privkey: &str) -> Result<String> {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_seckey_str(&secp, strip_hex_prefix(privkey))
+        .context("Private key must be 32 bytes of hex")?;
+    let signature = secp.sign_schnorr_no_aux_rand(&Message::from_digest(*sighash), &keypair);
+    Ok(hex::encode(signature.serialize()))
+}
+
+/// Check a BIP340 signature (hex) over a `sig_all_hash` against an x-only public key (hex)
+pub fn verify_signature(sighash: &[u8; 32], signature: &str, pubkey: &str) -> Result<()> {
+    let signature = schnorr::Signature::from_str(strip_hex_prefix(signature))
+        .with_context(|| format!("Invalid signature: {}", signature))?;
+    let pubkey = XOnlyPublicKey::from_str(strip_hex_prefix(pubkey))
+        .with_context(|| format!("Invalid public key: {}", pubkey))?;
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(*sighash), &pubkey)
+        .map_err(|_| {
+            AppError::SignatureMismatch {
+                detail: format!("Signature {} is not valid for public key {} over sig_all_hash {}", signature, pubkey, hex::encode(sighash)),
+                signers: vec![pubkey.to_string()],
+                threshold: None,
+            }
+            .into()
+        })
+}
+
+/// x-only public key (hex) of a private key (hex)
+pub fn pubkey_of(privkey: &str) -> Result<String> {
+    let secp = Secp256k1::new();
+    let keypair = Keypair::from_seckey_str(&secp, strip_hex_prefix(privkey))
+        .context("Private key must be 32 bytes of hex")?;
+    Ok(keypair.x_only_public_key().0.to_string())
+}
+
+/// Sign input `input_index` of a PSET for the Simplicity leaf `cmr` on the chain with `genesis_hash`
+///
+/// In [`SigningMode::Hal`] the signature from `hal-simplicity` is only returned if it
+/// verifies against the natively computed `sig_all_hash`, so a disagreement about what
+/// is being signed shows up here instead of as a failed jet at broadcast.
+pub fn sign_input(
+    hal: &dyn HalBackend,
+    mode: SigningMode,
+    pset_base64: &str,
+    input_index: u32,
+    cmr: &str,
+    genesis_hash: &str,
+    privkey: &str,
+) -> Result<String> {
+    let sighash = sig_all_hash(pset_base64, input_index, cmr, genesis_hash)?;
+    match mode {
+        SigningMode::Native => sign_hash(&sighash, privkey),
+        SigningMode::Hal => {
+            let signature = hal.sighash_and_sign(pset_base64, input_index, cmr, genesis_hash, privkey)?;
+            // The two disagree about what is being signed (transaction, spent outputs,
+            // control block or genesis hash); native signing is the comparison point
+            verify_signature(&sighash, &signature, &pubkey_of(privkey)?)
+                .context("hal-simplicity produced a signature that does not match the native sig_all_hash; try native signing to compare")?;
+            Ok(signature)
+        }
+    }
+}
+
+fn strip_hex_prefix(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::internal_key::InternalKey;
+    use crate::app_core::taproot::{spend_info, SIMPLICITY_LEAF_VERSION};
+    use elements::confidential::{Asset, Nonce, Value};
+    use elements::pset::{Input, Output};
+    use elements::taproot::LeafVersion;
+    use elements::{AssetId, OutPoint, Script, Txid};
+
+    /// CMR of `p2ms.simf` from WORKFLOW.md
+    const P2MS_CMR: &str = "af5b897effb80a06fa19362347b7807dc0e774eaf4271d6526545965b44ddc3e";
+    /// The keys `p2ms.simf` checks against: 1*G, 2*G and 3*G
+    const P2MS_KEYS: [(&str, &str); 3] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        ),
+    ];
+    /// Liquid testnet policy asset (tL-BTC)
+    const TLBTC: &str = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+
+    /// One-input PSET spending the p2ms contract (NUMS internal key) to a payment and a fee
+    fn p2ms_spend(payment_sats: u64) -> String {
+        let asset = Asset::Explicit(AssetId::from_str(TLBTC).unwrap());
+        let key = InternalKey::Nums.key_hex();
+        let info = spend_info(P2MS_CMR, &key).unwrap();
+        let leaf = (leaf_script(P2MS_CMR).unwrap(), LeafVersion::from_u8(SIMPLICITY_LEAF_VERSION).unwrap());
+        let control_block = info.control_block(&leaf).unwrap();
+        let contract_spk = Script::new_v1_p2tr_tweaked(info.output_key());
+
+        let mut pset = PartiallySignedTransaction::new_v2();
+        let mut input = Input::from_prevout(OutPoint::new(Txid::all_zeros(), 0));
+        input.witness_utxo = Some(TxOut {
+            asset,
+            value: Value::Explicit(100_000),
+            nonce: Nonce::Null,
+            script_pubkey: contract_spk,
+            witness: Default::default(),
+        });
+        input.tap_internal_key = Some(XOnlyPublicKey::from_str(&key).unwrap());
+        input.tap_scripts.insert(control_block, leaf);
+        pset.add_input(input);
+
+        let destination = Script::new_v1_p2tr(
+            &Secp256k1::new(),
+            XOnlyPublicKey::from_str(P2MS_KEYS[0].1).unwrap(),
+            None,
+        );
+        for (script_pubkey, amount) in [(destination, payment_sats), (Script::new(), 100_000 - payment_sats)] {
+            pset.add_output(Output::from_txout(TxOut {
+                asset,
+                value: Value::Explicit(amount),
+                nonce: Nonce::Null,
+                script_pubkey,
+                witness: Default::default(),
+            }));
+        }
+        base64::engine::general_purpose::STANDARD.encode(elements::encode::serialize(&pset))
+    }
+
+    #[test]
+    fn p2ms_keys_sign_and_verify() {
+        let pset = p2ms_spend(99_000);
+        let sighash = sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        for (privkey, pubkey) in P2MS_KEYS {
+            assert_eq!(pubkey_of(privkey).unwrap(), pubkey);
+            let signature = sign_hash(&sighash, privkey).unwrap();
+            verify_signature(&sighash, &signature, pubkey).unwrap();
+        }
+    }
+
+    /// Pinned from libsimplicity (through simplicity-lang) for the PSET above, not from a
+    /// hal run; it catches changes in how the PSET is mapped to the jet environment.
+    #[test]
+    fn p2ms_sighash_vector() {
+        let sighash = sig_all_hash(&p2ms_spend(99_000), 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        assert_eq!(
+            hex::encode(sighash),
+            "8b6eb7e1862abb634d9caa082849418ea7d45a805981ec9abb0f3e0625d4899b"
+        );
+    }
+
+    #[test]
+    fn signature_is_bound_to_its_key() {
+        let pset = p2ms_spend(99_000);
+        let sighash = sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        let signature = sign_hash(&sighash, P2MS_KEYS[0].0).unwrap();
+        assert!(verify_signature(&sighash, &signature, P2MS_KEYS[1].1).is_err());
+    }
+
+    #[test]
+    fn sighash_commits_to_outputs_and_genesis() {
+        let pset = p2ms_spend(99_000);
+        let sighash = sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        assert_eq!(sighash, sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap());
+
+        let other_amount = sig_all_hash(&p2ms_spend(98_000), 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        assert_ne!(sighash, other_amount);
+        let signature = sign_hash(&sighash, P2MS_KEYS[0].0).unwrap();
+        assert!(verify_signature(&other_amount, &signature, P2MS_KEYS[0].1).is_err());
+
+        let other_chain = sig_all_hash(&pset, 0, P2MS_CMR, &"00".repeat(32)).unwrap();
+        assert_ne!(sighash, other_chain);
+    }
+
+    #[test]
+    fn signs_for_the_contracts_chain() {
+        use crate::app_core::fakes::FakeHal;
+        let pset = p2ms_spend(99_000);
+        let liquid = "1466275836220db2944ca059a3a10ef6fd2ea684b0688d2c379296888a206003";
+        for mode in [SigningMode::Native, SigningMode::Hal] {
+            let signature = sign_input(&FakeHal::new(), mode, &pset, 0, P2MS_CMR, liquid, P2MS_KEYS[0].0).unwrap();
+            let on_liquid = sig_all_hash(&pset, 0, P2MS_CMR, liquid).unwrap();
+            let on_testnet = sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+            assert!(verify_signature(&on_liquid, &signature, P2MS_KEYS[0].1).is_ok());
+            assert!(verify_signature(&on_testnet, &signature, P2MS_KEYS[0].1).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_input_and_leaf() {
+        let pset = p2ms_spend(99_000);
+        assert!(sig_all_hash(&pset, 1, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).is_err());
+        assert!(sig_all_hash(&pset, 0, &"11".repeat(32), LIQUID_TESTNET_GENESIS_HASH).is_err());
+    }
+
+    /// The p2ms spend as broadcast: a script-path witness with the given Simplicity witness bytes
+    fn broadcast_spend(pset_base64: &str, witness: Vec<u8>) -> (Transaction, Vec<TxOut>) {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(pset_base64).unwrap();
+        let pset: PartiallySignedTransaction = elements::encode::deserialize(&bytes).unwrap();
+        let spent = vec![pset.inputs()[0].witness_utxo.clone().unwrap()];
+        let (control_block, (script, _)) = pset.inputs()[0].tap_scripts.iter().next().unwrap();
+        let mut tx = pset.extract_tx().unwrap();
+        tx.input[0].witness.script_witness = vec![witness, vec![0x00], script.to_bytes(), control_block.serialize()];
+        (tx, spent)
+    }
+
+    #[test]
+    fn broadcast_spend_has_the_signed_hash() {
+        let pset = p2ms_spend(99_000);
+        let (tx, spent) = broadcast_spend(&pset, Vec::new());
+        assert_eq!(
+            spent_sig_all_hash(&tx, &spent, 0, LIQUID_TESTNET_GENESIS_HASH).unwrap(),
+            sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap()
+        );
+        assert!(spent_sig_all_hash(&tx, &[], 0, LIQUID_TESTNET_GENESIS_HASH).is_err());
+    }
+
+    #[test]
+    fn finds_unaligned_signatures_in_the_witness() {
+        let pset = p2ms_spend(99_000);
+        let sighash = sig_all_hash(&pset, 0, P2MS_CMR, LIQUID_TESTNET_GENESIS_HASH).unwrap();
+        let signature = hex::decode(sign_hash(&sighash, P2MS_KEYS[1].0).unwrap()).unwrap();
+
+        // [None, Some(sig), None]: one bit for None, then the Some bit, then the signature
+        let mut witness = vec![0u8; 66];
+        witness[0] = 0b0100_0000;
+        for (i, byte) in signature.iter().enumerate() {
+            witness[i] |= byte >> 2;
+            witness[i + 1] |= byte << 6;
+        }
+
+        let pubkeys: Vec<String> = P2MS_KEYS.iter().map(|(_, pk)| pk.to_string()).collect();
+        assert_eq!(find_signatures(&sighash, &witness, &pubkeys), vec![false, true, false]);
+        assert_eq!(find_signatures(&[0u8; 32], &witness, &pubkeys), vec![false, false, false]);
+    }
+}
@@ -0,0 +1,153 @@
+//! On-disk application state
+//!
+//! Persists [`AppState`] as JSON under [`Settings::data_dir`] so contract records
+//! survive restarts and can be shared with the helper binaries. The browser build keeps
+//! the same JSON in `localStorage` (see [`platform`]).
+
+use super::models::{AppState, Contract, RedemptionRequest, RedemptionReview, Settings};
+use super::platform;
+use super::workflow::WorkflowState;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// JSON file holding the persisted [`AppState`]
+pub struct Store {
+    path: PathBuf,
+}
+
+impl Store {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// `state.json` in the Partnerfy data directory
+    pub fn default_path() -> PathBuf {
+        Settings::data_dir().join("state.json")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the state, or an empty one if nothing has been saved yet
+    pub fn load(&self) -> Result<AppState> {
+        let Some(content) = platform::read_file(&self.path)? else {
+            return Ok(AppState::default());
+        };
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid state file {}", self.path.display()))
+    }
+
+    /// Write the state, replacing the previous file atomically
+    pub fn save(&self, state: &AppState) -> Result<()> {
+        platform::with_lock(&self.path, || self.write(state))
+    }
+
+    fn write(&self, state: &AppState) -> Result<()> {
+        platform::write_file(&self.path, &serde_json::to_string_pretty(state)?)
+    }
+
+    /// Load the state, change it with `f` and save it, all under the store's file lock
+    ///
+    /// Other processes sharing the data directory update the same file, so every change
+    /// goes through here rather than a separate [`load`](Self::load) and [`save`](Self::save).
+    /// Nothing is saved if `f` fails.
+    pub fn update<T>(&self, f: impl FnOnce(&mut AppState) -> Result<T>) -> Result<T> {
+        platform::with_lock(&self.path, || {
+            let mut state = self.load()?;
+            let result = f(&mut state)?;
+            self.write(&state)?;
+            Ok(result)
+        })
+    }
+
+    /// Add a contract record, replacing any earlier record for the same address
+    pub fn save_contract(&self, contract: Contract) -> Result<()> {
+        self.update(|state| {
+            state.contracts.retain(|c| c.address != contract.address);
+            state.contracts.push(contract);
+            Ok(())
+        })
+    }
+
+    /// Look up the contract record for an address
+    pub fn find_contract(&self, address: &str) -> Result<Option<Contract>> {
+        Ok(self.load()?.contracts.into_iter().find(|c| c.address == address))
+    }
+
+    /// Record a partner's decision, replacing an earlier one for the same transaction
+    pub fn save_review(&self, review: RedemptionReview) -> Result<()> {
+        self.update(|state| {
+            state.reviews.retain(|r| r.unsigned_txid != review.unsigned_txid);
+            state.reviews.push(review);
+            Ok(())
+        })
+    }
+
+    /// Record a redemption a participant started, replacing an earlier one with the same workflow
+    pub fn save_redemption(&self, request: RedemptionRequest) -> Result<()> {
+        self.update(|state| {
+            state.redemptions.retain(|r| r.workflow != request.workflow);
+            state.redemptions.push(request);
+            Ok(())
+        })
+    }
+
+    /// Saved state of a contract workflow
+    pub fn load_workflow(&self, name: &str) -> Result<Option<WorkflowState>> {
+        Ok(self.load()?.workflows.remove(name))
+    }
+
+    /// Save a contract workflow's state, replacing the previous one
+    pub fn save_workflow(&self, name: &str, workflow: &WorkflowState) -> Result<()> {
+        self.update(|state| {
+            state.workflows.insert(name.to_string(), workflow.clone());
+            Ok(())
+        })
+    }
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new(Self::default_path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::templates::TemplateValues;
+    use std::sync::Arc;
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let dir = std::env::temp_dir().join(format!("partnerfy-store-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Arc::new(Store::new(dir.join("state.json")));
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    for i in 0..10 {
+                        let state = WorkflowState::new("p2ms", TemplateValues::new());
+                        store.save_workflow(&format!("w{}-{}", writer, i), &state).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(store.load().unwrap().workflows.len(), 80);
+        let leftovers: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
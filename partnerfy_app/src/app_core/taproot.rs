@@ -0,0 +1,247 @@
+//! Taproot output construction for Simplicity contracts
+//!
+//! A Simplicity contract is a single tap leaf whose script is the program's CMR,
+//! with leaf version `0xbe`, committed under the contract's internal key.
+//! Everything here is derived in Rust (CMR included) so it can be checked
+//! independently against what `hal-simplicity` reports.
+
+use super::internal_key::InternalKey;
+use anyhow::{Context, Result};
+use base64::Engine;
+use elements::pset::PartiallySignedTransaction;
+use elements::secp256k1_zkp::{Secp256k1, XOnlyPublicKey};
+use elements::taproot::{LeafVersion, TaprootBuilder, TaprootSpendInfo};
+use elements::confidential::{Asset, Nonce, Value};
+use elements::{Address, AddressParams, AssetId, Script, TxOut, TxOutWitness};
+use simplicity::jet::Elements;
+use simplicity::{BitIter, CommitNode};
+use std::str::FromStr;
+
+/// Tap leaf version used for Simplicity programs on Elements
+pub const SIMPLICITY_LEAF_VERSION: u8 = 0xbe;
+
+/// Internal key behind the address `hal-simplicity simplicity info` prints
+/// (borrowed from simplicity-webide). Contracts are not built with it; it is only
+/// used to reproduce hal's address for the cross-check.
+pub const HAL_INFO_INTERNAL_KEY: &str = "f5919fa64ce45f8306849072b26c1bfdd2937e6b81774796ff372bd1eb5362d2";
+
+/// Leaf script for a CMR: the 32 CMR bytes themselves
+pub fn leaf_script(cmr_hex: &str) -> Result<Script> {
+    let cmr = hex::decode(cmr_hex.trim()).context("CMR must be hex")?;
+    if cmr.len() != 32 {
+        return Err(anyhow::anyhow!("CMR must be 32 bytes, got {}", cmr.len()));
+    }
+    Ok(Script::from(cmr))
+}
+
+/// Taproot tree with the Simplicity leaf committed under `internal_key_hex`
+pub fn spend_info(cmr_hex: &str, internal_key_hex: &str) -> Result<TaprootSpendInfo> {
+    let internal_key = XOnlyPublicKey::from_str(internal_key_hex.trim())
+        .with_context(|| format!("Invalid internal key: {}", internal_key_hex))?;
+    let version = LeafVersion::from_u8(SIMPLICITY_LEAF_VERSION).expect("valid leaf version");
+    TaprootBuilder::new()
+        .add_leaf_with_ver(0, leaf_script(cmr_hex)?, version)
+        .context("Failed to add the Simplicity leaf")?
+        .finalize(&Secp256k1::new(), internal_key)
+        .map_err(|_| anyhow::anyhow!("Failed to finalize the taproot tree"))
+}
+
+/// Unconfidential contract address for a CMR and internal key
+pub fn contract_address(cmr_hex: &str, internal_key_hex: &str, params: &'static AddressParams) -> Result<Address> {
+    let info = spend_info(cmr_hex, internal_key_hex)?;
+    Ok(Address::p2tr(
+        &Secp256k1::new(),
+        info.internal_key(),
+        info.merkle_root(),
+        None,
+        params,
+    ))
+}
+
+/// CMR (hex) of a compiled program (base64, as printed by `simc`)
+pub fn program_cmr(program_base64: &str) -> Result<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(program_base64.trim())
+        .context("Program is not valid base64")?;
+    let program = CommitNode::<Elements>::decode(BitIter::from(bytes.as_slice()))
+        .map_err(|e| anyhow::anyhow!("Failed to decode Simplicity program: {}", e))?;
+    Ok(program.cmr().to_string())
+}
+
+/// Control block (hex) proving the Simplicity leaf is committed in the output key
+pub fn control_block(cmr_hex: &str, internal_key_hex: &str) -> Result<String> {
+    let info = spend_info(cmr_hex, internal_key_hex)?;
+    let version = LeafVersion::from_u8(SIMPLICITY_LEAF_VERSION).expect("valid leaf version");
+    let control_block = info
+        .control_block(&(leaf_script(cmr_hex)?, version))
+        .context("Simplicity leaf missing from the taproot tree")?;
+    Ok(hex::encode(control_block.serialize()))
+}
+
+/// Add the Simplicity spend data for a contract output to input `index` of a PSET
+///
+/// Sets the same fields as `hal-simplicity simplicity pset update-input`: the spent
+/// output (explicit asset and value), the internal key, the tap leaf with its control
+/// block, and the merkle root.
+pub fn update_pset_input(
+    pset_base64: &str,
+    index: usize,
+    script_pubkey_hex: &str,
+    asset_hex: &str,
+    value_sats: u64,
+    cmr_hex: &str,
+    internal_key_hex: &str,
+) -> Result<String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(pset_base64.trim())
+        .context("PSET is not valid base64")?;
+    let mut pset: PartiallySignedTransaction =
+        elements::encode::deserialize(&bytes).context("Failed to decode PSET")?;
+    let script_pubkey = Script::from(hex::decode(script_pubkey_hex.trim()).context("scriptPubKey must be hex")?);
+    let asset = AssetId::from_str(asset_hex.trim()).with_context(|| format!("Invalid asset id: {}", asset_hex))?;
+
+    let info = spend_info(cmr_hex, internal_key_hex)?;
+    let version = LeafVersion::from_u8(SIMPLICITY_LEAF_VERSION).expect("valid leaf version");
+    let leaf = leaf_script(cmr_hex)?;
+    let control_block = info
+        .control_block(&(leaf.clone(), version))
+        .context("Simplicity leaf missing from the taproot tree")?;
+
+    let input = pset
+        .inputs_mut()
+        .get_mut(index)
+        .with_context(|| format!("PSET has no input {}", index))?;
+    input.witness_utxo = Some(TxOut {
+        asset: Asset::Explicit(asset),
+        value: Value::Explicit(value_sats),
+        nonce: Nonce::Null,
+        script_pubkey,
+        witness: TxOutWitness::default(),
+    });
+    input.tap_internal_key = Some(info.internal_key());
+    input.tap_merkle_root = info.merkle_root();
+    input.tap_scripts.insert(control_block, (leaf, version));
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(elements::encode::serialize(&pset)))
+}
+
+/// Everything about a contract output that can be derived from the program and internal key
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContractOutput {
+    pub cmr: String,
+    pub address: String,
+    pub script_pubkey: String,
+    pub control_block: String,
+}
+
+impl ContractOutput {
+    /// Derive the CMR, address, scriptPubKey and control block of a compiled program
+    pub fn derive(program_base64: &str, internal_key: &InternalKey, params: &'static AddressParams) -> Result<Self> {
+        let cmr = program_cmr(program_base64)?;
+        let key = internal_key.key_hex();
+        let address = contract_address(&cmr, &key, params)?;
+        Ok(Self {
+            script_pubkey: hex::encode(address.script_pubkey().as_bytes()),
+            address: address.to_string(),
+            control_block: control_block(&cmr, &key)?,
+            cmr,
+        })
+    }
+
+    /// Compare with `hal-simplicity simplicity info` output; returns one line per mismatch
+    ///
+    /// hal's address always commits to [`HAL_INFO_INTERNAL_KEY`], so the same derivation
+    /// is repeated with that key and compared, independently of the contract's own key.
+    pub fn cross_check_hal(&self, hal_cmr: &str, hal_address: &str) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        if !self.cmr.eq_ignore_ascii_case(hal_cmr.trim()) {
+            mismatches.push(format!("CMR: derived {} but hal-simplicity reports {}", self.cmr, hal_cmr));
+        }
+        match contract_address(&self.cmr, HAL_INFO_INTERNAL_KEY, &AddressParams::LIQUID_TESTNET) {
+            Ok(address) if address.to_string() == hal_address.trim() => {}
+            Ok(address) => mismatches.push(format!(
+                "Address: derived {} for hal's internal key but hal-simplicity reports {}",
+                address, hal_address
+            )),
+            Err(e) => mismatches.push(format!("Address: failed to derive hal's address: {}", e)),
+        }
+        mismatches
+    }
+
+    /// Check that input `index` of a PSET carries this contract's leaf, control block and internal key
+    ///
+    /// Used after `hal-simplicity simplicity pset update-input` to confirm it committed to
+    /// the same output we derived.
+    pub fn check_pset_input(&self, pset_base64: &str, index: usize, internal_key: &InternalKey) -> Result<()> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(pset_base64.trim())
+            .context("PSET is not valid base64")?;
+        let pset: PartiallySignedTransaction =
+            elements::encode::deserialize(&bytes).context("Failed to decode PSET")?;
+        let input = pset
+            .inputs()
+            .get(index)
+            .with_context(|| format!("PSET has no input {}", index))?;
+
+        if let Some(utxo) = &input.witness_utxo {
+            let spk = hex::encode(utxo.script_pubkey.as_bytes());
+            if spk != self.script_pubkey {
+                return Err(anyhow::anyhow!(
+                    "Input {} spends scriptPubKey {} but the contract's is {}",
+                    index, spk, self.script_pubkey
+                ));
+            }
+        }
+        if let Some(key) = input.tap_internal_key {
+            if key.to_string() != internal_key.key_hex() {
+                return Err(anyhow::anyhow!(
+                    "Input {} uses internal key {} but the contract's is {}",
+                    index, key, internal_key.key_hex()
+                ));
+            }
+        }
+        let leaf = leaf_script(&self.cmr)?;
+        let found = input
+            .tap_scripts
+            .iter()
+            .find(|(_, (script, _))| *script == leaf)
+            .map(|(cb, _)| hex::encode(cb.serialize()));
+        match found {
+            Some(cb) if cb == self.control_block => Ok(()),
+            Some(cb) => Err(anyhow::anyhow!(
+                "Input {} has control block {} but the derived one is {}",
+                index, cb, self.control_block
+            )),
+            None => Err(anyhow::anyhow!("Input {} has no tap leaf for CMR {}", index, self.cmr)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elements::pset::Input;
+    use elements::OutPoint;
+
+    #[test]
+    fn updated_input_matches_the_derived_contract() {
+        let cmr = "aa".repeat(32);
+        let key = InternalKey::Nums;
+        let address = contract_address(&cmr, &key.key_hex(), &AddressParams::LIQUID_TESTNET).unwrap();
+        let output = ContractOutput {
+            cmr: cmr.clone(),
+            address: address.to_string(),
+            script_pubkey: hex::encode(address.script_pubkey().as_bytes()),
+            control_block: control_block(&cmr, &key.key_hex()).unwrap(),
+        };
+
+        let mut pset = PartiallySignedTransaction::new_v2();
+        pset.add_input(Input::from_prevout(OutPoint::default()));
+        let base = base64::engine::general_purpose::STANDARD.encode(elements::encode::serialize(&pset));
+        let asset = "144c654344aa716d6f3abcc1ca90e5641e4e2a7f633bc09fe3baf64585819a49";
+        let updated = update_pset_input(&base, 0, &output.script_pubkey, asset, 10_000, &cmr, &key.key_hex()).unwrap();
+
+        output.check_pset_input(&updated, 0, &key).unwrap();
+        assert!(update_pset_input(&base, 1, &output.script_pubkey, asset, 10_000, &cmr, &key.key_hex()).is_err());
+    }
+}
@@ -0,0 +1,666 @@
+//! Covenant template registry
+//!
+//! A template describes a Simplicity contract: the parameters needed to generate its
+//! `.simf` source, the witness values a spend must provide and what the spending
+//! transaction has to look like. Built-in templates live in `builtin_templates`; user
+//! templates are JSON files in `~/.partnerfy/templates` (see `TemplateRegistry::load`).
+//!
+//! User template sources use `{{param}}` placeholders, which are replaced by the
+//! validated parameter value (pubkeys as bare hex, amounts in sats, lists comma-separated).
+
+use crate::app_core::builtin_templates;
+use crate::app_core::models::{RefundLock, Settings};
+use crate::app_core::platform;
+use crate::app_core::qr;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Parameter values entered in a template form, keyed by parameter name
+pub type TemplateValues = BTreeMap<String, String>;
+
+/// Type of a template parameter, used for validation and form rendering
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParamKind {
+    /// 32-byte x-only public key (64 hex characters)
+    Pubkey,
+    /// List of x-only public keys, e.g. a partner list
+    PubkeyList { min: usize, max: usize },
+    /// Signature threshold between 1 and `max`
+    Threshold { max: u8 },
+    /// Amount in L-BTC, rendered in satoshis
+    Amount,
+    /// Block height (below the 500,000,000 lock time threshold)
+    BlockHeight,
+    /// UNIX timestamp (at or above the 500,000,000 lock time threshold)
+    Timestamp,
+    /// Arbitrary 32-byte value (64 hex characters)
+    Hash,
+}
+
+/// A parameter declared by a template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateParam {
+    pub name: String,
+    pub label: String,
+    #[serde(flatten)]
+    pub kind: ParamKind,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_true")]
+    pub required: bool,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub placeholder: Option<String>,
+    /// Optional heading the form groups this parameter under
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A witness value the spending transaction must provide
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WitnessField {
+    /// Name used in the program as `witness::NAME`
+    pub name: String,
+    /// SimplicityHL type, as written in the .wit file
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub description: String,
+    /// Only part of the witness when this parameter is set
+    #[serde(default)]
+    pub when: Option<String>,
+    /// Only part of the witness when this parameter is not set
+    #[serde(default)]
+    pub unless: Option<String>,
+}
+
+/// Role of an output in the spending transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputRole {
+    /// Payment to the destination
+    Payment,
+    /// Change back to the same covenant script
+    CovenantChange,
+    /// Explicit fee output
+    Fee,
+}
+
+/// What a spend of the contract requires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpendFlow {
+    /// Number of multisig keys that can sign over `sig_all_hash`
+    pub signers: usize,
+    /// Parameter holding the signature threshold
+    #[serde(default)]
+    pub threshold_param: Option<String>,
+    /// Required outputs, in order
+    pub outputs: Vec<OutputRole>,
+    /// Parameter holding the per-redemption cap on output 0
+    #[serde(default)]
+    pub payment_cap_param: Option<String>,
+    /// Parameter holding the oracle key; when set, spends need an oracle attestation
+    #[serde(default)]
+    pub oracle_param: Option<String>,
+    /// Parameters of a time-locked refund path (key, height, time)
+    #[serde(default)]
+    pub refund: Option<RefundParams>,
+}
+
+/// Parameter names of a time-locked refund path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefundParams {
+    pub pubkey_param: String,
+    pub height_param: String,
+    pub time_param: String,
+}
+
+impl SpendFlow {
+    /// Whether spends must send change back to the covenant
+    pub fn is_covenant(&self) -> bool {
+        self.outputs.contains(&OutputRole::CovenantChange)
+    }
+}
+
+/// Built-in source generators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    P2ms,
+    CovP2ms,
+}
+
+/// Who signs a contract's spends
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signers {
+    /// x-only public keys in slot order
+    pub pubkeys: Vec<String>,
+    /// Exact number of signatures the program checks for
+    pub threshold: usize,
+}
+
+/// A covenant template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CovenantTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Default file name for the generated source
+    #[serde(default = "default_file_name")]
+    pub file_name: String,
+    pub params: Vec<TemplateParam>,
+    pub witness: Vec<WitnessField>,
+    pub spend_flow: SpendFlow,
+    /// Source with `{{param}}` placeholders (user templates)
+    #[serde(default)]
+    pub source: String,
+    /// Source file relative to the template's JSON file, used when `source` is empty
+    #[serde(default)]
+    pub source_file: Option<String>,
+    #[serde(skip)]
+    pub builtin: Option<Builtin>,
+}
+
+fn default_file_name() -> String {
+    "contract.simf".to_string()
+}
+
+/// Whether a parameter has a non-empty value
+pub fn is_set(values: &TemplateValues, name: &str) -> bool {
+    values.get(name).is_some_and(|v| !v.trim().is_empty())
+}
+
+/// Trimmed value of a parameter, if set
+pub fn value<'a>(values: &'a TemplateValues, name: &str) -> Option<&'a str> {
+    values.get(name).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+fn is_hex32(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl TemplateParam {
+    /// Validate a value and convert it to the form used in the source
+    pub fn render_value(&self, raw: &str) -> Result<String> {
+        let raw = raw.trim();
+        match &self.kind {
+            ParamKind::Pubkey | ParamKind::Hash => {
+                let hex = raw.trim_start_matches("0x").to_lowercase();
+                if !is_hex32(&hex) {
+                    return Err(anyhow::anyhow!(
+                        "Invalid {}: must be 64 hex characters (32 bytes). Got: {} ({} chars)",
+                        self.label,
+                        hex,
+                        hex.len()
+                    ));
+                }
+                Ok(hex)
+            }
+            ParamKind::PubkeyList { min, max } => {
+                let keys: Vec<String> = raw
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|k| !k.is_empty())
+                    .map(|k| k.trim_start_matches("0x").to_lowercase())
+                    .collect();
+                if keys.len() < *min || keys.len() > *max {
+                    return Err(anyhow::anyhow!(
+                        "Invalid {}: expected between {} and {} public keys, got {}",
+                        self.label,
+                        min,
+                        max,
+                        keys.len()
+                    ));
+                }
+                if let Some(bad) = keys.iter().find(|k| !is_hex32(k)) {
+                    return Err(anyhow::anyhow!(
+                        "Invalid {}: {} is not a 64 hex character public key",
+                        self.label,
+                        bad
+                    ));
+                }
+                Ok(keys
+                    .iter()
+                    .map(|k| format!("0x{}", k))
+                    .collect::<Vec<_>>()
+                    .join(", "))
+            }
+            ParamKind::Threshold { max } => match raw.parse::<u8>() {
+                Ok(t) if t >= 1 && t <= *max => Ok(t.to_string()),
+                _ => Err(anyhow::anyhow!(
+                    "Invalid {}: must be a number between 1 and {}. Got: {}",
+                    self.label,
+                    max,
+                    raw
+                )),
+            },
+            // Parsed as a decimal string, so large amounts are exact or rejected, never rounded
+            ParamKind::Amount => match qr::parse_btc(raw) {
+                Ok(sats) if sats >= 1 => Ok(sats.to_string()),
+                Ok(_) => Err(anyhow::anyhow!(
+                    "Invalid {}: enter a positive L-BTC amount. Got: {}",
+                    self.label,
+                    raw
+                )),
+                Err(e) => Err(anyhow::anyhow!(
+                    "Invalid {}: enter a positive L-BTC amount ({}). Got: {}",
+                    self.label,
+                    e,
+                    raw
+                )),
+            },
+            ParamKind::BlockHeight | ParamKind::Timestamp => {
+                let kind = if self.kind == ParamKind::BlockHeight { "height" } else { "time" };
+                let value = raw
+                    .parse::<u32>()
+                    .map_err(|_| anyhow::anyhow!("Invalid {}: {} is not a number", self.label, raw))?;
+                RefundLock::new(kind, value)
+                    .map_err(|e| anyhow::anyhow!("Invalid {}: {}", self.label, e))?;
+                Ok(value.to_string())
+            }
+        }
+    }
+
+    /// Placeholder shown in the form when the template does not provide one
+    pub fn default_placeholder(&self) -> &'static str {
+        match self.kind {
+            ParamKind::Pubkey => "64 hex characters",
+            ParamKind::PubkeyList { .. } => "Comma-separated public keys (64 hex characters each)",
+            ParamKind::Threshold { .. } => "2",
+            ParamKind::Amount => "0.0005",
+            ParamKind::BlockHeight => "2000000",
+            ParamKind::Timestamp => "1767225600",
+            ParamKind::Hash => "64 hex characters",
+        }
+    }
+}
+
+impl CovenantTemplate {
+    /// Initial form values (defaults, or empty)
+    pub fn default_values(&self) -> TemplateValues {
+        self.params
+            .iter()
+            .map(|p| (p.name.clone(), p.default.clone().unwrap_or_default()))
+            .collect()
+    }
+
+    /// Validate all parameters, returning their rendered values
+    ///
+    /// Empty optional parameters are left out of the result.
+    pub fn validate(&self, values: &TemplateValues) -> Result<TemplateValues> {
+        let mut rendered = TemplateValues::new();
+        for param in &self.params {
+            match value(values, &param.name) {
+                Some(raw) => {
+                    rendered.insert(param.name.clone(), param.render_value(raw)?);
+                }
+                None if param.required => {
+                    return Err(anyhow::anyhow!("Please provide {}", param.label));
+                }
+                None => {}
+            }
+        }
+
+        if let Some(refund) = &self.spend_flow.refund {
+            let has_key = rendered.contains_key(&refund.pubkey_param);
+            let has_height = rendered.contains_key(&refund.height_param);
+            let has_time = rendered.contains_key(&refund.time_param);
+            if has_height && has_time {
+                return Err(anyhow::anyhow!(
+                    "Set either a refund block height or a refund time, not both"
+                ));
+            }
+            if has_key != (has_height || has_time) {
+                return Err(anyhow::anyhow!(
+                    "The refund path needs both a promoter public key and a refund lock (block height or time)"
+                ));
+            }
+        }
+
+        Ok(rendered)
+    }
+
+    /// Generate the `.simf` source for the given parameter values
+    pub fn render(&self, values: &TemplateValues) -> Result<String> {
+        let rendered = self.validate(values)?;
+        match self.builtin {
+            Some(Builtin::P2ms) => builtin_templates::p2ms_source(&rendered),
+            Some(Builtin::CovP2ms) => builtin_templates::cov_p2ms_source(&rendered),
+            None => self.render_placeholders(&rendered),
+        }
+    }
+
+    fn render_placeholders(&self, rendered: &TemplateValues) -> Result<String> {
+        let mut output = String::with_capacity(self.source.len());
+        let mut rest = self.source.as_str();
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| anyhow::anyhow!("Unclosed placeholder in template {}", self.id))?;
+            let name = after[..end].trim();
+            if !self.params.iter().any(|p| p.name == name) {
+                return Err(anyhow::anyhow!(
+                    "Template {} uses undeclared parameter {{{{{}}}}}",
+                    self.id,
+                    name
+                ));
+            }
+            let value = rendered.get(name).ok_or_else(|| {
+                anyhow::anyhow!("Template {} needs a value for {}", self.id, name)
+            })?;
+            output.push_str(value);
+            rest = &after[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// Human readable summary of the parameters and witness used for a generated source
+    pub fn summary(&self, values: &TemplateValues) -> String {
+        let mut lines = vec!["Parameters:".to_string()];
+        for param in &self.params {
+            if let Some(v) = value(values, &param.name) {
+                lines.push(format!("- {}: {}", param.label, v));
+            }
+        }
+        lines.push(String::new());
+        lines.push("Witness:".to_string());
+        for field in self.witness_fields(values) {
+            lines.push(format!("- {}: {}", field.name, field.ty));
+        }
+        lines.join("\n")
+    }
+
+    /// Witness fields that apply for the given parameter values
+    pub fn witness_fields(&self, values: &TemplateValues) -> Vec<&WitnessField> {
+        self.witness
+            .iter()
+            .filter(|w| w.when.as_deref().is_none_or(|p| is_set(values, p)))
+            .filter(|w| w.unless.as_deref().is_none_or(|p| !is_set(values, p)))
+            .collect()
+    }
+
+    /// Signature threshold for the given parameter values
+    pub fn threshold(&self, values: &TemplateValues) -> usize {
+        self.spend_flow
+            .threshold_param
+            .as_deref()
+            .and_then(|p| value(values, p))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(self.spend_flow.signers)
+    }
+
+    /// Public keys of the spend signers (every pubkey parameter except the oracle and refund keys)
+    pub fn signer_pubkeys(&self, values: &TemplateValues) -> Vec<String> {
+        let excluded = [
+            self.spend_flow.oracle_param.as_deref(),
+            self.spend_flow.refund.as_ref().map(|r| r.pubkey_param.as_str()),
+        ];
+        self.params
+            .iter()
+            .filter(|p| !excluded.contains(&Some(p.name.as_str())))
+            .filter_map(|p| match p.kind {
+                ParamKind::Pubkey | ParamKind::PubkeyList { .. } => value(values, &p.name),
+                _ => None,
+            })
+            .flat_map(|raw| raw.split(|c: char| c == ',' || c.is_whitespace()))
+            .filter(|k| !k.is_empty())
+            .map(|k| k.trim_start_matches("0x").to_lowercase())
+            .collect()
+    }
+
+    /// Signer keys and threshold for the given parameter values
+    pub fn signers(&self, values: &TemplateValues) -> Signers {
+        Signers { pubkeys: self.signer_pubkeys(values), threshold: self.threshold(values) }
+    }
+
+    /// Oracle key whose attestation spends need, if set
+    pub fn oracle_pubkey(&self, values: &TemplateValues) -> Option<String> {
+        self.spend_flow
+            .oracle_param
+            .as_deref()
+            .and_then(|p| value(values, p))
+            .map(|pk| pk.trim_start_matches("0x").to_lowercase())
+    }
+
+    /// Per-redemption cap on output 0 in L-BTC, if set
+    pub fn payment_cap(&self, values: &TemplateValues) -> Option<f64> {
+        self.spend_flow
+            .payment_cap_param
+            .as_deref()
+            .and_then(|p| value(values, p))
+            .and_then(|v| v.parse::<f64>().ok())
+    }
+
+    /// Promoter key and lock of the refund path, if set
+    pub fn refund(&self, values: &TemplateValues) -> Option<(String, RefundLock)> {
+        let refund = self.spend_flow.refund.as_ref()?;
+        let pubkey = value(values, &refund.pubkey_param)?.trim_start_matches("0x").to_lowercase();
+        let lock = if let Some(h) = value(values, &refund.height_param) {
+            RefundLock::new("height", h.parse().ok()?).ok()?
+        } else {
+            RefundLock::new("time", value(values, &refund.time_param)?.parse().ok()?).ok()?
+        };
+        Some((pubkey, lock))
+    }
+}
+
+/// All templates available to the app
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateRegistry {
+    templates: Vec<CovenantTemplate>,
+    /// Problems found while loading user templates
+    pub load_errors: Vec<String>,
+}
+
+impl TemplateRegistry {
+    /// Registry with only the built-in templates
+    pub fn builtin() -> Self {
+        Self {
+            templates: builtin_templates::all(),
+            load_errors: Vec::new(),
+        }
+    }
+
+    /// Default directory for user templates (`~/.partnerfy/templates`)
+    pub fn default_user_dir() -> PathBuf {
+        Settings::data_dir().join("templates")
+    }
+
+    /// Load the built-in templates plus every `*.json` template in `user_dir`
+    ///
+    /// Invalid user templates are skipped and reported in `load_errors`; a user
+    /// template with the same ID as a built-in one is rejected.
+    pub fn load(user_dir: &Path) -> Self {
+        let mut registry = Self::builtin();
+        let paths: Vec<PathBuf> = match platform::list_dir(user_dir) {
+            Ok(paths) => paths
+                .into_iter()
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(e) => {
+                registry.load_errors.push(format!("{:#}", e));
+                return registry;
+            }
+        };
+
+        for path in paths {
+            match Self::load_file(&path) {
+                Ok(template) => {
+                    if registry.get(&template.id).is_some() {
+                        registry.load_errors.push(format!(
+                            "{}: template ID '{}' is already registered",
+                            path.display(),
+                            template.id
+                        ));
+                    } else {
+                        registry.templates.push(template);
+                    }
+                }
+                Err(e) => registry.load_errors.push(format!("{}: {:#}", path.display(), e)),
+            }
+        }
+
+        registry
+    }
+
+    /// Load a single user template from a JSON file
+    pub fn load_file(path: &Path) -> Result<CovenantTemplate> {
+        let content = platform::read_file(path)?
+            .with_context(|| format!("{} does not exist", path.display()))?;
+        let mut template: CovenantTemplate =
+            serde_json::from_str(&content).context("Invalid template JSON")?;
+
+        if template.source.is_empty() {
+            let source_file = template
+                .source_file
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Template has neither 'source' nor 'source_file'"))?;
+            let source_path = path.parent().unwrap_or(Path::new(".")).join(source_file);
+            template.source = platform::read_file(&source_path)?
+                .with_context(|| format!("Template source {} does not exist", source_path.display()))?;
+        }
+
+        if template.id.trim().is_empty() {
+            return Err(anyhow::anyhow!("Template ID must not be empty"));
+        }
+
+        Ok(template)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CovenantTemplate> {
+        self.templates.iter().find(|t| t.id == id)
+    }
+
+    pub fn all(&self) -> &[CovenantTemplate] {
+        &self.templates
+    }
+
+    /// Templates whose spends do (or do not) keep change in the covenant
+    pub fn by_covenant(&self, covenant: bool) -> Vec<&CovenantTemplate> {
+        self.templates
+            .iter()
+            .filter(|t| t.spend_flow.is_covenant() == covenant)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn param(kind: ParamKind) -> TemplateParam {
+        TemplateParam {
+            name: "value".to_string(),
+            label: "Value".to_string(),
+            kind,
+            description: String::new(),
+            required: true,
+            default: None,
+            placeholder: None,
+            group: None,
+        }
+    }
+
+    /// User template with a placeholder source, as loaded from JSON
+    fn user_template(source: &str) -> CovenantTemplate {
+        serde_json::from_value(serde_json::json!({
+            "id": "capped_key",
+            "name": "Capped key",
+            "params": [
+                {"name": "owner", "label": "Owner", "type": "pubkey"},
+                {"name": "cap", "label": "Cap", "type": "amount", "required": false},
+                {"name": "partners", "label": "Partners", "type": "pubkey_list", "min": 1, "max": 2},
+            ],
+            "witness": [{"name": "SIG", "type": "Signature"}],
+            "spend_flow": {"signers": 1, "outputs": ["payment", "fee"]},
+            "source": source,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn amounts_render_in_sats() {
+        let amount = param(ParamKind::Amount);
+        assert_eq!(amount.render_value("0.0005").unwrap(), "50000");
+        assert_eq!(amount.render_value(" 1 ").unwrap(), "100000000");
+        assert_eq!(amount.render_value("0.00000001").unwrap(), "1");
+        assert_eq!(amount.render_value("0.12345678").unwrap(), "12345678");
+        assert_eq!(amount.render_value("21000000").unwrap(), "2100000000000000");
+        assert_eq!(amount.render_value("184467440737.09551615").unwrap(), u64::MAX.to_string());
+        // Less than one satoshi would be a cap of zero; past u64 sats would not fit the program
+        for bad in ["0", "0.000000001", "-1", "abc", "", "1e3", "184467440737.09551616", "184467440738"] {
+            assert!(amount.render_value(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn values_are_checked_against_their_kind() {
+        let key = "AB".repeat(32);
+        assert_eq!(param(ParamKind::Pubkey).render_value(&format!("0x{}", key)).unwrap(), key.to_lowercase());
+        assert!(param(ParamKind::Pubkey).render_value(&"ab".repeat(31)).is_err());
+        assert!(param(ParamKind::Hash).render_value(&"zz".repeat(32)).is_err());
+
+        let list = param(ParamKind::PubkeyList { min: 1, max: 2 });
+        assert_eq!(
+            list.render_value(&format!("{}, {}", "11".repeat(32), "22".repeat(32))).unwrap(),
+            format!("0x{}, 0x{}", "11".repeat(32), "22".repeat(32))
+        );
+        assert!(list.render_value(&["11".repeat(32), "22".repeat(32), "33".repeat(32)].join(",")).is_err());
+        assert!(list.render_value("1234").is_err());
+
+        let threshold = param(ParamKind::Threshold { max: 3 });
+        assert_eq!(threshold.render_value("3").unwrap(), "3");
+        assert!(threshold.render_value("0").is_err());
+        assert!(threshold.render_value("4").is_err());
+
+        assert_eq!(param(ParamKind::BlockHeight).render_value("499999999").unwrap(), "499999999");
+        assert!(param(ParamKind::BlockHeight).render_value("500000000").is_err());
+        assert!(param(ParamKind::Timestamp).render_value("499999999").is_err());
+    }
+
+    #[test]
+    fn validate_reports_missing_and_drops_empty_values() {
+        let template = user_template("{{owner}}");
+        let owner = "11".repeat(32);
+        let values = TemplateValues::from([
+            ("owner".to_string(), owner.clone()),
+            ("cap".to_string(), " ".to_string()),
+            ("partners".to_string(), "22".repeat(32)),
+        ]);
+        let rendered = template.validate(&values).unwrap();
+        assert_eq!(rendered.get("owner"), Some(&owner));
+        assert!(!rendered.contains_key("cap"));
+
+        let missing = TemplateValues::from([("owner".to_string(), owner)]);
+        assert_eq!(template.validate(&missing).unwrap_err().to_string(), "Please provide Partners");
+    }
+
+    #[test]
+    fn placeholders_must_be_declared_and_set() {
+        let values = TemplateValues::from([
+            ("owner".to_string(), "11".repeat(32)),
+            ("cap".to_string(), "0.001".to_string()),
+            ("partners".to_string(), "22".repeat(32)),
+        ]);
+        let source = user_template("let owner: Pubkey = 0x{{ owner }}; let cap: u64 = {{cap}};")
+            .render(&values)
+            .unwrap();
+        assert_eq!(source, format!("let owner: Pubkey = 0x{}; let cap: u64 = 100000;", "11".repeat(32)));
+
+        assert!(user_template("{{unknown}}").render(&values).is_err());
+        assert!(user_template("{{owner").render(&values).is_err());
+        let mut without_cap = values.clone();
+        without_cap.remove("cap");
+        assert!(user_template("{{cap}}").render(&without_cap).is_err());
+    }
+}
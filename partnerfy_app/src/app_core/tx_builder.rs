@@ -2,8 +2,11 @@
 //! 
 //! Handles transaction assembly, output construction, and change handling
 
+use crate::app_core::error::AppError;
 use crate::app_core::models::{TxOutput, RawTransaction, VoucherUTXO};
 use anyhow::{Result, Context};
+use elements::{confidential, Address, AssetId, LockTime, OutPoint, Sequence, Transaction, TxIn, TxOut, TxOutWitness, Txid};
+use std::str::FromStr;
 
 /// Transaction builder helper
 pub struct TxBuilder;
@@ -11,13 +14,20 @@ pub struct TxBuilder;
 impl TxBuilder {
     /// Build a transaction from voucher UTXO to partner and change
     /// 
-    /// This ensures that change output uses the same covenant address
+    /// This ensures that change output uses the same covenant address.
+    /// `max_payment` is the covenant's per-redemption cap on output 0, if any;
+    /// amounts above it are rejected here since the covenant would reject them on-chain.
     pub fn build_redemption_tx(
         voucher: &VoucherUTXO,
         partner_address: &str,
         partner_amount: f64,
         covenant_address: &str,
+        max_payment: Option<f64>,
     ) -> Result<RawTransaction> {
+        if let Some(cap) = max_payment {
+            Self::check_payment_cap(partner_amount, cap)?;
+        }
+
         let change_amount = voucher.amount - partner_amount;
         
         if change_amount < 0.0 {
@@ -47,29 +57,87 @@ impl TxBuilder {
         })
     }
 
+    /// Check a payment against the covenant's per-redemption cap
+    ///
+    /// Compared in satoshis, which is what `jet::output_amount` sees
+    pub fn check_payment_cap(partner_amount: f64, max_payment: f64) -> Result<()> {
+        let amount_sats = (partner_amount * 100_000_000.0).round() as u64;
+        let cap_sats = (max_payment * 100_000_000.0).round() as u64;
+        if amount_sats > cap_sats {
+            return Err(AppError::CovenantViolation {
+                detail: format!(
+                    "Payment {} L-BTC ({} sats) exceeds the per-redemption cap of {} L-BTC ({} sats)",
+                    partner_amount, amount_sats, max_payment, cap_sats
+                ),
+                required_outputs: vec![format!("Output 0: Partner payment of at most {} sats", cap_sats)],
+            }
+            .into());
+        }
+        Ok(())
+    }
+
     /// Build a split transaction to create multiple vouchers
-    pub fn build_split_tx(
-        input_txid: &str,
-        input_vout: u32,
-        covenant_address: &str,
-        voucher_amounts: &[f64],
-    ) -> Result<RawTransaction> {
-        let inputs = vec![(input_txid.to_string(), input_vout)];
-        let outputs: Vec<TxOutput> = voucher_amounts
-            .iter()
-            .map(|&amount| TxOutput {
-                address: covenant_address.to_string(),
-                amount,
-            })
-            .collect();
+    ///
+    /// One output per voucher, in order, so voucher `i` ends up at vout `i`. Outputs may
+    /// repeat an address (many vouchers under one covenant). `inputs` may be empty when
+    /// the wallet picks them (`fundrawtransaction`).
+    pub fn build_split_tx(inputs: &[(String, u32)], vouchers: &[TxOutput]) -> Result<RawTransaction> {
+        if vouchers.is_empty() {
+            return Err(anyhow::anyhow!("A split transaction needs at least one voucher output"));
+        }
+        if let Some(output) = vouchers.iter().find(|o| o.amount <= 0.0) {
+            return Err(anyhow::anyhow!(
+                "Voucher amount for {} must be positive, got {} L-BTC",
+                output.address,
+                output.amount
+            ));
+        }
 
         Ok(RawTransaction {
             hex: String::new(),
-            inputs,
-            outputs,
+            inputs: inputs.to_vec(),
+            outputs: vouchers.to_vec(),
         })
     }
 
+    /// Serialize an unsigned transaction with explicit `asset_hex` outputs
+    ///
+    /// `createrawtransaction` keys outputs by address and so cannot pay one address twice;
+    /// building the transaction here keeps every output.
+    pub fn to_unsigned_hex(raw: &RawTransaction, asset_hex: &str) -> Result<String> {
+        let asset = AssetId::from_str(asset_hex)
+            .with_context(|| format!("Invalid asset id: {}", asset_hex))?;
+        let input = raw
+            .inputs
+            .iter()
+            .map(|(txid, vout)| {
+                let txid = Txid::from_str(txid).with_context(|| format!("Invalid input txid: {}", txid))?;
+                Ok(TxIn {
+                    previous_output: OutPoint::new(txid, *vout),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let output = raw
+            .outputs
+            .iter()
+            .map(|o| {
+                let address = Address::from_str(&o.address)
+                    .with_context(|| format!("Invalid output address: {}", o.address))?;
+                Ok(TxOut {
+                    asset: confidential::Asset::Explicit(asset),
+                    value: confidential::Value::Explicit((o.amount * 100_000_000.0).round() as u64),
+                    nonce: confidential::Nonce::Null,
+                    script_pubkey: address.script_pubkey(),
+                    witness: TxOutWitness::default(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let tx = Transaction { version: 2, lock_time: LockTime::ZERO, input, output };
+        Ok(elements::encode::serialize_hex(&tx))
+    }
+
     /// Validate transaction outputs comply with covenant rules
     /// 
     /// Checks that outputs are either:
@@ -91,10 +159,11 @@ impl TxBuilder {
                 || addr == covenant_address;
             
             if !is_valid {
-                return Err(anyhow::anyhow!(
-                    "Output address {} is not allowed by covenant rules",
-                    addr
-                ));
+                return Err(AppError::CovenantViolation {
+                    detail: format!("Output address {} is not allowed by covenant rules", addr),
+                    required_outputs: Vec::new(),
+                }
+                .into());
             }
         }
         
@@ -108,3 +177,33 @@ impl TxBuilder {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_core::error;
+
+    fn voucher() -> VoucherUTXO {
+        VoucherUTXO {
+            txid: "00".repeat(32),
+            vout: 0,
+            amount: 0.001,
+            owner_pubkey: String::new(),
+            covenant_script: String::new(),
+            covenant_address: "covenant".to_string(),
+        }
+    }
+
+    #[test]
+    fn payment_cap_is_inclusive_to_the_satoshi() {
+        TxBuilder::check_payment_cap(0.0005, 0.0005).unwrap();
+        TxBuilder::check_payment_cap(0.00049999, 0.0005).unwrap();
+        let err = TxBuilder::check_payment_cap(0.00050001, 0.0005).unwrap_err();
+        assert!(matches!(error::find(&err), Some(AppError::CovenantViolation { .. })));
+
+        let at_cap = TxBuilder::build_redemption_tx(&voucher(), "partner", 0.0005, "covenant", Some(0.0005)).unwrap();
+        assert_eq!(at_cap.outputs.len(), 2);
+        assert_eq!(at_cap.outputs[1].address, "covenant");
+        assert!(TxBuilder::build_redemption_tx(&voucher(), "partner", 0.00050001, "covenant", Some(0.0005)).is_err());
+        assert!(TxBuilder::build_redemption_tx(&voucher(), "partner", 0.0009, "covenant", None).is_ok());
+    }
+}
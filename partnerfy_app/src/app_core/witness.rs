@@ -3,6 +3,7 @@
 //! Handles witness generation and serialization for covenant spending
 
 use crate::app_core::models::Witness;
+use crate::app_core::oracle::OracleAttestation;
 use anyhow::Result;
 use serde_json::json;
 
@@ -50,5 +51,59 @@ impl WitnessBuilder {
     pub fn create_empty_witness() -> String {
         json!({}).to_string()
     }
-}
 
+    /// Format signatures as a SimplicityHL `[Option<Signature>; N]` literal
+    ///
+    /// Signatures are hex strings without the `0x` prefix (as returned by
+    /// `HalWrapper::sighash_and_sign`). Example: `[Some(0xab..), None, Some(0xcd..)]`
+    pub fn maybe_sigs_value(sigs: &[Option<String>]) -> String {
+        let elements: Vec<String> = sigs
+            .iter()
+            .map(|sig| match sig {
+                Some(s) => format!("Some(0x{})", s),
+                None => "None".to_string(),
+            })
+            .collect();
+        format!("[{}]", elements.join(", "))
+    }
+
+    /// Build a SimplicityHL witness file (.wit) for simc
+    ///
+    /// Each entry is `(name, type, value)` and becomes
+    /// `"NAME": { "value": "<value>", "type": "<type>" }`
+    pub fn simf_witness_file(entries: &[(&str, &str, String)]) -> Result<String> {
+        let mut witness = serde_json::Map::new();
+        for (name, ty, value) in entries {
+            witness.insert(
+                name.to_string(),
+                json!({
+                    "value": value,
+                    "type": ty,
+                }),
+            );
+        }
+
+        serde_json::to_string_pretty(&serde_json::Value::Object(witness))
+            .map_err(|e| anyhow::anyhow!("Failed to serialize witness: {}", e))
+    }
+
+    /// Witness entries for an oracle-attested covenant
+    ///
+    /// Returns `INVOICE_HASH` (u256) and `ORACLE_SIG` (Signature) for `simf_witness_file`
+    pub fn oracle_entries(attestation: &OracleAttestation) -> Vec<(&'static str, &'static str, String)> {
+        vec![
+            ("INVOICE_HASH", "u256", format!("0x{}", attestation.invoice_hash)),
+            ("ORACLE_SIG", "Signature", format!("0x{}", attestation.signature)),
+        ]
+    }
+
+    /// Placeholder oracle entries for spends that do not execute the oracle check
+    ///
+    /// simc needs a value for every witness in the program, even in pruned branches
+    pub fn placeholder_oracle_entries() -> Vec<(&'static str, &'static str, String)> {
+        vec![
+            ("INVOICE_HASH", "u256", format!("0x{}", "00".repeat(32))),
+            ("ORACLE_SIG", "Signature", format!("0x{}", "00".repeat(64))),
+        ]
+    }
+}
@@ -0,0 +1,1984 @@
+//! Step-based contract workflow
+//!
+//! Every contract page walks the same steps: generate the `.simf` source from a template,
+//! compile it, create the contract address, fund it, build the spending PSET, sign and
+//! finalize it, and broadcast. [`Workflow`] runs those steps for any template; what differs
+//! between contracts (outputs, witness, payment cap, oracle, refund path) comes from the
+//! template's [`SpendFlow`](super::templates::SpendFlow).
+//!
+//! Each [`Step`] has a typed input and output. It checks its input against the current
+//! [`WorkflowState`] before doing anything, records its output in the state, and the state
+//! is saved after every step so a workflow can be picked up again after a restart.
+//! Private keys are step inputs only and never end up in the state.
+
+use super::audit::{self, AuditEvent};
+use super::contracts;
+use super::error::{self, AppError};
+use super::elements_rpc::{ElementsRPC, NodeBackend};
+use super::hal_wrapper::{HalBackend, HalWrapper, PsetInputUpdate};
+use super::internal_key::InternalKey;
+use super::models::{Contract, UtxoInfo};
+use super::oracle::{self, AttestedSpend, OracleAttestation, OracleClient};
+use super::platform;
+use super::qr;
+use super::redemption;
+use super::sighash::{self, SigningMode};
+use super::store::Store;
+use super::templates::{CovenantTemplate, OutputRole, TemplateRegistry, TemplateValues};
+use super::tx_builder::TxBuilder;
+use super::witness::WitnessBuilder;
+use super::contracts::VerificationReport;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Fee used for covenant spends and refunds, and the minimum accepted for plain spends
+pub const MIN_FEE_SATS: u64 = 100;
+
+/// How many times to poll `gettxout` (5 seconds apart) before falling back to the Blockstream API
+const UTXO_ATTEMPTS: u32 = 20;
+
+/// Steps of a contract workflow, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepId {
+    Generate,
+    Compile,
+    CreateContract,
+    Verify,
+    Fund,
+    CreatePset,
+    Attest,
+    Sign,
+    Reclaim,
+    Broadcast,
+}
+
+impl StepId {
+    /// Steps every workflow goes through, shown as the progress list
+    pub const MAIN: [StepId; 7] = [
+        StepId::Generate,
+        StepId::Compile,
+        StepId::CreateContract,
+        StepId::Fund,
+        StepId::CreatePset,
+        StepId::Sign,
+        StepId::Broadcast,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StepId::Generate => "Generate source",
+            StepId::Compile => "Compile",
+            StepId::CreateContract => "Create contract address",
+            StepId::Verify => "Verify contract",
+            StepId::Fund => "Fund",
+            StepId::CreatePset => "Create spending PSET",
+            StepId::Attest => "Oracle attestation",
+            StepId::Sign => "Sign and finalize",
+            StepId::Reclaim => "Reclaim expired vouchers",
+            StepId::Broadcast => "Broadcast",
+        }
+    }
+}
+
+/// Contract created by [`CreateContract`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractInfo {
+    pub cmr: String,
+    pub address: String,
+    /// Compiled program (base64) the address was derived from
+    pub program: String,
+    /// Genesis hash of the node's chain, which signatures commit to
+    #[serde(default = "sighash::default_genesis_hash")]
+    pub genesis_hash: String,
+}
+
+/// Funding output of the contract
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Funding {
+    pub txid: String,
+    pub vout: u32,
+    /// Amount in L-BTC, as entered
+    pub amount: String,
+}
+
+/// PSET spending the contract, ready for signing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpendPset {
+    pub pset: String,
+    pub destination: String,
+    pub value_sats: u64,
+    pub amount_sats: u64,
+    /// Change sent back to the covenant, for covenant spends
+    pub change_sats: Option<u64>,
+    pub fee_sats: u64,
+    /// Outputs as decoded by the node, with warnings if they do not match the template
+    pub structure: String,
+}
+
+/// What a finalized transaction does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FinalizedKind {
+    Spend,
+    Refund { destination: String, sweep_sats: u64, locktime: u32 },
+}
+
+/// Finalized transaction, ready to broadcast
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finalized {
+    pub pset: String,
+    pub tx_hex: String,
+    pub kind: FinalizedKind,
+    /// Whether the contract has covenant outputs (for the summary)
+    pub covenant: bool,
+    /// Public keys signed with in this step (co-signatures already in the PSET are not listed)
+    #[serde(default)]
+    pub signers: Vec<String>,
+}
+
+/// Everything a workflow has produced so far
+///
+/// Outputs of later steps are cleared when an earlier step is re-run, so the state never
+/// mixes, say, a PSET with a different contract.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowState {
+    #[serde(default)]
+    pub template_id: String,
+    #[serde(default)]
+    pub params: TemplateValues,
+    #[serde(default)]
+    pub simf_path: String,
+    #[serde(default)]
+    pub witness_path: String,
+    /// Compiled program (base64), compiled here or pasted
+    #[serde(default)]
+    pub program: String,
+    #[serde(default)]
+    pub internal_key: InternalKey,
+    #[serde(default)]
+    pub contract: Option<ContractInfo>,
+    /// `None` until "Verify Contract" runs; `Some(false)` if hal disagreed at creation
+    #[serde(default)]
+    pub verified: Option<bool>,
+    #[serde(default)]
+    pub funding: Option<Funding>,
+    #[serde(default)]
+    pub spend: Option<SpendPset>,
+    #[serde(default)]
+    pub attestation: Option<OracleAttestation>,
+    #[serde(default)]
+    pub finalized: Option<Finalized>,
+    #[serde(default)]
+    pub broadcast_txid: Option<String>,
+}
+
+impl WorkflowState {
+    /// Fresh state for a template
+    pub fn new(template_id: &str, params: TemplateValues) -> Self {
+        Self {
+            template_id: template_id.to_string(),
+            params,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a step's output is present
+    pub fn is_done(&self, step: StepId) -> bool {
+        match step {
+            StepId::Generate => !self.simf_path.is_empty() && Path::new(&self.simf_path).exists(),
+            StepId::Compile => !self.program.is_empty(),
+            StepId::CreateContract => self.contract.is_some(),
+            StepId::Verify => self.verified == Some(true),
+            StepId::Fund => self.funding.is_some(),
+            StepId::CreatePset => self.spend.is_some(),
+            StepId::Attest => self.attestation.is_some(),
+            StepId::Sign => matches!(self.finalized, Some(Finalized { kind: FinalizedKind::Spend, .. })),
+            StepId::Reclaim => matches!(self.finalized, Some(Finalized { kind: FinalizedKind::Refund { .. }, .. })),
+            StepId::Broadcast => self.broadcast_txid.is_some(),
+        }
+    }
+
+    /// First main step that has not produced its output yet
+    pub fn next_step(&self) -> Option<StepId> {
+        // A finalized spend or refund only has broadcasting left
+        if self.finalized.is_some() {
+            return (!self.is_done(StepId::Broadcast)).then_some(StepId::Broadcast);
+        }
+        // A pasted program needs neither the source nor the compile step
+        StepId::MAIN
+            .into_iter()
+            .filter(|step| !(*step <= StepId::Compile && self.contract.is_some()))
+            .find(|step| !self.is_done(*step))
+    }
+
+    fn clear_from_contract(&mut self) {
+        self.contract = None;
+        self.verified = None;
+        self.clear_from_funding();
+    }
+
+    fn clear_from_funding(&mut self) {
+        self.funding = None;
+        self.clear_from_spend();
+    }
+
+    fn clear_from_spend(&mut self) {
+        self.spend = None;
+        self.attestation = None;
+        self.finalized = None;
+        self.broadcast_txid = None;
+    }
+
+    fn contract(&self) -> Result<&ContractInfo> {
+        self.contract
+            .as_ref()
+            .context("Please create the contract address first")
+    }
+
+    fn funding(&self) -> Result<&Funding> {
+        self.funding
+            .as_ref()
+            .context("Please fund the contract address first")
+    }
+
+    /// The redemption an oracle attestation has to commit to
+    fn attested_spend(&self) -> Result<AttestedSpend> {
+        let spend = self.spend.as_ref().context("Please create the PSET first")?;
+        let funding = self.funding()?;
+        Ok(AttestedSpend {
+            output_script_pubkey: oracle::script_pubkey_hex(&spend.destination)
+                .context("Failed to derive scriptPubKey for destination")?,
+            amount_sats: spend.amount_sats,
+            txid: funding.txid.clone(),
+            vout: funding.vout,
+        })
+    }
+}
+
+/// Services the steps run against, plus the name the state is saved under
+///
+/// The node and tools default to elementsd and the CLI tools; tests swap in in-memory ones.
+pub struct Workflow<R = ElementsRPC, H = HalWrapper> {
+    pub name: String,
+    pub rpc: Arc<R>,
+    pub hal: Arc<H>,
+    pub registry: Arc<TemplateRegistry>,
+    pub store: Arc<Store>,
+}
+
+impl<R, H> Clone for Workflow<R, H> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            rpc: self.rpc.clone(),
+            hal: self.hal.clone(),
+            registry: self.registry.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+impl<R: NodeBackend, H: HalBackend> Workflow<R, H> {
+    pub fn new(
+        name: &str,
+        rpc: Arc<R>,
+        hal: Arc<H>,
+        registry: Arc<TemplateRegistry>,
+        store: Arc<Store>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            rpc,
+            hal,
+            registry,
+            store,
+        }
+    }
+
+    /// Saved state of this workflow, if any
+    pub fn resume(&self) -> Result<Option<WorkflowState>> {
+        self.store.load_workflow(&self.name)
+    }
+
+    pub fn save(&self, state: &WorkflowState) -> Result<()> {
+        self.store.save_workflow(&self.name, state)
+    }
+
+    /// Validate, run and record one step, then save the state
+    ///
+    /// Progress messages for long-running steps go to `progress`. The outcome is appended
+    /// to the audit log (see [`Step::audit`]).
+    pub async fn run<S: Step>(
+        &self,
+        step: S,
+        state: &mut WorkflowState,
+        input: S::Input,
+        progress: &dyn Fn(String),
+    ) -> Result<S::Output> {
+        step.validate(self, state, &input)?;
+        let result = step.run(self, state, input, progress).await;
+        match &result {
+            Ok(output) => {
+                step.apply(state, output);
+                for event in step.audit(state, output) {
+                    audit::record(&self.store, &self.name, event);
+                }
+            }
+            Err(e) => {
+                step.failed(state);
+                let event = AuditEvent::Failed { step: S::ID.label().to_string(), error: format!("{:#}", e) };
+                audit::record(&self.store, &self.name, event);
+            }
+        }
+        if let Err(e) = self.save(state) {
+            tracing::warn!("Failed to save workflow state for {}: {}", self.name, e);
+        }
+        result
+    }
+
+    pub fn template(&self, id: &str) -> Result<&CovenantTemplate> {
+        self.registry
+            .get(id)
+            .with_context(|| format!("Unknown covenant template: {}", id))
+    }
+}
+
+/// One step of a contract workflow
+pub trait Step {
+    type Input;
+    type Output;
+
+    const ID: StepId;
+
+    /// Check the input against the state before anything runs
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: &Self::Input) -> Result<()>;
+
+    /// Do the work; must not modify anything but files and the chain
+    fn run<R: NodeBackend, H: HalBackend>(
+        &self,
+        workflow: &Workflow<R, H>,
+        state: &WorkflowState,
+        input: Self::Input,
+        progress: &dyn Fn(String),
+    ) -> impl Future<Output = Result<Self::Output>>;
+
+    /// Record the output in the state
+    fn apply(&self, state: &mut WorkflowState, output: &Self::Output);
+
+    /// Update the state after `run` failed
+    fn failed(&self, _state: &mut WorkflowState) {}
+
+    /// Audit log events for the output, given the state after [`Step::apply`]
+    fn audit(&self, _state: &WorkflowState, _output: &Self::Output) -> Vec<AuditEvent> {
+        Vec::new()
+    }
+}
+
+/// Describe the outputs a template's spends must have
+pub fn describe_outputs(template: &CovenantTemplate) -> String {
+    required_outputs(template)
+        .iter()
+        .map(|o| format!("- {}", o))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The outputs a template's spends must have, in order (for [`AppError::CovenantViolation`])
+pub fn required_outputs(template: &CovenantTemplate) -> Vec<String> {
+    template
+        .spend_flow
+        .outputs
+        .iter()
+        .enumerate()
+        .map(|(i, role)| format!("Output {}: {}", i, role_label(*role)))
+        .collect()
+}
+
+fn role_label(role: OutputRole) -> &'static str {
+    match role {
+        OutputRole::Payment => "Payment",
+        OutputRole::CovenantChange => "Same covenant script (recursive)",
+        OutputRole::Fee => "Fee",
+    }
+}
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+// ---------------------------------------------------------------------------
+// Generate
+
+/// Render the template into a `.simf` file
+pub struct Generate;
+
+pub struct GenerateInput {
+    pub template_id: String,
+    pub params: TemplateValues,
+    pub simf_path: String,
+}
+
+pub struct Generated {
+    pub path: String,
+    pub file_name: String,
+    pub template_name: String,
+    pub summary: String,
+    pub covenant_outputs: Option<String>,
+    template_id: String,
+    params: TemplateValues,
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Successfully generated {} from template '{}'!\n\nFile: {}\n\n",
+            self.file_name, self.template_name, self.path
+        )?;
+        if let Some(outputs) = &self.covenant_outputs {
+            write!(f, "Covenant enforces:\n{}\n\n", outputs)?;
+        }
+        write!(f, "{}\n\nYou can now compile this file.", self.summary)
+    }
+}
+
+impl Step for Generate {
+    type Input = GenerateInput;
+    type Output = Generated;
+    const ID: StepId = StepId::Generate;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, _state: &WorkflowState, input: &GenerateInput) -> Result<()> {
+        workflow.template(&input.template_id)?;
+        if input.simf_path.is_empty() {
+            return Err(anyhow::anyhow!("Please enter a path for the .simf file"));
+        }
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, _state: &WorkflowState, input: GenerateInput, _progress: &dyn Fn(String)) -> Result<Generated> {
+        let template = workflow.template(&input.template_id)?;
+        let source = template.render(&input.params)?;
+        platform::write_file(Path::new(&input.simf_path), &source)
+            .map_err(|e| anyhow::anyhow!("Failed to write simf file: {}\n\nPath: {}", e, input.simf_path))?;
+        Ok(Generated {
+            file_name: template.file_name.clone(),
+            template_name: template.name.clone(),
+            summary: template.summary(&input.params),
+            covenant_outputs: template.spend_flow.is_covenant().then(|| describe_outputs(template)),
+            path: input.simf_path,
+            template_id: input.template_id,
+            params: input.params,
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Generated) {
+        state.template_id = output.template_id.clone();
+        state.params = output.params.clone();
+        state.simf_path = output.path.clone();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Compile
+
+/// Compile the `.simf` file with `simc`
+pub struct Compile;
+
+pub struct CompileInput {
+    pub simf_path: String,
+}
+
+pub struct Compiled {
+    pub path: String,
+    pub program: String,
+}
+
+impl fmt::Display for Compiled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Compilation successful!\n\nInput: {}\n\nCompiled program (first 100 chars): {}...\n\nYou can now create the contract address.",
+            self.path,
+            self.program.chars().take(100).collect::<String>()
+        )
+    }
+}
+
+impl Step for Compile {
+    type Input = CompileInput;
+    type Output = Compiled;
+    const ID: StepId = StepId::Compile;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, _workflow: &Workflow<R, H>, _state: &WorkflowState, input: &CompileInput) -> Result<()> {
+        if input.simf_path.is_empty() {
+            return Err(anyhow::anyhow!("Please enter a path to the .simf file"));
+        }
+        if !Path::new(&input.simf_path).exists() {
+            return Err(anyhow::anyhow!("File not found: {}", input.simf_path));
+        }
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, _state: &WorkflowState, input: CompileInput, _progress: &dyn Fn(String)) -> Result<Compiled> {
+        let program = workflow
+            .hal
+            .compile_simf(&input.simf_path)
+            .context("Compilation failed")?;
+        Ok(Compiled {
+            path: input.simf_path,
+            program,
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Compiled) {
+        state.simf_path = output.path.clone();
+        state.program = output.program.clone();
+    }
+
+    fn audit(&self, state: &WorkflowState, output: &Compiled) -> Vec<AuditEvent> {
+        let source = platform::read_file(Path::new(&output.path)).ok().flatten().unwrap_or_default();
+        vec![AuditEvent::Compiled {
+            template_id: state.template_id.clone(),
+            simf_path: output.path.clone(),
+            source_sha256: contracts::source_hash(&source),
+            program_sha256: audit::sha256_hex(output.program.as_bytes()),
+        }]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Create contract
+
+/// Derive the contract address for the compiled program and record the contract
+pub struct CreateContract;
+
+pub struct CreateContractInput {
+    pub template_id: String,
+    pub params: TemplateValues,
+    pub simf_path: String,
+    pub program: String,
+    pub internal_key: InternalKey,
+    /// Oracle signer recorded for redemptions; required when the template has an oracle key
+    pub oracle_url: String,
+}
+
+pub struct CreatedContract {
+    pub contract: ContractInfo,
+    /// Disagreements between the Rust derivation and `hal-simplicity simplicity info`
+    pub hal_mismatches: Vec<String>,
+    pub record_note: String,
+    pub covenant_outputs: Option<String>,
+    template_id: String,
+    params: TemplateValues,
+    simf_path: String,
+    internal_key: InternalKey,
+}
+
+impl fmt::Display for CreatedContract {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Contract created successfully!\n\nCMR: {}\nAddress: {}\n\n",
+            self.contract.cmr, self.contract.address
+        )?;
+        if let Some(outputs) = &self.covenant_outputs {
+            write!(f, "This covenant enforces:\n{}\n\n", outputs)?;
+        }
+        write!(f, "{}\n\n", self.record_note)?;
+        if self.hal_mismatches.is_empty() {
+            write!(f, "Address derived in Rust and cross-checked against hal-simplicity.")
+        } else {
+            write!(
+                f,
+                "WARNING: hal-simplicity disagrees with the Rust derivation. Do not fund this address.\n- {}",
+                self.hal_mismatches.join("\n- ")
+            )
+        }
+    }
+}
+
+impl Step for CreateContract {
+    type Input = CreateContractInput;
+    type Output = CreatedContract;
+    const ID: StepId = StepId::CreateContract;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, _state: &WorkflowState, input: &CreateContractInput) -> Result<()> {
+        if input.program.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Please enter a compiled Simplicity program (base64) or compile a .simf file first"
+            ));
+        }
+        // An aggregate internal key must match the signer keys in the form
+        if let InternalKey::Aggregate { signers, .. } = &input.internal_key {
+            let current = workflow
+                .template(&input.template_id)
+                .map(|t| t.signer_pubkeys(&input.params))
+                .unwrap_or_default();
+            if *signers != current {
+                return Err(anyhow::anyhow!(
+                    "The aggregate internal key was built from different signer keys. Select the aggregate key option again."
+                ));
+            }
+        }
+        let needs_oracle = workflow
+            .template(&input.template_id)
+            .is_ok_and(|t| t.oracle_pubkey(&input.params).is_some());
+        if needs_oracle && input.oracle_url.trim().is_empty() {
+            return Err(anyhow::anyhow!(
+                "Please enter the oracle signer URL. It is recorded with the contract so participants request attestations from the campaign's oracle."
+            ));
+        }
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, _state: &WorkflowState, input: CreateContractInput, _progress: &dyn Fn(String)) -> Result<CreatedContract> {
+        let info = workflow.hal.get_covenant_info(&input.program).map_err(|e| {
+            anyhow::anyhow!(
+                "Error calling hal-simplicity: {}\n\nPlease ensure:\n1. hal-simplicity is installed and in PATH\n2. The program is valid base64\n3. Try running: hal-simplicity simplicity simplicity info \"<your_program>\"",
+                e
+            )
+        })?;
+
+        // Derive the CMR and address in Rust and cross-check hal's output
+        let (output, hal_mismatches) = contracts::derive_contract(&input.program, &info, &input.internal_key)
+            .context("Failed to derive the contract address")?;
+
+        let genesis_hash = workflow
+            .rpc
+            .get_genesis_hash()
+            .await
+            .context("Failed to get the chain's genesis hash from the node")?;
+        let template = workflow.template(&input.template_id).ok();
+        let record = contracts::record_contract(
+            workflow.hal.as_ref(),
+            &workflow.store,
+            template,
+            &input.params,
+            &input.simf_path,
+            &input.program,
+            &info,
+            &input.internal_key,
+            &genesis_hash,
+            &input.oracle_url,
+        );
+        let record_note = match record {
+            Ok(c) if c.source_hash.is_empty() => "Contract recorded without source (program was not compiled from a .simf file here).".to_string(),
+            Ok(c) => format!("Contract recorded.\nSource SHA256: {}\nCompiler: {}\n\nCompare the source hash with the reviewed file (sha256sum) and press \"Verify Contract\" before funding.", c.source_hash, c.compiler_version),
+            Err(e) => format!("Warning: failed to save the contract record: {}", e),
+        };
+
+        Ok(CreatedContract {
+            contract: ContractInfo {
+                cmr: output.cmr,
+                address: output.address,
+                program: input.program,
+                genesis_hash,
+            },
+            hal_mismatches,
+            record_note,
+            covenant_outputs: template
+                .filter(|t| t.spend_flow.is_covenant())
+                .map(describe_outputs),
+            template_id: input.template_id,
+            params: input.params,
+            simf_path: input.simf_path,
+            internal_key: input.internal_key,
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &CreatedContract) {
+        state.clear_from_contract();
+        state.template_id = output.template_id.clone();
+        state.params = output.params.clone();
+        state.simf_path = output.simf_path.clone();
+        state.program = output.contract.program.clone();
+        state.internal_key = output.internal_key.clone();
+        state.contract = Some(output.contract.clone());
+        state.verified = if output.hal_mismatches.is_empty() { None } else { Some(false) };
+    }
+
+    fn audit(&self, _state: &WorkflowState, output: &CreatedContract) -> Vec<AuditEvent> {
+        vec![AuditEvent::ContractCreated {
+            template_id: output.template_id.clone(),
+            address: output.contract.address.clone(),
+            cmr: output.contract.cmr.clone(),
+            program_sha256: audit::sha256_hex(output.contract.program.as_bytes()),
+            internal_key: output.internal_key.key_hex(),
+        }]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Verify
+
+/// Recompile the recorded contract and check the CMR and address still match
+pub struct Verify;
+
+pub struct VerifyInput {
+    pub simf_path: String,
+}
+
+pub struct Verified {
+    pub record: Contract,
+    pub report: VerificationReport,
+}
+
+impl fmt::Display for Verified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.report.is_match() {
+            "Contract verified: the source recompiles to the recorded CMR and address."
+        } else {
+            "Contract verification FAILED. Do not fund this address until the mismatch is explained."
+        };
+        let or_none = |s: &str| if s.is_empty() { "(none)".to_string() } else { s.to_string() };
+        write!(
+            f,
+            "{}\n\nTemplate: {}\nSource SHA256: {}\nCompiler: {}\nInternal key: {}\n\n{}",
+            verdict,
+            or_none(&self.record.template_id),
+            or_none(&self.record.source_hash),
+            self.record.compiler_version,
+            self.record.internal_key,
+            self.report
+        )
+    }
+}
+
+impl Step for Verify {
+    type Input = VerifyInput;
+    type Output = Verified;
+    const ID: StepId = StepId::Verify;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, _workflow: &Workflow<R, H>, state: &WorkflowState, _input: &VerifyInput) -> Result<()> {
+        state.contract().map(|_| ())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: VerifyInput, _progress: &dyn Fn(String)) -> Result<Verified> {
+        let address = &state.contract()?.address;
+        let record = workflow
+            .store
+            .find_contract(address)
+            .context("Failed to load contract records")?
+            .with_context(|| format!("No contract record found for {}\n\nCreate the contract address first.", address))?;
+        let report = contracts::verify_contract(workflow.hal.as_ref(), &workflow.registry, &record, Some(&input.simf_path))
+            .context("Contract verification failed")?;
+        Ok(Verified { record, report })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Verified) {
+        state.verified = Some(output.report.is_match());
+    }
+
+    fn failed(&self, state: &mut WorkflowState) {
+        state.verified = Some(false);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fund
+
+/// Fund the contract address from the Liquid testnet faucet
+pub struct Fund;
+
+pub struct FundInput {
+    /// Amount in L-BTC
+    pub amount: String,
+}
+
+pub struct Funded {
+    pub address: String,
+    pub funding: Funding,
+}
+
+impl fmt::Display for Funded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let amount: f64 = self.funding.amount.parse().unwrap_or(0.0);
+        write!(
+            f,
+            "Funding successful via faucet!\n\nContract Address: {}\nAmount: {} L-BTC ({} sats)\nTransaction ID: {}\nVOUT: {}\n\nView on explorer: https://blockstream.info/liquidtestnet/tx/{}",
+            self.address,
+            self.funding.amount,
+            (amount * 100_000_000.0).round() as u64,
+            self.funding.txid,
+            self.funding.vout,
+            self.funding.txid
+        )
+    }
+}
+
+impl Step for Fund {
+    type Input = FundInput;
+    type Output = Funded;
+    const ID: StepId = StepId::Fund;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, _workflow: &Workflow<R, H>, state: &WorkflowState, input: &FundInput) -> Result<()> {
+        state.contract()?;
+        match input.amount.trim().parse::<f64>() {
+            Ok(amount) if amount > 0.0 => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "Invalid faucet amount: {}. Please enter a positive number.",
+                input.amount
+            )),
+        }
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, _workflow: &Workflow<R, H>, state: &WorkflowState, input: FundInput, _progress: &dyn Fn(String)) -> Result<Funded> {
+        let address = state.contract()?.address.clone();
+        let faucet_url = format!("https://liquidtestnet.com/faucet?address={}&action=lbtc", address);
+        let html = reqwest::Client::new()
+            .get(&faucet_url)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Error calling faucet API: {}\n\nURL: {}", e, faucet_url))?
+            .text()
+            .await
+            .context("Error reading faucet response")?;
+
+        let txid = [r"transaction\s+([a-f0-9]{64})", r"txid[:\s]+([a-f0-9]{64})"]
+            .iter()
+            .find_map(|pattern| {
+                Regex::new(pattern)
+                    .expect("valid regex")
+                    .captures(&html)
+                    .and_then(|c| c.get(1))
+                    .map(|m| m.as_str().to_string())
+            })
+            .with_context(|| {
+                format!(
+                    "Faucet response received but could not find transaction ID in response.\n\nResponse preview:\n{}",
+                    html.chars().take(500).collect::<String>()
+                )
+            })?;
+
+        Ok(Funded {
+            address,
+            funding: Funding {
+                txid,
+                vout: 0,
+                amount: input.amount.trim().to_string(),
+            },
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Funded) {
+        state.clear_from_funding();
+        state.funding = Some(output.funding.clone());
+    }
+
+    fn audit(&self, _state: &WorkflowState, output: &Funded) -> Vec<AuditEvent> {
+        vec![AuditEvent::Funded {
+            address: output.address.clone(),
+            txid: output.funding.txid.clone(),
+            vout: output.funding.vout,
+            amount_sats: qr::parse_btc(&output.funding.amount).unwrap_or_default(),
+        }]
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Create PSET
+
+/// Build the spending PSET and add the Simplicity input data with hal-simplicity
+pub struct CreatePset;
+
+pub struct CreatePsetInput {
+    pub destination: String,
+    /// Amount paid to the destination, in L-BTC
+    pub amount: f64,
+}
+
+impl fmt::Display for SpendPset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PSET updated successfully!\n\nUTXO value: {} L-BTC ({} sats)\nPayment: {} L-BTC ({} sats) to {}\n",
+            btc(self.value_sats), self.value_sats, btc(self.amount_sats), self.amount_sats, self.destination
+        )?;
+        if let Some(change) = self.change_sats {
+            writeln!(f, "Covenant change: {} L-BTC ({} sats)", btc(change), change)?;
+        }
+        write!(
+            f,
+            "Fee: {} L-BTC ({} sats)\n\n{}\n\nPSET (first 200 chars): {}...\n\nReady for signing.",
+            btc(self.fee_sats),
+            self.fee_sats,
+            self.structure,
+            self.pset.chars().take(200).collect::<String>()
+        )
+    }
+}
+
+impl Step for CreatePset {
+    type Input = CreatePsetInput;
+    type Output = SpendPset;
+    const ID: StepId = StepId::CreatePset;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: &CreatePsetInput) -> Result<()> {
+        state.contract()?;
+        state.funding()?;
+        if input.destination.trim().is_empty() {
+            return Err(anyhow::anyhow!("Please enter a destination address"));
+        }
+        if input.amount.is_nan() || input.amount <= 0.0 {
+            return Err(anyhow::anyhow!("Please enter a valid amount"));
+        }
+        // Reject amounts above the covenant's cap before waiting for the UTXO
+        let template = workflow.template(&state.template_id).ok();
+        if let Some(cap) = template.and_then(|t| t.payment_cap(&state.params)) {
+            TxBuilder::check_payment_cap(input.amount, cap)?;
+        }
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: CreatePsetInput, progress: &dyn Fn(String)) -> Result<SpendPset> {
+        let contract = state.contract()?;
+        let funding = state.funding()?;
+        let template = workflow.template(&state.template_id).ok();
+        let covenant = template.is_some_and(|t| t.spend_flow.is_covenant());
+        let destination = input.destination.trim().to_string();
+
+        let utxo = wait_for_utxo(workflow.rpc.as_ref(), &funding.txid, funding.vout, progress).await?;
+        let value_sats = utxo.value_sats;
+        let amount_sats = (input.amount * 100_000_000.0).round() as u64;
+        if amount_sats > value_sats {
+            return Err(anyhow::anyhow!(
+                "Spend amount {} L-BTC ({} sats) exceeds available UTXO value {} L-BTC ({} sats).\n\nPlease enter an amount less than or equal to the funded amount.",
+                input.amount, amount_sats, btc(value_sats), value_sats
+            ));
+        }
+
+        let (outputs, change_sats, fee_sats) = if covenant {
+            // Covenant spends pay output 0, send the change back to the covenant (output 1)
+            // and pay a fixed fee (output 2)
+            let change = value_sats
+                .checked_sub(amount_sats + MIN_FEE_SATS)
+                .filter(|change| *change > 0)
+                .ok_or_else(|| anyhow::anyhow!(
+                    "No change remaining. UTXO value {} L-BTC ({} sats) does not cover payment {} L-BTC ({} sats) + fee {} sats with change left over.\n\nThe covenant requires Output 1 to be the recursive covenant (change). Please reduce the spend amount.",
+                    btc(value_sats), value_sats, input.amount, amount_sats, MIN_FEE_SATS
+                ))?;
+            (
+                vec![(destination.clone(), btc(amount_sats)), (contract.address.clone(), btc(change))],
+                Some(change),
+                MIN_FEE_SATS,
+            )
+        } else {
+            // Plain spends pay the destination and leave the rest as fee
+            let fee = value_sats - amount_sats;
+            if fee < MIN_FEE_SATS {
+                return Err(anyhow::anyhow!(
+                    "Fee {} sats is too small (minimum: {} sats).\n\nUTXO value: {} L-BTC ({} sats)\nSpend amount: {} L-BTC ({} sats)\n\nPlease reduce the spend amount to allow for a reasonable fee.",
+                    fee, MIN_FEE_SATS, btc(value_sats), value_sats, input.amount, amount_sats
+                ));
+            }
+            (vec![(destination.clone(), btc(amount_sats))], None, fee)
+        };
+
+        progress(format!(
+            "Creating base PSET with:\nUTXO value: {} L-BTC ({} sats)\nSpend amount: {} L-BTC ({} sats)\nFee: {} L-BTC ({} sats)",
+            btc(value_sats), value_sats, btc(amount_sats), amount_sats, btc(fee_sats), fee_sats
+        ));
+        let base_pset = workflow
+            .rpc
+            .create_pset(&[(funding.txid.clone(), funding.vout)], &outputs, Some(btc(fee_sats)))
+            .await
+            .context("Failed to create base PSET with elements-cli")?;
+
+        progress("Updating PSET with Simplicity data...".to_string());
+        let pset = update_contract_input(workflow, state, &base_pset, &utxo)?;
+
+        progress("Decoding PSET to verify structure...".to_string());
+        let roles = template.map(|t| t.spend_flow.outputs.clone()).unwrap_or_default();
+        let structure = match workflow.rpc.decode_pset(&pset).await {
+            Ok(decoded) => describe_pset(&decoded, &roles),
+            Err(e) => format!("Could not decode the PSET to check its outputs: {}", e),
+        };
+
+        Ok(SpendPset {
+            pset,
+            destination,
+            value_sats,
+            amount_sats,
+            change_sats,
+            fee_sats,
+            structure,
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &SpendPset) {
+        state.clear_from_spend();
+        state.spend = Some(output.clone());
+    }
+
+    fn audit(&self, _state: &WorkflowState, output: &SpendPset) -> Vec<AuditEvent> {
+        vec![AuditEvent::PsetCreated {
+            unsigned_txid: audit::pset_txid(&output.pset),
+            pset_sha256: audit::sha256_hex(output.pset.trim().as_bytes()),
+            destination: output.destination.clone(),
+            amount_sats: output.amount_sats,
+            fee_sats: output.fee_sats,
+        }]
+    }
+}
+
+/// Wait for the funding output to show up in `gettxout`, then look it up
+///
+/// [`ElementsRPC::get_utxo_info`] falls back to the Blockstream API if the node never sees it.
+async fn wait_for_utxo(rpc: &impl NodeBackend, txid: &str, vout: u32, progress: &dyn Fn(String)) -> Result<UtxoInfo> {
+    progress("Waiting for UTXO to be available...".to_string());
+    for attempt in 1..=UTXO_ATTEMPTS {
+        if rpc.get_txout(txid, vout).await.is_ok_and(|data| !data.is_null()) {
+            break;
+        }
+        if attempt < UTXO_ATTEMPTS {
+            progress(format!("UTXO not available yet, waiting... (attempt {}/{})", attempt + 1, UTXO_ATTEMPTS));
+            platform::sleep(std::time::Duration::from_secs(5)).await;
+        } else {
+            progress(format!("UTXO not found after {} attempts. Trying Blockstream API...", UTXO_ATTEMPTS));
+        }
+    }
+    rpc.get_utxo_info(txid, vout).await
+}
+
+/// Add the Simplicity data for the contract to input 0 and check it against the Rust derivation
+fn update_contract_input<R: NodeBackend, H: HalBackend>(workflow: &Workflow<R, H>, state: &WorkflowState, base_pset: &str, utxo: &UtxoInfo) -> Result<String> {
+    let contract = state.contract()?;
+    let updated = workflow
+        .hal
+        .update_pset_input(
+            base_pset,
+            &PsetInputUpdate {
+                input_index: 0,
+                script_pubkey: &utxo.script_pubkey,
+                asset: &utxo.asset,
+                value: &format!("{:.8}", btc(utxo.value_sats)),
+                cmr: &contract.cmr,
+                internal_key: &state.internal_key.key_hex(),
+            },
+        )
+        .context("Failed to update PSET with Simplicity data")?;
+
+    // Independently confirm hal committed input 0 to the contract output derived in Rust
+    contracts::check_pset_input(&contract.program, &state.internal_key, &updated, 0).map_err(|e| {
+        anyhow::anyhow!(
+            "hal-simplicity produced a PSET that does not match the contract derived in Rust:\n\n{}\n\nCheck the CMR and internal key before spending.",
+            e
+        )
+    })?;
+    Ok(updated)
+}
+
+/// Summarise the outputs of a `decodepsbt` result and compare them with the template's roles
+fn describe_pset(decoded: &serde_json::Value, roles: &[OutputRole]) -> String {
+    let outputs = decoded
+        .get("outputs")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let mut lines = vec![format!("OUTPUTS ({}):", outputs.len())];
+    for (i, output) in outputs.iter().enumerate() {
+        let amount = output.get("amount").and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let address = output
+            .get("script")
+            .and_then(|s| s.get("address"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("N/A");
+        let role = roles.get(i).map(|r| role_label(*r)).unwrap_or("Other");
+        lines.push(format!(
+            "  Output {}: {} L-BTC ({} sats) to {} [{}]",
+            i, amount, (amount * 100_000_000.0).round() as u64, address, role
+        ));
+    }
+    if !roles.is_empty() && outputs.len() != roles.len() {
+        lines.push(format!(
+            "\nWARNING: Expected {} outputs but found {}! The contract requires:\n{}",
+            roles.len(),
+            outputs.len(),
+            roles
+                .iter()
+                .enumerate()
+                .map(|(i, r)| format!("- Output {}: {}", i, role_label(*r)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+    lines.join("\n")
+}
+
+// ---------------------------------------------------------------------------
+// Attest
+
+/// Get an oracle attestation for the invoice paid by output 0 of the spending PSET
+pub struct Attest;
+
+pub struct AttestInput {
+    pub oracle_url: String,
+    pub invoice_id: String,
+}
+
+pub struct Attested {
+    pub attestation: OracleAttestation,
+}
+
+impl fmt::Display for Attested {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Oracle attestation verified!\n\nInvoice: {}\nInvoice hash: {}\nPayment output scriptPubKey: {}\nPayment amount: {} sats\nVoucher UTXO: {}:{}\nOracle signature: {}\n\nThe attestation will be added to the witness when signing.",
+            self.attestation.invoice_id,
+            self.attestation.invoice_hash,
+            self.attestation.spend.output_script_pubkey,
+            self.attestation.spend.amount_sats,
+            self.attestation.spend.txid,
+            self.attestation.spend.vout,
+            self.attestation.signature
+        )
+    }
+}
+
+impl Step for Attest {
+    type Input = AttestInput;
+    type Output = Attested;
+    const ID: StepId = StepId::Attest;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: &AttestInput) -> Result<()> {
+        if input.invoice_id.trim().is_empty() {
+            return Err(anyhow::anyhow!("Please enter the partner invoice ID"));
+        }
+        state.attested_spend()?;
+        workflow
+            .template(&state.template_id)?
+            .oracle_pubkey(&state.params)
+            .context("Please set the oracle public key used by the covenant")?;
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: AttestInput, _progress: &dyn Fn(String)) -> Result<Attested> {
+        let oracle_pk = workflow
+            .template(&state.template_id)?
+            .oracle_pubkey(&state.params)
+            .context("Please set the oracle public key used by the covenant")?;
+        // The attestation commits to output 0's script and amount and to the voucher UTXO
+        let spend = state.attested_spend()?;
+        let attestation = OracleClient::new(&input.oracle_url)
+            .request_attestation(input.invoice_id.trim(), &spend)
+            .await
+            .context("Failed to get oracle attestation")?;
+        // Check the attestation before it goes into the witness
+        attestation
+            .verify(&oracle_pk, &spend)
+            .context("Oracle attestation rejected")?;
+        Ok(Attested { attestation })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Attested) {
+        state.attestation = Some(output.attestation.clone());
+    }
+
+    fn failed(&self, state: &mut WorkflowState) {
+        state.attestation = None;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sign
+
+/// Sign the spending PSET, build the witness, and finalize the transaction
+pub struct Sign;
+
+pub struct SignInput {
+    /// One entry per signer slot of the template; empty entries are skipped
+    pub privkeys: Vec<String>,
+    pub mode: SigningMode,
+    pub witness_path: String,
+    /// Copy of the PSET carrying signatures from other signers (e.g. the partner);
+    /// empty to sign the PSET from step 4 with `privkeys` alone
+    pub cosigned_pset: String,
+}
+
+impl SignInput {
+    /// The PSET to sign and finalize
+    fn pset<'a>(&'a self, spend: &'a SpendPset) -> &'a str {
+        match self.cosigned_pset.trim() {
+            "" => &spend.pset,
+            cosigned => cosigned,
+        }
+    }
+}
+
+impl fmt::Display for Finalized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let preview = self.tx_hex.chars().take(200).collect::<String>();
+        match &self.kind {
+            FinalizedKind::Spend => {
+                write!(f, "Transaction finalized successfully!\n\nTransaction Hex (first 200 chars): {}...\n\nReady to broadcast.", preview)?;
+                if self.covenant {
+                    write!(f, "\n\nNote: the covenant checks the outputs (payment, recursive change, fee) when the transaction is validated.")?;
+                }
+                Ok(())
+            }
+            FinalizedKind::Refund { destination, sweep_sats, locktime } => write!(
+                f,
+                "Refund transaction finalized!\n\nSweeping {} L-BTC ({} sats) to {}\nnLockTime: {}\nFee: {} sats\n\nTransaction Hex (first 200 chars): {}...\n\nReady to broadcast.",
+                btc(*sweep_sats), sweep_sats, destination, locktime, MIN_FEE_SATS, preview
+            ),
+        }
+    }
+}
+
+impl Step for Sign {
+    type Input = SignInput;
+    type Output = Finalized;
+    const ID: StepId = StepId::Sign;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: &SignInput) -> Result<()> {
+        state.contract()?;
+        let spend = state.spend.as_ref().context("Please create the PSET first")?;
+        if state.simf_path.is_empty() || input.witness_path.is_empty() {
+            return Err(anyhow::anyhow!("Please provide both .simf file path and witness file path"));
+        }
+        let template = workflow.template(&state.template_id)?;
+
+        if !input.cosigned_pset.trim().is_empty() {
+            let same = redemption::same_transaction(&spend.pset, &input.cosigned_pset)
+                .context("Invalid co-signed PSET")?;
+            if !same {
+                return Err(anyhow::anyhow!(
+                    "The co-signed PSET is for a different transaction than the PSET from step 4.\n\nSignatures are PSET-specific: ask the partner to co-sign the current PSET."
+                ));
+            }
+        }
+        let collected = collected_signatures(state, template, input.pset(spend))?;
+
+        // Each key must belong to the signer in its slot, or the program's signature check fails
+        let signers = template.signer_pubkeys(&state.params);
+        let mut wrong = Vec::new();
+        for (i, privkey) in input.privkeys.iter().enumerate().filter(|(_, k)| !k.trim().is_empty()) {
+            let pubkey = sighash::pubkey_of(privkey)
+                .map_err(|e| anyhow::anyhow!("Private key {}: {}", i + 1, e))?;
+            if let Some(expected) = signers.get(i).filter(|pk| **pk != pubkey) {
+                wrong.push(format!("Private key {} belongs to {} but signer {} is {}", i + 1, pubkey, i + 1, expected));
+            }
+        }
+        if !wrong.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{}\n\nThe signatures must be in the same positions as the public keys in the contract.",
+                wrong.join("\n")
+            ));
+        }
+
+        let provided = (0..signers.len().max(input.privkeys.len()))
+            .filter(|&i| {
+                input.privkeys.get(i).is_some_and(|k| !k.trim().is_empty())
+                    || collected.get(i).is_some_and(Option::is_some)
+            })
+            .count();
+        let threshold = template.threshold(&state.params);
+        if provided < threshold {
+            return Err(anyhow::anyhow!(
+                "Please provide at least {} private keys ({}-of-{} multisig).{}",
+                threshold,
+                threshold,
+                template.spend_flow.signers,
+                if collected.iter().any(Option::is_some) {
+                    " Signatures already in the co-signed PSET count towards the threshold."
+                } else {
+                    ""
+                }
+            ));
+        }
+        // The program checks for exactly `threshold` signatures, so a surplus one fails it
+        if provided > threshold {
+            let already = collected.iter().filter(|s| s.is_some()).count();
+            return Err(anyhow::anyhow!(
+                "{} signatures would go into the witness, but the {}-of-{} contract takes exactly {}.\n\nLeave out {} of the private keys.{}",
+                provided,
+                threshold,
+                template.spend_flow.signers,
+                threshold,
+                provided - threshold,
+                if already > 0 {
+                    format!(" The co-signed PSET already carries {} signature(s).", already)
+                } else {
+                    String::new()
+                }
+            ));
+        }
+
+        if let Some(oracle_pk) = template.oracle_pubkey(&state.params) {
+            let attestation = state
+                .attestation
+                .as_ref()
+                .context("This covenant requires an oracle attestation. Request one before signing.")?;
+            // The attestation must match the current PSET, or the covenant will reject it
+            state
+                .attested_spend()
+                .and_then(|attested| attestation.verify(&oracle_pk, &attested))
+                .map_err(|e| anyhow::anyhow!("Oracle attestation does not match this redemption: {}\n\nRequest a new attestation for the current PSET.", e))?;
+        }
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: SignInput, progress: &dyn Fn(String)) -> Result<Finalized> {
+        let contract = state.contract()?;
+        let spend = state.spend.as_ref().context("Please create the PSET first")?;
+        let template = workflow.template(&state.template_id)?;
+
+        // Signatures are PSET-specific: sign and finalize the very same PSET, starting from
+        // the co-signatures it already carries
+        let pset = input.pset(spend);
+        let mut signatures = collected_signatures(state, template, pset)?;
+        signatures.resize(template.spend_flow.signers.max(input.privkeys.len()).max(signatures.len()), None);
+        let mut errors = Vec::new();
+        let mut signers = Vec::new();
+        for (i, privkey) in input.privkeys.iter().enumerate().filter(|(_, k)| !k.trim().is_empty()) {
+            progress(format!("Signing with private key {}...", i + 1));
+            match sighash::sign_input(workflow.hal.as_ref(), input.mode, pset, 0, &contract.cmr, &contract.genesis_hash, privkey) {
+                Ok(sig) => {
+                    signatures[i] = Some(sig);
+                    signers.extend(sighash::pubkey_of(privkey).ok());
+                }
+                Err(e) => errors.push(format!("Failed to sign with key {}:\n{}", i + 1, e)),
+            }
+        }
+        let threshold = template.threshold(&state.params);
+        let count = signatures.iter().filter(|s| s.is_some()).count();
+        if count < threshold {
+            return Err(anyhow::anyhow!(
+                "Only {} signature(s) generated (need {} for {}-of-{} multisig).\n\nErrors:\n{}",
+                count, threshold, threshold, template.spend_flow.signers, errors.join("\n\n")
+            ));
+        }
+        if count > threshold {
+            return Err(anyhow::anyhow!(
+                "{} signatures generated, but the {}-of-{} contract takes exactly {}",
+                count, threshold, template.spend_flow.signers, threshold
+            ));
+        }
+
+        progress("Updating witness file with signatures...".to_string());
+        let witness = spend_witness(template, &state.params, &SpendPath::Multisig(&signatures), state.attestation.as_ref())?;
+        let pset = compile_and_finalize(workflow, state, pset, &witness, &input.witness_path, progress)
+            .await
+            .map_err(|e| finalize_guidance(template, &state.params, e))?;
+        progress("Finalizing PSBT...".to_string());
+        let tx_hex = workflow.rpc.finalize_pset(&pset).await.context("Failed to finalize PSBT")?;
+
+        Ok(Finalized {
+            pset,
+            tx_hex,
+            kind: FinalizedKind::Spend,
+            covenant: template.spend_flow.is_covenant(),
+            signers,
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Finalized) {
+        state.finalized = Some(output.clone());
+        state.broadcast_txid = None;
+    }
+
+    fn audit(&self, _state: &WorkflowState, output: &Finalized) -> Vec<AuditEvent> {
+        finalized_events(output)
+    }
+}
+
+/// Valid signatures already in a PSET, one slot per signer of the template
+///
+/// An invalid signature is an error rather than an empty slot: it means the PSET
+/// was changed after it was signed.
+fn collected_signatures(state: &WorkflowState, template: &CovenantTemplate, pset: &str) -> Result<Vec<Option<String>>> {
+    let contract = state.contract()?;
+    let found = redemption::signatures(pset, &contract.cmr)?;
+    if found.is_empty() {
+        return Ok(Vec::new());
+    }
+    let sighash = sighash::sig_all_hash(pset, 0, &contract.cmr, &contract.genesis_hash)?;
+    for (pubkey, signature) in &found {
+        sighash::verify_signature(&sighash, signature, pubkey)
+            .context("The PSET carries an invalid co-signature; the transaction changed after it was co-signed, ask for a new co-signature")?;
+    }
+    Ok(redemption::slot_signatures(&template.signer_pubkeys(&state.params), &found))
+}
+
+/// Audit events for a finalized spend or refund: who signed, then the transaction
+fn finalized_events(output: &Finalized) -> Vec<AuditEvent> {
+    vec![
+        audit::signed(&output.pset, output.signers.clone()),
+        AuditEvent::Finalized {
+            txid: audit::tx_txid(&output.tx_hex),
+            tx_sha256: audit::sha256_hex(output.tx_hex.trim().as_bytes()),
+            refund: matches!(output.kind, FinalizedKind::Refund { .. }),
+        },
+    ]
+}
+
+/// Which branch a spend takes
+enum SpendPath<'a> {
+    /// Participant signatures, one slot per signer
+    Multisig(&'a [Option<String>]),
+    /// Promoter signature on the refund branch
+    Refund(&'a str),
+}
+
+/// Witness file for a spend, filled in from the template's declared witness fields
+///
+/// Signature arrays (`[Option<Signature>; N]`) take the participant signatures, an
+/// `Either<.., Signature>` takes them as `Left` or the refund signature as `Right`, and the
+/// oracle fields take the attestation (or placeholders on the refund branch, where the
+/// oracle check is pruned but simc still needs values).
+fn spend_witness(
+    template: &CovenantTemplate,
+    params: &TemplateValues,
+    path: &SpendPath,
+    attestation: Option<&OracleAttestation>,
+) -> Result<String> {
+    let oracle_entries = match (path, attestation) {
+        (SpendPath::Multisig(_), Some(att)) => WitnessBuilder::oracle_entries(att),
+        _ => WitnessBuilder::placeholder_oracle_entries(),
+    };
+    let mut entries = Vec::new();
+    for field in template.witness_fields(params) {
+        let value = if let Some((_, _, value)) = oracle_entries.iter().find(|(name, _, _)| *name == field.name) {
+            value.clone()
+        } else if field.ty.starts_with("Either<") {
+            match path {
+                SpendPath::Multisig(sigs) => format!("Left({})", WitnessBuilder::maybe_sigs_value(sigs)),
+                SpendPath::Refund(sig) => format!("Right(0x{})", sig),
+            }
+        } else if field.ty.starts_with("[Option<Signature>") {
+            match path {
+                SpendPath::Multisig(sigs) => WitnessBuilder::maybe_sigs_value(sigs),
+                SpendPath::Refund(_) => return Err(anyhow::anyhow!("Template {} has no refund branch", template.id)),
+            }
+        } else if field.ty == "Signature" {
+            match path {
+                SpendPath::Multisig(sigs) => format!("0x{}", sigs.iter().flatten().next().context("No signature to put in the witness")?),
+                SpendPath::Refund(sig) => format!("0x{}", sig),
+            }
+        } else {
+            return Err(anyhow::anyhow!(
+                "Don't know how to fill witness {} of type {} in template {}",
+                field.name, field.ty, template.id
+            ));
+        };
+        entries.push((field.name.as_str(), field.ty.as_str(), value));
+    }
+    WitnessBuilder::simf_witness_file(&entries)
+        .context("Failed to serialize updated witness JSON")
+}
+
+/// Compile the program with the witness and finalize input 0 of the PSET with hal-simplicity
+///
+/// The witness is written to `witness_path` so it can be inspected afterwards.
+async fn compile_and_finalize<R: NodeBackend, H: HalBackend>(
+    workflow: &Workflow<R, H>,
+    state: &WorkflowState,
+    pset: &str,
+    witness: &str,
+    witness_path: &str,
+    progress: &dyn Fn(String),
+) -> Result<String> {
+    let temp_witness_path = format!("{}.tmp", witness_path);
+    platform::write_file(Path::new(&temp_witness_path), witness)
+        .context("Failed to write updated witness file")?;
+
+    progress("Compiling program with updated witness file...".to_string());
+    let compiled = workflow.hal.compile_simf_with_witness(&state.simf_path, &temp_witness_path);
+    let _ = platform::remove_file(Path::new(&temp_witness_path));
+    let (program, witness_data) = compiled.context("Failed to compile with witness")?;
+
+    if let Err(e) = platform::write_file(Path::new(witness_path), witness) {
+        progress(format!("Warning: Could not save updated witness file: {}. Continuing...", e));
+    }
+
+    progress("Finalizing PSET with program and witness...".to_string());
+    workflow.hal.finalize_pset_with_witness(pset, 0, &program, &witness_data)
+}
+
+/// Explain a failed finalize in terms of the template's signers and outputs
+///
+/// A jet failing while hal-simplicity finalizes means a signature check in the program
+/// failed, which becomes an [`AppError::SignatureMismatch`] listing the expected signers.
+fn finalize_guidance(template: &CovenantTemplate, params: &TemplateValues, error: anyhow::Error) -> anyhow::Error {
+    let jet_failed = matches!(
+        error::find(&error),
+        Some(AppError::ToolFailed { stderr, stdout, .. })
+            if [stderr, stdout].iter().any(|o| o.contains("Jet failed") || o.contains("failed during execution"))
+    );
+    if !jet_failed {
+        return error.context("Failed to finalize PSET");
+    }
+    let mut detail = String::from("The program failed during execution (Jet failed)");
+    if template.spend_flow.is_covenant() {
+        detail.push_str(&format!(". The covenant also requires exactly these outputs:\n{}", describe_outputs(template)));
+    }
+    error.context(AppError::SignatureMismatch {
+        detail,
+        signers: template.signer_pubkeys(params),
+        threshold: Some(template.threshold(params)),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Reclaim
+
+/// Promoter sweep of an expired voucher through the time-locked refund branch
+pub struct Reclaim;
+
+pub struct ReclaimInput {
+    pub destination: String,
+    pub privkey: String,
+    pub mode: SigningMode,
+    pub witness_path: String,
+}
+
+impl Step for Reclaim {
+    type Input = ReclaimInput;
+    type Output = Finalized;
+    const ID: StepId = StepId::Reclaim;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: &ReclaimInput) -> Result<()> {
+        workflow
+            .template(&state.template_id)?
+            .refund(&state.params)
+            .context("This contract was not generated with a promoter refund path")?;
+        if state.contract.is_none() || state.funding.is_none() {
+            return Err(anyhow::anyhow!("Please create and fund the contract address first"));
+        }
+        if input.destination.trim().is_empty() {
+            return Err(anyhow::anyhow!("Please enter the promoter refund address"));
+        }
+        if input.privkey.trim().is_empty() {
+            return Err(anyhow::anyhow!("Please enter the promoter private key"));
+        }
+        if state.simf_path.is_empty() || input.witness_path.is_empty() {
+            return Err(anyhow::anyhow!("Please provide both .simf file path and witness file path"));
+        }
+        Ok(())
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, input: ReclaimInput, progress: &dyn Fn(String)) -> Result<Finalized> {
+        let template = workflow.template(&state.template_id)?;
+        let (_, lock) = template
+            .refund(&state.params)
+            .context("This contract was not generated with a promoter refund path")?;
+        let contract = state.contract()?;
+        let funding = state.funding()?;
+        let destination = input.destination.trim().to_string();
+
+        // The refund branch only executes once the lock has expired, otherwise the
+        // node rejects the transaction as non-final
+        progress("Checking whether the refund lock has expired...".to_string());
+        let chain_info = workflow
+            .rpc
+            .get_blockchain_info()
+            .await
+            .context("Failed to get blockchain info")?;
+        let tip_height = chain_info["blocks"].as_u64().unwrap_or(0);
+        let median_time = chain_info["mediantime"].as_u64().unwrap_or(0);
+        if !lock.is_expired(tip_height, median_time) {
+            return Err(anyhow::anyhow!(
+                "Vouchers have not expired yet.\n\nRefund unlocks at: {}\nCurrent tip height: {}\nCurrent median time past: {}\n\nTry again once the lock has passed.",
+                lock, tip_height, median_time
+            ));
+        }
+
+        let utxo = workflow
+            .rpc
+            .get_utxo_info(&funding.txid, funding.vout)
+            .await
+            .context("Failed to look up voucher UTXO")?;
+
+        // The refund branch does not enforce the covenant structure, so a single
+        // sweep output plus the fee output is enough
+        if utxo.value_sats <= MIN_FEE_SATS {
+            return Err(anyhow::anyhow!(
+                "UTXO value {} sats does not cover the {} sats fee",
+                utxo.value_sats, MIN_FEE_SATS
+            ));
+        }
+        let sweep_sats = utxo.value_sats - MIN_FEE_SATS;
+
+        progress(format!("Creating refund PSET with nLockTime {} (input sequence 0xfffffffe)...", lock.locktime()));
+        let base_pset = workflow
+            .rpc
+            .create_pset_with_locktime(
+                &[(funding.txid.clone(), funding.vout)],
+                &[(destination.clone(), btc(sweep_sats))],
+                Some(btc(MIN_FEE_SATS)),
+                Some(lock.locktime()),
+            )
+            .await
+            .context("Failed to create refund PSET")?;
+        let pset = update_contract_input(workflow, state, &base_pset, &utxo)?;
+
+        progress("Signing with the promoter key...".to_string());
+        let signature = sighash::sign_input(workflow.hal.as_ref(), input.mode, &pset, 0, &contract.cmr, &contract.genesis_hash, &input.privkey)
+            .context("Failed to sign with the promoter key")?;
+        let witness = spend_witness(template, &state.params, &SpendPath::Refund(&signature), None)
+            .context("Failed to build refund witness")?;
+
+        let finalized = compile_and_finalize(workflow, state, &pset, &witness, &input.witness_path, progress)
+            .await
+            .map_err(|e| anyhow::anyhow!(
+                "Failed to finalize refund PSET: {}\n\nCheck that the promoter private key matches the promoter public key in the contract and that the lock in the form matches the one compiled into the .simf file.",
+                e
+            ))?;
+        let tx_hex = workflow
+            .rpc
+            .finalize_pset(&finalized)
+            .await
+            .context("Failed to finalize refund PSBT")?;
+
+        Ok(Finalized {
+            pset: finalized,
+            tx_hex,
+            kind: FinalizedKind::Refund {
+                destination,
+                sweep_sats,
+                locktime: lock.locktime(),
+            },
+            covenant: template.spend_flow.is_covenant(),
+            signers: sighash::pubkey_of(&input.privkey).into_iter().collect(),
+        })
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Finalized) {
+        state.finalized = Some(output.clone());
+        state.broadcast_txid = None;
+    }
+
+    fn audit(&self, _state: &WorkflowState, output: &Finalized) -> Vec<AuditEvent> {
+        finalized_events(output)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Broadcast
+
+/// Send the finalized transaction to the node
+pub struct Broadcast;
+
+pub struct Broadcasted {
+    pub txid: String,
+}
+
+impl fmt::Display for Broadcasted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transaction broadcast successfully!\n\nTransaction ID: {}\n\nView on explorer: https://blockstream.info/liquidtestnet/tx/{}",
+            self.txid, self.txid
+        )
+    }
+}
+
+impl Step for Broadcast {
+    type Input = ();
+    type Output = Broadcasted;
+    const ID: StepId = StepId::Broadcast;
+
+    fn validate<R: NodeBackend, H: HalBackend>(&self, _workflow: &Workflow<R, H>, state: &WorkflowState, _input: &()) -> Result<()> {
+        state
+            .finalized
+            .as_ref()
+            .map(|_| ())
+            .context("Please finalize the transaction first")
+    }
+
+    async fn run<R: NodeBackend, H: HalBackend>(&self, workflow: &Workflow<R, H>, state: &WorkflowState, _input: (), _progress: &dyn Fn(String)) -> Result<Broadcasted> {
+        let finalized = state.finalized.as_ref().context("Please finalize the transaction first")?;
+        let template = workflow.template(&state.template_id).ok();
+        let txid = workflow
+            .rpc
+            .send_raw_transaction(&finalized.tx_hex)
+            .await
+            .map_err(|e| broadcast_guidance(template, e))?;
+        Ok(Broadcasted { txid })
+    }
+
+    fn audit(&self, _state: &WorkflowState, output: &Broadcasted) -> Vec<AuditEvent> {
+        vec![AuditEvent::Broadcast { txid: output.txid.clone() }]
+    }
+
+    fn apply(&self, state: &mut WorkflowState, output: &Broadcasted) {
+        state.broadcast_txid = Some(output.txid.clone());
+    }
+}
+
+/// Explain a rejected broadcast in terms of the template
+///
+/// A covenant spend whose script check fails at the node breaks the covenant's rules
+/// or signatures; it is reported as an [`AppError::CovenantViolation`] listing the
+/// outputs the covenant enforces, with the node's rejection as its cause.
+fn broadcast_guidance(template: Option<&CovenantTemplate>, error: anyhow::Error) -> anyhow::Error {
+    let script_failed = matches!(error::find(&error), Some(AppError::NodeRejected { reason, .. }) if error::is_script_failure(reason));
+    match template.filter(|t| t.spend_flow.is_covenant()) {
+        Some(template) if script_failed => error.context(AppError::CovenantViolation {
+            detail: "Failed to broadcast transaction: the covenant's script check failed".to_string(),
+            required_outputs: required_outputs(template),
+        }),
+        _ => error.context("Failed to broadcast transaction"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn funded_state() -> WorkflowState {
+        let mut state = WorkflowState::new("p2ms", TemplateValues::new());
+        state.program = "program".to_string();
+        state.contract = Some(ContractInfo {
+            cmr: "cmr".to_string(),
+            address: "address".to_string(),
+            program: "program".to_string(),
+            genesis_hash: sighash::default_genesis_hash(),
+        });
+        state.funding = Some(Funding {
+            txid: "00".repeat(32),
+            vout: 0,
+            amount: "0.001".to_string(),
+        });
+        state
+    }
+
+    #[test]
+    fn next_step_follows_outputs() {
+        let mut state = WorkflowState::new("p2ms", TemplateValues::new());
+        assert_eq!(state.next_step(), Some(StepId::Generate));
+
+        state = funded_state();
+        assert_eq!(state.next_step(), Some(StepId::CreatePset));
+
+        state.finalized = Some(Finalized {
+            pset: String::new(),
+            tx_hex: "00".to_string(),
+            kind: FinalizedKind::Spend,
+            covenant: false,
+            signers: Vec::new(),
+        });
+        assert_eq!(state.next_step(), Some(StepId::Broadcast));
+        state.broadcast_txid = Some("11".repeat(32));
+        assert_eq!(state.next_step(), None);
+    }
+
+    #[test]
+    fn new_contract_clears_downstream_outputs() {
+        let mut state = funded_state();
+        state.verified = Some(true);
+        state.clear_from_contract();
+        assert!(state.contract.is_none());
+        assert!(state.funding.is_none());
+        assert_eq!(state.verified, None);
+        assert_eq!(state.program, "program");
+    }
+
+    #[test]
+    fn state_survives_a_restart() {
+        let path = std::env::temp_dir().join(format!("partnerfy-workflow-{}.json", std::process::id()));
+        let store = Store::new(path.clone());
+        store.save_workflow("p2ms", &funded_state()).unwrap();
+
+        let resumed = Store::new(path.clone()).load_workflow("p2ms").unwrap().unwrap();
+        assert_eq!(resumed.contract, funded_state().contract);
+        assert_eq!(resumed.funding, funded_state().funding);
+        assert!(store.load_workflow("voucher").unwrap().is_none());
+        let _ = std::fs::remove_file(path);
+    }
+
+    // End-to-end runs against the in-memory node and tools; no daemon needed
+
+    use crate::app_core::fakes::{FakeHal, FakeNode};
+    use crate::app_core::taproot::{self, HAL_INFO_INTERNAL_KEY};
+    use elements::AddressParams;
+
+    const FUNDED_SATS: u64 = 100_000;
+
+    fn key(n: u8) -> String {
+        format!("{:064x}", n)
+    }
+
+    /// Workflow over the fakes with its files in a fresh temp dir
+    fn fake_workflow(name: &str) -> (Workflow<FakeNode, FakeHal>, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("partnerfy-e2e-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let workflow = Workflow::new(
+            name,
+            Arc::new(FakeNode::new()),
+            Arc::new(FakeHal::new()),
+            Arc::new(TemplateRegistry::builtin()),
+            Arc::new(Store::new(dir.join("store.json"))),
+        );
+        (workflow, dir)
+    }
+
+    fn destination() -> String {
+        taproot::contract_address(&"11".repeat(32), HAL_INFO_INTERNAL_KEY, &AddressParams::LIQUID_TESTNET)
+            .unwrap()
+            .to_string()
+    }
+
+    /// Generate, compile and create the contract, then fund it from the fake node
+    async fn funded(workflow: &Workflow<FakeNode, FakeHal>, dir: &Path, template_id: &str, params: TemplateValues) -> WorkflowState {
+        let progress = |_: String| {};
+        let mut state = WorkflowState::new(template_id, params.clone());
+        let simf_path = dir.join("contract.simf").display().to_string();
+        let generate = GenerateInput { template_id: template_id.to_string(), params: params.clone(), simf_path: simf_path.clone() };
+        workflow.run(Generate, &mut state, generate, &progress).await.unwrap();
+        workflow.run(Compile, &mut state, CompileInput { simf_path: simf_path.clone() }, &progress).await.unwrap();
+        let create = CreateContractInput {
+            template_id: template_id.to_string(),
+            params,
+            simf_path,
+            program: state.program.clone(),
+            internal_key: state.internal_key.clone(),
+            oracle_url: String::new(),
+        };
+        workflow.run(CreateContract, &mut state, create, &progress).await.unwrap();
+
+        let (txid, vout) = workflow.rpc.fund(&state.contract().unwrap().address, FUNDED_SATS).unwrap();
+        state.funding = Some(Funding { txid, vout, amount: format!("{:.8}", btc(FUNDED_SATS)) });
+        state
+    }
+
+    fn sign_input(dir: &Path, privkeys: Vec<String>, mode: SigningMode) -> SignInput {
+        SignInput {
+            privkeys,
+            mode,
+            witness_path: dir.join("contract.wit").display().to_string(),
+            cosigned_pset: String::new(),
+        }
+    }
+
+    fn p2ms_params() -> TemplateValues {
+        TemplateValues::from([
+            ("participant_1".to_string(), sighash::pubkey_of(&key(1)).unwrap()),
+            ("participant_2".to_string(), sighash::pubkey_of(&key(2)).unwrap()),
+            ("participant_3".to_string(), sighash::pubkey_of(&key(3)).unwrap()),
+            ("threshold".to_string(), "2".to_string()),
+        ])
+    }
+
+    fn voucher_params() -> TemplateValues {
+        let mut params = p2ms_params();
+        params.insert("max_payment".to_string(), "0.0005".to_string());
+        params
+    }
+
+    #[tokio::test]
+    async fn p2ms_spend_runs_end_to_end() {
+        let (workflow, dir) = fake_workflow("p2ms");
+        let progress = |_: String| {};
+        let mut state = funded(&workflow, &dir, "p2ms", p2ms_params()).await;
+        let funding = state.funding.clone().unwrap();
+
+        let pset = CreatePsetInput { destination: destination(), amount: 0.0009 };
+        let spend = workflow.run(CreatePset, &mut state, pset, &progress).await.unwrap();
+        assert_eq!(spend.fee_sats, 10_000);
+
+        let input = sign_input(&dir, vec![key(1), key(2), String::new()], SigningMode::Hal);
+        let finalized = workflow.run(Sign, &mut state, input, &progress).await.unwrap();
+        assert_eq!(finalized.signers, vec![sighash::pubkey_of(&key(1)).unwrap(), sighash::pubkey_of(&key(2)).unwrap()]);
+        let witness = std::fs::read_to_string(dir.join("contract.wit")).unwrap();
+        assert_eq!(witness.matches("Some(0x").count(), 2);
+
+        let broadcast = workflow.run(Broadcast, &mut state, (), &progress).await.unwrap();
+        let accepted = workflow.rpc.accepted();
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].txid().to_string(), broadcast.txid);
+        assert!(!workflow.rpc.is_unspent(&funding.txid, funding.vout));
+        assert!(workflow.rpc.is_unspent(&broadcast.txid, 0));
+        assert_eq!(state.next_step(), None);
+        assert!(workflow.hal.calls().contains(&"sighash_and_sign"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn sign_refuses_more_keys_than_the_threshold() {
+        let (workflow, dir) = fake_workflow("surplus");
+        let progress = |_: String| {};
+        let mut state = funded(&workflow, &dir, "p2ms", p2ms_params()).await;
+        let pset = CreatePsetInput { destination: destination(), amount: 0.0009 };
+        workflow.run(CreatePset, &mut state, pset, &progress).await.unwrap();
+
+        let input = sign_input(&dir, vec![key(1), key(2), key(3)], SigningMode::Hal);
+        let err = workflow.run(Sign, &mut state, input, &progress).await.unwrap_err();
+        assert!(err.to_string().contains("takes exactly 2"), "{}", err);
+        assert!(!workflow.hal.calls().contains(&"sighash_and_sign"));
+        assert!(state.finalized.is_none());
+
+        let input = sign_input(&dir, vec![key(1), String::new(), key(3)], SigningMode::Hal);
+        workflow.run(Sign, &mut state, input, &progress).await.unwrap();
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn voucher_redemption_keeps_change_in_the_covenant() {
+        let (workflow, dir) = fake_workflow("voucher");
+        let progress = |_: String| {};
+        let mut state = funded(&workflow, &dir, "cov_p2ms", voucher_params()).await;
+        let address = state.contract().unwrap().address.clone();
+
+        // Above the cap is refused before any PSET is built
+        let over = CreatePsetInput { destination: destination(), amount: 0.0006 };
+        let err = workflow.run(CreatePset, &mut state, over, &progress).await.unwrap_err();
+        assert!(matches!(error::find(&err), Some(AppError::CovenantViolation { .. })));
+
+        let pset = CreatePsetInput { destination: destination(), amount: 0.0004 };
+        let spend = workflow.run(CreatePset, &mut state, pset, &progress).await.unwrap();
+        assert_eq!(spend.change_sats, Some(FUNDED_SATS - 40_000 - MIN_FEE_SATS));
+
+        let input = sign_input(&dir, vec![String::new(), key(2), key(3)], SigningMode::Native);
+        workflow.run(Sign, &mut state, input, &progress).await.unwrap();
+        assert!(!workflow.hal.calls().contains(&"sighash_and_sign"));
+
+        // A script failure at the node is reported as a covenant violation
+        workflow.rpc.reject_next(-26, "non-mandatory-script-verify-flag (Assertion failed)");
+        let Err(err) = workflow.run(Broadcast, &mut state, (), &progress).await else {
+            panic!("the node accepted a rejected transaction");
+        };
+        assert!(matches!(error::find(&err), Some(AppError::CovenantViolation { .. })));
+        assert!(state.broadcast_txid.is_none());
+
+        let broadcast = workflow.run(Broadcast, &mut state, (), &progress).await.unwrap();
+        let change = workflow.rpc.get_txout(&broadcast.txid, 1).await.unwrap();
+        assert_eq!(change["scriptPubKey"]["address"], address.as_str());
+        assert_eq!(change["value"], btc(FUNDED_SATS - 40_000 - MIN_FEE_SATS));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn voucher_refund_waits_for_the_lock() {
+        let (workflow, dir) = fake_workflow("refund");
+        let progress = |_: String| {};
+        let mut params = voucher_params();
+        params.insert("promoter_pubkey".to_string(), sighash::pubkey_of(&key(4)).unwrap());
+        params.insert("refund_lock_height".to_string(), "1100".to_string());
+        let mut state = funded(&workflow, &dir, "cov_p2ms", params).await;
+
+        let reclaim = || ReclaimInput {
+            destination: destination(),
+            privkey: key(4),
+            mode: SigningMode::Native,
+            witness_path: dir.join("refund.wit").display().to_string(),
+        };
+        let err = workflow.run(Reclaim, &mut state, reclaim(), &progress).await.unwrap_err();
+        assert!(err.to_string().contains("not expired"));
+
+        workflow.rpc.set_tip(1_100, 1_700_000_000);
+        workflow.run(Reclaim, &mut state, reclaim(), &progress).await.unwrap();
+        let broadcast = workflow.run(Broadcast, &mut state, (), &progress).await.unwrap();
+        assert_eq!(workflow.rpc.accepted()[0].lock_time.to_consensus_u32(), 1_100);
+        assert!(workflow.rpc.is_unspent(&broadcast.txid, 0));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,335 @@
+//! Local oracle signer service
+//!
+//! A stand-in for a partner's point-of-sale oracle. It signs redemption attestations
+//! (see `app_core::oracle`) so oracle-attested vouchers can be redeemed.
+//!
+//! Usage:
+//!   oracle_signer --key <secret key hex> (--invoices invoices.txt | --allow-any-invoice) [--listen 127.0.0.1:8787]
+//!
+//! The key can also be given with `PARTNERFY_ORACLE_KEY`. The `--invoices` file holds one sale
+//! per line, `invoice_id,amount_sats,payee`, where the payee is an address or a hex
+//! scriptPubKey. A request is attested only if its payment output matches the sale, and each
+//! invoice only once: attested IDs are appended to `<invoices file>.used` and stay used across
+//! restarts. `--allow-any-invoice` attests every request and is meant for local testing only.
+//!
+//! Endpoints:
+//! - `GET /pubkey` -> `{"pubkey": "<x-only hex>"}`
+//! - `POST /attest` with `{"invoice_id": "...", "output_script_pubkey": "<hex>", "amount_sats": 1000,
+//!   "txid": "<voucher txid>", "vout": 0}` -> attestation JSON
+
+use anyhow::{Context, Result};
+use elements::Address;
+use partnerfy_app::app_core::csv;
+use partnerfy_app::app_core::http;
+use partnerfy_app::app_core::oracle::{AttestationRequest, OracleSigner};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+struct Oracle {
+    signer: OracleSigner,
+    /// `None` with `--allow-any-invoice`
+    invoices: Option<Invoices>,
+}
+
+/// A sale the oracle may attest once
+#[derive(Debug, PartialEq)]
+struct Sale {
+    amount_sats: u64,
+    /// scriptPubKey the payment must go to, lowercase hex
+    script_pubkey: String,
+}
+
+/// The `--invoices` file and the invoices already attested
+struct Invoices {
+    sales: HashMap<String, Sale>,
+    used: Mutex<HashSet<String>>,
+    used_path: PathBuf,
+}
+
+impl Invoices {
+    /// Read `invoice_id,amount_sats,payee` lines, and the used IDs from `<path>.used`
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read invoice list {}", path.display()))?;
+        let mut sales = HashMap::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, sale) = parse_sale(line).with_context(|| format!("{} line {}", path.display(), i + 1))?;
+            sales.insert(id, sale);
+        }
+
+        let used_path = PathBuf::from(format!("{}.used", path.display()));
+        let used = match std::fs::read_to_string(&used_path) {
+            Ok(content) => content.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", used_path.display())),
+        };
+        Ok(Self { sales, used: Mutex::new(used), used_path })
+    }
+
+    /// Remember an attested invoice, on disk first so a restart cannot attest it again
+    fn mark_used(&self, used: &mut HashSet<String>, invoice_id: &str) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.used_path)
+            .with_context(|| format!("Failed to open {}", self.used_path.display()))?;
+        writeln!(file, "{}", invoice_id)?;
+        file.sync_all()?;
+        used.insert(invoice_id.to_string());
+        Ok(())
+    }
+}
+
+fn parse_sale(line: &str) -> Result<(String, Sale)> {
+    let [id, amount, payee] = <[String; 3]>::try_from(csv::cells(line))
+        .map_err(|_| anyhow::anyhow!("Expected invoice_id,amount_sats,payee"))?;
+    if id.is_empty() {
+        return Err(anyhow::anyhow!("Missing invoice ID"));
+    }
+    let amount_sats = amount.parse().with_context(|| format!("Invalid amount_sats '{}'", amount))?;
+    let script_pubkey = match hex::decode(&payee) {
+        Ok(script) if !script.is_empty() => hex::encode(script),
+        _ => {
+            let address = Address::from_str(&payee)
+                .map_err(|e| anyhow::anyhow!("Payee '{}' is neither an address nor a hex script: {}", payee, e))?;
+            hex::encode(address.script_pubkey().as_bytes())
+        }
+    };
+    Ok((id, Sale { amount_sats, script_pubkey }))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut key = std::env::var("PARTNERFY_ORACLE_KEY").ok();
+    let mut listen = "127.0.0.1:8787".to_string();
+    let mut invoices_path: Option<String> = None;
+    let mut allow_any_invoice = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" => key = args.next(),
+            "--listen" => listen = args.next().context("--listen requires an address")?,
+            "--invoices" => invoices_path = Some(args.next().context("--invoices requires a file")?),
+            "--allow-any-invoice" => allow_any_invoice = true,
+            "-h" | "--help" => {
+                println!("Usage: oracle_signer --key <secret key hex> (--invoices invoices.txt | --allow-any-invoice) [--listen 127.0.0.1:8787]");
+                return Ok(());
+            }
+            other => return Err(anyhow::anyhow!("Unknown argument: {}", other)),
+        }
+    }
+
+    let key = key.context("Oracle secret key required (--key or PARTNERFY_ORACLE_KEY)")?;
+    let signer = OracleSigner::new(&key)?;
+
+    let invoices = match (invoices_path, allow_any_invoice) {
+        (Some(_), true) => return Err(anyhow::anyhow!("Use either --invoices or --allow-any-invoice, not both")),
+        (None, false) => {
+            return Err(anyhow::anyhow!(
+                "Give the invoices to attest with --invoices <file>, or pass --allow-any-invoice to attest every invoice ID (testing only)"
+            ))
+        }
+        (Some(path), false) => Some(Invoices::load(Path::new(&path))?),
+        (None, true) => None,
+    };
+
+    let listener = TcpListener::bind(&listen)
+        .await
+        .with_context(|| format!("Failed to bind {}", listen))?;
+    println!("Oracle signer listening on http://{}", listen);
+    println!("Oracle public key: {}", signer.pubkey_hex());
+    match &invoices {
+        Some(invoices) => println!(
+            "Attesting {} known invoice(s), {} already used",
+            invoices.sales.len(),
+            invoices.used.lock().unwrap().len()
+        ),
+        None => println!("WARNING: attesting any invoice ID (--allow-any-invoice); use for testing only"),
+    }
+
+    let oracle = Arc::new(Oracle { signer, invoices });
+    let permits = Arc::new(Semaphore::new(http::MAX_CONNECTIONS));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let Ok(permit) = permits.clone().try_acquire_owned() else {
+            http::refuse_busy(stream);
+            continue;
+        };
+        let oracle = oracle.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &oracle).await {
+                eprintln!("Request failed: {}", e);
+            }
+            drop(permit);
+        });
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, oracle: &Oracle) -> Result<()> {
+    let Some(request) = http::read_request(&mut stream).await? else {
+        return Ok(());
+    };
+
+    let (status, response) = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/pubkey") => (200, json!({"pubkey": oracle.signer.pubkey_hex()})),
+        ("POST", "/attest") => attest(oracle, &request.body),
+        (method, "/pubkey" | "/attest") => {
+            (405, json!({"error": format!("{} is not allowed on {}", method, request.path)}))
+        }
+        _ => (404, json!({"error": format!("No route for {} {}", request.method, request.path)})),
+    };
+
+    println!("{} {} -> {}", request.method, request.path, status);
+    http::write_response(&mut stream, status, &response).await
+}
+
+fn attest(oracle: &Oracle, body: &[u8]) -> (u16, serde_json::Value) {
+    let request: AttestationRequest = match serde_json::from_slice(body) {
+        Ok(r) => r,
+        Err(e) => return (400, json!({"error": format!("Invalid request body: {}", e)})),
+    };
+
+    let invoice_id = request.invoice_id.trim();
+    if invoice_id.is_empty() {
+        return (400, json!({"error": "invoice_id is required"}));
+    }
+
+    let Some(invoices) = &oracle.invoices else {
+        return match oracle.signer.attest(invoice_id, &request.spend) {
+            Ok(attestation) => (200, json!(attestation)),
+            Err(e) => (400, json!({"error": e.to_string()})),
+        };
+    };
+
+    let Some(sale) = invoices.sales.get(invoice_id) else {
+        return (403, json!({"error": format!("Unknown invoice: {}", invoice_id)}));
+    };
+    if request.spend.amount_sats != sale.amount_sats {
+        return (
+            403,
+            json!({"error": format!("Invoice {} is for {} sats, not {}", invoice_id, sale.amount_sats, request.spend.amount_sats)}),
+        );
+    }
+    if !request.spend.output_script_pubkey.trim().eq_ignore_ascii_case(&sale.script_pubkey) {
+        return (403, json!({"error": format!("Invoice {} is payable to a different output", invoice_id)}));
+    }
+
+    // Held from the check to the write, so two requests cannot both use the invoice
+    let mut used = invoices.used.lock().unwrap();
+    if used.contains(invoice_id) {
+        return (409, json!({"error": format!("Invoice {} was already attested", invoice_id)}));
+    }
+    let attestation = match oracle.signer.attest(invoice_id, &request.spend) {
+        Ok(attestation) => attestation,
+        Err(e) => return (400, json!({"error": e.to_string()})),
+    };
+    if let Err(e) = invoices.mark_used(&mut used, invoice_id) {
+        return (500, json!({"error": format!("Failed to record the invoice as used: {:#}", e)}));
+    }
+    (200, json!(attestation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use partnerfy_app::app_core::oracle::{AttestedSpend, OracleAttestation};
+
+    /// An oracle whose invoice file sells `spend()` as INV-1, in a fresh directory named `name`
+    fn oracle(name: &str) -> Oracle {
+        let dir = std::env::temp_dir().join(format!("partnerfy-oracle-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("invoices.txt");
+        std::fs::write(&path, format!("# invoice,amount,payee\nINV-1,40000,{}\n", spend().output_script_pubkey)).unwrap();
+        Oracle { signer: OracleSigner::new(&format!("{:064x}", 7)).unwrap(), invoices: Some(Invoices::load(&path).unwrap()) }
+    }
+
+    fn spend() -> AttestedSpend {
+        AttestedSpend {
+            output_script_pubkey: format!("5120{}", "ab".repeat(32)),
+            amount_sats: 40_000,
+            txid: "cd".repeat(32),
+            vout: 1,
+        }
+    }
+
+    fn body(invoice_id: &str) -> Vec<u8> {
+        serde_json::to_vec(&AttestationRequest { invoice_id: invoice_id.to_string(), spend: spend() }).unwrap()
+    }
+
+    #[test]
+    fn attests_known_invoices_only() {
+        let oracle = oracle("known");
+        let (status, response) = attest(&oracle, &body("INV-1"));
+        assert_eq!(status, 200);
+        let attestation: OracleAttestation = serde_json::from_value(response).unwrap();
+        attestation.verify(&oracle.signer.pubkey_hex(), &spend()).unwrap();
+        assert!(attestation.verify(&oracle.signer.pubkey_hex(), &AttestedSpend { amount_sats: 50_000, ..spend() }).is_err());
+
+        assert_eq!(attest(&oracle, &body("INV-2")).0, 403);
+        assert_eq!(attest(&oracle, &body("INV-1")).0, 409);
+        assert_eq!(attest(&oracle, &body(" ")).0, 400);
+        assert_eq!(attest(&oracle, b"{}").0, 400);
+        let bad_txid = AttestationRequest { invoice_id: "INV-1".to_string(), spend: AttestedSpend { txid: "xyz".to_string(), ..spend() } };
+        assert_eq!(attest(&self::oracle("bad-txid"), &serde_json::to_vec(&bad_txid).unwrap()).0, 400);
+    }
+
+    #[test]
+    fn rejects_spends_that_do_not_match_the_sale() {
+        let oracle = oracle("mismatch");
+        let request = |spend: AttestedSpend| {
+            serde_json::to_vec(&AttestationRequest { invoice_id: "INV-1".to_string(), spend }).unwrap()
+        };
+
+        let (status, error) = attest(&oracle, &request(AttestedSpend { amount_sats: 40_001, ..spend() }));
+        assert_eq!(status, 403);
+        assert!(error["error"].as_str().unwrap().contains("40000 sats"));
+
+        let other_payee = AttestedSpend { output_script_pubkey: format!("5120{}", "ef".repeat(32)), ..spend() };
+        assert_eq!(attest(&oracle, &request(other_payee)).0, 403);
+
+        // Neither mismatch used up the invoice
+        assert_eq!(attest(&oracle, &request(spend())).0, 200);
+    }
+
+    #[test]
+    fn attested_invoices_stay_used_after_a_restart() {
+        let oracle = oracle("replay");
+        assert_eq!(attest(&oracle, &body("INV-1")).0, 200);
+        assert_eq!(attest(&oracle, &body("INV-1")).0, 409);
+
+        let invoices_path = oracle.invoices.as_ref().unwrap().used_path.with_extension("");
+        let restarted = Oracle { invoices: Some(Invoices::load(&invoices_path).unwrap()), ..oracle };
+        assert_eq!(attest(&restarted, &body("INV-1")).0, 409);
+    }
+
+    #[test]
+    fn parses_sales_with_an_address_or_a_script() {
+        let (id, sale) = parse_sale(&format!("INV-9, 1500, {}", "AB".repeat(4))).unwrap();
+        assert_eq!((id.as_str(), sale), ("INV-9", Sale { amount_sats: 1500, script_pubkey: "abababab".to_string() }));
+
+        let address = elements::Address::from_script(
+            &elements::Script::from(hex::decode(spend().output_script_pubkey).unwrap()),
+            None,
+            &elements::AddressParams::LIQUID_TESTNET,
+        )
+        .unwrap();
+        let (_, sale) = parse_sale(&format!("INV-9,1500,{}", address)).unwrap();
+        assert_eq!(sale.script_pubkey, spend().output_script_pubkey);
+
+        assert!(parse_sale("INV-9,1500").is_err());
+        assert!(parse_sale("INV-9,lots,51").is_err());
+        assert!(parse_sale("INV-9,1500,not an address").is_err());
+    }
+}
@@ -0,0 +1,392 @@
+//! Headless Partnerfy command line
+//!
+//! Runs the contract workflow steps of the desktop app (see `app_core::workflow`) without
+//! the UI, for scripting voucher issuance and runbooks. Progress is saved in the same
+//! `~/.partnerfy/state.json` the app uses. `--workflow p2ms` or `--workflow voucher`
+//! continues where the P2MS or Voucher page left off, and contracts created here show up
+//! on the dashboard.
+//!
+//! Usage:
+//!   partnerfy [--workflow <name>] <command> [options]
+//!
+//! Every command prints one JSON object on stdout. On failure it prints
+//! `{"ok": false, "error": "..."}` and exits with status 1. Progress messages go to stderr.
+
+use anyhow::{Context, Result};
+use partnerfy_app::app_core::audit;
+use partnerfy_app::app_core::error;
+use partnerfy_app::app_core::issuance;
+use partnerfy_app::app_core::oracle::DEFAULT_ORACLE_URL;
+use partnerfy_app::app_core::redemption;
+use partnerfy_app::app_core::reports::{self, ReportFilter};
+use partnerfy_app::app_core::sighash::{self, SigningMode};
+use partnerfy_app::app_core::workflow::{self, Workflow, WorkflowState};
+use partnerfy_app::app_core::{
+    ElementsRPC, HalWrapper, InternalKey, Settings, Store, TemplateRegistry, TemplateValues,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage: partnerfy [--workflow <name>] <command> [options]
+
+Commands:
+  generate   --template <id> [--param <name>=<value>]... [--out <file.simf>]
+  compile    [--simf <file.simf>]
+  address    [--program <base64>] [--internal-key nums|random|aggregate] [--oracle <url>]
+  fund       --amount <L-BTC>
+  spend      --destination <address> --amount <L-BTC> [--invoice <id>] [--oracle <url>]
+  sign       --key <hex> [--pset <base64>] [--native]
+  finalize   [--key <hex>]... [--pset <base64>] [--witness <file.wit>] [--native]
+  broadcast
+  status
+  report     [--partner <address>] [--contract <address>] [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--unconfirmed] [--csv <file>]
+  issue      --csv <file> [--contract <address>] [--max-outputs <n>] [--fee-rate <sat/vB>] [--dry-run]
+
+The workflow defaults to \"cli\". Use \"p2ms\" or \"voucher\" to continue the desktop pages.
+`issue` funds vouchers from the node's wallet for every row of a pubkey,amount[,contract]
+CSV; --dry-run prints the batches without funding them.";
+
+/// Options of a command: `--name value` pairs (repeatable) and bare `--flag`s
+struct Options(BTreeMap<String, Vec<String>>);
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .with_context(|| format!("Unexpected argument: {}\n\n{}", arg, USAGE))?
+                .to_string();
+            let value = match args.peek() {
+                Some(next) if !next.starts_with("--") => args.next().unwrap_or_default(),
+                _ => String::new(),
+            };
+            options.entry(name).or_default().push(value);
+        }
+        Ok(Self(options))
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).and_then(|v| v.last()).map(String::as_str)
+    }
+
+    fn all(&self, name: &str) -> Vec<String> {
+        self.0.get(name).cloned().unwrap_or_default()
+    }
+
+    fn require(&self, name: &str) -> Result<&str> {
+        self.get(name)
+            .filter(|v| !v.is_empty())
+            .with_context(|| format!("--{} is required\n\n{}", name, USAGE))
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    fn mode(&self) -> SigningMode {
+        if self.flag("native") {
+            SigningMode::Native
+        } else {
+            SigningMode::Hal
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let result = run(std::env::args().skip(1).collect()).await;
+    match result {
+        Ok(output) => println!("{}", serde_json::to_string_pretty(&output).unwrap_or_default()),
+        Err(e) => {
+            let guidance = error::find(&e).map(|e| e.guidance());
+            println!("{}", json!({ "ok": false, "error": format!("{:#}", e), "guidance": guidance }));
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn run(mut args: Vec<String>) -> Result<Value> {
+    let mut name = "cli".to_string();
+    if args.first().map(String::as_str) == Some("--workflow") {
+        args.remove(0);
+        if args.is_empty() {
+            return Err(anyhow::anyhow!("--workflow requires a name"));
+        }
+        name = args.remove(0);
+    }
+    if args.is_empty() || matches!(args[0].as_str(), "-h" | "--help" | "help") {
+        println!("{}", USAGE);
+        std::process::exit(0);
+    }
+    let command = args.remove(0);
+    let options = Options::parse(args.into_iter())?;
+
+    let registry = TemplateRegistry::load(&TemplateRegistry::default_user_dir());
+    for error in &registry.load_errors {
+        eprintln!("Skipping covenant template: {}", error);
+    }
+    let flow = Workflow::new(
+        &name,
+        Arc::new(ElementsRPC::new(Settings::default())?),
+        Arc::new(HalWrapper::new(None)),
+        Arc::new(registry),
+        Arc::new(Store::default()),
+    );
+    let mut state = flow.resume()?.unwrap_or_default();
+    let progress = |message: String| eprintln!("{}", message);
+
+    let mut output = match command.as_str() {
+        "generate" => generate(&flow, &mut state, &options, &progress).await?,
+        "compile" => {
+            let simf_path = options.get("simf").unwrap_or(&state.simf_path).to_string();
+            let out = flow.run(workflow::Compile, &mut state, workflow::CompileInput { simf_path }, &progress).await?;
+            json!({ "message": out.to_string(), "simf_path": out.path, "program": out.program })
+        }
+        "address" => address(&flow, &mut state, &options, &progress).await?,
+        "fund" => {
+            let input = workflow::FundInput { amount: options.require("amount")?.to_string() };
+            let out = flow.run(workflow::Fund, &mut state, input, &progress).await?;
+            json!({ "message": out.to_string(), "address": out.address, "funding": out.funding })
+        }
+        "spend" => spend(&flow, &mut state, &options, &progress).await?,
+        "sign" => sign(&flow, &mut state, &options)?,
+        "finalize" => finalize(&flow, &mut state, &options, &progress).await?,
+        "broadcast" => {
+            let out = flow.run(workflow::Broadcast, &mut state, (), &progress).await?;
+            json!({ "message": out.to_string(), "txid": out.txid })
+        }
+        "issue" => issue(&flow, &options, &progress).await?,
+        "report" => report(&flow, &options).await?,
+        "status" => json!({
+            "next_step": state.next_step().map(|s| s.label()),
+            "state": state,
+        }),
+        other => return Err(anyhow::anyhow!("Unknown command: {}\n\n{}", other, USAGE)),
+    };
+
+    if let Value::Object(map) = &mut output {
+        map.insert("ok".to_string(), json!(true));
+        map.insert("workflow".to_string(), json!(name));
+        map.insert("command".to_string(), json!(command));
+    }
+    Ok(output)
+}
+
+async fn generate(flow: &Workflow, state: &mut WorkflowState, options: &Options, progress: &dyn Fn(String)) -> Result<Value> {
+    let template_id = options.require("template")?.to_string();
+    let template = flow.template(&template_id)?;
+    let mut params: TemplateValues = template.default_values();
+    for pair in options.all("param") {
+        let (key, value) = pair
+            .split_once('=')
+            .with_context(|| format!("--param expects <name>=<value>, got '{}'", pair))?;
+        params.insert(key.to_string(), value.to_string());
+    }
+    let params = template.validate(&params)?;
+    let simf_path = match options.get("out") {
+        Some(path) if !path.is_empty() => path.to_string(),
+        _ => {
+            let dir = Settings::data_dir().join("workflows");
+            std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+            dir.join(format!("{}.simf", flow.name)).to_string_lossy().to_string()
+        }
+    };
+
+    if state.template_id != template_id {
+        *state = WorkflowState::new(&template_id, params.clone());
+    }
+    let input = workflow::GenerateInput { template_id, params: params.clone(), simf_path };
+    let out = flow.run(workflow::Generate, state, input, progress).await?;
+    Ok(json!({ "message": out.to_string(), "simf_path": out.path, "params": params }))
+}
+
+async fn address(flow: &Workflow, state: &mut WorkflowState, options: &Options, progress: &dyn Fn(String)) -> Result<Value> {
+    let template = flow.template(&state.template_id)?;
+    let internal_key = match options.get("internal-key").unwrap_or("") {
+        "" => state.internal_key.clone(),
+        "nums" => InternalKey::Nums,
+        "random" => InternalKey::random_unspendable()?,
+        "aggregate" => InternalKey::aggregate(&template.signer_pubkeys(&state.params))?,
+        other => {
+            return Err(anyhow::anyhow!(
+                "Unknown internal key '{}'\n\nUse nums, random or aggregate.",
+                other
+            ))
+        }
+    };
+    let input = workflow::CreateContractInput {
+        template_id: state.template_id.clone(),
+        params: state.params.clone(),
+        simf_path: state.simf_path.clone(),
+        program: options.get("program").unwrap_or(&state.program).trim().to_string(),
+        internal_key,
+        oracle_url: match template.oracle_pubkey(&state.params) {
+            Some(_) => options.get("oracle").unwrap_or(DEFAULT_ORACLE_URL).to_string(),
+            None => String::new(),
+        },
+    };
+    let out = flow.run(workflow::CreateContract, state, input, progress).await?;
+    Ok(json!({
+        "message": out.to_string(),
+        "contract": out.contract,
+        "hal_mismatches": out.hal_mismatches,
+    }))
+}
+
+async fn spend(flow: &Workflow, state: &mut WorkflowState, options: &Options, progress: &dyn Fn(String)) -> Result<Value> {
+    let amount: f64 = options
+        .require("amount")?
+        .parse()
+        .context("--amount must be a number of L-BTC")?;
+    let input = workflow::CreatePsetInput {
+        destination: options.require("destination")?.to_string(),
+        amount,
+    };
+    let out = flow.run(workflow::CreatePset, state, input, progress).await?;
+
+    let template = flow.template(&state.template_id)?;
+    let mut attestation = None;
+    if template.oracle_pubkey(&state.params).is_some() {
+        let input = workflow::AttestInput {
+            oracle_url: options.get("oracle").unwrap_or(DEFAULT_ORACLE_URL).to_string(),
+            invoice_id: options
+                .require("invoice")
+                .context("This covenant needs an oracle attestation for the partner's invoice")?
+                .to_string(),
+        };
+        attestation = Some(flow.run(workflow::Attest, state, input, progress).await?.attestation);
+    }
+    Ok(json!({ "message": out.to_string(), "spend": out, "attestation": attestation }))
+}
+
+/// Add one signer's signature to the PSET without finalizing, so keys held by different
+/// people can sign one after the other
+fn sign(flow: &Workflow, state: &mut WorkflowState, options: &Options) -> Result<Value> {
+    let privkey = options.require("key")?;
+    let contract = state.contract.clone().context("Please create the contract address first")?;
+    let spend = state.spend.as_mut().context("Please create the PSET first (partnerfy spend)")?;
+    let template = flow.registry.get(&state.template_id).context("Unknown covenant template")?;
+    let signers = template.signers(&state.params);
+
+    let pset = match options.get("pset") {
+        Some(pset) if !pset.is_empty() => {
+            if !redemption::same_transaction(&spend.pset, pset)? {
+                return Err(anyhow::anyhow!(
+                    "--pset is for a different transaction than this workflow's PSET"
+                ));
+            }
+            pset.to_string()
+        }
+        _ => spend.pset.clone(),
+    };
+    let (signed, signature) = redemption::co_sign(flow.hal.as_ref(), options.mode(), &pset, &contract.cmr, &contract.genesis_hash, &signers, privkey)?;
+    spend.pset = signed.clone();
+    let pubkey = sighash::pubkey_of(privkey)?;
+    audit::record(&flow.store, &flow.name, audit::signed(&signed, vec![pubkey.clone()]));
+    let slot = signers.pubkeys.iter().position(|s| *s == pubkey).map(|i| i + 1);
+    let collected = redemption::signatures(&signed, &contract.cmr)?.len();
+    flow.save(state)?;
+
+    Ok(json!({
+        "message": format!("Signed as signer {}; {} of {} signatures collected", slot.unwrap_or(0), collected, signers.threshold),
+        "pubkey": pubkey,
+        "slot": slot,
+        "signature": signature,
+        "pset": signed,
+    }))
+}
+
+async fn finalize(flow: &Workflow, state: &mut WorkflowState, options: &Options, progress: &dyn Fn(String)) -> Result<Value> {
+    let template = flow.template(&state.template_id)?;
+    let signers = template.signer_pubkeys(&state.params);
+
+    // Keys go into the slot of the signer they belong to
+    let mut privkeys = vec![String::new(); signers.len()];
+    for privkey in options.all("key").into_iter().filter(|k| !k.is_empty()) {
+        let pubkey = sighash::pubkey_of(&privkey)?;
+        let slot = signers
+            .iter()
+            .position(|s| *s == pubkey)
+            .with_context(|| format!("Key for {} is not one of the contract's signers", pubkey))?;
+        privkeys[slot] = privkey;
+    }
+    let witness_path = match options.get("witness") {
+        Some(path) if !path.is_empty() => path.to_string(),
+        _ if !state.witness_path.is_empty() => state.witness_path.clone(),
+        _ => std::path::Path::new(&state.simf_path).with_extension("wit").to_string_lossy().to_string(),
+    };
+    let input = workflow::SignInput {
+        privkeys,
+        mode: options.mode(),
+        witness_path,
+        cosigned_pset: options.get("pset").unwrap_or_default().to_string(),
+    };
+    let out = flow.run(workflow::Sign, state, input, progress).await?;
+    Ok(json!({ "message": out.to_string(), "tx_hex": out.tx_hex, "pset": out.pset }))
+}
+
+async fn issue(flow: &Workflow, options: &Options, progress: &dyn Fn(String)) -> Result<Value> {
+    let path = options.require("csv")?;
+    let csv = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let max_outputs = match options.get("max-outputs") {
+        Some(n) => n.parse().with_context(|| format!("--max-outputs expects a whole number, got '{}'", n))?,
+        None => issuance::DEFAULT_MAX_OUTPUTS,
+    };
+    let fee_rate = match options.get("fee-rate") {
+        Some(rate) => rate.parse().with_context(|| format!("--fee-rate expects sat/vB, got '{}'", rate))?,
+        None => issuance::DEFAULT_FEE_RATE,
+    };
+
+    let rows = issuance::parse_csv(&csv)?;
+    let allocations = issuance::allocate(&flow.store.load()?, &rows, options.get("contract").filter(|c| !c.is_empty()))?;
+    let batches = issuance::plan(allocations, max_outputs, fee_rate)?;
+    let required_sats = issuance::required_sats(&batches);
+    if options.flag("dry-run") {
+        return Ok(json!({ "batches": batches, "required_sats": required_sats }));
+    }
+
+    let mut issued = Vec::new();
+    for batch in &batches {
+        match issuance::issue_batch(&flow.rpc, &flow.store, batch, fee_rate, progress).await {
+            Ok(result) => issued.push(result),
+            Err(e) => {
+                return Err(e.context(format!(
+                    "Batch {} of {} failed; {} batch(es) were broadcast and registered: {}",
+                    batch.index + 1,
+                    batches.len(),
+                    issued.len(),
+                    serde_json::to_string(&issued).unwrap_or_default()
+                )))
+            }
+        }
+    }
+    let vouchers: usize = issued.iter().map(|b| b.vouchers.len()).sum();
+    Ok(json!({ "message": format!("Issued {} vouchers in {} transaction(s)", vouchers, issued.len()), "issued": issued }))
+}
+
+async fn report(flow: &Workflow, options: &Options) -> Result<Value> {
+    let date = |name: &str| -> Result<Option<u64>> {
+        match options.get(name).filter(|v| !v.is_empty()) {
+            Some(value) => audit::parse_date(value)
+                .map(Some)
+                .with_context(|| format!("--{} expects a YYYY-MM-DD date, got '{}'", name, value)),
+            None => Ok(None),
+        }
+    };
+    let filter = ReportFilter {
+        partner: options.get("partner").filter(|v| !v.is_empty()).map(str::to_string),
+        contract: options.get("contract").filter(|v| !v.is_empty()).map(str::to_string),
+        from: date("from")?,
+        // Through the end of the "to" day
+        to: date("to")?.map(|t| t + 86_399),
+        confirmed_only: !options.flag("unconfirmed"),
+    };
+    let report = reports::load(&flow.rpc, &flow.store.load()?, filter).await;
+    if let Some(path) = options.get("csv").filter(|p| !p.is_empty()) {
+        std::fs::write(path, report.to_csv()).with_context(|| format!("Failed to write {}", path))?;
+    }
+    Ok(json!({ "report": report }))
+}
@@ -0,0 +1,107 @@
+//! Partnerfy REST API server
+//!
+//! Serves `app_core::api` over HTTP so partner point-of-sale systems can create and check
+//! redemptions without the desktop app. It shares `~/.partnerfy/state.json` with the app.
+//!
+//! Usage:
+//!   partnerfy_api [--listen 127.0.0.1:8790] (--token <bearer token> | --token-file <path>)
+//!
+//! The token can also be given with `PARTNERFY_API_TOKEN`. It is required, must be at least
+//! 16 characters, and every request must carry `Authorization: Bearer <token>`. Put the
+//! server behind a TLS reverse proxy before exposing it beyond localhost.
+
+use anyhow::{Context, Result};
+use partnerfy_app::app_core::api::Api;
+use partnerfy_app::app_core::http;
+use partnerfy_app::app_core::{ElementsRPC, HalWrapper, Settings, Store, TemplateRegistry, Workflow};
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+
+const USAGE: &str =
+    "Usage: partnerfy_api [--listen 127.0.0.1:8790] (--token <bearer token> | --token-file <path>)";
+
+/// Shortest bearer token the server accepts
+const MIN_TOKEN_LEN: usize = 16;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let mut token = std::env::var("PARTNERFY_API_TOKEN").ok().filter(|t| !t.is_empty());
+    let mut listen = "127.0.0.1:8790".to_string();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = args.next().context("--listen requires an address")?,
+            "--token" => token = Some(args.next().context("--token requires a value")?),
+            "--token-file" => {
+                let path = args.next().context("--token-file requires a path")?;
+                let contents = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read token file {}", path))?;
+                token = Some(contents.trim().to_string());
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            other => return Err(anyhow::anyhow!("Unknown argument: {}", other)),
+        }
+    }
+
+    let token = token.context(format!("A bearer token is required\n{}", USAGE))?;
+    if token.len() < MIN_TOKEN_LEN {
+        return Err(anyhow::anyhow!("The bearer token must be at least {} characters", MIN_TOKEN_LEN));
+    }
+
+    let registry = TemplateRegistry::load(&TemplateRegistry::default_user_dir());
+    for error in &registry.load_errors {
+        eprintln!("Skipping covenant template: {}", error);
+    }
+    let base = Workflow::new(
+        "api",
+        Arc::new(ElementsRPC::new(Settings::default())?),
+        Arc::new(HalWrapper::new(None)),
+        Arc::new(registry),
+        Arc::new(Store::default()),
+    );
+
+    let listener = TcpListener::bind(&listen)
+        .await
+        .with_context(|| format!("Failed to bind {}", listen))?;
+    println!("Partnerfy API listening on http://{}", listen);
+
+    // Workflow steps are not `Send`, so connections are served on one thread
+    let api = Rc::new(Api::new(base, token));
+    let permits = Arc::new(Semaphore::new(http::MAX_CONNECTIONS));
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async move {
+            loop {
+                let (stream, _) = listener.accept().await?;
+                let Ok(permit) = permits.clone().try_acquire_owned() else {
+                    http::refuse_busy(stream);
+                    continue;
+                };
+                let api = api.clone();
+                tokio::task::spawn_local(async move {
+                    if let Err(e) = handle_connection(stream, &api).await {
+                        eprintln!("Request failed: {}", e);
+                    }
+                    drop(permit);
+                });
+            }
+        })
+        .await
+}
+
+async fn handle_connection(mut stream: TcpStream, api: &Api) -> Result<()> {
+    let Some(request) = http::read_request(&mut stream).await? else {
+        return Ok(());
+    };
+    let (status, response) = api.handle(&request).await;
+    println!("{} {} -> {}", request.method, request.path, status);
+    http::write_response(&mut stream, status, &response).await
+}
@@ -0,0 +1,850 @@
+use crate::app_core::error;
+use crate::app_core::oracle::{OracleClient, DEFAULT_ORACLE_URL};
+use crate::app_core::qr::{self, PaymentUri};
+use crate::app_core::sighash::SigningMode;
+use crate::app_core::workflow::{self, FinalizedKind, Step, StepId, Workflow, WorkflowState};
+use crate::app_core::{ElementsRPC, HalWrapper, InternalKey, Store, TemplateRegistry, TemplateValues};
+use crate::Route;
+use crate::components::{InternalKeyPicker, PartnerAddresses, QrCode, QrImport, SigningModeSelect, TemplateForm, TemplateSelect};
+use dioxus::prelude::*;
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// Signals a running step reports into
+#[derive(Clone, Copy)]
+struct StepRunner {
+    state: Signal<WorkflowState>,
+    status: Signal<String>,
+    loading: Signal<bool>,
+}
+
+impl StepRunner {
+    /// Run a step against a copy of the state and publish the result
+    fn run<S>(self, workflow: Workflow, step: S, input: S::Input, then: impl FnOnce(&S::Output) + 'static)
+    where
+        S: Step + 'static,
+        S::Input: 'static,
+        S::Output: Display,
+    {
+        let StepRunner { mut state, mut status, mut loading } = self;
+        spawn(async move {
+            loading.set(true);
+            status.set(format!("{}...", S::ID.label()));
+            let mut current = state.read().clone();
+            let progress = move |message: String| {
+                let mut status = status;
+                status.set(message);
+            };
+            let result = workflow.run(step, &mut current, input, &progress).await;
+            state.set(current);
+            match result {
+                Ok(output) => {
+                    status.set(output.to_string());
+                    then(&output);
+                }
+                Err(e) => status.set(error::describe(&e)),
+            }
+            loading.set(false);
+        });
+    }
+}
+
+/// Contract page driven by the shared [`Workflow`] engine
+///
+/// Shows the steps that apply to the selected template: the oracle and refund
+/// sections only appear when the template has them. Progress is saved under `name`
+/// and restored when the page is opened again.
+#[component]
+pub fn ContractWorkflow(
+    /// Key the workflow state is saved under
+    name: String,
+    title: String,
+    /// Contract name used in section headings
+    label: String,
+    /// Offer covenant templates instead of plain ones
+    covenant: bool,
+    default_template: String,
+    panel_id: String,
+) -> Element {
+    let registry = consume_context::<Arc<TemplateRegistry>>();
+    let workflow = Workflow::new(
+        &name,
+        consume_context::<Arc<ElementsRPC>>(),
+        consume_context::<Arc<HalWrapper>>(),
+        registry.clone(),
+        consume_context::<Arc<Store>>(),
+    );
+
+    let initial = use_hook({
+        let workflow = workflow.clone();
+        let registry = registry.clone();
+        let default_template = default_template.clone();
+        move || {
+            let resumed = workflow.resume().unwrap_or_else(|e| {
+                tracing::warn!("Failed to load saved workflow {}: {}", workflow.name, e);
+                None
+            });
+            resumed.filter(|s| registry.get(&s.template_id).is_some()).unwrap_or_else(|| {
+                let defaults = registry.get(&default_template).map(|t| t.default_values()).unwrap_or_default();
+                WorkflowState::new(&default_template, defaults)
+            })
+        }
+    });
+    let state = use_signal(|| initial.clone());
+    let status_message = use_signal(|| match initial.next_step() {
+        Some(step) if initial.contract.is_some() => format!("Resumed saved progress. Next step: {}", step.label()),
+        _ => String::new(),
+    });
+    let is_loading = use_signal(|| false);
+    let runner = StepRunner { state, status: status_message, loading: is_loading };
+
+    // Form inputs, restored from the saved state where it has them
+    let mut template_id = use_signal(|| initial.template_id.clone());
+    let mut template_values = use_signal(|| initial.params.clone());
+    let mut simf_file_path = use_signal(|| initial.simf_path.clone());
+    let mut contract_program_input = use_signal(|| initial.program.clone());
+    let mut internal_key = use_signal(|| initial.internal_key.clone());
+    let mut witness_file_path = use_signal(|| initial.witness_path.clone());
+    let mut faucet_amount = use_signal(|| "0.001".to_string());
+    let mut spend_destination = use_signal(|| initial.spend.as_ref().map(|s| s.destination.clone()).unwrap_or_default());
+    let mut spend_amount = use_signal(|| {
+        initial
+            .spend
+            .as_ref()
+            .map(|s| format!("{}", s.amount_sats as f64 / 100_000_000.0))
+            .unwrap_or_default()
+    });
+    let mut privkeys = use_signal(Vec::<String>::new);
+    let mut cosigned_pset = use_signal(String::new);
+    let signing_mode = use_signal(SigningMode::default);
+    let mut oracle_url = use_signal(|| DEFAULT_ORACLE_URL.to_string());
+    let mut invoice_id = use_signal(|| {
+        initial.attestation.as_ref().map(|a| a.invoice_id.clone()).unwrap_or_default()
+    });
+    let mut promoter_privkey = use_signal(String::new);
+    let mut reclaim_destination = use_signal(String::new);
+
+    let template = use_memo({
+        let registry = registry.clone();
+        move || registry.get(&template_id()).cloned()
+    });
+    let signer_pubkeys = use_memo(move || template().map(|t| t.signer_pubkeys(&template_values.read())).unwrap_or_default());
+    let oracle_key = use_memo(move || template().and_then(|t| t.oracle_pubkey(&template_values.read())));
+    let refund_path = use_memo(move || template().and_then(|t| t.refund(&template_values.read())));
+
+    let file_name = template().map(|t| t.file_name).unwrap_or_else(|| "contract.simf".to_string());
+    let simf_placeholder = format!("/path/to/{}", file_name);
+    let witness_placeholder = format!("/path/to/{}.wit", file_name.trim_end_matches(".simf"));
+    let outputs_note = template()
+        .filter(|t| t.spend_flow.is_covenant())
+        .map(|t| format!("\n\nNote: Covenant enforces {} outputs:\n{}", t.spend_flow.outputs.len(), workflow::describe_outputs(&t)))
+        .unwrap_or_default();
+
+    let generate_simf = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::GenerateInput {
+                template_id: template_id(),
+                params: template_values(),
+                simf_path: simf_file_path(),
+            };
+            runner.run(workflow.clone(), workflow::Generate, input, |_| {});
+        }
+    };
+
+    let compile_simf = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::CompileInput { simf_path: simf_file_path() };
+            runner.run(workflow.clone(), workflow::Compile, input, move |out: &workflow::Compiled| {
+                contract_program_input.set(out.program.clone());
+            });
+        }
+    };
+
+    let create_contract_address = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::CreateContractInput {
+                template_id: template_id(),
+                params: template_values(),
+                simf_path: simf_file_path(),
+                program: contract_program_input().trim().to_string(),
+                internal_key: internal_key(),
+                oracle_url: if oracle_key().is_some() { oracle_url().trim().to_string() } else { String::new() },
+            };
+            runner.run(workflow.clone(), workflow::CreateContract, input, |_| {});
+        }
+    };
+
+    let verify_contract = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::VerifyInput { simf_path: simf_file_path() };
+            runner.run(workflow.clone(), workflow::Verify, input, |_| {});
+        }
+    };
+
+    let fund_via_faucet = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::FundInput { amount: faucet_amount() };
+            runner.run(workflow.clone(), workflow::Fund, input, |_| {});
+        }
+    };
+
+    let fetch_oracle_pubkey = move |_| {
+        let mut status_message = status_message;
+        let mut is_loading = is_loading;
+        spawn(async move {
+            is_loading.set(true);
+            let url = oracle_url.read().clone();
+            status_message.set(format!("Fetching oracle public key from {}...", url));
+
+            match OracleClient::new(&url).pubkey().await {
+                Ok(pk) => {
+                    if let Some(param) = template().and_then(|t| t.spend_flow.oracle_param) {
+                        template_values.write().insert(param, pk.clone());
+                    }
+                    status_message.set(format!("Oracle public key: {}\n\nThis key will be embedded in the covenant.", pk));
+                }
+                Err(e) => {
+                    status_message.set(format!(
+                        "Failed to fetch oracle public key: {}\n\nIs the oracle signer running? Start it with:\ncargo run --bin oracle_signer -- --key <secret key hex> --invoices <file>",
+                        error::describe(&e)
+                    ));
+                }
+            }
+
+            is_loading.set(false);
+        });
+    };
+
+    let request_attestation = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::AttestInput {
+                oracle_url: oracle_url(),
+                invoice_id: invoice_id(),
+            };
+            runner.run(workflow.clone(), workflow::Attest, input, |_| {});
+        }
+    };
+
+    let create_spend_pset = {
+        let workflow = workflow.clone();
+        move |_| {
+            let mut status_message = status_message;
+            let amount = match spend_amount().trim().parse::<f64>() {
+                Ok(amount) => amount,
+                Err(_) => {
+                    status_message.set("Please enter a valid amount".to_string());
+                    return;
+                }
+            };
+            let input = workflow::CreatePsetInput { destination: spend_destination(), amount };
+            runner.run(workflow.clone(), workflow::CreatePset, input, |_| {});
+        }
+    };
+
+    let sign_and_finalize = {
+        let workflow = workflow.clone();
+        move |_| {
+            let mut keys = privkeys();
+            keys.resize(signer_pubkeys.read().len().max(keys.len()), String::new());
+            let input = workflow::SignInput {
+                privkeys: keys,
+                mode: signing_mode(),
+                witness_path: witness_file_path(),
+                cosigned_pset: cosigned_pset(),
+            };
+            runner.run(workflow.clone(), workflow::Sign, input, |_| {});
+        }
+    };
+
+    let reclaim_expired = {
+        let workflow = workflow.clone();
+        move |_| {
+            let input = workflow::ReclaimInput {
+                destination: reclaim_destination(),
+                privkey: promoter_privkey(),
+                mode: signing_mode(),
+                witness_path: witness_file_path(),
+            };
+            runner.run(workflow.clone(), workflow::Reclaim, input, |_| {});
+        }
+    };
+
+    let broadcast_tx = {
+        let workflow = workflow.clone();
+        move |_| {
+            runner.run(workflow.clone(), workflow::Broadcast, (), |_| {});
+        }
+    };
+
+    // Forget the saved progress and start again from the default template
+    let start_over = {
+        let workflow = workflow.clone();
+        let registry = registry.clone();
+        let default_template = default_template.clone();
+        move |_| {
+            let mut state = state;
+            let mut status_message = status_message;
+            let defaults: TemplateValues = registry.get(&default_template).map(|t| t.default_values()).unwrap_or_default();
+            let fresh = WorkflowState::new(&default_template, defaults.clone());
+            if let Err(e) = workflow.save(&fresh) {
+                status_message.set(format!("Failed to reset the saved workflow: {}", e));
+                return;
+            }
+            state.set(fresh);
+            template_id.set(default_template.clone());
+            template_values.set(defaults);
+            simf_file_path.set(String::new());
+            contract_program_input.set(String::new());
+            internal_key.set(InternalKey::default());
+            witness_file_path.set(String::new());
+            spend_destination.set(String::new());
+            spend_amount.set(String::new());
+            privkeys.set(Vec::new());
+            cosigned_pset.set(String::new());
+            invoice_id.set(String::new());
+            promoter_privkey.set(String::new());
+            reclaim_destination.set(String::new());
+            status_message.set(String::new());
+        }
+    };
+
+    let current = state();
+    let next_step = current.next_step();
+    let contract = current.contract.clone();
+    let funding = current.funding.clone();
+    let spend = current.spend.clone();
+    let finalized = current.finalized.clone();
+    let spend_tx = finalized.clone().filter(|f| f.kind == FinalizedKind::Spend);
+    let refund_tx = finalized.filter(|f| f.kind != FinalizedKind::Spend);
+    let verified = current.verified;
+
+    let progress_items = StepId::MAIN.into_iter().map(|step| {
+        let (marker, style) = if current.is_done(step) {
+            ("✓", "color: #2e7d32;")
+        } else if Some(step) == next_step {
+            ("→", "font-weight: 600;")
+        } else {
+            ("·", "color: #666;")
+        };
+        rsx! {
+            li { key: "{step.label()}", style: "{style}", "{marker} {step.label()}" }
+        }
+    });
+
+    let signer_inputs = signer_pubkeys().into_iter().enumerate().map(|(i, pubkey)| {
+        let value = privkeys.read().get(i).cloned().unwrap_or_default();
+        rsx! {
+            div { key: "{i}", style: "margin-bottom: 16px;",
+                label { "Private Key {i + 1} (hex)" }
+                input {
+                    r#type: "text",
+                    value: "{value}",
+                    oninput: move |evt| {
+                        let mut keys = privkeys.write();
+                        if keys.len() <= i {
+                            keys.resize(i + 1, String::new());
+                        }
+                        keys[i] = evt.value().to_string();
+                    },
+                    placeholder: "Private key for signer {i + 1}"
+                }
+                p { style: "font-size: 0.875rem; color: #666; margin-top: 4px; word-break: break-all;",
+                    "Must match public key {pubkey} (optional)"
+                }
+            }
+        }
+    });
+
+    rsx! {
+        div { id: "{panel_id}",
+            h1 { style: "font-size: 2rem; margin-bottom: 24px;", "{title}" }
+
+            div { class: "panel-section",
+                h2 { "Progress" }
+                ul { style: "list-style: none; padding-left: 0; margin-bottom: 16px;",
+                    {progress_items}
+                }
+                button {
+                    class: "button",
+                    onclick: start_over,
+                    disabled: is_loading(),
+                    "Start Over"
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "0. Generate {label} Simplicity Source File" }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Simplicity Source File (.simf) Output Path" }
+                    input {
+                        r#type: "text",
+                        value: "{simf_file_path}",
+                        oninput: move |evt| simf_file_path.set(evt.value().to_string()),
+                        placeholder: "{simf_placeholder}"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Enter the full path where the .simf file will be generated"
+                    }
+                }
+
+                TemplateSelect {
+                    templates: registry.by_covenant(covenant).into_iter().cloned().collect::<Vec<_>>(),
+                    selected: template_id,
+                    values: template_values,
+                }
+
+                if let Some(template) = template() {
+                    TemplateForm { template, values: template_values }
+                }
+
+                if template().is_some_and(|t| t.spend_flow.oracle_param.is_some()) {
+                    div { style: "margin-bottom: 16px;",
+                        label { "Oracle Signer URL" }
+                        div { style: "display: flex; gap: 8px;",
+                            input {
+                                r#type: "text",
+                                value: "{oracle_url}",
+                                oninput: move |evt| oracle_url.set(evt.value().to_string()),
+                                placeholder: DEFAULT_ORACLE_URL
+                            }
+                            button {
+                                class: "button",
+                                onclick: fetch_oracle_pubkey,
+                                disabled: is_loading(),
+                                "Fetch Oracle Key"
+                            }
+                        }
+                    }
+                }
+
+                button {
+                    class: "button",
+                    onclick: generate_simf,
+                    disabled: is_loading(),
+                    "Generate .simf File"
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "1. Compile Simplicity Source (Optional)" }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Simplicity Source File (.simf)" }
+                    input {
+                        r#type: "text",
+                        value: "{simf_file_path}",
+                        oninput: move |evt| simf_file_path.set(evt.value().to_string()),
+                        placeholder: "{simf_placeholder}"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Enter the full path to your .simf source file"
+                    }
+                }
+
+                button {
+                    class: "button",
+                    onclick: compile_simf,
+                    disabled: is_loading(),
+                    "Compile .simf File"
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "2. Create {label} Contract Address" }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Compiled Simplicity Program (base64) - Required" }
+                    textarea {
+                        rows: "6",
+                        value: "{contract_program_input}",
+                        oninput: move |evt| contract_program_input.set(evt.value().to_string()),
+                        placeholder: "Paste compiled program base64 here or compile from .simf above"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Paste the base64-encoded compiled Simplicity program"
+                    }
+                }
+
+                InternalKeyPicker {
+                    choice: internal_key,
+                    signers: signer_pubkeys()
+                }
+
+                button {
+                    class: "button",
+                    onclick: create_contract_address,
+                    disabled: is_loading(),
+                    "Create Contract Address"
+                }
+
+                if contract.is_some() {
+                    button {
+                        class: "button",
+                        style: "margin-left: 8px;",
+                        onclick: verify_contract,
+                        disabled: is_loading(),
+                        "Verify Contract"
+                    }
+                }
+
+                if let Some(contract) = contract.clone() {
+                    div { class: "info-box info", style: "margin-top: 16px;",
+                        p { style: "font-weight: 600; margin-bottom: 8px;", "Contract Address:" }
+                        p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all;",
+                            "{contract.address}"
+                        }
+                        p { style: "font-weight: 600; margin-top: 8px; margin-bottom: 4px;", "CMR:" }
+                        p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem;",
+                            "{contract.cmr}"
+                        }
+                        p { style: "margin-top: 8px;",
+                            Link {
+                                to: Route::ContractDetailPage { address: contract.address.clone() },
+                                style: "color: #0066cc; text-decoration: underline;",
+                                "Transaction history →"
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "3. Fund Contract Address via Faucet" }
+
+                if contract.is_some() && verified != Some(true) {
+                    div { class: "info-box info", style: "margin-bottom: 16px;",
+                        if verified == Some(false) {
+                            "Verification failed for this contract. Do not fund it until the mismatch is explained."
+                        } else {
+                            "This contract has not been verified yet. Use \"Verify Contract\" above before funding."
+                        }
+                    }
+                }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Contract Address" }
+                    input {
+                        value: "{contract.as_ref().map(|c| c.address.clone()).unwrap_or_default()}",
+                        placeholder: "Will be auto-filled after creating contract",
+                        readonly: true
+                    }
+                    if let Some(contract) = contract.as_ref() {
+                        QrCode {
+                            data: {
+                                let uri = PaymentUri::new(&contract.address);
+                                match qr::parse_btc(&faucet_amount()) {
+                                    Ok(sats) if sats > 0 => uri.with_amount(sats),
+                                    _ => uri,
+                                }
+                                .to_uri()
+                            },
+                            caption: "Scan with a Liquid wallet to fund the contract without the faucet"
+                        }
+                    }
+                }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Faucet Amount (L-BTC)" }
+                    input {
+                        r#type: "number",
+                        step: "0.00000001",
+                        min: "0.00000001",
+                        value: "{faucet_amount}",
+                        oninput: move |evt| faucet_amount.set(evt.value().to_string()),
+                        placeholder: "0.001"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Amount to request from the Liquid Testnet faucet (default: 0.001 L-BTC)"
+                    }
+                }
+
+                button {
+                    class: "button",
+                    onclick: fund_via_faucet,
+                    disabled: is_loading() || contract.is_none() || faucet_amount().is_empty(),
+                    "Fund via Faucet"
+                }
+
+                if let Some(funding) = funding.clone() {
+                    div { class: "info-box info", style: "margin-top: 16px;",
+                        p { style: "font-weight: 600; margin-bottom: 8px;", "Funding Transaction ID:" }
+                        p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all;",
+                            "{funding.txid}"
+                        }
+                        p { style: "font-weight: 600; margin-top: 8px; margin-bottom: 4px;", "VOUT:" }
+                        p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem;",
+                            "{funding.vout}"
+                        }
+                        p { style: "margin-top: 8px;",
+                            a {
+                                href: format!("https://blockstream.info/liquidtestnet/tx/{}", funding.txid),
+                                target: "_blank",
+                                style: "color: #0066cc; text-decoration: underline;",
+                                "View on Blockstream Explorer →"
+                            }
+                        }
+                        p { style: "margin-top: 8px; font-weight: 600;",
+                            "UTXO Reference: {funding.txid}:{funding.vout}"
+                        }
+                        if covenant {
+                            p { style: "margin-top: 8px;",
+                                Link {
+                                    to: Route::PromoterLineagePage { txid: funding.txid.clone(), vout: funding.vout },
+                                    style: "color: #0066cc; text-decoration: underline;",
+                                    "Follow voucher lineage →"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { id: "spend-{name}", class: "panel-section",
+                h2 { "4. Create Spending PSET" }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Destination Address" }
+                    input {
+                        r#type: "text",
+                        value: "{spend_destination}",
+                        list: "partners-{name}",
+                        oninput: move |evt| spend_destination.set(evt.value().to_string()),
+                        placeholder: "Enter destination address"
+                    }
+                    PartnerAddresses { id: "partners-{name}" }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Address to send the funds to (Output 0). Start typing to pick a partner from the address book."
+                    }
+                    QrImport {
+                        label: "Or scan the partner's payment request",
+                        on_import: move |text: String| {
+                            let mut status_message = status_message;
+                            match PaymentUri::parse(&text) {
+                                Ok(uri) => {
+                                    spend_destination.set(uri.address.clone());
+                                    if let Some(sats) = uri.amount_sats {
+                                        spend_amount.set(format!("{}", sats as f64 / 100_000_000.0));
+                                    }
+                                }
+                                Err(e) => status_message.set(format!("Not a payment request: {}", e)),
+                            }
+                        }
+                    }
+                }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Amount (L-BTC)" }
+                    input {
+                        r#type: "number",
+                        step: "0.00000001",
+                        min: "0",
+                        value: "{spend_amount}",
+                        oninput: move |evt| spend_amount.set(evt.value().to_string()),
+                        placeholder: "0.0005"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Amount to send (must be less than or equal to the funded amount and the covenant's per-redemption cap, if any){outputs_note}"
+                    }
+                }
+
+                if oracle_key().is_some() {
+                    div { style: "margin-bottom: 16px;",
+                        label { "Partner Invoice ID" }
+                        div { style: "display: flex; gap: 8px;",
+                            input {
+                                r#type: "text",
+                                value: "{invoice_id}",
+                                oninput: move |evt| invoice_id.set(evt.value().to_string()),
+                                placeholder: "Invoice ID from the partner's point of sale"
+                            }
+                            button {
+                                class: "button",
+                                onclick: request_attestation,
+                                disabled: is_loading() || current.spend.is_none(),
+                                "Request Attestation"
+                            }
+                        }
+                        p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                            "Create the PSET first: the oracle signs the invoice together with Output 0's destination and amount and the voucher UTXO being spent; the attestation is added to the witness as INVOICE_HASH and ORACLE_SIG"
+                        }
+                        if let Some(att) = current.attestation.clone() {
+                            div { class: "info-box info", style: "margin-top: 8px;",
+                                p { style: "font-weight: 600; margin-bottom: 4px;", "Attestation for invoice {att.invoice_id}" }
+                                p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;", "ORACLE_SIG: {att.signature}" }
+                            }
+                        }
+                    }
+                }
+
+                div { style: "margin-top: 16px; margin-bottom: 16px;",
+                    label { "Internal Key (Taproot)" }
+                    p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all;",
+                        "{current.internal_key}"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Chosen when the contract address was created (step 2)"
+                    }
+                }
+
+                button {
+                    class: "button",
+                    onclick: create_spend_pset,
+                    disabled: is_loading() || funding.is_none() || contract.is_none(),
+                    "Create and Update PSET"
+                }
+
+                if let Some(spend) = spend.clone() {
+                    div { class: "info-box info", style: "margin-top: 16px;",
+                        p { style: "font-weight: 600; margin-bottom: 8px;", "PSET Ready for Signing:" }
+                        textarea {
+                            rows: "4",
+                            readonly: true,
+                            value: "{spend.pset}",
+                            style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; width: 100%;"
+                        }
+                        p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                            "To redeem with a partner, send them this PSET to review and co-sign on the Partner page."
+                        }
+                        QrCode { data: spend.pset.clone(), caption: "Redemption PSET for the partner to scan" }
+                    }
+                }
+
+                div { style: "margin-top: 24px; margin-bottom: 16px;",
+                    label { "Witness File Path (.wit)" }
+                    input {
+                        r#type: "text",
+                        value: "{witness_file_path}",
+                        oninput: move |evt| witness_file_path.set(evt.value().to_string()),
+                        placeholder: "{witness_placeholder}"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Path to witness file (will be updated with signatures)"
+                    }
+                }
+
+                {signer_inputs}
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Co-signed PSET (optional)" }
+                    textarea {
+                        rows: "3",
+                        value: "{cosigned_pset}",
+                        oninput: move |evt| cosigned_pset.set(evt.value().to_string()),
+                        placeholder: "Paste the PSET the partner returned after co-signing",
+                        style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; width: 100%;"
+                    }
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                        "Signatures it carries fill their signer slots, so only the remaining keys are needed above"
+                    }
+                    QrImport { on_import: move |text: String| cosigned_pset.set(text) }
+                }
+
+                SigningModeSelect { mode: signing_mode }
+
+                button {
+                    class: "button",
+                    onclick: sign_and_finalize,
+                    disabled: is_loading() || spend.is_none() || witness_file_path().is_empty() || simf_file_path().is_empty(),
+                    "Sign and Finalize Transaction"
+                }
+
+                if let Some(tx) = spend_tx {
+                    div { class: "info-box info", style: "margin-top: 16px;",
+                        p { style: "font-weight: 600; margin-bottom: 8px;", "Transaction Hex:" }
+                        textarea {
+                            rows: "4",
+                            readonly: true,
+                            value: "{tx.tx_hex}",
+                            style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; width: 100%;"
+                        }
+                        button {
+                            class: "button",
+                            onclick: broadcast_tx.clone(),
+                            disabled: is_loading(),
+                            style: "margin-top: 8px;",
+                            "Broadcast Transaction"
+                        }
+                    }
+                }
+            }
+
+            if refund_path().is_some() {
+                div { id: "reclaim-vouchers", class: "panel-section",
+                    h2 { "5. Reclaim Expired Vouchers (Promoter)" }
+
+                    p { style: "font-size: 0.875rem; color: #666; margin-bottom: 16px;",
+                        "Sweeps the funded voucher UTXO back to the promoter through the refund branch. The transaction's nLockTime is set to the refund lock and the input sequence to 0xfffffffe so the lock is enforced."
+                    }
+
+                    div { style: "margin-bottom: 16px;",
+                        label { "Refund Destination Address" }
+                        input {
+                            r#type: "text",
+                            value: "{reclaim_destination}",
+                            list: "partners-{name}",
+                            oninput: move |evt| reclaim_destination.set(evt.value().to_string()),
+                            placeholder: "Promoter address to receive the swept funds"
+                        }
+                    }
+
+                    div { style: "margin-bottom: 16px;",
+                        label { "Promoter Private Key (hex)" }
+                        input {
+                            r#type: "text",
+                            value: "{promoter_privkey}",
+                            oninput: move |evt| promoter_privkey.set(evt.value().to_string()),
+                            placeholder: "Private key matching the promoter public key"
+                        }
+                    }
+
+                    SigningModeSelect { mode: signing_mode }
+
+                    button {
+                        class: "button",
+                        onclick: reclaim_expired,
+                        disabled: is_loading() || funding.is_none() || contract.is_none() || witness_file_path().is_empty() || simf_file_path().is_empty(),
+                        "Reclaim Expired Vouchers"
+                    }
+
+                    if let Some(tx) = refund_tx {
+                        div { class: "info-box info", style: "margin-top: 16px;",
+                            p { style: "font-weight: 600; margin-bottom: 8px;", "Refund Transaction Hex:" }
+                            textarea {
+                                rows: "4",
+                                readonly: true,
+                                value: "{tx.tx_hex}",
+                                style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; width: 100%;"
+                            }
+                            button {
+                                class: "button",
+                                onclick: broadcast_tx,
+                                disabled: is_loading(),
+                                style: "margin-top: 8px;",
+                                "Broadcast Transaction"
+                            }
+                        }
+                    }
+                }
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;",
+                        "{status_message}"
+                    }
+                }
+            }
+
+            if is_loading() {
+                div { class: "loading", "Loading" }
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::app_core::InternalKey;
+use dioxus::prelude::*;
+
+/// Choice of Taproot internal key for a new contract
+///
+/// `signers` are the keys a cooperative aggregate key is built from.
+#[component]
+pub fn InternalKeyPicker(choice: Signal<InternalKey>, signers: Vec<String>) -> Element {
+    let mut error = use_signal(|| None::<String>);
+
+    let kind = match choice() {
+        InternalKey::Nums => "nums",
+        InternalKey::Unspendable { .. } => "unspendable",
+        InternalKey::Aggregate { .. } => "aggregate",
+    };
+    let explanation = choice().verify();
+
+    let aggregate_signers = signers.clone();
+    rsx! {
+        div { style: "margin-bottom: 16px;",
+            label { "Internal Key (Taproot)" }
+            select {
+                value: "{kind}",
+                onchange: move |evt| {
+                    let next = match evt.value().as_str() {
+                        "unspendable" => InternalKey::random_unspendable(),
+                        "aggregate" => InternalKey::aggregate(&aggregate_signers),
+                        _ => Ok(InternalKey::Nums),
+                    };
+                    match next {
+                        Ok(key) => {
+                            error.set(None);
+                            choice.set(key);
+                        }
+                        Err(e) => error.set(Some(format!("{}", e))),
+                    }
+                },
+                option { value: "nums", "NUMS point H (default, key path disabled)" }
+                option { value: "unspendable", "Provably unspendable key from a random tweak" }
+                option { value: "aggregate", "Aggregate of the signer keys (cooperative key-path spend)" }
+            }
+            if let InternalKey::Unspendable { .. } = choice() {
+                button {
+                    class: "button",
+                    style: "margin-top: 8px;",
+                    onclick: move |_| match InternalKey::random_unspendable() {
+                        Ok(key) => choice.set(key),
+                        Err(e) => error.set(Some(format!("{}", e))),
+                    },
+                    "New Random Tweak"
+                }
+            }
+            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all; margin-top: 8px;",
+                "{choice().key_hex()}"
+            }
+            match explanation {
+                Ok(text) => rsx! {
+                    p { style: "font-size: 0.875rem; color: #666; margin-top: 4px; white-space: pre-wrap; word-break: break-all;", "{text}" }
+                },
+                Err(e) => rsx! {
+                    p { style: "font-size: 0.875rem; color: #b00020; margin-top: 4px;", "{e}" }
+                },
+            }
+            if let Some(e) = error() {
+                p { style: "font-size: 0.875rem; color: #b00020; margin-top: 4px;", "{e}" }
+            }
+            if signers.is_empty() {
+                p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                    "Fill in the signer public keys above to use an aggregate key."
+                }
+            }
+        }
+    }
+}
@@ -7,3 +7,21 @@ pub use hero::Hero;
 
 mod echo;
 pub use echo::Echo;
+
+mod template_form;
+pub use template_form::{TemplateForm, TemplateSelect};
+
+mod internal_key_picker;
+pub use internal_key_picker::InternalKeyPicker;
+
+mod signing_mode_select;
+pub use signing_mode_select::SigningModeSelect;
+
+mod qr;
+pub use qr::{QrCode, QrImport};
+
+mod contract_workflow;
+pub use contract_workflow::ContractWorkflow;
+
+mod partner_addresses;
+pub use partner_addresses::PartnerAddresses;
@@ -0,0 +1,20 @@
+use crate::app_core::Store;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+/// Suggestions from the partner address book for an address input
+///
+/// Render it next to the input and point the input's `list` attribute at `id`.
+#[component]
+pub fn PartnerAddresses(id: String) -> Element {
+    let store = consume_context::<Arc<Store>>();
+    let partners = store.load().map(|s| s.partners).unwrap_or_default();
+
+    rsx! {
+        datalist { id: "{id}",
+            for partner in partners {
+                option { key: "{partner.address}", value: "{partner.address}", "{partner.name}" }
+            }
+        }
+    }
+}
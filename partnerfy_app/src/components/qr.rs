@@ -0,0 +1,131 @@
+use crate::app_core::{error, platform};
+use crate::app_core::qr::{self, Assembler};
+use dioxus::prelude::*;
+use std::time::Duration;
+
+/// How long each part of an animated QR code is shown
+const FRAME_INTERVAL: Duration = Duration::from_millis(800);
+
+/// Button that shows `data` as a QR code, animated through its parts when it is too long
+/// for one
+#[component]
+pub fn QrCode(data: String, #[props(default)] caption: String) -> Element {
+    let mut visible = use_signal(|| false);
+    let mut paused = use_signal(|| false);
+    let mut index = use_signal(|| 0usize);
+    let frames = use_memo(use_reactive!(|data| qr::frames(&data)));
+
+    use_future(move || async move {
+        loop {
+            platform::sleep(FRAME_INTERVAL).await;
+            let count = frames.peek().len();
+            if visible() && !paused() && count > 1 {
+                index.set((index() + 1) % count);
+            }
+        }
+    });
+
+    let count = frames.read().len();
+    let current = index() % count.max(1);
+    let image = frames.read().get(current).map(|f| qr::svg_data_uri(f));
+
+    rsx! {
+        div { style: "margin-top: 8px;",
+            button {
+                class: "button",
+                onclick: move |_| visible.toggle(),
+                disabled: data.trim().is_empty(),
+                if visible() { "Hide QR Code" } else { "Show QR Code" }
+            }
+            if visible() {
+                div { style: "margin-top: 8px; text-align: center;",
+                    match image {
+                        Some(Ok(src)) => rsx! {
+                            img { src: "{src}", alt: "QR code", style: "width: 320px; max-width: 100%; background: white;" }
+                        },
+                        Some(Err(e)) => rsx! {
+                            p { "{e}" }
+                        },
+                        None => rsx! {},
+                    }
+                    if !caption.is_empty() {
+                        p { style: "font-size: 0.875rem; color: #666;", "{caption}" }
+                    }
+                    if count > 1 {
+                        p { style: "font-size: 0.875rem; color: #666;",
+                            "Part {current + 1} of {count}. Keep the scanner on the code until every part is read."
+                        }
+                        button {
+                            class: "button",
+                            onclick: move |_| paused.toggle(),
+                            if paused() { "Play" } else { "Pause" }
+                        }
+                        if paused() {
+                            button {
+                                class: "button",
+                                style: "margin-left: 8px;",
+                                onclick: move |_| index.set((current + 1) % count),
+                                "Next Part"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// File picker that reads QR codes from images and hands the decoded text to `on_import`
+///
+/// Multi-part codes can be imported from one picture holding every part, or from several
+/// pictures picked one after the other.
+#[component]
+pub fn QrImport(on_import: EventHandler<String>, #[props(default)] label: String) -> Element {
+    let mut assembler = use_signal(Assembler::default);
+    let mut message = use_signal(String::new);
+    let label = if label.is_empty() { "Import from QR Image".to_string() } else { label };
+
+    let import = move |evt: FormEvent| {
+        let Some(engine) = evt.files() else {
+            return;
+        };
+        let mut collected = assembler();
+        for path in engine.files() {
+            let added = qr::scan_image(&path)
+                .and_then(|frames| frames.iter().try_for_each(|frame| collected.add(frame)));
+            if let Err(e) = added {
+                message.set(format!("Failed to read {}: {}", path, error::describe(&e)));
+                return;
+            }
+        }
+        if collected.is_complete() {
+            match collected.finish() {
+                Ok(text) => {
+                    message.set(String::new());
+                    on_import.call(text);
+                }
+                Err(e) => message.set(format!("Failed to import the QR code: {}", e)),
+            }
+            assembler.set(Assembler::default());
+        } else {
+            let (scanned, total) = collected.progress();
+            message.set(format!("Read {} of {} parts. Pick pictures of the remaining parts.", scanned, total));
+            assembler.set(collected);
+        }
+    };
+
+    rsx! {
+        div { style: "margin-top: 8px;",
+            label { style: "font-size: 0.875rem;", "{label}" }
+            input {
+                r#type: "file",
+                accept: "image/*",
+                multiple: true,
+                onchange: import,
+            }
+            if !message().is_empty() {
+                p { style: "font-size: 0.875rem; color: #666; white-space: pre-wrap;", "{message}" }
+            }
+        }
+    }
+}
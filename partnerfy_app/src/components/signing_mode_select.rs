@@ -0,0 +1,25 @@
+use crate::app_core::sighash::SigningMode;
+use dioxus::prelude::*;
+
+/// Choice between signing through `hal-simplicity` (checked natively) and signing in Rust
+#[component]
+pub fn SigningModeSelect(mode: Signal<SigningMode>) -> Element {
+    let value = match mode() {
+        SigningMode::Hal => "hal",
+        SigningMode::Native => "native",
+    };
+    rsx! {
+        div { style: "margin-bottom: 16px;",
+            label { "Signing" }
+            select {
+                value: "{value}",
+                onchange: move |evt| mode.set(match evt.value().as_str() {
+                    "native" => SigningMode::Native,
+                    _ => SigningMode::Hal,
+                }),
+                option { value: "hal", "hal-simplicity sighash (verified against native sig_all_hash)" }
+                option { value: "native", "Native (compute sig_all_hash and sign without the CLI)" }
+            }
+        }
+    }
+}
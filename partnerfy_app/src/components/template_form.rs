@@ -0,0 +1,96 @@
+use crate::app_core::templates::{CovenantTemplate, ParamKind, TemplateValues};
+use dioxus::prelude::*;
+
+/// Form for a covenant template's parameters
+///
+/// Renders one input per declared parameter, grouped under the parameter's `group`
+/// heading, and writes the entered values into `values`.
+#[component]
+pub fn TemplateForm(template: CovenantTemplate, values: Signal<TemplateValues>) -> Element {
+    let mut previous_group: Option<String> = None;
+    let fields = template.params.iter().map(|param| {
+        let heading = match &param.group {
+            Some(group) if previous_group.as_ref() != Some(group) => Some(group.clone()),
+            _ => None,
+        };
+        previous_group = param.group.clone();
+
+        let name = param.name.clone();
+        let field = name.clone();
+        let label = if param.required {
+            param.label.clone()
+        } else {
+            format!("{} (optional)", param.label)
+        };
+        let input_type = match param.kind {
+            ParamKind::Threshold { .. }
+            | ParamKind::Amount
+            | ParamKind::BlockHeight
+            | ParamKind::Timestamp => "number",
+            _ => "text",
+        };
+        let step = if param.kind == ParamKind::Amount { "0.00000001" } else { "1" };
+        let placeholder = param
+            .placeholder
+            .clone()
+            .unwrap_or_else(|| param.default_placeholder().to_string());
+        let current = values.read().get(&name).cloned().unwrap_or_default();
+        let description = param.description.clone();
+
+        rsx! {
+            div { key: "{name}",
+                if let Some(heading) = heading {
+                    h3 { style: "font-size: 1rem; font-weight: 600; margin: 24px 0 8px;", "{heading}" }
+                }
+                div { style: "margin-bottom: 16px;",
+                    label { "{label}" }
+                    input {
+                        r#type: input_type,
+                        step: step,
+                        min: "0",
+                        value: "{current}",
+                        oninput: move |evt| {
+                            values.write().insert(field.clone(), evt.value().to_string());
+                        },
+                        placeholder: "{placeholder}"
+                    }
+                    if !description.is_empty() {
+                        p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;", "{description}" }
+                    }
+                }
+            }
+        }
+    });
+
+    rsx! {
+        {fields}
+    }
+}
+
+/// Dropdown of templates; selecting one resets `values` to its defaults
+#[component]
+pub fn TemplateSelect(
+    templates: Vec<CovenantTemplate>,
+    selected: Signal<String>,
+    values: Signal<TemplateValues>,
+) -> Element {
+    let options = templates.clone();
+    rsx! {
+        div { style: "margin-bottom: 16px;",
+            label { "Covenant Template" }
+            select {
+                value: "{selected}",
+                onchange: move |evt| {
+                    let id = evt.value();
+                    if let Some(template) = templates.iter().find(|t| t.id == id) {
+                        values.set(template.default_values());
+                    }
+                    selected.set(id);
+                },
+                for template in options {
+                    option { key: "{template.id}", value: "{template.id}", "{template.name}" }
+                }
+            }
+        }
+    }
+}
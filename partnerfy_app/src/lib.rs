@@ -0,0 +1,6 @@
+//! Partnerfy core library
+//!
+//! Shared by the desktop app and the helper binaries in `src/bin`.
+
+/// Define core modules for Elements RPC, transaction building, and covenant handling.
+pub mod app_core;
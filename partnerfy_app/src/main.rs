@@ -1,18 +1,20 @@
 //! Partnerfy - Covenant-based voucher management on Liquid Testnet
 //!
 //! A desktop app for issuing, managing, and redeeming Simplicity covenant-based vouchers.
+//! Built with `--features web` (no `server`), the participant and partner pages run in the browser.
 
 use dioxus::prelude::*;
 
-use views::{P2MS as P2MSPage, Voucher as VoucherPage, Navbar, Landing as LandingPage, Instructions as InstructionsPage};
-use app_core::{ElementsRPC, HalWrapper, Settings};
+use views::{Promoter as PromoterPage, Participant as ParticipantPage, Partner as PartnerPage, P2MS as P2MSPage, Voucher as VoucherPage, ContractDetail as ContractDetailPage, LineageView as LineagePage, PromoterNavbar, ParticipantNavbar, PartnerNavbar, Landing as LandingPage, Instructions as InstructionsPage, Audit as AuditPage, Issuance as IssuancePage, Reports as ReportsPage, AddressBook as AddressBookPage};
+use app_core::{ElementsRPC, HalWrapper, Settings, Store, TemplateRegistry};
 
 /// Define a components module that contains all shared components for our app.
 mod components;
 /// Define a views module that contains the UI for all Layouts and Routes for our app.
 mod views;
-/// Define core modules for Elements RPC, transaction building, and covenant handling.
-mod app_core;
+/// Core modules for Elements RPC, transaction building, and covenant handling live in the library
+/// so the helper binaries (e.g. `oracle_signer`) can share them.
+use partnerfy_app::app_core;
 
 /// The Route enum is used to define the structure of internal routes in our app. All route enums need to derive
 /// the [`Routable`] trait, which provides the necessary methods for the router to work.
@@ -29,12 +31,45 @@ enum Route {
     LandingPage {},
     #[route("/instructions")]
     InstructionsPage {},
-    // Role-based pages (with navbar)
-    #[layout(Navbar)]
-        #[route("/p2ms")]
-        P2MSPage {},
-        #[route("/voucher")]
-        VoucherPage {},
+    // Role-based route trees, each with its own navbar
+    #[nest("/promoter")]
+        #[layout(PromoterNavbar)]
+            #[route("/")]
+            PromoterPage {},
+            #[route("/p2ms")]
+            P2MSPage {},
+            #[route("/voucher")]
+            VoucherPage {},
+            #[route("/issue")]
+            IssuancePage {},
+            #[route("/contract/:address")]
+            ContractDetailPage { address: String },
+            #[route("/lineage/:txid/:vout", LineagePage)]
+            PromoterLineagePage { txid: String, vout: u32 },
+            #[route("/reports", ReportsPage)]
+            PromoterReportsPage {},
+            #[route("/partners", AddressBookPage)]
+            PromoterPartnersPage {},
+            #[route("/audit")]
+            AuditPage {},
+        #[end_layout]
+    #[end_nest]
+    #[nest("/participant")]
+        #[layout(ParticipantNavbar)]
+            #[route("/")]
+            ParticipantPage {},
+            #[route("/lineage/:txid/:vout", LineagePage)]
+            ParticipantLineagePage { txid: String, vout: u32 },
+            #[route("/partners", AddressBookPage)]
+            ParticipantPartnersPage {},
+        #[end_layout]
+    #[end_nest]
+    #[nest("/partner")]
+        #[layout(PartnerNavbar)]
+            #[route("/")]
+            PartnerPage {},
+            #[route("/reports", ReportsPage)]
+            PartnerReportsPage {},
 }
 
 // We can import assets in dioxus with the `asset!` macro. This macro takes a path to an asset relative to the crate root.
@@ -45,7 +80,8 @@ const MAIN_CSS: Asset = asset!("/assets/styling/main.css");
 const TAILWIND_CSS: Asset = asset!("/assets/tailwind.css");
 
 fn main() {
-    // Initialize logging
+    // Initialize logging (the web build logs to the browser console through dioxus)
+    #[cfg(not(target_arch = "wasm32"))]
     tracing_subscriber::fmt::init();
     
     // The `launch` function is the main entry point for a dioxus app. It takes a component and renders it with the platform feature
@@ -80,6 +116,16 @@ fn App() -> Element {
     let hal_wrapper = Arc::new(HalWrapper::new(None));
     provide_context(hal_wrapper);
     
+    // Load covenant templates (built-ins plus ~/.partnerfy/templates)
+    let templates = TemplateRegistry::load(&TemplateRegistry::default_user_dir());
+    for error in &templates.load_errors {
+        eprintln!("Skipping covenant template: {}", error);
+    }
+    provide_context(Arc::new(templates));
+    
+    // Persisted contract records and other state (~/.partnerfy/state.json)
+    provide_context(Arc::new(Store::default()));
+    
     // Provide settings context
     provide_context(settings);
     
@@ -0,0 +1,255 @@
+//! Partner address book page
+//!
+//! Add, edit and delete the partners vouchers can be spent at, and move the book
+//! between apps as CSV or JSON (see `app_core::address_book`). The promoter keeps the
+//! book; participants import it so their wallet can pay partners by name.
+
+use crate::app_core::address_book::{self, ImportSummary};
+use crate::app_core::{platform, Partner, Settings, Store};
+use base64::Engine;
+use dioxus::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+
+#[component]
+pub fn AddressBook() -> Element {
+    let store = consume_context::<Arc<Store>>();
+    let settings = consume_context::<Settings>();
+
+    let mut partners = use_signal(|| store.load().map(|s| s.partners).unwrap_or_default());
+    // Address of the entry being edited, `None` when adding
+    let mut editing = use_signal(|| None::<String>);
+    let mut name = use_signal(String::new);
+    let mut address = use_signal(String::new);
+    let mut pubkey = use_signal(String::new);
+    let mut import_path = use_signal(String::new);
+    let mut import_text = use_signal(String::new);
+    let mut status_message = use_signal(String::new);
+
+    let mut clear_form = move || {
+        editing.set(None);
+        name.set(String::new());
+        address.set(String::new());
+        pubkey.set(String::new());
+    };
+
+    let save = {
+        let store = store.clone();
+        let chain = settings.chain.clone();
+        move |_| {
+            let entry = Partner {
+                address: address(),
+                pubkey: Some(pubkey()).filter(|k| !k.trim().is_empty()),
+                name: name(),
+            };
+            let result = store.update(|state| {
+                let saved = address_book::save_partner(state, editing().as_deref(), &entry, &chain)?;
+                Ok((saved, state.partners.clone()))
+            });
+            match result {
+                Ok((saved, book)) => {
+                    status_message.set(format!("Saved {}", saved.name));
+                    partners.set(book);
+                    clear_form();
+                }
+                Err(e) => status_message.set(e.to_string()),
+            }
+        }
+    };
+
+    let delete = {
+        let store = store.clone();
+        move |target: String| {
+            let mut status_message = status_message;
+            let mut partners = partners;
+            let result = store.update(|state| {
+                address_book::delete_partner(state, &target);
+                Ok(state.partners.clone())
+            });
+            match result {
+                Ok(book) => {
+                    partners.set(book);
+                    status_message.set("Partner deleted".to_string());
+                }
+                Err(e) => status_message.set(e.to_string()),
+            }
+        }
+    };
+
+    let load_file = move |_| match platform::read_file(Path::new(import_path().trim())) {
+        Ok(Some(text)) => {
+            import_text.set(text);
+            status_message.set(String::new());
+        }
+        Ok(None) => status_message.set(format!("File not found: {}", import_path())),
+        Err(e) => status_message.set(e.to_string()),
+    };
+
+    let import = {
+        let store = store.clone();
+        let chain = settings.chain.clone();
+        move |_| {
+            let result = store.update(|state| {
+                let entries = address_book::parse_import(&import_text())?;
+                let summary = address_book::import(state, &entries, &chain)?;
+                Ok((summary, state.partners.clone()))
+            });
+            match result {
+                Ok((ImportSummary { added, updated }, book)) => {
+                    status_message.set(format!("Imported {} new and {} updated partner(s)", added, updated));
+                    partners.set(book);
+                    import_text.set(String::new());
+                }
+                Err(e) => status_message.set(e.to_string()),
+            }
+        }
+    };
+
+    let save_export = move |extension: &'static str| {
+        let mut status_message = status_message;
+        let book = partners();
+        let contents = match extension {
+            "csv" => Ok(address_book::export_csv(&book)),
+            _ => address_book::export_json(&book),
+        };
+        let path = Settings::data_dir().join(format!("partners.{}", extension));
+        match contents.and_then(|c| platform::write_file(&path, &c)) {
+            Ok(()) => status_message.set(format!("Saved {}", path.display())),
+            Err(e) => status_message.set(format!("Failed to export the address book: {}", e)),
+        }
+    };
+
+    let book = partners();
+    let csv_uri = format!(
+        "data:text/csv;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(address_book::export_csv(&book))
+    );
+    let json_uri = format!(
+        "data:application/json;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(address_book::export_json(&book).unwrap_or_default())
+    );
+
+    rsx! {
+        div { id: "address-book-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 8px;", "Partner Address Book" }
+            p { style: "margin-bottom: 16px; color: #666;",
+                "The partners vouchers can be spent at. Addresses must be unconfidential {settings.chain} addresses so partner payments stay explicit."
+            }
+
+            div { class: "panel-section",
+                h2 { if editing().is_some() { "Edit Partner" } else { "Add a Partner" } }
+                label { "Name" }
+                input { r#type: "text", value: "{name}", placeholder: "Cafe on Main Street", oninput: move |evt| name.set(evt.value()) }
+                label { "Address" }
+                input { r#type: "text", value: "{address}", placeholder: "Unconfidential payment address", oninput: move |evt| address.set(evt.value()) }
+                label { "Public key (optional)" }
+                input { r#type: "text", value: "{pubkey}", placeholder: "Key the partner co-signs redemptions with", oninput: move |evt| pubkey.set(evt.value()) }
+                div { style: "display: flex; gap: 8px; margin-top: 12px;",
+                    button {
+                        class: "button",
+                        disabled: name().trim().is_empty() || address().trim().is_empty(),
+                        onclick: save,
+                        if editing().is_some() { "Save Changes" } else { "Add Partner" }
+                    }
+                    if editing().is_some() {
+                        button { class: "button outline", onclick: move |_| clear_form(), "Cancel" }
+                    }
+                }
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;", "{status_message}" }
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "Partners" }
+                if book.is_empty() {
+                    p { "No partners yet. Add one above or import an address book." }
+                } else {
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "Name" }
+                                th { "Address" }
+                                th { "Public key" }
+                                th {}
+                            }
+                        }
+                        tbody {
+                            for partner in book.iter().cloned() {
+                                tr { key: "{partner.address}",
+                                    td { "{partner.name}" }
+                                    td { class: "mono", style: "word-break: break-all;", "{partner.address}" }
+                                    td { class: "mono", style: "word-break: break-all;", {partner.pubkey.clone().unwrap_or_default()} }
+                                    td { style: "white-space: nowrap;",
+                                        button {
+                                            class: "button outline",
+                                            onclick: {
+                                                let partner = partner.clone();
+                                                move |_| {
+                                                    editing.set(Some(partner.address.clone()));
+                                                    name.set(partner.name.clone());
+                                                    address.set(partner.address.clone());
+                                                    pubkey.set(partner.pubkey.clone().unwrap_or_default());
+                                                }
+                                            },
+                                            "Edit"
+                                        }
+                                        button {
+                                            class: "button outline",
+                                            style: "margin-left: 4px;",
+                                            onclick: {
+                                                let delete = delete.clone();
+                                                let target = partner.address.clone();
+                                                move |_| delete(target.clone())
+                                            },
+                                            "Delete"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div { style: "display: flex; gap: 8px; margin-top: 12px;",
+                    if cfg!(feature = "server") {
+                        button { class: "button outline", disabled: book.is_empty(), onclick: move |_| save_export("csv"), "Export CSV" }
+                        button { class: "button outline", disabled: book.is_empty(), onclick: move |_| save_export("json"), "Export JSON" }
+                    } else {
+                        a { class: "button outline", href: "{csv_uri}", download: "partners.csv", "Download CSV" }
+                        a { class: "button outline", href: "{json_uri}", download: "partners.json", "Download JSON" }
+                    }
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "Import" }
+                p { "A JSON export, or a CSV with a header line naming name, address and, optionally, pubkey. Partners already in the book are updated by address." }
+                if cfg!(feature = "server") {
+                    div { style: "display: flex; gap: 8px; align-items: flex-end;",
+                        div { style: "flex: 1;",
+                            label { "File" }
+                            input {
+                                r#type: "text",
+                                value: "{import_path}",
+                                placeholder: "/path/to/partners.csv",
+                                oninput: move |evt| import_path.set(evt.value()),
+                            }
+                        }
+                        button { class: "button outline", disabled: import_path().trim().is_empty(), onclick: load_file, "Load" }
+                    }
+                }
+                label { "Contents" }
+                textarea {
+                    rows: "6",
+                    value: "{import_text}",
+                    placeholder: "name,address,pubkey\nCafe,<unconfidential address>,",
+                    oninput: move |evt| import_text.set(evt.value()),
+                }
+                button { class: "button", disabled: import_text().trim().is_empty(), onclick: import, "Import" }
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Audit log viewer
+//!
+//! Lists the entries of `audit.jsonl`, newest first, with filters by event, source, date
+//! and free text, and shows whether the hash chain is intact.
+
+use crate::app_core::audit::{self, AuditEntry, AuditEvent, AuditFilter, AuditLog};
+use crate::app_core::Store;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn short(value: &str) -> String {
+    if value.len() > 20 {
+        format!("{}…{}", &value[..10], &value[value.len() - 8..])
+    } else {
+        value.to_string()
+    }
+}
+
+/// One-line summary of what an entry records
+fn details(event: &AuditEvent) -> String {
+    match event {
+        AuditEvent::Compiled { template_id, simf_path, program_sha256, .. } => {
+            format!("{} ({}), program {}", simf_path, template_id, short(program_sha256))
+        }
+        AuditEvent::ContractCreated { template_id, address, cmr, .. } => {
+            format!("{} ({}), CMR {}", address, template_id, short(cmr))
+        }
+        AuditEvent::Funded { address, txid, vout, amount_sats } => {
+            format!("{} sats to {} in {}:{}", amount_sats, address, short(txid), vout)
+        }
+        AuditEvent::PsetCreated { unsigned_txid, destination, amount_sats, fee_sats, .. } => {
+            format!("{} sats to {} (fee {} sats), tx {}", amount_sats, destination, fee_sats, short(unsigned_txid))
+        }
+        AuditEvent::Signed { unsigned_txid, pubkeys, .. } => {
+            let keys = if pubkeys.is_empty() { "no new signatures".to_string() } else { pubkeys.join(", ") };
+            format!("tx {} by {}", short(unsigned_txid), keys)
+        }
+        AuditEvent::Finalized { txid, refund, .. } => {
+            format!("{} {}", if *refund { "refund" } else { "spend" }, txid)
+        }
+        AuditEvent::Broadcast { txid } => txid.clone(),
+        AuditEvent::Failed { step, error } => {
+            format!("{}: {}", step, error.lines().next().unwrap_or_default())
+        }
+    }
+}
+
+#[component]
+pub fn Audit() -> Element {
+    let store = consume_context::<Arc<Store>>();
+    let log = AuditLog::for_store(&store);
+    let path = log.path().display().to_string();
+
+    let mut kind = use_signal(String::new);
+    let mut source = use_signal(String::new);
+    let mut from = use_signal(String::new);
+    let mut to = use_signal(String::new);
+    let mut search = use_signal(String::new);
+    let mut entries = use_resource(move || {
+        let log = AuditLog::for_store(&store);
+        async move { log.entries().map_err(|e| e.to_string()) }
+    });
+
+    let filter = AuditFilter {
+        kind: Some(kind()).filter(|k| !k.is_empty()),
+        source: source(),
+        from: audit::parse_date(&from()),
+        // Through the end of the "to" day
+        to: audit::parse_date(&to()).map(|t| t + 86_399),
+        search: search(),
+    };
+
+    let body = match &*entries.read() {
+        None => rsx! {
+            div { class: "loading", "Loading" }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "status-message",
+                pre { style: "white-space: pre-wrap; font-family: inherit;", "{e}" }
+            }
+        },
+        Some(Ok(all)) => {
+            let problems = audit::verify(all);
+            let intact = problems.is_empty();
+            let shown: Vec<AuditEntry> = all.iter().rev().filter(|e| filter.matches(e)).cloned().collect();
+            rsx! {
+                div { class: if intact { "info-box info" } else { "info-box warning" },
+                    if intact {
+                        p { "Hash chain intact: {all.len()} entries, none edited or removed." }
+                    } else {
+                        p { "The log was modified outside Partnerfy:" }
+                        for problem in problems {
+                            p { "{problem}" }
+                        }
+                    }
+                }
+                p { style: "margin: 12px 0;", "Showing {shown.len()} of {all.len()} entries" }
+                if !shown.is_empty() {
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "Time (UTC)" }
+                                th { "Source" }
+                                th { "Event" }
+                                th { "Details" }
+                                th { "Hash" }
+                            }
+                        }
+                        tbody {
+                            for entry in shown {
+                                tr { key: "{entry.hash}",
+                                    td { "{audit::format_time(entry.timestamp)}" }
+                                    td { "{entry.source}" }
+                                    td { "{entry.event.kind()}" }
+                                    td { style: "word-break: break-all;", "{details(&entry.event)}" }
+                                    td { class: "mono", title: "{entry.hash}", "{short(&entry.hash)}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { id: "audit-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 8px;", "Audit Log" }
+            p { style: "margin-bottom: 16px; color: #666;",
+                "Every compile, contract, funding, PSET, signature, finalization and broadcast, appended to {path}. Signatures are logged by public key only."
+            }
+
+            div { class: "panel-section",
+                div { style: "display: flex; flex-wrap: wrap; gap: 12px; align-items: flex-end;",
+                    div {
+                        label { "Event" }
+                        select {
+                            value: "{kind}",
+                            onchange: move |evt| kind.set(evt.value()),
+                            option { value: "", "All" }
+                            for name in AuditEvent::KINDS {
+                                option { value: "{name}", "{name}" }
+                            }
+                        }
+                    }
+                    div {
+                        label { "Source" }
+                        input {
+                            r#type: "text",
+                            placeholder: "workflow, partner, api",
+                            value: "{source}",
+                            oninput: move |evt| source.set(evt.value()),
+                        }
+                    }
+                    div {
+                        label { "From" }
+                        input { r#type: "date", value: "{from}", oninput: move |evt| from.set(evt.value()) }
+                    }
+                    div {
+                        label { "To" }
+                        input { r#type: "date", value: "{to}", oninput: move |evt| to.set(evt.value()) }
+                    }
+                    div {
+                        label { "Search" }
+                        input {
+                            r#type: "text",
+                            placeholder: "txid, address, public key, hash",
+                            value: "{search}",
+                            oninput: move |evt| search.set(evt.value()),
+                        }
+                    }
+                    button { class: "button", onclick: move |_| entries.restart(), "Refresh" }
+                }
+            }
+
+            {body}
+        }
+    }
+}
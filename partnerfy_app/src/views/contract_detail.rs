@@ -0,0 +1,185 @@
+//! Contract detail page
+//!
+//! Shows every funding and spend of a contract address, with amounts, outputs,
+//! confirmation status and which of the contract's signers signed each spend.
+
+use crate::app_core::error;
+use crate::app_core::history::{self, ContractHistory, HistoryEntry};
+use crate::app_core::{ElementsRPC, Store, TemplateRegistry};
+use crate::Route;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+#[component]
+pub fn ContractDetail(address: String) -> Element {
+    let rpc_context = consume_context::<Arc<ElementsRPC>>();
+    let registry = consume_context::<Arc<TemplateRegistry>>();
+    let store = consume_context::<Arc<Store>>();
+
+    let current = address.clone();
+    let mut history = use_resource(use_reactive!(|(current,)| {
+        let rpc_context = rpc_context.clone();
+        let registry = registry.clone();
+        let store = store.clone();
+        async move {
+            let record = store.find_contract(&current).map_err(|e| e.to_string())?;
+            history::contract_history(&rpc_context, &registry, record.as_ref(), &current)
+                .await
+                .map_err(|e| error::describe(&e))
+        }
+    }));
+
+    let body = match &*history.read() {
+        None => rsx! {
+            div { class: "loading", "Loading" }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "status-message",
+                pre { style: "white-space: pre-wrap; font-family: inherit;", "{e}" }
+            }
+        },
+        Some(Ok(h)) => rsx! {
+            HistorySummary { history: h.clone() }
+            if h.entries.is_empty() {
+                div { class: "panel-section",
+                    p { "No transactions found for this address yet. A funding may take a moment to show up in the Blockstream index." }
+                }
+            }
+            for entry in h.entries.iter().cloned() {
+                HistoryItem { key: "{entry.txid}", entry }
+            }
+        },
+    };
+
+    rsx! {
+        div { id: "contract-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 24px;", "Contract History" }
+
+            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all; margin-bottom: 16px;",
+                "{address}"
+            }
+
+            button {
+                class: "button",
+                style: "margin-bottom: 16px;",
+                onclick: move |_| history.restart(),
+                "Refresh"
+            }
+
+            {body}
+        }
+    }
+}
+
+#[component]
+fn HistorySummary(history: ContractHistory) -> Element {
+    let balance = history.balance_sats();
+    let tip = history
+        .tip_height
+        .map(|h| h.to_string())
+        .unwrap_or_else(|| "unknown (node and Blockstream API unreachable)".to_string());
+    let template = history.template_id.clone().unwrap_or_else(|| "(no contract record)".to_string());
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Summary" }
+            div { class: "info-box info",
+                p { "Template: {template}" }
+                p { "Transactions: {history.entries.len()}" }
+                p { "Unspent balance: {btc(balance)} L-BTC ({balance} sats)" }
+                p { "Tip height: {tip}" }
+            }
+            if !history.known_keys.is_empty() {
+                p { style: "font-weight: 600; margin-top: 16px; margin-bottom: 4px;", "Keys checked in witnesses:" }
+                for (label, pubkey) in history.known_keys.iter().cloned() {
+                    p { key: "{label}", style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;",
+                        "{label}: {pubkey}"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn HistoryItem(entry: HistoryEntry) -> Element {
+    let received = entry.received_sats();
+    let spent = entry.spent_sats();
+    let fee = entry.fee_sats.map(|f| format!("{} sats", f)).unwrap_or_else(|| "unknown".to_string());
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "{entry.kind()}" }
+
+            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all;",
+                "{entry.txid}"
+            }
+            p { style: "margin-top: 8px;",
+                a {
+                    href: format!("https://blockstream.info/liquidtestnet/tx/{}", entry.txid),
+                    target: "_blank",
+                    style: "color: #0066cc; text-decoration: underline;",
+                    "View on Blockstream Explorer →"
+                }
+            }
+
+            div { class: "info-box info", style: "margin-top: 16px;",
+                p { "Status: {entry.status}" }
+                if received > 0 {
+                    p { "Paid to contract: {btc(received)} L-BTC ({received} sats)" }
+                }
+                if spent > 0 {
+                    p { "Spent from contract: {btc(spent)} L-BTC ({spent} sats)" }
+                }
+                p { "Fee: {fee}" }
+            }
+
+            p { style: "font-weight: 600; margin-top: 16px; margin-bottom: 4px;", "Outputs:" }
+            for output in entry.outputs.iter().cloned() {
+                p { key: "{output.index}", style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;",
+                    {
+                        let value = output
+                            .value_sats
+                            .map(|v| format!("{} L-BTC ({} sats)", btc(v), v))
+                            .unwrap_or_else(|| "confidential".to_string());
+                        let to = if output.fee {
+                            "fee".to_string()
+                        } else {
+                            output.address.clone().unwrap_or_else(|| "(no address)".to_string())
+                        };
+                        let tag = if output.to_contract { " [contract]" } else { "" };
+                        format!("Output {}: {} to {}{}", output.index, value, to, tag)
+                    }
+                    if output.to_contract {
+                        Link {
+                            to: Route::PromoterLineagePage { txid: entry.txid.clone(), vout: output.index },
+                            style: "margin-left: 8px; color: #0066cc; text-decoration: underline;",
+                            "Follow lineage →"
+                        }
+                    }
+                }
+            }
+
+            if !entry.spends.is_empty() {
+                p { style: "font-weight: 600; margin-top: 16px; margin-bottom: 4px;", "Contract inputs:" }
+                for spend in entry.spends.iter().cloned() {
+                    div { key: "{spend.input}", style: "margin-bottom: 8px;",
+                        p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;",
+                            "Input {spend.input}: {spend.prev_txid}:{spend.prev_vout}"
+                        }
+                        if !spend.signers.is_empty() {
+                            p { "Signed by: {spend.signers.join(\", \")}" }
+                        }
+                        if let Some(note) = spend.note.clone() {
+                            p { style: "font-size: 0.875rem; color: #666;", "{note}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
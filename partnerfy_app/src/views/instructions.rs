@@ -135,7 +135,7 @@ pub fn Instructions() -> Element {
                                 "0. Generate Voucher Simplicity Source File"
                             }
                             p { style: "color: #666; margin-bottom: 8px; line-height: 1.6;",
-                                "Enter the output path for your .simf file and provide three 32-byte public keys (64 hex characters each) for the three participants. Click 'Generate cov_p2ms.simf File' to create the Simplicity source file with covenant structure."
+                                "Enter the output path for your .simf file and provide three 32-byte public keys (64 hex characters each) for the three participants. Optionally set a maximum payment per redemption; the covenant then rejects any spend that pays more than the cap to Output 0. Click 'Generate cov_p2ms.simf File' to create the Simplicity source file with covenant structure."
                             }
                         }
                         div {
@@ -186,6 +186,22 @@ pub fn Instructions() -> Element {
                                 "Once the transaction is finalized, click 'Broadcast Transaction' to send it to the Liquid Network. The covenant ensures that change (Output 1) automatically returns to the same covenant, maintaining the spending restrictions."
                             }
                         }
+                        div {
+                            h3 { style: "font-size: 1.25rem; font-weight: 600; margin-bottom: 8px; color: #00090C;",
+                                "Optional: Oracle-Attested Redemption"
+                            }
+                            p { style: "color: #666; margin-bottom: 8px; line-height: 1.6;",
+                                "If the contract requires an oracle attestation, start the oracle signer (cargo run --bin oracle_signer -- --key <hex> --invoices <file>), create the PSET in step 4, then enter the partner invoice ID and click 'Request Attestation' before signing. The oracle signs the invoice together with the payment output, its amount and the voucher UTXO being spent, and the signature is added to the witness."
+                            }
+                        }
+                        div {
+                            h3 { style: "font-size: 1.25rem; font-weight: 600; margin-bottom: 8px; color: #00090C;",
+                                "Optional: Reclaim Expired Vouchers"
+                            }
+                            p { style: "color: #666; margin-bottom: 8px; line-height: 1.6;",
+                                "If the contract was generated with a promoter refund path, the promoter can sweep unredeemed vouchers once the refund lock (block height or UNIX time) has passed. The refund transaction sets nLockTime to the lock and the input sequence to 0xfffffffe, and only needs the promoter's signature."
+                            }
+                        }
                     }
                 }
 
@@ -0,0 +1,254 @@
+//! Bulk voucher issuance page
+//!
+//! The promoter imports a CSV of participants and amounts, reviews how the vouchers are
+//! split into transactions and what the wallet will pay, then funds and broadcasts the
+//! batches one after the other (see `app_core::issuance`).
+
+use crate::app_core::error;
+use crate::app_core::issuance::{self, Batch, IssuedBatch};
+use crate::app_core::{platform, ElementsRPC, Store};
+use dioxus::prelude::*;
+use std::path::Path;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn short(value: &str) -> String {
+    if value.len() > 20 {
+        format!("{}…{}", &value[..10], &value[value.len() - 8..])
+    } else {
+        value.to_string()
+    }
+}
+
+#[component]
+pub fn Issuance() -> Element {
+    let rpc_context = consume_context::<Arc<ElementsRPC>>();
+    let store = consume_context::<Arc<Store>>();
+    let contracts = store.load().map(|s| s.contracts).unwrap_or_default();
+
+    let mut csv_path = use_signal(String::new);
+    let mut csv_text = use_signal(String::new);
+    let mut shared = use_signal(String::new);
+    let mut max_outputs = use_signal(|| issuance::DEFAULT_MAX_OUTPUTS.to_string());
+    let mut fee_rate = use_signal(|| issuance::DEFAULT_FEE_RATE.to_string());
+    let mut batches = use_signal(Vec::<Batch>::new);
+    let mut issued = use_signal(Vec::<IssuedBatch>::new);
+    let mut status_message = use_signal(String::new);
+    let mut is_loading = use_signal(|| false);
+
+    let balance = use_resource({
+        let rpc_context = rpc_context.clone();
+        move || {
+            let rpc_context = rpc_context.clone();
+            async move { rpc_context.get_balance().await.map_err(|e| error::describe(&e)) }
+        }
+    });
+
+    let load_file = move |_| match platform::read_file(Path::new(csv_path().trim())) {
+        Ok(Some(text)) => {
+            csv_text.set(text);
+            status_message.set(String::new());
+        }
+        Ok(None) => status_message.set(format!("File not found: {}", csv_path())),
+        Err(e) => status_message.set(e.to_string()),
+    };
+
+    let make_plan = {
+        let store = store.clone();
+        move |_| {
+            issued.set(Vec::new());
+            batches.set(Vec::new());
+            let result = (|| {
+                let max = max_outputs()
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| anyhow::anyhow!("Please enter a whole number of outputs per transaction"))?;
+                let rate = fee_rate()
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| anyhow::anyhow!("Please enter a fee rate in sat/vB, e.g. 0.1"))?;
+                let rows = issuance::parse_csv(&csv_text())?;
+                let state = store.load()?;
+                let shared = shared();
+                let allocations = issuance::allocate(&state, &rows, Some(shared.as_str()).filter(|s| !s.is_empty()))?;
+                issuance::plan(allocations, max, rate)
+            })();
+            match result {
+                Ok(plan) => {
+                    status_message.set(format!(
+                        "{} vouchers in {} transaction(s). Review the plan, then fund and issue.",
+                        plan.iter().map(|b| b.allocations.len()).sum::<usize>(),
+                        plan.len()
+                    ));
+                    batches.set(plan);
+                }
+                Err(e) => status_message.set(error::describe(&e)),
+            }
+        }
+    };
+
+    let issue = move |_| {
+        let rpc_context = rpc_context.clone();
+        let store = store.clone();
+        spawn(async move {
+            is_loading.set(true);
+            let rate = fee_rate().trim().parse::<f64>().unwrap_or(issuance::DEFAULT_FEE_RATE);
+            let done: Vec<usize> = issued.read().iter().map(|b| b.index).collect();
+            let pending: Vec<Batch> = batches().into_iter().filter(|b| !done.contains(&b.index)).collect();
+            for batch in pending {
+                let progress = move |message: String| {
+                    let mut status_message = status_message;
+                    status_message.set(message);
+                };
+                match issuance::issue_batch(&rpc_context, &store, &batch, rate, &progress).await {
+                    Ok(result) => issued.write().push(result),
+                    Err(e) => {
+                        status_message.set(format!(
+                            "Batch {} failed: {}\n\nBatches already broadcast are registered. Fix the problem and press \"Fund and Issue\" again to continue with the remaining batches.",
+                            batch.index + 1,
+                            error::describe(&e)
+                        ));
+                        is_loading.set(false);
+                        return;
+                    }
+                }
+            }
+            let count: usize = issued.read().iter().map(|b| b.vouchers.len()).sum();
+            status_message.set(format!("Issued {} vouchers. They now show up in the participants' wallets and on the dashboard.", count));
+            is_loading.set(false);
+        });
+    };
+
+    let plan = batches();
+    let total: u64 = plan.iter().map(Batch::total_sats).sum();
+    let fees: u64 = plan.iter().map(|b| b.estimated_fee_sats).sum();
+    let required = issuance::required_sats(&plan);
+    let results = issued();
+    let remaining = plan.len() - results.len().min(plan.len());
+
+    rsx! {
+        div { id: "issuance-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 8px;", "Bulk Voucher Issuance" }
+            p { style: "margin-bottom: 16px; color: #666;",
+                "Issue vouchers to many participants at once. Each transaction splits the node wallet's L-BTC into voucher outputs, and every voucher is registered against its participant."
+            }
+
+            div { class: "panel-section",
+                h2 { "1. Participants" }
+                p { "A CSV with a header line. Columns: pubkey (participant key), amount (L-BTC) and, optionally, contract (a recorded contract address)." }
+                div { style: "display: flex; gap: 8px; align-items: flex-end;",
+                    div { style: "flex: 1;",
+                        label { "CSV file" }
+                        input {
+                            r#type: "text",
+                            value: "{csv_path}",
+                            placeholder: "/path/to/participants.csv",
+                            oninput: move |evt| csv_path.set(evt.value()),
+                        }
+                    }
+                    button { class: "button outline", disabled: csv_path().trim().is_empty(), onclick: load_file, "Load" }
+                }
+                label { "CSV contents" }
+                textarea {
+                    rows: "8",
+                    value: "{csv_text}",
+                    placeholder: "pubkey,amount,contract\n<participant key hex>,0.001,\n<participant key hex>,0.002,<contract address>",
+                    oninput: move |evt| csv_text.set(evt.value()),
+                }
+            }
+
+            div { class: "panel-section",
+                h2 { "2. Contracts and Fees" }
+                label { "Contract" }
+                select {
+                    value: "{shared}",
+                    onchange: move |evt| shared.set(evt.value()),
+                    option { value: "", "Per participant (the recorded contract with the participant's key)" }
+                    for contract in contracts {
+                        option { value: "{contract.address}", "{contract.template_id}: {contract.address}" }
+                    }
+                }
+                p { style: "font-size: 0.875rem; color: #666;", "A contract column in the CSV overrides this for its row." }
+                div { style: "display: flex; gap: 12px;",
+                    div {
+                        label { "Outputs per transaction" }
+                        input { r#type: "number", min: "1", value: "{max_outputs}", oninput: move |evt| max_outputs.set(evt.value()) }
+                    }
+                    div {
+                        label { "Fee rate (sat/vB)" }
+                        input { r#type: "text", value: "{fee_rate}", oninput: move |evt| fee_rate.set(evt.value()) }
+                    }
+                }
+                button { class: "button", disabled: is_loading() || csv_text().trim().is_empty(), onclick: make_plan, "Plan Issuance" }
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;", "{status_message}" }
+                }
+            }
+
+            if !plan.is_empty() {
+                div { class: "panel-section",
+                    h2 { "3. Fund and Issue" }
+                    p { "Vouchers: {btc(total)} L-BTC ({total} sats). Estimated fees: {fees} sats. The wallet needs about {btc(required)} L-BTC." }
+                    match &*balance.read() {
+                        Some(Ok(available)) => rsx! {
+                            p { "Wallet balance: {available} L-BTC" }
+                            if (available * 100_000_000.0).round() < required as f64 {
+                                div { class: "info-box warning",
+                                    p { "The wallet balance does not cover this issuance. Fund the node's wallet first." }
+                                }
+                            }
+                        },
+                        Some(Err(e)) => rsx! { p { "Could not read the wallet balance: {e}" } },
+                        None => rsx! { p { "Reading the wallet balance..." } },
+                    }
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "Transaction" }
+                                th { "Vouchers" }
+                                th { "Amount (sats)" }
+                                th { "Est. fee (sats)" }
+                                th { "Status" }
+                            }
+                        }
+                        tbody {
+                            for batch in plan.iter() {
+                                tr { key: "{batch.index}",
+                                    td { "{batch.index + 1}" }
+                                    td { "{batch.allocations.len()}" }
+                                    td { "{batch.total_sats()}" }
+                                    td { "{batch.estimated_fee_sats}" }
+                                    td {
+                                        if let Some(done) = results.iter().find(|r| r.index == batch.index) {
+                                            a {
+                                                href: "https://blockstream.info/liquidtestnet/tx/{done.txid}",
+                                                target: "_blank",
+                                                class: "mono",
+                                                "Broadcast {short(&done.txid)} (fee {done.fee_sats} sats)"
+                                            }
+                                        } else {
+                                            "Pending"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    button {
+                        class: "button",
+                        style: "margin-top: 16px;",
+                        disabled: is_loading() || remaining == 0,
+                        onclick: issue,
+                        if is_loading() { "Issuing..." } else { "Fund and Issue ({remaining} transactions)" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,18 @@
 //! Landing page for Partnerfy
+//!
+//! Introduces the app and lets the user pick their role, which decides the route tree
+//! and navbar they get.
 
+use super::role_home;
+use crate::app_core::{Role, Store};
 use dioxus::prelude::*;
+use std::sync::Arc;
 
 #[component]
 pub fn Landing() -> Element {
+    let store = consume_context::<Arc<Store>>();
+    let last_role = store.load().ok().and_then(|state| state.role);
+
     rsx! {
         div { style: "min-height: 100vh; background-color: var(--background);",
             // Header
@@ -37,18 +46,39 @@ pub fn Landing() -> Element {
                     p { style: "font-size: 1.25rem; color: var(--muted-foreground); margin-bottom: 40px; max-width: 600px; margin-left: auto; margin-right: auto; line-height: 1.6;",
                         "Send restricted LBTC to event participants that can only be spent with pre-approved partners. Secure, transparent, and powered by SimplicityHL."
                     }
-                    div { style: "display: flex; flex-direction: row; gap: 16px; align-items: center; justify-content: center; flex-wrap: wrap;",
-                        Link {
-                            to: crate::Route::P2MSPage {},
-                            class: "button",
-                            style: "font-size: 1.125rem; padding: 16px 32px;",
-                            "Multisig →"
-                        }
-                        Link {
-                            to: crate::Route::VoucherPage {},
-                            class: "button outline",
-                            style: "font-size: 1.125rem; padding: 16px 32px;",
-                            "Voucher"
+                    h2 { style: "font-size: 1.5rem; font-weight: 600; margin-bottom: 16px; color: var(--foreground);",
+                        "Who are you?"
+                    }
+                    div { style: "display: grid; grid-template-columns: repeat(auto-fit, minmax(220px, 1fr)); gap: 16px; text-align: left;",
+                        for role in Role::ALL {
+                            button {
+                                class: if last_role == Some(role) { "panel-section role-card selected" } else { "panel-section role-card" },
+                                onclick: {
+                                    let store = store.clone();
+                                    move |_| {
+                                        // Remember the choice so the next visit highlights it
+                                        let saved = store.update(|state| {
+                                            state.role = Some(role);
+                                            Ok(())
+                                        });
+                                        if let Err(e) = saved {
+                                            tracing::warn!("Failed to remember the role: {}", e);
+                                        }
+                                        navigator().push(role_home(role));
+                                    }
+                                },
+                                h3 { style: "font-size: 1.25rem; font-weight: 600; margin-bottom: 8px; color: var(--foreground);",
+                                    "{role.label()} →"
+                                }
+                                p { style: "color: var(--muted-foreground); line-height: 1.6;", "{role.description()}" }
+                                if role == Role::Promoter && !cfg!(feature = "server") {
+                                    p { style: "color: var(--muted-foreground); font-size: 0.875rem; margin-top: 8px;",
+                                        "Desktop app only"
+                                    }
+                                } else if last_role == Some(role) {
+                                    p { style: "color: var(--accent); font-size: 0.875rem; margin-top: 8px;", "Last used" }
+                                }
+                            }
                         }
                     }
                 }
@@ -0,0 +1,169 @@
+//! Covenant lineage page
+//!
+//! Follows a voucher from its funding outpoint through every redemption, showing the
+//! balance left in the covenant at each hop and the payment each redemption made.
+
+use crate::app_core::error;
+use crate::app_core::lineage::{self, Lineage, LineageEnd, LineageHop, Payment};
+use crate::app_core::ElementsRPC;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn amount(value_sats: Option<u64>) -> String {
+    value_sats
+        .map(|v| format!("{} L-BTC ({} sats)", btc(v), v))
+        .unwrap_or_else(|| "confidential".to_string())
+}
+
+fn payment_text(payment: &Payment) -> String {
+    format!(
+        "{} to {}",
+        amount(payment.value_sats),
+        payment.address.as_deref().unwrap_or("(no address)")
+    )
+}
+
+#[component]
+pub fn LineageView(txid: String, vout: u32) -> Element {
+    let rpc_context = consume_context::<Arc<ElementsRPC>>();
+
+    let (start_txid, start_vout) = (txid.clone(), vout);
+    let mut lineage = use_resource(use_reactive!(|(start_txid, start_vout)| {
+        let rpc_context = rpc_context.clone();
+        async move {
+            lineage::follow_lineage(&rpc_context, &start_txid, start_vout)
+                .await
+                .map_err(|e| error::describe(&e))
+        }
+    }));
+
+    let body = match &*lineage.read() {
+        None => rsx! {
+            div { class: "loading", "Loading" }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "status-message",
+                pre { style: "white-space: pre-wrap; font-family: inherit;", "{e}" }
+            }
+        },
+        Some(Ok(l)) => rsx! {
+            LineageSummary { lineage: l.clone() }
+            for (i, hop) in l.hops.iter().cloned().enumerate() {
+                HopItem { key: "{hop.txid}:{hop.vout}", index: i, hop }
+            }
+            LineageTail { end: l.end.clone() }
+        },
+    };
+
+    rsx! {
+        div { id: "lineage-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 24px;", "Voucher Lineage" }
+
+            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all; margin-bottom: 16px;",
+                "Starting at {txid}:{vout}"
+            }
+
+            button {
+                class: "button",
+                style: "margin-bottom: 16px;",
+                onclick: move |_| lineage.restart(),
+                "Refresh"
+            }
+
+            {body}
+        }
+    }
+}
+
+#[component]
+fn LineageSummary(lineage: Lineage) -> Element {
+    let address = lineage.address.clone().unwrap_or_else(|| lineage.script_pubkey.clone());
+    let remaining = lineage.remaining_sats();
+    let paid = lineage.paid_sats();
+    let state = match &lineage.end {
+        LineageEnd::Unspent => "Open: the last hop is unspent",
+        LineageEnd::Closed { .. } => "Closed: the last hop was spent without change back to the covenant",
+        LineageEnd::Truncated => "Stopped following after the maximum number of hops",
+    };
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Summary" }
+            div { class: "info-box info",
+                p { style: "word-break: break-all;", "Covenant: {address}" }
+                p { "State: {state}" }
+                p { "Redemptions: {lineage.redemptions()}" }
+                p { "Paid out (output 0): {btc(paid)} L-BTC ({paid} sats)" }
+                p { "Remaining balance: {btc(remaining)} L-BTC ({remaining} sats)" }
+            }
+        }
+    }
+}
+
+#[component]
+fn HopItem(index: usize, hop: LineageHop) -> Element {
+    let title = if index == 0 { "Funding".to_string() } else { format!("Redemption {}", index) };
+    let remaining = amount(hop.value_sats);
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "{title}" }
+            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all;",
+                "{hop.txid}:{hop.vout}"
+            }
+            div { class: "info-box info", style: "margin-top: 8px;",
+                if let Some(payment) = hop.payment.as_ref() {
+                    p { style: "word-break: break-all;", "Payment (output 0): {payment_text(payment)}" }
+                }
+                p { "Remaining in covenant: {remaining}" }
+                if let Some(fee) = hop.fee_sats {
+                    p { "Fee: {fee} sats" }
+                }
+                p { "Status: {hop.status}" }
+            }
+            p { style: "margin-top: 8px;",
+                a {
+                    href: format!("https://blockstream.info/liquidtestnet/tx/{}", hop.txid),
+                    target: "_blank",
+                    style: "color: #0066cc; text-decoration: underline;",
+                    "View on Blockstream Explorer →"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn LineageTail(end: LineageEnd) -> Element {
+    match end {
+        LineageEnd::Unspent => rsx! {
+            div { class: "panel-section",
+                p { "The last hop is unspent: this is the voucher's current balance." }
+            }
+        },
+        LineageEnd::Truncated => rsx! {
+            div { class: "panel-section",
+                p { "Stopped after {lineage::MAX_HOPS} hops. Start again from the last hop above to continue." }
+            }
+        },
+        LineageEnd::Closed { txid, status, outputs } => rsx! {
+            div { class: "panel-section",
+                h2 { "Closed" }
+                p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; word-break: break-all;",
+                    "{txid}"
+                }
+                div { class: "info-box info", style: "margin-top: 8px;",
+                    p { "Spent without change back to the covenant (for example a promoter refund)." }
+                    for (i, output) in outputs.iter().enumerate() {
+                        p { key: "{i}", style: "word-break: break-all;", "Output {i}: {payment_text(output)}" }
+                    }
+                    p { "Status: {status}" }
+                }
+            }
+        },
+    }
+}
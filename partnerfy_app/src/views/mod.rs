@@ -1,8 +1,8 @@
 //! The views module contains the components for all Layouts and Routes for our app. Each layout and route in our [`Route`]
 //! enum will render one of these components.
 //!
-//! The [`Navbar`] component will be rendered on all pages of our app since every page is under the layout. The layout defines
-//! a common wrapper around all child routes.
+//! Each role (promoter, participant, partner) has its own route tree under a navbar layout ([`PromoterNavbar`],
+//! [`ParticipantNavbar`], [`PartnerNavbar`]). The layout defines a common wrapper around all child routes.
 
 mod landing;
 pub use landing::Landing;
@@ -16,5 +16,32 @@ pub use p2ms::P2MS;
 mod voucher;
 pub use voucher::Voucher;
 
+mod contract_detail;
+pub use contract_detail::ContractDetail;
+
+mod lineage;
+pub use lineage::LineageView;
+
+mod promoter;
+pub use promoter::Promoter;
+
+mod participant;
+pub use participant::Participant;
+
+mod partner;
+pub use partner::Partner;
+
+mod audit;
+pub use audit::Audit;
+
+mod issuance;
+pub use issuance::Issuance;
+
+mod reports;
+pub use reports::Reports;
+
+mod address_book;
+pub use address_book::AddressBook;
+
 mod navbar;
-pub use navbar::Navbar;
+pub use navbar::{role_home, ParticipantNavbar, PartnerNavbar, PromoterNavbar};
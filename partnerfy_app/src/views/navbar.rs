@@ -1,13 +1,52 @@
+use crate::app_core::Role;
 use crate::Route;
 use dioxus::prelude::*;
 
 const NAVBAR_CSS: Asset = asset!("/assets/styling/navbar.css");
 
-/// The Navbar component that will be rendered on all pages of our app since every page is under the layout.
-/// 
-/// This layout component wraps the UI of role-based routes in a common navbar.
+/// Start page of a role's route tree
+pub fn role_home(role: Role) -> Route {
+    match role {
+        Role::Promoter => Route::PromoterPage {},
+        Role::Participant => Route::ParticipantPage {},
+        Role::Partner => Route::PartnerPage {},
+    }
+}
+
+/// Pages a role can reach from its navbar
+///
+/// Each role only sees its own actions: funding, contract history and the audit log are
+/// promoter pages, so participants and partners never reach them.
+fn role_links(role: Role) -> Vec<(Route, &'static str)> {
+    match role {
+        Role::Promoter => vec![
+            (Route::PromoterPage {}, "Dashboard"),
+            (Route::P2MSPage {}, "P2MS"),
+            (Route::VoucherPage {}, "Voucher"),
+            (Route::IssuancePage {}, "Issue"),
+            (Route::PromoterPartnersPage {}, "Partners"),
+            (Route::PromoterReportsPage {}, "Reports"),
+            (Route::AuditPage {}, "Audit"),
+        ],
+        Role::Participant => vec![
+            (Route::ParticipantPage {}, "Wallet"),
+            (Route::ParticipantPartnersPage {}, "Partners"),
+        ],
+        Role::Partner => vec![
+            (Route::PartnerPage {}, "Redemptions"),
+            (Route::PartnerReportsPage {}, "Statements"),
+        ],
+    }
+}
+
+/// Navbar shared by the role layouts
+///
+/// The role layouts below wrap the UI of their routes in this navbar.
 #[component]
-pub fn Navbar() -> Element {
+fn RoleNavbar(role: Role) -> Element {
+    // Issuing vouchers compiles contracts, which the web build cannot do
+    let available = role != Role::Promoter || cfg!(feature = "server");
+
     rsx! {
         document::Link { rel: "stylesheet", href: NAVBAR_CSS }
 
@@ -16,19 +55,43 @@ pub fn Navbar() -> Element {
             Link {
                 to: Route::LandingPage {},
                 style: "margin-right: auto;",
+                title: "Switch role",
                 "🏠 Home"
             }
-            Link {
-                to: Route::P2MSPage {},
-                "P2MS"
-            }
-            Link {
-                to: Route::VoucherPage {},
-                "Voucher"
+            span { class: "role-badge", "{role.label()}" }
+            if available {
+                for (to, label) in role_links(role) {
+                    Link { to, "{label}" }
+                }
             }
         }
 
-        // The `Outlet` component is used to render the next component inside the layout.
-        Outlet::<Route> {}
+        if available {
+            // The `Outlet` component is used to render the next component inside the layout.
+            Outlet::<Route> {}
+        } else {
+            div { class: "info-box warning", style: "max-width: 800px; margin: 40px auto;",
+                p { "The promoter tools compile contracts with simc and are not available in the web app." }
+                p { "Use the desktop app or the partnerfy command line to issue vouchers." }
+            }
+        }
     }
 }
+
+/// Layout of the promoter routes
+#[component]
+pub fn PromoterNavbar() -> Element {
+    rsx! { RoleNavbar { role: Role::Promoter } }
+}
+
+/// Layout of the participant routes
+#[component]
+pub fn ParticipantNavbar() -> Element {
+    rsx! { RoleNavbar { role: Role::Participant } }
+}
+
+/// Layout of the partner routes
+#[component]
+pub fn PartnerNavbar() -> Element {
+    rsx! { RoleNavbar { role: Role::Partner } }
+}
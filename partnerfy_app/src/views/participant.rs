@@ -0,0 +1,554 @@
+//! Participant voucher wallet page
+//!
+//! Lists the vouchers a participant holds with their balances, starts redemptions at
+//! partners from the address book, and follows them until they are on chain. Technical
+//! details (outpoints, CMRs, PSETs) stay hidden unless the participant asks for them.
+
+use crate::app_core::error;
+use crate::app_core::participant::{self, RedemptionOrder, RedemptionStatus, Wallet, WalletVoucher};
+use crate::app_core::sighash::SigningMode;
+use crate::app_core::{ElementsRPC, HalWrapper, Partner, RedemptionRequest, Store, TemplateRegistry, Workflow};
+use crate::app_core::qr::PaymentUri;
+use crate::components::{QrCode, QrImport, SigningModeSelect};
+use crate::Route;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn short(value: &str) -> String {
+    if value.len() > 20 {
+        format!("{}…{}", &value[..10], &value[value.len() - 8..])
+    } else {
+        value.to_string()
+    }
+}
+
+/// The workflow services redemptions run against
+fn redemption_workflow() -> Workflow {
+    Workflow::new(
+        participant::WORKFLOW_PREFIX,
+        consume_context::<Arc<ElementsRPC>>(),
+        consume_context::<Arc<HalWrapper>>(),
+        consume_context::<Arc<TemplateRegistry>>(),
+        consume_context::<Arc<Store>>(),
+    )
+}
+
+fn partner_name(partners: &[Partner], address: &str) -> String {
+    partners
+        .iter()
+        .find(|p| p.address == address)
+        .map(|p| p.name.clone())
+        .unwrap_or_else(|| short(address))
+}
+
+#[component]
+pub fn Participant() -> Element {
+    let rpc_context = consume_context::<Arc<ElementsRPC>>();
+    let store = consume_context::<Arc<Store>>();
+
+    let saved = use_signal({
+        let store = store.clone();
+        move || store.load().unwrap_or_default()
+    });
+    let keys = participant::participant_keys(&saved.read());
+    let mut pubkey = use_signal(|| keys.first().cloned().unwrap_or_default());
+    let mut show_details = use_signal(|| false);
+    let mut selected = use_signal(|| None::<WalletVoucher>);
+    let status_message = use_signal(String::new);
+    let is_loading = use_signal(|| false);
+
+    let current = pubkey();
+    let mut wallet = use_resource(use_reactive!(|(current,)| {
+        let rpc_context = rpc_context.clone();
+        let store = store.clone();
+        async move {
+            let state = store.load().map_err(|e| e.to_string())?;
+            let mut saved = saved;
+            saved.set(state.clone());
+            Ok::<_, String>(participant::load_wallet(&rpc_context, &state, &current).await)
+        }
+    }));
+
+    let partners = saved.read().partners.clone();
+    let body = match &*wallet.read() {
+        None => rsx! {
+            div { class: "loading", "Loading your vouchers" }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "status-message",
+                pre { style: "white-space: pre-wrap; font-family: inherit;", "{e}" }
+            }
+        },
+        Some(Ok(w)) => rsx! {
+            Vouchers { wallet: w.clone(), show_details: show_details(), selected }
+            if let Some(voucher) = selected() {
+                RedeemForm {
+                    participant_pubkey: pubkey(),
+                    voucher,
+                    partners: partners.clone(),
+                    show_details: show_details(),
+                    on_started: move |_| {
+                        selected.set(None);
+                        wallet.restart();
+                    },
+                    status_message,
+                    is_loading,
+                }
+            }
+            Pending {
+                pending: w.pending.clone(),
+                partners: partners.clone(),
+                show_details: show_details(),
+                on_finished: move |_| wallet.restart(),
+                status_message,
+                is_loading,
+            }
+            Completed { wallet: w.clone(), show_details: show_details() }
+        },
+    };
+
+    rsx! {
+        div { id: "participant-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 24px;", "My Vouchers" }
+
+            div { class: "panel-section",
+                if keys.is_empty() {
+                    p { "No vouchers have been issued to you yet. Ask the promoter to register your vouchers." }
+                } else {
+                    label { "Who are you?" }
+                    select {
+                        onchange: move |evt| {
+                            selected.set(None);
+                            pubkey.set(evt.value().to_string());
+                        },
+                        for (i, key) in keys.iter().enumerate() {
+                            option { key: "{key}", value: "{key}", selected: *key == pubkey(), "Participant {i + 1} ({short(key)})" }
+                        }
+                    }
+                }
+                div { style: "display: flex; gap: 16px; align-items: center; margin-top: 16px;",
+                    button {
+                        class: "button",
+                        onclick: move |_| wallet.restart(),
+                        "Refresh"
+                    }
+                    label { style: "display: flex; gap: 8px; align-items: center;",
+                        input {
+                            r#type: "checkbox",
+                            checked: show_details(),
+                            onchange: move |evt| show_details.set(evt.checked()),
+                        }
+                        "Show technical details"
+                    }
+                }
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;", "{status_message}" }
+                }
+            }
+            if is_loading() {
+                div { class: "loading", "Working" }
+            }
+
+            {body}
+        }
+    }
+}
+
+#[component]
+fn Vouchers(wallet: Wallet, show_details: bool, selected: Signal<Option<WalletVoucher>>) -> Element {
+    let total = wallet.balance_sats();
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Balance: {btc(total)} L-BTC" }
+            if wallet.vouchers.is_empty() {
+                p { "You have no vouchers yet." }
+            }
+            div { class: "voucher-list",
+                for (i, v) in wallet.vouchers.iter().cloned().enumerate() {
+                    div {
+                        key: "{v.voucher.txid}:{v.voucher.vout}",
+                        class: if selected().is_some_and(|s| s.voucher.txid == v.voucher.txid && s.voucher.vout == v.voucher.vout) { "voucher-item selected" } else { "voucher-item" },
+                        onclick: {
+                            let v = v.clone();
+                            move |_| {
+                                if v.can_redeem() {
+                                    selected.set(Some(v.clone()));
+                                }
+                            }
+                        },
+                        div { class: "voucher-id", "Voucher {i + 1}" }
+                        div { class: "voucher-amount",
+                            match &v.lineage {
+                                Ok(_) if v.balance_sats() > 0 => format!("{} L-BTC left", btc(v.balance_sats())),
+                                Ok(_) => "Used up".to_string(),
+                                Err(_) => "Balance unavailable, try Refresh".to_string(),
+                            }
+                        }
+                        if v.contract.is_none() {
+                            p { style: "font-size: 0.875rem; color: #666;", "This voucher's contract is not in this app, so it cannot be spent from here." }
+                        } else if v.can_redeem() {
+                            p { style: "font-size: 0.875rem; color: #666;", "Click to spend at a partner" }
+                        }
+                        if show_details {
+                            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all; margin-top: 8px;",
+                                "Issued in {v.voucher.txid}:{v.voucher.vout}"
+                            }
+                            if let Some((txid, vout, _)) = v.current_utxo() {
+                                p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;", "Current UTXO: {txid}:{vout}" }
+                            }
+                            if let Some(contract) = v.contract.as_ref() {
+                                p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;", "Contract: {contract.address} (CMR {contract.cmr})" }
+                            }
+                            if let Err(e) = &v.lineage {
+                                p { style: "font-size: 0.8rem; word-break: break-all;", "{e}" }
+                            }
+                            Link {
+                                to: Route::ParticipantLineagePage { txid: v.voucher.txid.clone(), vout: v.voucher.vout },
+                                style: "color: #0066cc; text-decoration: underline; font-size: 0.875rem;",
+                                "Follow voucher lineage →"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn RedeemForm(
+    participant_pubkey: String,
+    voucher: WalletVoucher,
+    partners: Vec<Partner>,
+    show_details: bool,
+    on_started: EventHandler<()>,
+    status_message: Signal<String>,
+    is_loading: Signal<bool>,
+) -> Element {
+    let mut partner_address = use_signal(|| partners.first().map(|p| p.address.clone()).unwrap_or_default());
+    let mut amount = use_signal(String::new);
+    let mut invoice_id = use_signal(String::new);
+    let mut privkey = use_signal(String::new);
+    let signing_mode = use_signal(SigningMode::default);
+    let base = redemption_workflow();
+
+    let needs_invoice = voucher
+        .contract
+        .as_ref()
+        .and_then(|c| base.registry.get(&c.template_id).and_then(|t| t.oracle_pubkey(&c.params)))
+        .is_some();
+    let balance = voucher.balance_sats();
+
+    let start = {
+        let base = base.clone();
+        let voucher = voucher.clone();
+        let participant_pubkey = participant_pubkey.clone();
+        move |_| {
+            let mut status_message = status_message;
+            let mut is_loading = is_loading;
+            let amount = match amount().trim().parse::<f64>() {
+                Ok(v) if v > 0.0 => v,
+                _ => {
+                    status_message.set("Please enter the amount to pay".to_string());
+                    return;
+                }
+            };
+            let order = RedemptionOrder {
+                participant_pubkey: participant_pubkey.clone(),
+                voucher: voucher.clone(),
+                partner_address: partner_address(),
+                amount,
+                invoice_id: invoice_id(),
+                privkey: privkey(),
+                mode: signing_mode(),
+            };
+            let base = base.clone();
+            spawn(async move {
+                is_loading.set(true);
+                let progress = move |message: String| {
+                    let mut status_message = status_message;
+                    status_message.set(message);
+                };
+                match participant::start_redemption(&base, order, &progress).await {
+                    Ok(request) => {
+                        status_message.set(format!(
+                            "Redemption started: {} L-BTC to the partner.\n\nSend the request to the partner (\"Show request\" under Pending Redemptions). Once they approve it, paste their answer there and press Finish.",
+                            btc(request.amount_sats)
+                        ));
+                        privkey.set(String::new());
+                        on_started.call(());
+                    }
+                    Err(e) => status_message.set(format!("Could not start the redemption: {}", error::describe(&e))),
+                }
+                is_loading.set(false);
+            });
+        }
+    };
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Spend a Voucher" }
+            p { style: "margin-bottom: 16px;", "Available: {btc(balance)} L-BTC" }
+
+            div { style: "margin-bottom: 16px;",
+                label { "Partner" }
+                if partners.is_empty() {
+                    p {
+                        "No partners in the address book yet. "
+                        Link { to: Route::ParticipantPartnersPage {}, "Import the promoter's address book" }
+                        " or scan a payment request."
+                    }
+                } else {
+                    select {
+                        onchange: move |evt| partner_address.set(evt.value().to_string()),
+                        for p in partners.iter() {
+                            option { key: "{p.address}", value: "{p.address}", selected: p.address == partner_address(), "{p.name}" }
+                        }
+                    }
+                }
+                if show_details || !partners.iter().any(|p| p.address == partner_address()) {
+                    p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all; margin-top: 4px;", "{partner_address}" }
+                }
+                QrImport {
+                    label: "Or scan the partner's payment request",
+                    on_import: move |text: String| {
+                        let mut status_message = status_message;
+                        match PaymentUri::parse(&text) {
+                            Ok(uri) => {
+                                partner_address.set(uri.address.clone());
+                                if let Some(sats) = uri.amount_sats {
+                                    amount.set(format!("{}", btc(sats)));
+                                }
+                            }
+                            Err(e) => status_message.set(format!("That QR code is not a payment request: {}", e)),
+                        }
+                    }
+                }
+            }
+
+            div { style: "margin-bottom: 16px;",
+                label { "Amount (L-BTC)" }
+                input {
+                    r#type: "number",
+                    step: "0.00000001",
+                    min: "0",
+                    value: "{amount}",
+                    oninput: move |evt| amount.set(evt.value().to_string()),
+                    placeholder: "0.0003"
+                }
+                p { style: "font-size: 0.875rem; color: #666; margin-top: 4px;",
+                    "What remains stays on your voucher. A small network fee is taken from the voucher."
+                }
+            }
+
+            if needs_invoice {
+                div { style: "margin-bottom: 16px;",
+                    label { "Invoice Number" }
+                    input {
+                        r#type: "text",
+                        value: "{invoice_id}",
+                        oninput: move |evt| invoice_id.set(evt.value().to_string()),
+                        placeholder: "From the partner's receipt"
+                    }
+                }
+            }
+
+            div { style: "margin-bottom: 16px;",
+                label { "Your Secret Key" }
+                input {
+                    r#type: "password",
+                    value: "{privkey}",
+                    oninput: move |evt| privkey.set(evt.value().to_string()),
+                    placeholder: "The key you received with your vouchers"
+                }
+            }
+
+            if show_details {
+                SigningModeSelect { mode: signing_mode }
+            }
+
+            button {
+                class: "button",
+                onclick: start,
+                disabled: is_loading() || partner_address().is_empty() || privkey().trim().is_empty(),
+                "Start Redemption"
+            }
+        }
+    }
+}
+
+#[component]
+fn Pending(
+    pending: Vec<(RedemptionRequest, RedemptionStatus)>,
+    partners: Vec<Partner>,
+    show_details: bool,
+    on_finished: EventHandler<()>,
+    status_message: Signal<String>,
+    is_loading: Signal<bool>,
+) -> Element {
+    if pending.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Pending Redemptions" }
+            for (request, status) in pending {
+                PendingItem {
+                    key: "{request.workflow}",
+                    partner: partner_name(&partners, &request.partner_address),
+                    request,
+                    status,
+                    show_details,
+                    on_finished,
+                    status_message,
+                    is_loading,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PendingItem(
+    request: RedemptionRequest,
+    status: RedemptionStatus,
+    partner: String,
+    show_details: bool,
+    on_finished: EventHandler<()>,
+    status_message: Signal<String>,
+    is_loading: Signal<bool>,
+) -> Element {
+    let mut show_request = use_signal(|| false);
+    let mut answer = use_signal(String::new);
+    let base = redemption_workflow();
+    let request_pset = participant::request_pset(&base, &request).map_err(|e| error::describe(&e));
+    let finishable = !matches!(status, RedemptionStatus::Rejected { .. } | RedemptionStatus::Broadcast { .. });
+
+    let finish = {
+        let base = base.clone();
+        let request = request.clone();
+        move |_| {
+            let mut status_message = status_message;
+            let mut is_loading = is_loading;
+            let base = base.clone();
+            let request = request.clone();
+            spawn(async move {
+                is_loading.set(true);
+                let progress = move |message: String| {
+                    let mut status_message = status_message;
+                    status_message.set(message);
+                };
+                match participant::finish_redemption(&base, &request, &answer(), &progress).await {
+                    Ok(txid) => {
+                        status_message.set(format!("Redemption sent! It will show as completed once the network confirms it.\n\nTransaction: {}", txid));
+                        on_finished.call(());
+                    }
+                    Err(e) => status_message.set(format!("Could not finish the redemption: {}", error::describe(&e))),
+                }
+                is_loading.set(false);
+            });
+        }
+    };
+
+    rsx! {
+        div { class: "info-box info",
+            p { style: "font-weight: 600;", "{btc(request.amount_sats)} L-BTC to {partner}" }
+            p { "{status}" }
+            if show_details {
+                p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;",
+                    "Txid {request.unsigned_txid}, spending {request.voucher_txid}:{request.voucher_vout}, fee {request.fee_sats} sats"
+                }
+            }
+
+            if finishable {
+                button {
+                    class: "button",
+                    style: "margin-top: 8px;",
+                    onclick: move |_| show_request.toggle(),
+                    if show_request() { "Hide request" } else { "Show request" }
+                }
+                if show_request() {
+                    match &request_pset {
+                        Ok(pset) => rsx! {
+                            p { style: "font-size: 0.875rem; color: #666; margin-top: 8px;", "Send this text to {partner} so they can approve it:" }
+                            textarea {
+                                rows: "4",
+                                readonly: true,
+                                value: "{pset}",
+                                style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; width: 100%;"
+                            }
+                            QrCode { data: pset.clone(), caption: "Let {partner} scan this code" }
+                        },
+                        Err(e) => rsx! {
+                            p { "{e}" }
+                        },
+                    }
+                }
+
+                div { style: "margin-top: 16px;",
+                    label { "Partner's Answer" }
+                    textarea {
+                        rows: "3",
+                        value: "{answer}",
+                        oninput: move |evt| answer.set(evt.value().to_string()),
+                        placeholder: "Paste the approved request the partner sent back",
+                        style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; width: 100%;"
+                    }
+                    QrImport {
+                        label: "Or scan the partner's answer",
+                        on_import: move |text: String| answer.set(text)
+                    }
+                    button {
+                        class: "button",
+                        style: "margin-top: 8px;",
+                        onclick: finish,
+                        disabled: is_loading() || answer().trim().is_empty(),
+                        "Finish"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn Completed(wallet: Wallet, show_details: bool) -> Element {
+    if wallet.completed.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Completed Redemptions" }
+            for c in wallet.completed.iter().cloned() {
+                div { key: "{c.txid}", class: "info-box info",
+                    p { style: "font-weight: 600;",
+                        {
+                            let to = c.partner.clone().or_else(|| c.address.as_deref().map(short)).unwrap_or_else(|| "unknown".to_string());
+                            let value = c.value_sats.map(|v| format!("{} L-BTC", btc(v))).unwrap_or_else(|| "Confidential amount".to_string());
+                            format!("{} to {}", value, to)
+                        }
+                    }
+                    p { "{c.status}" }
+                    if show_details {
+                        a {
+                            href: format!("https://blockstream.info/liquidtestnet/tx/{}", c.txid),
+                            target: "_blank",
+                            style: "color: #0066cc; text-decoration: underline; font-size: 0.875rem;",
+                            "View on Blockstream Explorer →"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! Partner redemption review page
+//!
+//! A partner pastes the redemption PSET a participant sent them, checks it against the
+//! contract rules, and either co-signs their multisig slot or rejects it with a reason.
+//! Decisions are kept in the app state.
+
+use crate::app_core::audit;
+use crate::app_core::error;
+use crate::app_core::qr::{self, PaymentUri};
+use crate::app_core::redemption::{self, Review};
+use crate::app_core::sighash::{self, SigningMode};
+use crate::app_core::{HalWrapper, RedemptionReview, ReviewDecision, Store, TemplateRegistry};
+use crate::components::{QrCode, QrImport, SigningModeSelect};
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn amount(value_sats: Option<u64>) -> String {
+    value_sats
+        .map(|v| format!("{} L-BTC ({} sats)", btc(v), v))
+        .unwrap_or_else(|| "confidential".to_string())
+}
+
+#[component]
+pub fn Partner() -> Element {
+    let hal = consume_context::<Arc<HalWrapper>>();
+    let registry = consume_context::<Arc<TemplateRegistry>>();
+    let store = consume_context::<Arc<Store>>();
+
+    let saved = use_signal({
+        let store = store.clone();
+        move || store.load().unwrap_or_default()
+    });
+    let mut partner_address = use_signal(|| {
+        saved.peek().partners.first().map(|p| p.address.clone()).unwrap_or_default()
+    });
+    let mut pset_input = use_signal(String::new);
+    let mut expected_amount = use_signal(String::new);
+    let mut review = use_signal(|| None::<Review>);
+    let mut privkey = use_signal(String::new);
+    let signing_mode = use_signal(SigningMode::default);
+    let mut reject_reason = use_signal(String::new);
+    let mut cosigned = use_signal(String::new);
+    let mut status_message = use_signal(String::new);
+
+    let validate = {
+        let registry = registry.clone();
+        let store = store.clone();
+        move |_| {
+            let mut saved = saved;
+            cosigned.set(String::new());
+            let amount_sats = match expected_amount().trim() {
+                "" => None,
+                raw => match raw.parse::<f64>() {
+                    Ok(v) if v > 0.0 => Some((v * 100_000_000.0).round() as u64),
+                    _ => {
+                        status_message.set("Please enter a valid expected amount, or leave it empty".to_string());
+                        return;
+                    }
+                },
+            };
+            let state = match store.load() {
+                Ok(state) => state,
+                Err(e) => {
+                    status_message.set(format!("Failed to load the app state: {}", e));
+                    return;
+                }
+            };
+            match redemption::review(&state, &registry, &pset_input(), &partner_address(), amount_sats) {
+                Ok(r) => {
+                    status_message.set(if r.report.is_valid() {
+                        "The redemption follows the contract rules. Co-sign it below.".to_string()
+                    } else {
+                        format!("The redemption breaks the contract rules:\n{}", r.report.failures())
+                    });
+                    reject_reason.set(r.report.failures());
+                    review.set(Some(r));
+                }
+                Err(e) => {
+                    status_message.set(format!("Failed to check the redemption: {}", error::describe(&e)));
+                    review.set(None);
+                }
+            }
+            saved.set(state);
+        }
+    };
+
+    let co_sign = {
+        let hal = hal.clone();
+        let registry = registry.clone();
+        let store = store.clone();
+        move |_| {
+            let mut saved = saved;
+            let Some(r) = review() else {
+                return;
+            };
+            if privkey().trim().is_empty() {
+                status_message.set("Please enter your private key".to_string());
+                return;
+            }
+            let signers = r.signers(&registry);
+            match redemption::co_sign(hal.as_ref(), signing_mode(), &r.redemption.pset, &r.contract.cmr, &r.contract.genesis_hash, &signers, &privkey()) {
+                Ok((pset, signature)) => {
+                    let pubkey = sighash::pubkey_of(&privkey()).unwrap_or_default();
+                    audit::record(&store, "partner", audit::signed(&pset, vec![pubkey.clone()]));
+                    let slot = signers.pubkeys.iter().position(|s| *s == pubkey).map(|i| i + 1).unwrap_or(0);
+                    let record = r.record(ReviewDecision::CoSigned { pubkey, signature });
+                    let saved_note = match store.save_review(record) {
+                        Ok(()) => String::new(),
+                        Err(e) => format!("\n\nWarning: the decision was not saved: {}", e),
+                    };
+                    status_message.set(format!(
+                        "Co-signed as signer {}. Send the PSET below back to the participant to finalize and broadcast.{}",
+                        slot, saved_note
+                    ));
+                    cosigned.set(pset);
+                    privkey.set(String::new());
+                    if let Ok(state) = store.load() {
+                        saved.set(state);
+                    }
+                }
+                Err(e) => status_message.set(format!("Failed to co-sign: {}", error::describe(&e))),
+            }
+        }
+    };
+
+    let reject = {
+        let store = store.clone();
+        move |_| {
+            let mut saved = saved;
+            let Some(r) = review() else {
+                return;
+            };
+            let reason = reject_reason().trim().to_string();
+            if reason.is_empty() {
+                status_message.set("Please give a reason for the rejection".to_string());
+                return;
+            }
+            match store.save_review(r.record(ReviewDecision::Rejected { reason: reason.clone() })) {
+                Ok(()) => {
+                    status_message.set(format!("Redemption {} rejected:\n{}", r.redemption.unsigned_txid, reason));
+                    review.set(None);
+                    if let Ok(state) = store.load() {
+                        saved.set(state);
+                    }
+                }
+                Err(e) => status_message.set(format!("Failed to save the rejection: {}", e)),
+            }
+        }
+    };
+
+    let partners = saved.read().partners.clone();
+    let mut reviews = saved.read().reviews.clone();
+    reviews.reverse();
+    let current = review();
+
+    rsx! {
+        div { id: "partner-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 24px;", "Review Redemption" }
+
+            div { class: "panel-section",
+                h2 { "1. Import Redemption PSET" }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Your Partner Address" }
+                    if !partners.is_empty() {
+                        select {
+                            style: "margin-bottom: 8px;",
+                            onchange: move |evt| partner_address.set(evt.value().to_string()),
+                            for p in partners.iter() {
+                                option { key: "{p.address}", value: "{p.address}", selected: p.address == partner_address(), "{p.name}" }
+                            }
+                        }
+                    }
+                    input {
+                        r#type: "text",
+                        value: "{partner_address}",
+                        oninput: move |evt| partner_address.set(evt.value().to_string()),
+                        placeholder: "Address the redemption must pay (Output 0)"
+                    }
+                }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Expected Amount (L-BTC, optional)" }
+                    input {
+                        r#type: "number",
+                        step: "0.00000001",
+                        min: "0",
+                        value: "{expected_amount}",
+                        oninput: move |evt| expected_amount.set(evt.value().to_string()),
+                        placeholder: "Invoice total"
+                    }
+                    QrCode {
+                        data: {
+                            let uri = PaymentUri::new(&partner_address());
+                            let uri = match qr::parse_btc(&expected_amount()) {
+                                Ok(sats) if sats > 0 => uri.with_amount(sats),
+                                _ => uri,
+                            };
+                            let name = partners.iter().find(|p| p.address == partner_address()).map(|p| p.name.clone());
+                            uri.with_label(&name.unwrap_or_default()).to_uri()
+                        },
+                        caption: "Payment request for the participant to scan"
+                    }
+                }
+
+                div { style: "margin-bottom: 16px;",
+                    label { "Redemption PSET" }
+                    textarea {
+                        rows: "5",
+                        value: "{pset_input}",
+                        oninput: move |evt| pset_input.set(evt.value().to_string()),
+                        placeholder: "Paste the PSET the participant sent (base64)",
+                        style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; width: 100%;"
+                    }
+                    QrImport { on_import: move |text: String| pset_input.set(text) }
+                }
+
+                button {
+                    class: "button",
+                    onclick: validate,
+                    disabled: pset_input().trim().is_empty() || partner_address().trim().is_empty(),
+                    "Decode and Validate"
+                }
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;", "{status_message}" }
+                }
+            }
+
+            if let Some(r) = current {
+                div { class: "panel-section",
+                    h2 { "2. Check Against the Contract" }
+                    div { class: "info-box info",
+                        p { style: "word-break: break-all;", "Contract: {r.contract.address} ({r.contract.template_id})" }
+                        p { style: "word-break: break-all;", "Spends: {r.redemption.input_txid}:{r.redemption.input_vout} holding {amount(r.redemption.spent_value_sats)}" }
+                        for (i, out) in r.redemption.outputs.iter().enumerate() {
+                            p { key: "{i}", style: "word-break: break-all;",
+                                {
+                                    let to = if out.fee {
+                                        "fee".to_string()
+                                    } else {
+                                        out.address.clone().unwrap_or_else(|| out.script_pubkey.clone())
+                                    };
+                                    format!("Output {}: {} to {}", i, amount(out.value_sats), to)
+                                }
+                            }
+                        }
+                    }
+                    ul { style: "list-style: none; padding-left: 0;",
+                        for check in r.report.checks.iter() {
+                            li { key: "{check.name}", style: if check.passed { "color: #2e7d32;" } else { "color: #c62828;" },
+                                if check.passed { "✓ " } else { "✗ " }
+                                strong { "{check.name}: " }
+                                "{check.detail}"
+                            }
+                        }
+                    }
+                    p { "Signatures present: {r.report.signed_slots.len()} of {r.report.threshold} required" }
+                }
+
+                div { class: "panel-section",
+                    h2 { "3. Co-sign or Reject" }
+
+                    div { style: "margin-bottom: 16px;",
+                        label { "Your Private Key (hex)" }
+                        input {
+                            r#type: "password",
+                            value: "{privkey}",
+                            oninput: move |evt| privkey.set(evt.value().to_string()),
+                            placeholder: "Key of your signer slot in the contract"
+                        }
+                    }
+                    SigningModeSelect { mode: signing_mode }
+                    button {
+                        class: "button",
+                        onclick: co_sign,
+                        disabled: !r.report.is_valid(),
+                        "Co-sign"
+                    }
+
+                    div { style: "margin-top: 24px; margin-bottom: 16px;",
+                        label { "Rejection Reason" }
+                        textarea {
+                            rows: "3",
+                            value: "{reject_reason}",
+                            oninput: move |evt| reject_reason.set(evt.value().to_string()),
+                            placeholder: "Why the redemption is not accepted",
+                            style: "width: 100%;"
+                        }
+                    }
+                    button { class: "button", onclick: reject, "Reject" }
+                }
+            }
+
+            if !cosigned().is_empty() {
+                div { class: "panel-section",
+                    h2 { "Co-signed PSET" }
+                    textarea {
+                        rows: "5",
+                        readonly: true,
+                        value: "{cosigned}",
+                        style: "font-family: 'Roboto Mono', monospace; font-size: 0.9rem; width: 100%;"
+                    }
+                    QrCode { data: cosigned(), caption: "Co-signed PSET for the participant to scan" }
+                }
+            }
+
+            if !reviews.is_empty() {
+                div { class: "panel-section",
+                    h2 { "Past Decisions" }
+                    for r in reviews {
+                        ReviewItem { key: "{r.unsigned_txid}", review: r }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ReviewItem(review: RedemptionReview) -> Element {
+    let decision = match &review.decision {
+        ReviewDecision::CoSigned { pubkey, .. } => format!("Co-signed with {}", pubkey),
+        ReviewDecision::Rejected { reason } => format!("Rejected: {}", reason),
+    };
+
+    rsx! {
+        div { class: "info-box info",
+            p { style: "font-family: 'Roboto Mono', monospace; font-size: 0.8rem; word-break: break-all;",
+                "{review.unsigned_txid}"
+            }
+            p { style: "word-break: break-all;", "Contract: {review.contract_address}" }
+            p { style: "word-break: break-all;", "Pays {amount(review.amount_sats)} to {review.partner_address}" }
+            p { style: "white-space: pre-wrap;", "{decision}" }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Promoter dashboard
+//!
+//! One page over the saved app state: contracts and the value they lock, vouchers
+//! outstanding per participant, redemptions per partner, and the node's sync status.
+//! Each row links to the history, lineage or explorer page it summarises.
+
+use crate::app_core::dashboard::{self, ContractRow, Dashboard, NodeStatus, ParticipantRow, PartnerRow};
+use crate::app_core::{contracts, error, ElementsRPC, HalWrapper, Store, TemplateRegistry};
+use crate::Route;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn short(value: &str) -> String {
+    if value.len() > 20 {
+        format!("{}…{}", &value[..10], &value[value.len() - 8..])
+    } else {
+        value.to_string()
+    }
+}
+
+#[component]
+pub fn Promoter() -> Element {
+    let rpc_context = consume_context::<Arc<ElementsRPC>>();
+    let store = consume_context::<Arc<Store>>();
+    let status_message = use_signal(String::new);
+
+    let mut dashboard = use_resource(move || {
+        let rpc_context = rpc_context.clone();
+        let store = store.clone();
+        async move {
+            let state = store.load().map_err(|e| e.to_string())?;
+            Ok::<_, String>(dashboard::load(&rpc_context, &state).await)
+        }
+    });
+
+    let body = match &*dashboard.read() {
+        None => rsx! {
+            div { class: "loading", "Loading" }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "status-message",
+                pre { style: "white-space: pre-wrap; font-family: inherit;", "{e}" }
+            }
+        },
+        Some(Ok(d)) => rsx! {
+            NodeSection { node: d.node.clone() }
+            ContractsSection { dashboard: d.clone(), status_message }
+            ParticipantsSection { participants: d.participants.clone() }
+            PartnersSection { partners: d.partners.clone() }
+        },
+    };
+
+    rsx! {
+        div { id: "promoter-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 24px;", "Promoter Dashboard" }
+
+            button {
+                class: "button",
+                style: "margin-bottom: 16px;",
+                onclick: move |_| dashboard.restart(),
+                "Refresh"
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;", "{status_message}" }
+                }
+            }
+
+            {body}
+        }
+    }
+}
+
+#[component]
+fn NodeSection(node: Result<NodeStatus, String>) -> Element {
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Node" }
+            match node {
+                Ok(node) => rsx! {
+                    div { class: if node.is_synced() { "info-box info" } else { "info-box warning" },
+                        p { "Chain: {node.chain}" }
+                        p { "Blocks: {node.blocks} / headers: {node.headers}" }
+                        p { "Verification progress: {node.verification_progress * 100.0:.2}%" }
+                        p {
+                            if node.is_synced() {
+                                "Synced"
+                            } else {
+                                "Still syncing: balances may be incomplete until the node catches up"
+                            }
+                        }
+                    }
+                },
+                Err(e) => rsx! {
+                    div { class: "info-box warning",
+                        p { "Node unreachable; balances come from the Blockstream API only." }
+                        pre { style: "white-space: pre-wrap; font-family: inherit; font-size: 0.8rem;", "{e}" }
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn ContractsSection(dashboard: Dashboard, status_message: Signal<String>) -> Element {
+    let total = dashboard.total_locked_sats();
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Contracts" }
+            p { style: "margin-bottom: 12px;",
+                "{dashboard.contracts.len()} recorded, {btc(total)} L-BTC ({total} sats) locked"
+            }
+            if dashboard.contracts.is_empty() {
+                p { "No contracts recorded yet. Create one from the P2MS or Voucher page." }
+            } else {
+                table { class: "data-table",
+                    thead {
+                        tr {
+                            th { "Address" }
+                            th { "Template" }
+                            th { "Locked" }
+                            th { "Actions" }
+                        }
+                    }
+                    tbody {
+                        for row in dashboard.contracts.iter().cloned() {
+                            ContractItem { key: "{row.address}", row, status_message }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ContractItem(row: ContractRow, status_message: Signal<String>) -> Element {
+    let hal = consume_context::<Arc<HalWrapper>>();
+    let registry = consume_context::<Arc<TemplateRegistry>>();
+    let store = consume_context::<Arc<Store>>();
+
+    let locked = match &row.locked_sats {
+        Ok(sats) => format!("{} L-BTC", btc(*sats)),
+        Err(_) => "unavailable".to_string(),
+    };
+
+    let address = row.address.clone();
+    let verify = move |_| {
+        let mut status_message = status_message;
+        let message = match store.find_contract(&address) {
+            Ok(Some(record)) => match contracts::verify_contract(hal.as_ref(), &registry, &record, None) {
+                Ok(report) if report.is_match() => format!("{} verified:\n\n{}", address, report),
+                Ok(report) => format!("{} does NOT match its record:\n\n{}", address, report),
+                Err(e) => format!("Failed to verify {}: {}", address, error::describe(&e)),
+            },
+            Ok(None) => format!("No record found for {}", address),
+            Err(e) => format!("Failed to load the contract record: {}", e),
+        };
+        status_message.set(message);
+    };
+
+    rsx! {
+        tr {
+            td { class: "mono", title: "{row.address}", "{short(&row.address)}" }
+            td { "{row.template_id}" }
+            td { title: row.locked_sats.as_ref().err().cloned().unwrap_or_default(), "{locked}" }
+            td { class: "actions",
+                Link { to: Route::ContractDetailPage { address: row.address.clone() }, "History" }
+                button { class: "button", onclick: verify, "Verify" }
+                a {
+                    href: format!("https://blockstream.info/liquidtestnet/address/{}", row.address),
+                    target: "_blank",
+                    "Explorer"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ParticipantsSection(participants: Vec<ParticipantRow>) -> Element {
+    let outstanding: u64 = participants.iter().map(|p| p.outstanding_sats).sum();
+
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Participants" }
+            p { style: "margin-bottom: 12px;", "{btc(outstanding)} L-BTC ({outstanding} sats) outstanding in vouchers" }
+            if participants.is_empty() {
+                p { "No participants or vouchers recorded yet." }
+            } else {
+                table { class: "data-table",
+                    thead {
+                        tr {
+                            th { "Participant" }
+                            th { "Vouchers" }
+                            th { "Outstanding" }
+                            th { "Redeemed" }
+                            th { "Lineage" }
+                        }
+                    }
+                    tbody {
+                        for row in participants {
+                            tr { key: "{row.pubkey}",
+                                td { class: "mono", title: "{row.pubkey}", "{short(&row.pubkey)}" }
+                                td { "{row.vouchers.len()}" }
+                                td { "{btc(row.outstanding_sats)} L-BTC" }
+                                td { "{btc(row.redeemed_sats)} L-BTC" }
+                                td { class: "actions",
+                                    for (txid, vout) in row.vouchers.iter().cloned() {
+                                        Link {
+                                            key: "{txid}:{vout}",
+                                            to: Route::PromoterLineagePage { txid: txid.clone(), vout },
+                                            "{short(&txid)}:{vout}"
+                                        }
+                                    }
+                                    for error in row.errors.iter() {
+                                        span { key: "{error}", title: "{error}", "⚠" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PartnersSection(partners: Vec<PartnerRow>) -> Element {
+    rsx! {
+        div { class: "panel-section",
+            h2 { "Partners" }
+            if partners.is_empty() {
+                p { "No partners recorded yet." }
+            } else {
+                table { class: "data-table",
+                    thead {
+                        tr {
+                            th { "Partner" }
+                            th { "Address" }
+                            th { "Redemptions" }
+                            th { "Received" }
+                            th { "Actions" }
+                        }
+                    }
+                    tbody {
+                        for row in partners {
+                            tr { key: "{row.address}",
+                                td { "{row.name}" }
+                                td { class: "mono", title: "{row.address}", "{short(&row.address)}" }
+                                td { "{row.redemptions}" }
+                                td { "{btc(row.redeemed_sats)} L-BTC" }
+                                td { class: "actions",
+                                    a {
+                                        href: format!("https://blockstream.info/liquidtestnet/address/{}", row.address),
+                                        target: "_blank",
+                                        "Explorer"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Redemption and settlement reports page
+//!
+//! Statements of what partners received through vouchers, narrowed by partner, contract
+//! and date range, with totals and CSV/JSON export (see `app_core::reports`). The
+//! desktop app saves exports under `~/.partnerfy/reports`; the web app downloads them.
+
+use crate::app_core::reports::{self, Report, ReportFilter};
+use crate::app_core::{audit, platform, ElementsRPC, Settings, Store};
+use base64::Engine;
+use dioxus::prelude::*;
+use std::sync::Arc;
+
+fn btc(sats: u64) -> f64 {
+    sats as f64 / 100_000_000.0
+}
+
+fn short(value: &str) -> String {
+    if value.len() > 20 {
+        format!("{}…{}", &value[..10], &value[value.len() - 8..])
+    } else {
+        value.to_string()
+    }
+}
+
+fn amount(value_sats: Option<u64>) -> String {
+    value_sats.map(|v| v.to_string()).unwrap_or_else(|| "confidential".to_string())
+}
+
+/// File name for an export of `report`
+fn export_name(report: &Report, extension: &str) -> String {
+    let date = |t: Option<u64>| t.map(|t| audit::format_time(t)[..10].to_string());
+    let range = match (date(report.filter.from), date(report.filter.to)) {
+        (Some(from), Some(to)) => format!("{}_{}", from, to),
+        (Some(from), None) => format!("from-{}", from),
+        (None, Some(to)) => format!("to-{}", to),
+        (None, None) => "all".to_string(),
+    };
+    format!("redemptions-{}.{}", range, extension)
+}
+
+#[component]
+pub fn Reports() -> Element {
+    let rpc_context = consume_context::<Arc<ElementsRPC>>();
+    let store = consume_context::<Arc<Store>>();
+    let state = store.load().unwrap_or_default();
+
+    let mut partner = use_signal(String::new);
+    let mut contract = use_signal(String::new);
+    let mut from = use_signal(String::new);
+    let mut to = use_signal(String::new);
+    let mut confirmed_only = use_signal(|| true);
+    let mut report = use_signal(|| None::<Report>);
+    let mut status_message = use_signal(String::new);
+    let mut is_loading = use_signal(|| false);
+
+    let run = move |_| {
+        let rpc_context = rpc_context.clone();
+        let store = store.clone();
+        let filter = ReportFilter {
+            partner: Some(partner()).filter(|p| !p.is_empty()),
+            contract: Some(contract()).filter(|c| !c.is_empty()),
+            from: audit::parse_date(&from()),
+            // Through the end of the "to" day
+            to: audit::parse_date(&to()).map(|t| t + 86_399),
+            confirmed_only: confirmed_only(),
+        };
+        spawn(async move {
+            is_loading.set(true);
+            status_message.set(String::new());
+            match store.load() {
+                Ok(state) => report.set(Some(reports::load(&rpc_context, &state, filter).await)),
+                Err(e) => status_message.set(format!("Failed to load the app state: {}", e)),
+            }
+            is_loading.set(false);
+        });
+    };
+
+    let save = move |extension: &'static str| {
+        let Some(report) = report() else {
+            return;
+        };
+        let contents = match extension {
+            "csv" => Ok(report.to_csv()),
+            _ => report.to_json(),
+        };
+        let path = Settings::data_dir().join("reports").join(export_name(&report, extension));
+        let mut status_message = status_message;
+        match contents.and_then(|c| platform::write_file(&path, &c)) {
+            Ok(()) => status_message.set(format!("Saved {}", path.display())),
+            Err(e) => status_message.set(format!("Failed to save the report: {}", e)),
+        }
+    };
+
+    let body = match report() {
+        None => rsx! {},
+        Some(report) => {
+            let csv_uri = format!(
+                "data:text/csv;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(report.to_csv())
+            );
+            let json_uri = format!(
+                "data:application/json;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(report.to_json().unwrap_or_default())
+            );
+            rsx! {
+                for error in report.errors.iter() {
+                    div { class: "info-box warning", p { "Skipped {error}" } }
+                }
+
+                div { class: "panel-section",
+                    h2 { "Totals" }
+                    p { "{report.lines.len()} redemptions, {btc(report.total_sats())} L-BTC ({report.total_sats()} sats) paid, {report.total_fee_sats()} sats in fees" }
+                    div { style: "display: flex; gap: 8px; margin: 12px 0;",
+                        if cfg!(feature = "server") {
+                            button { class: "button outline", onclick: move |_| save("csv"), "Save CSV" }
+                            button { class: "button outline", onclick: move |_| save("json"), "Save JSON" }
+                        } else {
+                            a { class: "button outline", href: "{csv_uri}", download: "{export_name(&report, \"csv\")}", "Download CSV" }
+                            a { class: "button outline", href: "{json_uri}", download: "{export_name(&report, \"json\")}", "Download JSON" }
+                        }
+                    }
+                    h3 { "By partner" }
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "Partner" }
+                                th { "Address" }
+                                th { "Redemptions" }
+                                th { "Received (sats)" }
+                                th { "Fees (sats)" }
+                            }
+                        }
+                        tbody {
+                            for total in report.by_partner.iter() {
+                                tr { key: "{total.address}",
+                                    td { "{total.label}" }
+                                    td { class: "mono", title: "{total.address}", "{short(&total.address)}" }
+                                    td { "{total.redemptions}" }
+                                    td {
+                                        "{total.amount_sats}"
+                                        if total.confidential > 0 { " + {total.confidential} confidential" }
+                                    }
+                                    td { "{total.fee_sats}" }
+                                }
+                            }
+                        }
+                    }
+                    h3 { style: "margin-top: 16px;", "By contract" }
+                    table { class: "data-table",
+                        thead {
+                            tr {
+                                th { "Contract" }
+                                th { "Template" }
+                                th { "Redemptions" }
+                                th { "Paid (sats)" }
+                                th { "Fees (sats)" }
+                            }
+                        }
+                        tbody {
+                            for total in report.by_contract.iter() {
+                                tr { key: "{total.address}",
+                                    td { class: "mono", title: "{total.address}", "{short(&total.address)}" }
+                                    td { "{total.label}" }
+                                    td { "{total.redemptions}" }
+                                    td { "{total.amount_sats}" }
+                                    td { "{total.fee_sats}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "panel-section",
+                    h2 { "Redemptions" }
+                    if report.lines.is_empty() {
+                        p { "No redemptions match these filters." }
+                    } else {
+                        table { class: "data-table",
+                            thead {
+                                tr {
+                                    th { "Date (UTC)" }
+                                    th { "Transaction" }
+                                    th { "Partner" }
+                                    th { "Contract" }
+                                    th { "Amount (sats)" }
+                                    th { "Fee (sats)" }
+                                    th { "Block" }
+                                }
+                            }
+                            tbody {
+                                for line in report.lines.iter() {
+                                    tr { key: "{line.txid}:{line.vout}",
+                                        td { {line.time.map(audit::format_time).unwrap_or_else(|| "Unconfirmed".to_string())} }
+                                        td {
+                                            a {
+                                                href: "https://blockstream.info/liquidtestnet/tx/{line.txid}",
+                                                target: "_blank",
+                                                class: "mono",
+                                                "{short(&line.txid)}:{line.vout}"
+                                            }
+                                        }
+                                        td { "{line.partner_name}" }
+                                        td { class: "mono", title: "{line.contract}", "{short(&line.contract)}" }
+                                        td { "{amount(line.amount_sats)}" }
+                                        td { {line.fee_sats.map(|f| f.to_string()).unwrap_or_default()} }
+                                        td { {line.height.map(|h| h.to_string()).unwrap_or_default()} }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { id: "reports-panel",
+            h1 { style: "font-size: 2rem; margin-bottom: 8px;", "Redemption Reports" }
+            p { style: "margin-bottom: 16px; color: #666;",
+                "What partners received through vouchers, from the on-chain history of the recorded contracts. Dates are block times in UTC."
+            }
+
+            div { class: "panel-section",
+                div { style: "display: flex; flex-wrap: wrap; gap: 12px; align-items: flex-end;",
+                    div {
+                        label { "Partner" }
+                        select {
+                            value: "{partner}",
+                            onchange: move |evt| partner.set(evt.value()),
+                            option { value: "", "All partners" }
+                            for p in state.partners.iter() {
+                                option { value: "{p.address}", "{p.name}" }
+                            }
+                        }
+                    }
+                    div {
+                        label { "Contract" }
+                        select {
+                            value: "{contract}",
+                            onchange: move |evt| contract.set(evt.value()),
+                            option { value: "", "All contracts" }
+                            for c in state.contracts.iter() {
+                                option { value: "{c.address}", "{c.template_id}: {short(&c.address)}" }
+                            }
+                        }
+                    }
+                    div {
+                        label { "From" }
+                        input { r#type: "date", value: "{from}", oninput: move |evt| from.set(evt.value()) }
+                    }
+                    div {
+                        label { "To" }
+                        input { r#type: "date", value: "{to}", oninput: move |evt| to.set(evt.value()) }
+                    }
+                    label { style: "display: flex; gap: 6px; align-items: center;",
+                        input {
+                            r#type: "checkbox",
+                            checked: confirmed_only(),
+                            onchange: move |evt| confirmed_only.set(evt.checked()),
+                        }
+                        "Confirmed only"
+                    }
+                    button { class: "button", disabled: is_loading(), onclick: run,
+                        if is_loading() { "Loading..." } else { "Run Report" }
+                    }
+                }
+            }
+
+            if !status_message().is_empty() {
+                div { class: "status-message",
+                    pre { style: "white-space: pre-wrap; font-family: inherit;", "{status_message}" }
+                }
+            }
+
+            {body}
+        }
+    }
+}